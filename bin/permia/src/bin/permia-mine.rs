@@ -4,10 +4,21 @@
 //!
 //! Usage:
 //!   permia-mine --difficulty 1000000 --blocks 5
+//!   permia-mine --target-time 2 --blocks 5
+//!   permia-mine --difficulty 100000 --blocks 20 --adjust-difficulty
+//!
+//! Blocks are stamped like the node miner stamps them, so after every block the miner
+//! prints the difficulty the chain's retargeting sets for the next block;
+//! `--adjust-difficulty` mines the next block at it.
 
+use alloy_consensus::Header;
 use alloy_primitives::{Address, B256, U256};
 use clap::Parser;
-use permia_miner::{BlockTemplate, MiningConfig, MiningWorker};
+use permia_consensus::difficulty::DifficultyCalculator;
+use permia_miner::{
+    current_timestamp, measure_hashrate, next_timestamp, BatchTuning, BlockTemplate,
+    DifficultyTuner, MiningConfig, MiningWorker, DEFAULT_CALIBRATION_TIME,
+};
 use std::time::Duration;
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
@@ -29,6 +40,11 @@ struct Args {
     #[arg(long, short = 'd', default_value = "1000000")]
     difficulty: u64,
 
+    /// Target solve time in seconds; calibrates and tunes the difficulty instead of
+    /// using `--difficulty`
+    #[arg(long, conflicts_with = "difficulty")]
+    target_time: Option<f64>,
+
     /// Number of blocks to mine (0 = unlimited)
     #[arg(long, short = 'n', default_value = "1")]
     blocks: u64,
@@ -36,6 +52,43 @@ struct Args {
     /// Timeout per block in seconds
     #[arg(long, default_value = "300")]
     timeout: u64,
+
+    /// Retarget the difficulty after every block like the chain does
+    #[arg(long, conflicts_with = "target_time")]
+    adjust_difficulty: bool,
+}
+
+/// Chain difficulty retargeting, previewed after every block and applied on request
+///
+/// The difficulty is computed from the header timestamps exactly as the chain does, so
+/// with their second granularity a block mined within a second counts as a one second
+/// block.
+struct Retarget {
+    calculator: DifficultyCalculator,
+    apply: bool,
+}
+
+impl Retarget {
+    fn new(args: &Args) -> Self {
+        // Standalone difficulties are far below the chain minimum
+        let calculator = DifficultyCalculator::new().with_min_difficulty(U256::from(1));
+        Self { calculator, apply: args.adjust_difficulty }
+    }
+
+    /// Report how the chain retargets for a block at `timestamp` on top of `parent`,
+    /// returning the difficulty to mine that block at
+    fn after_block(&self, parent: &Header, timestamp: u64) -> U256 {
+        let next = self.calculator.calculate(parent, timestamp);
+        info!(
+            target: "permia::mine",
+            block_time_secs = timestamp.saturating_sub(parent.timestamp),
+            target_ms = self.calculator.target_block_time_ms(),
+            next_difficulty = %next,
+            applied = self.apply,
+            "Difficulty retarget"
+        );
+        if self.apply { next } else { parent.difficulty }
+    }
 }
 
 fn main() -> eyre::Result<()> {
@@ -62,6 +115,7 @@ fn main() -> eyre::Result<()> {
         miner = %miner_address,
         threads = threads,
         difficulty = args.difficulty,
+        target_time = ?args.target_time,
         blocks = args.blocks,
         "Starting Permia CPU miner"
     );
@@ -69,14 +123,42 @@ fn main() -> eyre::Result<()> {
     let config = MiningConfig {
         threads,
         batch_size: 10_000,
+        batch_tuning: Some(BatchTuning::default()),
         max_duration: Some(Duration::from_secs(args.timeout)),
+        ..MiningConfig::default()
     };
 
+    if args.target_time.is_some_and(|secs| !(secs.is_finite() && secs > 0.0)) {
+        eyre::bail!("--target-time must be a positive number of seconds");
+    }
+
+    let mut tuner = args.target_time.map(|secs| {
+        let target_time = Duration::from_secs_f64(secs);
+        info!(
+            target: "permia::mine",
+            calibration_secs = DEFAULT_CALIBRATION_TIME.as_secs_f64(),
+            "Calibrating hashrate..."
+        );
+        let hashrate = measure_hashrate(&config, DEFAULT_CALIBRATION_TIME);
+        let tuner = DifficultyTuner::calibrate(hashrate, target_time);
+        info!(
+            target: "permia::mine",
+            hashrate = format!("{:.2} H/s", hashrate),
+            target_secs = secs,
+            difficulty = %tuner.difficulty(),
+            "Calibrated difficulty"
+        );
+        tuner
+    });
+    let mut difficulty = tuner.as_ref().map_or(U256::from(args.difficulty), DifficultyTuner::difficulty);
+    let retarget = Retarget::new(&args);
+
     let worker = MiningWorker::new(config);
     let mut blocks_mined = 0u64;
     let mut parent_hash = B256::ZERO;
     let mut block_number = 0u64;
     let mut total_hashes = 0u64;
+    let mut timestamp = current_timestamp();
 
     let start_time = std::time::Instant::now();
 
@@ -96,24 +178,19 @@ fn main() -> eyre::Result<()> {
         }
 
         // Create block template
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-
         let template = BlockTemplate::new(
             parent_hash,
             block_number,
             timestamp,
             miner_address,
-            U256::from(args.difficulty),
+            difficulty,
         );
 
         info!(
             target: "permia::mine",
             block = block_number,
             parent = %parent_hash,
-            difficulty = args.difficulty,
+            difficulty = %difficulty,
             "Mining block..."
         );
 
@@ -138,6 +215,14 @@ fn main() -> eyre::Result<()> {
                 block_number += 1;
                 blocks_mined += 1;
                 total_hashes += result.hashes_computed;
+
+                let parent = template.to_sealed_header(result.nonce, result.mix_hash);
+                timestamp = next_timestamp(&parent);
+                let retargeted = retarget.after_block(&parent, timestamp);
+                difficulty = match tuner.as_mut() {
+                    Some(tuner) => tuner.record_solve_time(result.duration),
+                    None => retargeted,
+                };
             }
             Err(e) => {
                 tracing::error!(target: "permia::mine", error = %e, "Mining failed");
@@ -148,3 +233,30 @@ fn main() -> eyre::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust_difficulty() {
+        let difficulty = U256::from(1_000_000u64);
+        let parent = Header { difficulty, timestamp: 1_700_000_000, ..Default::default() };
+
+        // Same retargeting as the chain
+        let retarget = Retarget::new(&Args::parse_from(["permia-mine", "--adjust-difficulty"]));
+        for timestamp in [parent.timestamp, parent.timestamp + 1, parent.timestamp + 10] {
+            let expected = retarget.calculator.calculate(&parent, timestamp);
+            assert_eq!(retarget.after_block(&parent, timestamp), expected);
+        }
+        assert!(retarget.after_block(&parent, parent.timestamp) > difficulty);
+        assert!(retarget.after_block(&parent, parent.timestamp + 10) < difficulty);
+
+        // Only previewed without the flag
+        let retarget = Retarget::new(&Args::parse_from(["permia-mine"]));
+        assert_eq!(retarget.after_block(&parent, parent.timestamp), difficulty);
+
+        // The tuner already adjusts the difficulty
+        assert!(Args::try_parse_from(["permia-mine", "--target-time", "2", "--adjust-difficulty"]).is_err());
+    }
+}
@@ -7,78 +7,290 @@
 //!
 //! # Mining Mode
 //!
-//! When running with `--dev`, the node will automatically mine blocks
-//! at regular intervals using Reth's LocalMiner infrastructure.
+//! Blocks are only produced by the node miner, also with `--dev`: Reth's LocalMiner
+//! builds difficulty-less blocks the PermiaHash consensus rejects. With
+//! `--dev --dev.block-time`, the node miner paces the blocks it seals to that interval
+//! instead of sealing them as fast as the hashrate allows.
+//!
+//! `--regtest` freezes the difficulty at `--regtest.difficulty` instead of adjusting
+//! it to block times, so block production in integration tests is reproducible. Both
+//! the node's consensus and the node miner use the frozen difficulty. It is refused on
+//! mainnet and testnet.
+//!
+//! Nodes that mine keep the node miner on the canonical tip, and import the blocks it
+//! seals through the engine the same way as blocks received from peers. The node miner
+//! only mines empty blocks, transactions in the pool are not included.
+//!
+//! Executing a block credits its block reward to the beneficiary and the chain's
+//! treasury, so every block's state root commits to the reward.
 //!
 //! # P2P Block Validation
 //!
 //! Incoming blocks from peers are validated using PermiaHash PoW before import.
+//!
+//! # External Mining
+//!
+//! The `permia_getWork`/`permia_submitWork` RPC methods expose the node miner's
+//! current work to external miners.
+//!
+//! # Genesis
+//!
+//! `permia genesis export` prints a chain's genesis, and `permia_getGenesis` serves the
+//! genesis of a running node, for configuring new nodes.
+//!
+//! # Validator Mode
+//!
+//! With `--role validator` the node does not mine, not even with `--dev`. Instead it
+//! signs a finality vote with the key from `--validator.key` for every new canonical
+//! block and gossips it to peers over `pvote`. Nodes of every role count the votes of
+//! the validators listed in the genesis `permiaValidators` field.
 
 #![allow(missing_docs)]
 
+use alloy_primitives::U256;
 use clap::Parser;
-use permia_cli::PermiaChainSpecParser;
-use permia_gossip::spawn_block_announcer;
-use permia_node::{PermiaConsensusBuilder, PermiaNetworkBuilder};
+use parking_lot::RwLock;
+use permia_cli::{PermiaArgs, PermiaChainSpecParser, PermiaCommands};
+use permia_finality::{
+    spawn_finality_updater, FinalityTracker, SharedFinalityTracker, SharedValidatorSet,
+    ValidatorSet, ValidatorSigner,
+};
+use permia_gossip::{
+    install_vote_gossip, p2p_block_channel, spawn_block_announcer, spawn_vote_producer,
+    HeaviestChainRule, PermiaP2PImporter, VoteProducer,
+};
+use permia_miner::spawn_node_miner;
+use permia_node::{
+    record_import_latency, run_block_submitter, spawn_mining_driver, FinalityGuard,
+    PermiaConsensusBuilder, PermiaExecutorBuilder, PermiaGenesisApiServer, PermiaGenesisRpc,
+    PermiaMiningApiServer, PermiaMiningRpc, PermiaNetworkBuilder, PermiaStatusApiServer,
+    PermiaStatusRpc, PermiaValidatorApiServer, PermiaValidatorRpc,
+};
+use permia_payload::PermiaBuilderConfig;
+use reth_chainspec::permia_block_time_ms;
 use reth_ethereum_cli::Cli;
 use reth_node_builder::Node;
 use reth_node_ethereum::EthereumNode;
+use reth_rpc_server_types::DefaultRpcModuleValidator;
+use std::sync::Arc;
 use tracing::info;
 
+/// Valid P2P blocks buffered for import
+const P2P_IMPORT_BUFFER: usize = 64;
+
+/// Reth's command line with the Permia chains, arguments and commands
+type PermiaCli = Cli<PermiaChainSpecParser, PermiaArgs, DefaultRpcModuleValidator, PermiaCommands>;
+
 fn main() {
     // Install signal handlers
     reth_cli_util::sigsegv_handler::install();
-    
+
     // Enable backtraces
     if std::env::var_os("RUST_BACKTRACE").is_none() {
         unsafe { std::env::set_var("RUST_BACKTRACE", "1") };
     }
-    
+
     // Run the Permia node using Reth's CLI infrastructure
-    if let Err(err) =
-        Cli::<PermiaChainSpecParser, ()>::parse().run(async move |builder, _| {
-            info!(target: "permia::cli", "Launching Permia node with PermiaHash PoW");
-            
-            // Log consensus info
-            let consensus = PermiaConsensusBuilder::new().build_standalone();
-            let min_difficulty = consensus.min_difficulty();
-            info!(
-                target: "permia::cli",
-                min_difficulty = %min_difficulty,
-                "PermiaHash consensus initialized"
-            );
-            
-            // Use EthereumNode as base with Permia's custom network builder
-            // - PermiaNetworkBuilder validates incoming P2P blocks with PermiaHash PoW
-            // - LocalMiner is enabled in dev mode (--dev flag)
-            // - Blocks are submitted via Engine API
-            let handle = builder
-                .with_types::<EthereumNode>()
-                .with_components(
-                    EthereumNode::components()
-                        .network(PermiaNetworkBuilder::default())
-                )
-                .with_add_ons(EthereumNode::default().add_ons())
-                .launch_with_debug_capabilities()
-                .await?;
-            
-            info!(
-                target: "permia::cli",
-                chain_id = %handle.node.chain_spec().chain.id(),
-                "Permia node running with PermiaHash P2P validation"
-            );
-            
-            // Spawn block announcer to broadcast mined blocks to peers
-            let network = handle.node.network.clone();
-            let provider = handle.node.provider.clone();
-            handle.node.task_executor.spawn_critical("permia-block-announcer", Box::pin(async move {
+    let cli = PermiaCli::parse();
+    if let Err(err) = cli.run(async move |mut builder, args| {
+        info!(target: "permia::cli", "Launching Permia node with PermiaHash PoW");
+
+        // Only miners produce blocks, validators don't pace blocks with --dev
+        args.configure_node(builder.config_mut());
+        args.validate_chain(builder.config().chain.chain.id())?;
+
+        // Consensus the node validates blocks with, and the node miner mines at
+        let mut consensus_builder = PermiaConsensusBuilder::new();
+        if args.regtest {
+            consensus_builder =
+                consensus_builder.with_fixed_difficulty(U256::from(args.regtest_difficulty));
+        }
+        let consensus = consensus_builder.build_with_chain_spec(builder.config().chain.clone());
+        let min_difficulty = consensus.min_difficulty();
+        info!(
+            target: "permia::cli",
+            min_difficulty = %min_difficulty,
+            regtest = args.regtest,
+            "PermiaHash consensus initialized"
+        );
+
+        // Finality state, reported over permia_status and fed by votes in validator mode
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+
+        // Node miner whose work is served over permia_getWork/permia_submitWork
+        let mut miner = None;
+        if args.role.mines() {
+            let mut builder_config = PermiaBuilderConfig::from_chain_spec(&builder.config().chain);
+            if let Some(iterations) = args.max_mining_iterations {
+                builder_config = builder_config.with_max_mining_iterations(iterations);
+            }
+            let mut miner_config = builder_config
+                .node_miner_config()
+                .with_hash_config(consensus.hash_config().clone())
+                .with_finality(Arc::clone(&tracker));
+            let dev = &builder.config().dev;
+            if let Some(interval) = dev.block_time.filter(|_| dev.dev) {
+                miner_config = miner_config.with_block_interval(interval);
+            }
+            miner_config.check_memory()?;
+            miner = Some(spawn_node_miner(miner_config));
+        }
+        let mining_rpc = miner.as_ref().map(|(handle, _)| PermiaMiningRpc::new(handle.clone()));
+        let genesis_rpc = PermiaGenesisRpc::new(builder.config().chain.clone());
+
+        // Validators listed in the genesis, whose votes finalize blocks
+        let validator_set: SharedValidatorSet =
+            Arc::new(RwLock::new(ValidatorSet::from_genesis(&builder.config().chain.genesis)?));
+        info!(
+            target: "permia::cli",
+            validators = validator_set.read().len(),
+            "Loaded genesis validator set"
+        );
+        let status_tracker = Arc::clone(&tracker);
+        let status_validators = Arc::clone(&validator_set);
+        let validator_rpc = PermiaValidatorRpc::new(Arc::clone(&validator_set));
+        let status_miner = miner.as_ref().map(|(handle, _)| handle.clone());
+        let status_consensus = Arc::clone(&consensus);
+        let target_block_time = permia_block_time_ms(&builder.config().chain);
+
+        // Vote signer, loaded up front so a bad key fails before the node starts
+        let signer = match &args.validator_key {
+            Some(path) if args.role.votes() => Some(ValidatorSigner::from_file(path)?),
+            _ => None,
+        };
+
+        // Valid P2P blocks, imported once the engine is up
+        let (import_tx, import_rx) = p2p_block_channel(P2P_IMPORT_BUFFER);
+        let mined_import_tx = import_tx.clone();
+        let network_builder = PermiaNetworkBuilder::default()
+            .with_importer(import_tx)
+            .with_hash_config(consensus.hash_config().clone());
+
+        // Use EthereumNode as base with Permia's custom network, executor and consensus
+        // builders
+        // - PermiaNetworkBuilder validates incoming P2P blocks with PermiaHash PoW
+        // - PermiaExecutorBuilder credits the block reward when executing blocks
+        // - PermiaConsensusBuilder validates every block the engine imports with PermiaHash PoW,
+        //   instead of the beacon consensus
+        // - Blocks are submitted via Engine API
+        let handle = builder
+            .with_types::<EthereumNode>()
+            .with_components(
+                EthereumNode::components()
+                    .network(network_builder)
+                    .executor(PermiaExecutorBuilder::default())
+                    .consensus(consensus_builder),
+            )
+            .with_add_ons(EthereumNode::default().add_ons())
+            .extend_rpc_modules(move |ctx| {
+                ctx.modules.merge_configured(genesis_rpc.into_rpc())?;
+                let mut status_rpc =
+                    PermiaStatusRpc::new(ctx.provider().clone(), status_tracker, status_validators)
+                        .with_target_block_time(target_block_time)
+                        .with_consensus(status_consensus);
+                if let Some(miner) = status_miner {
+                    status_rpc = status_rpc.with_miner(miner);
+                }
+                ctx.modules.merge_configured(status_rpc.into_rpc())?;
+                ctx.modules.merge_configured(validator_rpc.into_rpc())?;
+                if let Some(mining_rpc) = mining_rpc {
+                    ctx.modules.merge_configured(mining_rpc.into_rpc())?;
+                }
+                Ok(())
+            })
+            // Not launched with debug capabilities, whose dev LocalMiner builds blocks
+            // without proof of work
+            .launch()
+            .await?;
+
+        info!(
+            target: "permia::cli",
+            chain_id = %handle.node.chain_spec().chain.id(),
+            role = ?args.role,
+            "Permia node running with PermiaHash P2P validation"
+        );
+
+        // Import valid P2P blocks through the engine, following the heaviest chain
+        // but never reverting finalized blocks
+        let guard = FinalityGuard::new(Arc::clone(&tracker), Arc::clone(&validator_set));
+        let fork_choice =
+            HeaviestChainRule::new(handle.node.provider.clone()).with_reorg_guard(guard);
+        let importer = PermiaP2PImporter::new(
+            import_rx,
+            handle.node.add_ons_handle.beacon_engine_handle.clone(),
+        )
+        .with_fork_choice(fork_choice);
+        handle.node.task_executor.spawn_critical("permia-p2p-importer", Box::pin(importer.run()));
+
+        // Observe the import latency of the canonical chain for permia_status
+        let import_latency =
+            record_import_latency(handle.node.provider.clone(), Arc::clone(&consensus));
+        handle.node.task_executor.spawn_critical("permia-import-latency", Box::pin(import_latency));
+
+        // Spawn block announcer to broadcast mined blocks to peers
+        let network = handle.node.network.clone();
+        let provider = handle.node.provider.clone();
+        handle.node.task_executor.spawn_critical(
+            "permia-block-announcer",
+            Box::pin(async move {
                 info!(target: "permia::cli", "Starting block announcer for P2P propagation");
                 spawn_block_announcer(network, provider).await;
-            }));
-            
-            handle.wait_for_node_exit().await
-        })
-    {
+            }),
+        );
+
+        // Mine on the canonical tip, and import the blocks sealed by the node miner,
+        // including externally submitted work, like blocks from peers
+        if let Some((miner, mined_rx)) = miner {
+            let executor = &handle.node.task_executor;
+            executor.spawn_critical(
+                "permia-mining-driver",
+                Box::pin(spawn_mining_driver(
+                    handle.node.provider.clone(),
+                    handle.node.evm_config.clone(),
+                    miner,
+                    Arc::clone(&consensus),
+                )),
+            );
+            executor.spawn_critical(
+                "permia-block-submitter",
+                Box::pin(run_block_submitter(
+                    handle.node.provider.clone(),
+                    handle.node.chain_spec(),
+                    mined_rx,
+                    mined_import_tx,
+                )),
+            );
+        }
+
+        // Track finality of the canonical chain
+        handle.node.task_executor.spawn_critical(
+            "permia-finality-updater",
+            Box::pin(spawn_finality_updater(handle.node.provider.clone(), Arc::clone(&tracker))),
+        );
+
+        // Count the votes gossiped by validators, whatever the role of this node
+        let (broadcaster, receiver) = install_vote_gossip(
+            &handle.node.network,
+            Arc::clone(&tracker),
+            Arc::clone(&validator_set),
+        );
+        let executor = &handle.node.task_executor;
+        executor.spawn_critical("permia-vote-receiver", Box::pin(receiver.run()));
+
+        // Vote on canonical blocks and gossip the votes to peers
+        if let Some(signer) = signer {
+            info!(target: "permia::cli", validator = %signer.address(), "Starting validator");
+
+            let producer =
+                VoteProducer::new(signer, broadcaster, Arc::clone(&tracker), validator_set);
+            executor.spawn_critical(
+                "permia-vote-producer",
+                Box::pin(spawn_vote_producer(handle.node.provider.clone(), producer)),
+            );
+        }
+
+        handle.wait_for_node_exit().await
+    }) {
         eprintln!("Error: {err:?}");
         std::process::exit(1);
     }
@@ -2,9 +2,12 @@
 //!
 //! Provides CPU mining functionality for the Permia network.
 
-use alloy_primitives::{Address, B256, U256};
+use alloy_primitives::{Address, Bytes, B256, U256};
 use clap::Parser;
-use permia_miner::{BlockTemplate, MiningConfig, MiningWorker};
+use permia_miner::{
+    current_timestamp, validate_extra_data, BatchTuning, BlockTemplate, MiningConfig,
+    MiningWorker, DEFAULT_EXTRA_DATA,
+};
 use std::time::Duration;
 use tracing::info;
 
@@ -26,6 +29,16 @@ pub struct MineArgs {
     /// Maximum blocks to mine (0 = unlimited)
     #[arg(long, default_value = "1")]
     pub blocks: u64,
+
+    /// Extra data stamped into mined headers, e.g. a pool tag (max 32 bytes)
+    #[arg(long, default_value = "permia", value_parser = parse_extra_data)]
+    pub extra_data: Bytes,
+}
+
+/// Parse extra data from its text form, rejecting tags that don't fit in a header
+fn parse_extra_data(value: &str) -> Result<Bytes, String> {
+    validate_extra_data(value.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(Bytes::copy_from_slice(value.as_bytes()))
 }
 
 impl MineArgs {
@@ -48,7 +61,9 @@ impl MineArgs {
         let config = MiningConfig {
             threads,
             batch_size: 10_000,
+            batch_tuning: Some(BatchTuning::default()),
             max_duration: Some(Duration::from_secs(60)),
+            ..MiningConfig::default()
         };
 
         let worker = MiningWorker::new(config);
@@ -68,18 +83,14 @@ impl MineArgs {
             }
 
             // Create block template
-            let timestamp = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64;
-
-            let template = BlockTemplate::new(
+            let mut template = BlockTemplate::new(
                 parent_hash,
                 block_number,
-                timestamp,
+                current_timestamp(),
                 self.miner,
                 U256::from(self.difficulty),
             );
+            template.extra_data = self.extra_data.clone();
 
             info!(
                 target: "permia::mine",
@@ -125,6 +136,7 @@ pub fn demo_mine() -> eyre::Result<()> {
         threads: 1,
         difficulty: 100, // Very easy for demo
         blocks: 3,
+        extra_data: Bytes::from_static(DEFAULT_EXTRA_DATA),
     };
     args.run()
 }
@@ -141,10 +153,21 @@ mod tests {
             threads: 1,
             difficulty: 1, // Minimum difficulty
             blocks: 1,
+            extra_data: Bytes::from_static(DEFAULT_EXTRA_DATA),
         };
         
         // This should complete quickly with difficulty=1
         let result = args.run();
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_extra_data_arg() {
+        let args = MineArgs::try_parse_from(["mine", "--extra-data", "pool-7"]).unwrap();
+        assert_eq!(args.extra_data, Bytes::from_static(b"pool-7"));
+        assert_eq!(MineArgs::try_parse_from(["mine"]).unwrap().extra_data, Bytes::from_static(DEFAULT_EXTRA_DATA));
+
+        // Oversized tags are rejected when parsing the arguments
+        assert!(MineArgs::try_parse_from(["mine", "--extra-data", &"x".repeat(33)]).is_err());
+    }
 }
@@ -20,8 +20,13 @@ mod api;
 /// Permia chain specifications
 pub mod permia;
 pub use permia::{
-    permia_chain_spec, permia_chain_spec_by_name, PERMIA_DEV, PERMIA_MAINNET, PERMIA_TESTNET,
-    PERMIA_DEVNET_CHAIN_ID, PERMIA_MAINNET_CHAIN_ID, PERMIA_TESTNET_CHAIN_ID, PERMIA_BLOCK_TIME_MS,
+    permia_block_time_ms, permia_chain_spec, permia_chain_spec_by_name,
+    permia_chain_spec_from_genesis, permia_dag_version, permia_max_service_proofs,
+    permia_treasury_address, PERMIA_BLOCK_TIME_FIELD, PERMIA_BLOCK_TIME_MS,
+    PERMIA_DAG_VERSION_FIELD, PERMIA_DEV, PERMIA_DEVNET_CHAIN_ID, PERMIA_MAINNET,
+    PERMIA_MAINNET_CHAIN_ID, PERMIA_MAINNET_GENESIS_TIMESTAMP, PERMIA_MAX_SERVICE_PROOFS_FIELD,
+    PERMIA_TESTNET, PERMIA_TESTNET_CHAIN_ID, PERMIA_TESTNET_GENESIS_TIMESTAMP,
+    PERMIA_TREASURY_FIELD,
 };
 /// The chain info module.
 mod info;
@@ -8,6 +8,8 @@
 use crate::{make_genesis_header, BaseFeeParams, BaseFeeParamsKind, ChainSpec};
 use alloc::sync::Arc;
 use alloy_chains::Chain;
+use alloy_genesis::Genesis;
+use alloy_primitives::Address;
 use reth_ethereum_forks::DEV_HARDFORKS;
 use reth_primitives_traits::{sync::LazyLock, SealedHeader};
 
@@ -20,62 +22,114 @@ pub const PERMIA_TESTNET_CHAIN_ID: u64 = 42070;
 /// Permia devnet chain ID
 pub const PERMIA_DEVNET_CHAIN_ID: u64 = 42071;
 
-/// Target block time in milliseconds
+/// Permia mainnet genesis timestamp: 2026-03-01 00:00:00 UTC
+///
+/// Fixed so every node derives the same genesis hash. Devnet keeps a zero timestamp.
+pub const PERMIA_MAINNET_GENESIS_TIMESTAMP: u64 = 1_772_323_200;
+
+/// Permia testnet genesis timestamp: 2026-01-15 00:00:00 UTC
+pub const PERMIA_TESTNET_GENESIS_TIMESTAMP: u64 = 1_768_435_200;
+
+/// Default target block time in milliseconds
 pub const PERMIA_BLOCK_TIME_MS: u64 = 400;
 
+/// Genesis config field overriding the target block time, in milliseconds
+pub const PERMIA_BLOCK_TIME_FIELD: &str = "permiaBlockTimeMs";
+
+/// Genesis config field overriding the maximum number of service proofs in a block
+pub const PERMIA_MAX_SERVICE_PROOFS_FIELD: &str = "permiaMaxServiceProofs";
+
+/// Genesis config field with the treasury address
+pub const PERMIA_TREASURY_FIELD: &str = "permiaTreasury";
+
+/// Genesis config field selecting the PermiaHash DAG version
+pub const PERMIA_DAG_VERSION_FIELD: &str = "permiaDagVersion";
+
 /// Permia devnet specification
 pub static PERMIA_DEV: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
     let genesis = serde_json::from_str(include_str!("../res/genesis/permia-dev.json"))
         .expect("Can't deserialize Permia dev genesis json");
-    let hardforks = DEV_HARDFORKS.clone();
-    ChainSpec {
-        chain: Chain::from_id(PERMIA_DEVNET_CHAIN_ID),
-        genesis_header: SealedHeader::seal_slow(make_genesis_header(&genesis, &hardforks)),
-        genesis,
-        paris_block_and_final_difficulty: None, // Permia uses PoW, not PoS
-        hardforks,
-        base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
-        deposit_contract: None,
-        ..Default::default()
-    }
-    .into()
+    permia_chain_spec_from_genesis(PERMIA_DEVNET_CHAIN_ID, genesis).into()
 });
 
 /// Permia testnet specification
 pub static PERMIA_TESTNET: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
     let genesis = serde_json::from_str(include_str!("../res/genesis/permia-testnet.json"))
         .expect("Can't deserialize Permia testnet genesis json");
-    let hardforks = DEV_HARDFORKS.clone();
-    ChainSpec {
-        chain: Chain::from_id(PERMIA_TESTNET_CHAIN_ID),
-        genesis_header: SealedHeader::seal_slow(make_genesis_header(&genesis, &hardforks)),
-        genesis,
-        paris_block_and_final_difficulty: None,
-        hardforks,
-        base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
-        deposit_contract: None,
-        ..Default::default()
-    }
-    .into()
+    permia_chain_spec_from_genesis(PERMIA_TESTNET_CHAIN_ID, genesis).into()
 });
 
 /// Permia mainnet specification
 pub static PERMIA_MAINNET: LazyLock<Arc<ChainSpec>> = LazyLock::new(|| {
     let genesis = serde_json::from_str(include_str!("../res/genesis/permia-mainnet.json"))
         .expect("Can't deserialize Permia mainnet genesis json");
+    permia_chain_spec_from_genesis(PERMIA_MAINNET_CHAIN_ID, genesis).into()
+});
+
+/// Build a Permia chain spec from a genesis
+///
+/// All Permia networks share the same hardforks and base fee parameters, they only
+/// differ in chain ID and genesis.
+pub fn permia_chain_spec_from_genesis(chain_id: u64, genesis: Genesis) -> ChainSpec {
     let hardforks = DEV_HARDFORKS.clone();
     ChainSpec {
-        chain: Chain::from_id(PERMIA_MAINNET_CHAIN_ID),
+        chain: Chain::from_id(chain_id),
         genesis_header: SealedHeader::seal_slow(make_genesis_header(&genesis, &hardforks)),
         genesis,
-        paris_block_and_final_difficulty: None,
+        paris_block_and_final_difficulty: None, // Permia uses PoW, not PoS
         hardforks,
         base_fee_params: BaseFeeParamsKind::Constant(BaseFeeParams::ethereum()),
         deposit_contract: None,
         ..Default::default()
     }
-    .into()
-});
+}
+
+/// Target block time of a Permia chain in milliseconds
+///
+/// Read from the [`PERMIA_BLOCK_TIME_FIELD`] genesis config field, defaulting to
+/// [`PERMIA_BLOCK_TIME_MS`]. This is the single source of the block time for
+/// difficulty retargeting, the miner and the payload builder.
+pub fn permia_block_time_ms(spec: &ChainSpec) -> u64 {
+    spec.genesis
+        .config
+        .extra_fields
+        .get(PERMIA_BLOCK_TIME_FIELD)
+        .and_then(|value| value.as_u64())
+        .filter(|ms| *ms > 0)
+        .unwrap_or(PERMIA_BLOCK_TIME_MS)
+}
+
+/// Maximum number of service proofs in a block of a Permia chain, if overridden
+///
+/// Read from the [`PERMIA_MAX_SERVICE_PROOFS_FIELD`] genesis config field. Chains
+/// without it, or with a limit of zero, use the protocol default of
+/// `permia_services::MAX_SERVICE_PROOFS_PER_BLOCK`. Limits beyond `usize` are clamped.
+pub fn permia_max_service_proofs(spec: &ChainSpec) -> Option<usize> {
+    spec.genesis
+        .config
+        .extra_fields
+        .get(PERMIA_MAX_SERVICE_PROOFS_FIELD)
+        .and_then(|value| value.as_u64())
+        .filter(|max| *max > 0)
+        .map(|max| usize::try_from(max).unwrap_or(usize::MAX))
+}
+
+/// PermiaHash DAG version of a Permia chain, if selected
+///
+/// Read from the [`PERMIA_DAG_VERSION_FIELD`] genesis config field. Chains without it use
+/// the first version. The version is not checked here, see
+/// `permia_consensus::pow::DagVersion` for the known ones.
+pub fn permia_dag_version(spec: &ChainSpec) -> Option<u64> {
+    spec.genesis.config.extra_fields.get(PERMIA_DAG_VERSION_FIELD).and_then(|value| value.as_u64())
+}
+
+/// Treasury address of a Permia chain, if it has a treasury
+///
+/// Read from the [`PERMIA_TREASURY_FIELD`] genesis config field. Chains without it, like
+/// the testnet, pay no share of the block reward to a treasury.
+pub fn permia_treasury_address(spec: &ChainSpec) -> Option<Address> {
+    spec.genesis.config.extra_fields.get_deserialized(PERMIA_TREASURY_FIELD).and_then(Result::ok)
+}
 
 /// Get Permia chain spec by chain ID
 pub fn permia_chain_spec(chain_id: u64) -> Option<Arc<ChainSpec>> {
@@ -116,6 +170,58 @@ mod tests {
         assert_eq!(PERMIA_MAINNET.chain.id(), PERMIA_MAINNET_CHAIN_ID);
     }
 
+    #[test]
+    fn test_genesis_timestamps() {
+        assert_ne!(PERMIA_MAINNET_GENESIS_TIMESTAMP, 0);
+        assert_eq!(PERMIA_MAINNET.genesis.timestamp, PERMIA_MAINNET_GENESIS_TIMESTAMP);
+        assert_eq!(PERMIA_MAINNET.genesis_header().timestamp, PERMIA_MAINNET_GENESIS_TIMESTAMP);
+        assert_eq!(PERMIA_TESTNET.genesis.timestamp, PERMIA_TESTNET_GENESIS_TIMESTAMP);
+        assert_eq!(PERMIA_DEV.genesis.timestamp, 0);
+    }
+
+    #[test]
+    fn test_block_time() {
+        assert_eq!(permia_block_time_ms(&PERMIA_MAINNET), PERMIA_BLOCK_TIME_MS);
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.extra_fields.insert(PERMIA_BLOCK_TIME_FIELD.into(), 1000.into());
+        let spec = permia_chain_spec_from_genesis(PERMIA_DEVNET_CHAIN_ID, genesis);
+        assert_eq!(permia_block_time_ms(&spec), 1000);
+    }
+
+    #[test]
+    fn test_max_service_proofs() {
+        assert_eq!(permia_max_service_proofs(&PERMIA_MAINNET), None);
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.extra_fields.insert(PERMIA_MAX_SERVICE_PROOFS_FIELD.into(), 16.into());
+        let spec = permia_chain_spec_from_genesis(PERMIA_DEVNET_CHAIN_ID, genesis.clone());
+        assert_eq!(permia_max_service_proofs(&spec), Some(16));
+
+        // A limit of zero would reject every block with proofs, the default applies
+        genesis.config.extra_fields.insert(PERMIA_MAX_SERVICE_PROOFS_FIELD.into(), 0.into());
+        let spec = permia_chain_spec_from_genesis(PERMIA_DEVNET_CHAIN_ID, genesis);
+        assert_eq!(permia_max_service_proofs(&spec), None);
+    }
+
+    #[test]
+    fn test_dag_version() {
+        assert_eq!(permia_dag_version(&PERMIA_MAINNET), None);
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.extra_fields.insert(PERMIA_DAG_VERSION_FIELD.into(), 2.into());
+        let spec = permia_chain_spec_from_genesis(PERMIA_DEVNET_CHAIN_ID, genesis);
+        assert_eq!(permia_dag_version(&spec), Some(2));
+    }
+
+    #[test]
+    fn test_treasury_address() {
+        let treasury = Address::with_last_byte(1);
+        assert_eq!(permia_treasury_address(&PERMIA_MAINNET), Some(treasury));
+        assert_eq!(permia_treasury_address(&PERMIA_DEV), Some(treasury));
+        assert_eq!(permia_treasury_address(&PERMIA_TESTNET), None);
+    }
+
     #[test]
     fn test_chain_spec_lookup() {
         assert!(permia_chain_spec(42069).is_some());
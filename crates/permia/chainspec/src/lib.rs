@@ -4,11 +4,21 @@
 //! - Mainnet (chain ID: 42069)
 //! - Testnet (chain ID: 42070)
 //! - Devnet (chain ID: 42071)
+//!
+//! The genesis of every network is taken from the reth chain specs
+//! (`reth_chainspec::PERMIA_*`), so the two definitions can't drift apart.
+//! [`PermiaChainSpec::to_reth_chain_spec`] and [`PermiaChainSpec::from_reth`]
+//! convert between them.
 
-use alloy_genesis::{ChainConfig, Genesis};
-use alloy_primitives::{address, b256, Address, B256, U256};
+use alloy_genesis::Genesis;
+use alloy_primitives::{address, Address, B256, U256};
 use once_cell::sync::Lazy;
-use std::collections::BTreeMap;
+use reth_chainspec::{
+    permia_block_time_ms, permia_chain_spec_from_genesis, permia_treasury_address, ChainSpec,
+    PERMIA_BLOCK_TIME_FIELD, PERMIA_TREASURY_FIELD,
+};
+use std::sync::Arc;
+use thiserror::Error;
 
 /// Permia mainnet chain ID
 pub const PERMIA_MAINNET_CHAIN_ID: u64 = 42069;
@@ -19,8 +29,8 @@ pub const PERMIA_TESTNET_CHAIN_ID: u64 = 42070;
 /// Permia devnet chain ID
 pub const PERMIA_DEVNET_CHAIN_ID: u64 = 42071;
 
-/// Target block time in milliseconds (400ms = 2.5 blocks/second)
-pub const BLOCK_TIME_MS: u64 = 400;
+/// Default target block time in milliseconds (400ms = 2.5 blocks/second)
+pub const BLOCK_TIME_MS: u64 = reth_chainspec::PERMIA_BLOCK_TIME_MS;
 
 /// Target block time as Duration
 pub const BLOCK_TIME: std::time::Duration = std::time::Duration::from_millis(BLOCK_TIME_MS);
@@ -28,48 +38,67 @@ pub const BLOCK_TIME: std::time::Duration = std::time::Duration::from_millis(BLO
 /// Maximum block gas limit
 pub const MAX_BLOCK_GAS: u64 = 60_000_000;
 
-/// Treasury address
+/// Treasury address of mainnet and devnet
 pub const TREASURY_ADDRESS: Address = address!("0000000000000000000000000000000000000001");
 
 /// PermiaSwap POL address
 pub const PERMIASWAP_POL_ADDRESS: Address = address!("0000000000000000000000000000000000000002");
 
+/// Genesis balance of [`TREASURY_ADDRESS`] in wei
+pub const TREASURY_ALLOCATION: u128 = 100_000_000_000_000_000_000_000_000; // 100M MIA
+
+/// Genesis balance of [`PERMIASWAP_POL_ADDRESS`] in wei
+pub const PERMIASWAP_POL_ALLOCATION: u128 = 50_000_000_000_000_000_000_000_000; // 50M MIA
+
 /// Permia mainnet genesis hash
-pub static PERMIA_MAINNET_GENESIS_HASH: Lazy<B256> = Lazy::new(|| {
-    b256!("0000000000000000000000000000000000000000000000000000000000000000")
-});
+///
+/// Computed from the sealed mainnet genesis header on first access.
+pub static PERMIA_MAINNET_GENESIS_HASH: Lazy<B256> =
+    Lazy::new(|| reth_chainspec::PERMIA_MAINNET.genesis_hash());
 
 /// Permia mainnet chain spec
 pub static PERMIA_MAINNET: Lazy<PermiaChainSpec> = Lazy::new(|| {
-    PermiaChainSpec {
+    let spec = PermiaChainSpec {
         chain_id: PERMIA_MAINNET_CHAIN_ID,
         name: "permia-mainnet".to_string(),
-        genesis: permia_mainnet_genesis(),
+        genesis: reth_chainspec::PERMIA_MAINNET.genesis.clone(),
         block_time_ms: BLOCK_TIME_MS,
         max_block_gas: MAX_BLOCK_GAS,
-    }
+        treasury_address: permia_treasury_address(&reth_chainspec::PERMIA_MAINNET),
+        pol_address: Some(PERMIASWAP_POL_ADDRESS),
+    };
+    spec.validate_genesis_alloc().expect("permia-mainnet genesis funds the treasury and POL");
+    spec
 });
 
 /// Permia testnet chain spec
 pub static PERMIA_TESTNET: Lazy<PermiaChainSpec> = Lazy::new(|| {
-    PermiaChainSpec {
+    let spec = PermiaChainSpec {
         chain_id: PERMIA_TESTNET_CHAIN_ID,
         name: "permia-testnet".to_string(),
-        genesis: permia_testnet_genesis(),
+        genesis: reth_chainspec::PERMIA_TESTNET.genesis.clone(),
         block_time_ms: BLOCK_TIME_MS,
         max_block_gas: MAX_BLOCK_GAS,
-    }
+        treasury_address: permia_treasury_address(&reth_chainspec::PERMIA_TESTNET),
+        pol_address: None,
+    };
+    spec.validate_genesis_alloc().expect("permia-testnet genesis funds its treasury");
+    spec
 });
 
 /// Permia devnet chain spec (for local development)
 pub static PERMIA_DEVNET: Lazy<PermiaChainSpec> = Lazy::new(|| {
-    PermiaChainSpec {
+    let spec = PermiaChainSpec {
         chain_id: PERMIA_DEVNET_CHAIN_ID,
         name: "permia-dev".to_string(),
-        genesis: permia_devnet_genesis(),
+        genesis: reth_chainspec::PERMIA_DEV.genesis.clone(),
         block_time_ms: BLOCK_TIME_MS,
         max_block_gas: MAX_BLOCK_GAS,
-    }
+        treasury_address: permia_treasury_address(&reth_chainspec::PERMIA_DEV),
+        pol_address: Some(PERMIASWAP_POL_ADDRESS),
+    };
+    spec.validate_genesis_alloc().expect("permia-dev genesis funds the treasury and POL");
+    spec
 });
 
 /// Permia chain specification
@@ -85,6 +114,26 @@ pub struct PermiaChainSpec {
     pub block_time_ms: u64,
     /// Maximum block gas
     pub max_block_gas: u64,
+    /// Treasury address, funded with [`TREASURY_ALLOCATION`] at genesis and paid a share
+    /// of every block reward
+    pub treasury_address: Option<Address>,
+    /// PermiaSwap POL address, funded with [`PERMIASWAP_POL_ALLOCATION`] at genesis
+    pub pol_address: Option<Address>,
+}
+
+/// Chain spec errors
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ChainSpecError {
+    /// A genesis allocation does not match the documented amount
+    #[error("genesis allocates {actual} wei to {address}, expected {expected}")]
+    GenesisAllocMismatch {
+        /// Allocated address
+        address: Address,
+        /// Documented balance
+        expected: U256,
+        /// Balance in the genesis alloc
+        actual: U256,
+    },
 }
 
 impl PermiaChainSpec {
@@ -107,129 +156,74 @@ impl PermiaChainSpec {
             _ => None,
         }
     }
-}
-
-/// Create mainnet genesis
-fn permia_mainnet_genesis() -> Genesis {
-    let mut alloc = BTreeMap::new();
-    
-    // Treasury allocation (10% of supply = 100M MIA)
-    alloc.insert(
-        TREASURY_ADDRESS,
-        alloy_genesis::GenesisAccount {
-            balance: U256::from(100_000_000u64) * U256::from(10u64).pow(U256::from(18u64)),
-            ..Default::default()
-        },
-    );
-    
-    // PermiaSwap POL allocation (5% = 50M MIA)
-    alloc.insert(
-        PERMIASWAP_POL_ADDRESS,
-        alloy_genesis::GenesisAccount {
-            balance: U256::from(50_000_000u64) * U256::from(10u64).pow(U256::from(18u64)),
-            ..Default::default()
-        },
-    );
-    
-    Genesis {
-        config: ChainConfig {
-            chain_id: PERMIA_MAINNET_CHAIN_ID,
-            homestead_block: Some(0),
-            eip150_block: Some(0),
-            eip155_block: Some(0),
-            eip158_block: Some(0),
-            byzantium_block: Some(0),
-            constantinople_block: Some(0),
-            petersburg_block: Some(0),
-            istanbul_block: Some(0),
-            berlin_block: Some(0),
-            london_block: Some(0),
-            ..Default::default()
-        },
-        nonce: 0x42069,
-        timestamp: 0,
-        gas_limit: MAX_BLOCK_GAS,
-        difficulty: U256::from(1u64 << 20),
-        alloc,
-        ..Default::default()
-    }
-}
-
-/// Create testnet genesis
-fn permia_testnet_genesis() -> Genesis {
-    let mut alloc = BTreeMap::new();
     
-    // Faucet allocation for testnet
-    let faucet = address!("0000000000000000000000000000000000001000");
-    alloc.insert(
-        faucet,
-        alloy_genesis::GenesisAccount {
-            balance: U256::from(1_000_000_000u64) * U256::from(10u64).pow(U256::from(18u64)),
-            ..Default::default()
-        },
-    );
-    
-    Genesis {
-        config: ChainConfig {
-            chain_id: PERMIA_TESTNET_CHAIN_ID,
-            homestead_block: Some(0),
-            eip150_block: Some(0),
-            eip155_block: Some(0),
-            eip158_block: Some(0),
-            byzantium_block: Some(0),
-            constantinople_block: Some(0),
-            petersburg_block: Some(0),
-            istanbul_block: Some(0),
-            berlin_block: Some(0),
-            london_block: Some(0),
-            ..Default::default()
-        },
-        nonce: 0x42070,
-        timestamp: 0,
-        gas_limit: MAX_BLOCK_GAS,
-        difficulty: U256::from(1u64 << 16), // Lower difficulty for testnet
-        alloc,
-        ..Default::default()
+    /// Check that the genesis alloc funds the treasury and POL addresses
+    ///
+    /// Networks without a treasury or POL address skip the respective check.
+    pub fn validate_genesis_alloc(&self) -> Result<(), ChainSpecError> {
+        let allocations = [
+            (self.treasury_address, TREASURY_ALLOCATION),
+            (self.pol_address, PERMIASWAP_POL_ALLOCATION),
+        ];
+        
+        for (address, expected) in allocations {
+            let Some(address) = address else { continue };
+            let expected = U256::from(expected);
+            let actual = self.genesis.alloc.get(&address).map(|account| account.balance).unwrap_or_default();
+            if actual != expected {
+                return Err(ChainSpecError::GenesisAllocMismatch { address, expected, actual });
+            }
+        }
+        
+        Ok(())
     }
-}
-
-/// Create devnet genesis (for local development)
-fn permia_devnet_genesis() -> Genesis {
-    let mut alloc = BTreeMap::new();
     
-    // Dev accounts with plenty of funds
-    for i in 1..=10 {
-        let addr = Address::from_word(B256::from(U256::from(i)));
-        alloc.insert(
-            addr,
-            alloy_genesis::GenesisAccount {
-                balance: U256::from(1_000_000u64) * U256::from(10u64).pow(U256::from(18u64)),
-                ..Default::default()
-            },
-        );
+    /// Convert to a reth [`ChainSpec`]
+    ///
+    /// The genesis gas limit and chain ID are taken from `max_block_gas` and `chain_id`,
+    /// and `block_time_ms` and `treasury_address` are recorded in the genesis config.
+    pub fn to_reth_chain_spec(&self) -> Arc<ChainSpec> {
+        let mut genesis = self.genesis.clone();
+        genesis.config.chain_id = self.chain_id;
+        genesis.gas_limit = self.max_block_gas;
+        let fields = &mut genesis.config.extra_fields;
+        fields.insert(PERMIA_BLOCK_TIME_FIELD.to_string(), self.block_time_ms.into());
+        match self.treasury_address {
+            Some(treasury) => {
+                fields.insert(PERMIA_TREASURY_FIELD.to_string(), treasury.to_string().into());
+            }
+            None => {
+                fields.remove(PERMIA_TREASURY_FIELD);
+            }
+        }
+        
+        Arc::new(permia_chain_spec_from_genesis(self.chain_id, genesis))
     }
     
-    Genesis {
-        config: ChainConfig {
-            chain_id: PERMIA_DEVNET_CHAIN_ID,
-            homestead_block: Some(0),
-            eip150_block: Some(0),
-            eip155_block: Some(0),
-            eip158_block: Some(0),
-            byzantium_block: Some(0),
-            constantinople_block: Some(0),
-            petersburg_block: Some(0),
-            istanbul_block: Some(0),
-            berlin_block: Some(0),
-            london_block: Some(0),
-            ..Default::default()
-        },
-        nonce: 0x42071,
-        timestamp: 0,
-        gas_limit: MAX_BLOCK_GAS,
-        difficulty: U256::from(1u64 << 10), // Very low difficulty for dev
-        alloc,
-        ..Default::default()
+    /// Create from a reth [`ChainSpec`]
+    ///
+    /// The treasury address is read from the genesis config. Known Permia networks keep
+    /// their name and POL address, others are named `permia-<chain id>` and have no POL.
+    /// Fails if the genesis doesn't fund the treasury and POL, see
+    /// [`Self::validate_genesis_alloc`].
+    pub fn from_reth(spec: &ChainSpec) -> Result<Self, ChainSpecError> {
+        let chain_id = spec.chain.id();
+        let known = Self::from_chain_id(chain_id);
+        let name = known
+            .map(|known| known.name.clone())
+            .unwrap_or_else(|| format!("permia-{chain_id}"));
+        
+        let permia = Self {
+            chain_id,
+            name,
+            genesis: spec.genesis.clone(),
+            block_time_ms: permia_block_time_ms(spec),
+            max_block_gas: spec.genesis.gas_limit,
+            treasury_address: permia_treasury_address(spec),
+            pol_address: known.and_then(|known| known.pol_address),
+        };
+        permia.validate_genesis_alloc()?;
+        Ok(permia)
     }
 }
 
@@ -248,4 +242,144 @@ mod tests {
         assert!(PermiaChainSpec::from_name("mainnet").is_some());
         assert!(PermiaChainSpec::from_chain_id(42069).is_some());
     }
+    
+    #[test]
+    fn test_mainnet_genesis_hash() {
+        let sealed = reth_chainspec::PERMIA_MAINNET.sealed_genesis_header();
+        
+        // Pinned, so an edit of the genesis that changes the header can't go unnoticed.
+        // Config fields such as `permiaTreasury` are not part of the header.
+        let expected = alloy_primitives::b256!(
+            "0be5793866cc1fe3a3bc4e476c69099c579959a45abe541ad03330d6fd57940d"
+        );
+        assert_eq!(*PERMIA_MAINNET_GENESIS_HASH, expected);
+        assert_eq!(*PERMIA_MAINNET_GENESIS_HASH, sealed.hash());
+        assert_eq!(*PERMIA_MAINNET_GENESIS_HASH, PERMIA_MAINNET.to_reth_chain_spec().genesis_hash());
+        
+        // Other networks have a different genesis
+        assert_ne!(*PERMIA_MAINNET_GENESIS_HASH, reth_chainspec::PERMIA_TESTNET.genesis_hash());
+        assert_ne!(*PERMIA_MAINNET_GENESIS_HASH, reth_chainspec::PERMIA_DEV.genesis_hash());
+    }
+    
+    #[test]
+    fn test_matches_reth_chain_specs() {
+        let pairs = [
+            (&*PERMIA_MAINNET, reth_chainspec::PERMIA_MAINNET.clone()),
+            (&*PERMIA_TESTNET, reth_chainspec::PERMIA_TESTNET.clone()),
+            (&*PERMIA_DEVNET, reth_chainspec::PERMIA_DEV.clone()),
+        ];
+        
+        for (permia, reth) in pairs {
+            let converted = permia.to_reth_chain_spec();
+            
+            assert_eq!(converted.chain.id(), reth.chain.id());
+            assert_eq!(permia.chain_id, reth.chain.id());
+            assert_eq!(converted.genesis_header().gas_limit, reth.genesis_header().gas_limit);
+            assert_eq!(permia.max_block_gas, reth.genesis_header().gas_limit);
+            assert_eq!(converted.genesis_header().difficulty, reth.genesis_header().difficulty);
+            assert_eq!(converted.genesis_hash(), reth.genesis_hash());
+        }
+    }
+    
+    #[test]
+    fn test_reth_round_trip() {
+        for reth in [
+            reth_chainspec::PERMIA_MAINNET.clone(),
+            reth_chainspec::PERMIA_TESTNET.clone(),
+            reth_chainspec::PERMIA_DEV.clone(),
+        ] {
+            let permia = PermiaChainSpec::from_reth(&reth).unwrap();
+            assert_eq!(PermiaChainSpec::from_chain_id(permia.chain_id).unwrap().name, permia.name);
+            assert_eq!(permia.to_reth_chain_spec().genesis_hash(), reth.genesis_hash());
+        }
+        
+        let mut custom = PERMIA_DEVNET.clone();
+        custom.chain_id = 4242;
+        custom.max_block_gas = 30_000_000;
+        custom.block_time_ms = 1000;
+        
+        let reth = custom.to_reth_chain_spec();
+        assert_eq!(reth.chain.id(), 4242);
+        assert_eq!(reth.genesis_header().gas_limit, 30_000_000);
+        assert_eq!(permia_block_time_ms(&reth), 1000);
+        
+        let back = PermiaChainSpec::from_reth(&reth).unwrap();
+        assert_eq!(back.name, "permia-4242");
+        assert_eq!(back.chain_id, 4242);
+        assert_eq!(back.max_block_gas, 30_000_000);
+        assert_eq!(back.block_time_ms, 1000);
+    }
+    
+    #[test]
+    fn test_mainnet_funds_treasury_and_pol() {
+        let mia = U256::from(10u64).pow(U256::from(18));
+        let alloc = &PERMIA_MAINNET.genesis.alloc;
+        
+        assert_eq!(PERMIA_MAINNET.treasury_address, Some(TREASURY_ADDRESS));
+        assert_eq!(PERMIA_MAINNET.pol_address, Some(PERMIASWAP_POL_ADDRESS));
+        assert_eq!(alloc[&TREASURY_ADDRESS].balance, U256::from(100_000_000u64) * mia);
+        assert_eq!(alloc[&PERMIASWAP_POL_ADDRESS].balance, U256::from(50_000_000u64) * mia);
+        assert_eq!(PERMIA_MAINNET.validate_genesis_alloc(), Ok(()));
+        
+        // Underfunding the POL is caught
+        let mut spec = PERMIA_MAINNET.clone();
+        spec.genesis.alloc.get_mut(&PERMIASWAP_POL_ADDRESS).unwrap().balance = mia;
+        assert_eq!(
+            spec.validate_genesis_alloc(),
+            Err(ChainSpecError::GenesisAllocMismatch {
+                address: PERMIASWAP_POL_ADDRESS,
+                expected: U256::from(PERMIASWAP_POL_ALLOCATION),
+                actual: mia,
+            })
+        );
+        
+        // As is a missing treasury
+        spec.genesis.alloc.remove(&TREASURY_ADDRESS);
+        assert_eq!(
+            spec.validate_genesis_alloc(),
+            Err(ChainSpecError::GenesisAllocMismatch {
+                address: TREASURY_ADDRESS,
+                expected: U256::from(TREASURY_ALLOCATION),
+                actual: U256::ZERO,
+            })
+        );
+        
+        // The round trip through reth keeps the addresses
+        let back = PermiaChainSpec::from_reth(&PERMIA_MAINNET.to_reth_chain_spec()).unwrap();
+        assert_eq!(back.treasury_address, Some(TREASURY_ADDRESS));
+        assert_eq!(back.validate_genesis_alloc(), Ok(()));
+        
+        // Reth chain specs with an unfunded treasury are rejected
+        assert_eq!(
+            PermiaChainSpec::from_reth(&spec.to_reth_chain_spec()).unwrap_err(),
+            ChainSpecError::GenesisAllocMismatch {
+                address: TREASURY_ADDRESS,
+                expected: U256::from(TREASURY_ALLOCATION),
+                actual: U256::ZERO,
+            }
+        );
+    }
+    
+    #[test]
+    fn test_treasury_from_genesis() {
+        assert_eq!(PERMIA_MAINNET.treasury_address, Some(TREASURY_ADDRESS));
+        assert_eq!(PERMIA_DEVNET.treasury_address, Some(TREASURY_ADDRESS));
+        assert_eq!(PERMIA_TESTNET.treasury_address, None);
+        
+        // Custom chains get the treasury recorded in their genesis
+        let treasury = Address::repeat_byte(7);
+        let mut custom = PERMIA_DEVNET.clone();
+        custom.chain_id = 7;
+        custom.treasury_address = Some(treasury);
+        let account = custom.genesis.alloc.remove(&TREASURY_ADDRESS).unwrap();
+        assert!(PermiaChainSpec::from_reth(&custom.to_reth_chain_spec()).is_err());
+        
+        custom.genesis.alloc.insert(treasury, account);
+        let back = PermiaChainSpec::from_reth(&custom.to_reth_chain_spec()).unwrap();
+        assert_eq!(back.treasury_address, Some(treasury));
+        
+        custom.treasury_address = None;
+        let back = PermiaChainSpec::from_reth(&custom.to_reth_chain_spec()).unwrap();
+        assert_eq!(back.treasury_address, None);
+    }
 }
@@ -0,0 +1,173 @@
+//! Permia node arguments
+//!
+//! Extra arguments the Permia node accepts on top of reth's `node` command.
+
+use clap::{Args, ValueEnum};
+use reth_chainspec::{PERMIA_MAINNET_CHAIN_ID, PERMIA_TESTNET_CHAIN_ID};
+use reth_node_core::node_config::NodeConfig;
+use std::path::PathBuf;
+
+/// What a node contributes to the network besides following the chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NodeRole {
+    /// Run the node miner, which mines empty blocks only, and serve work to external
+    /// miners
+    #[default]
+    Miner,
+    /// Vote on canonical blocks for finality, without mining
+    Validator,
+}
+
+impl NodeRole {
+    /// Whether the node runs the node miner
+    pub const fn mines(self) -> bool {
+        matches!(self, Self::Miner)
+    }
+
+    /// Whether the node signs and gossips finality votes
+    pub const fn votes(self) -> bool {
+        matches!(self, Self::Validator)
+    }
+}
+
+/// Default fixed difficulty in regtest mode
+pub const DEFAULT_REGTEST_DIFFICULTY: u64 = 1;
+
+/// Permia-specific node arguments
+#[derive(Debug, Clone, Default, Args)]
+pub struct PermiaArgs {
+    /// Node role
+    #[arg(long, value_enum, default_value_t = NodeRole::Miner)]
+    pub role: NodeRole,
+
+    /// File holding the hex-encoded validator signing key
+    #[arg(long = "validator.key", value_name = "PATH", required_if_eq("role", "validator"))]
+    pub validator_key: Option<PathBuf>,
+
+    /// Regtest mode: freeze the difficulty instead of adjusting it to block times
+    #[arg(long)]
+    pub regtest: bool,
+
+    /// Difficulty of every block in regtest mode
+    #[arg(
+        long = "regtest.difficulty",
+        value_name = "DIFFICULTY",
+        default_value_t = DEFAULT_REGTEST_DIFFICULTY,
+        requires = "regtest"
+    )]
+    pub regtest_difficulty: u64,
+
+    /// Nonces the node miner tries for a block before giving up on it until the next tip
+    #[arg(long = "mining.max-iterations", value_name = "ITERATIONS")]
+    pub max_mining_iterations: Option<u64>,
+}
+
+impl PermiaArgs {
+    /// Adjust the node config to the node role
+    ///
+    /// Only miners produce blocks, so other roles turn off `--dev` block production and
+    /// follow the blocks of the network instead.
+    pub fn configure_node<ChainSpec>(&self, config: &mut NodeConfig<ChainSpec>) {
+        if !self.role.mines() {
+            config.dev.dev = false;
+        }
+    }
+
+    /// Check the arguments can be used on the chain with `chain_id`
+    ///
+    /// A frozen difficulty lets anyone mine the chain at will, so `--regtest` is refused
+    /// on the public networks.
+    pub fn validate_chain(&self, chain_id: u64) -> eyre::Result<()> {
+        if self.regtest && matches!(chain_id, PERMIA_MAINNET_CHAIN_ID | PERMIA_TESTNET_CHAIN_ID) {
+            eyre::bail!("--regtest is not allowed on chain {chain_id}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Debug, Parser)]
+    struct CommandParser {
+        #[command(flatten)]
+        args: PermiaArgs,
+    }
+
+    #[test]
+    fn test_default_role_mines() {
+        let args = CommandParser::parse_from(["permia"]).args;
+        assert_eq!(args.role, NodeRole::Miner);
+        assert!(args.role.mines() && !args.role.votes());
+    }
+
+    #[test]
+    fn test_validator_role() {
+        let args =
+            CommandParser::parse_from(["permia", "--role", "validator", "--validator.key", "key.hex"])
+                .args;
+        assert_eq!(args.role, NodeRole::Validator);
+        assert_eq!(args.validator_key, Some(PathBuf::from("key.hex")));
+
+        // Validators vote and never mine
+        assert!(args.role.votes() && !args.role.mines());
+
+        // A validator needs a key to sign with
+        assert!(CommandParser::try_parse_from(["permia", "--role", "validator"]).is_err());
+    }
+
+    #[test]
+    fn test_validator_role_disables_dev_miner() {
+        let miner = CommandParser::parse_from(["permia"]).args;
+        let mut config = NodeConfig::test().dev();
+        miner.configure_node(&mut config);
+        assert!(config.dev.dev);
+
+        let validator =
+            CommandParser::parse_from(["permia", "--role", "validator", "--validator.key", "key.hex"])
+                .args;
+        validator.configure_node(&mut config);
+        assert!(!config.dev.dev);
+    }
+
+    #[test]
+    fn test_regtest() {
+        let args = CommandParser::parse_from(["permia"]).args;
+        assert!(!args.regtest);
+
+        let args = CommandParser::parse_from(["permia", "--regtest"]).args;
+        assert!(args.regtest);
+        assert_eq!(args.regtest_difficulty, DEFAULT_REGTEST_DIFFICULTY);
+
+        let args =
+            CommandParser::parse_from(["permia", "--regtest", "--regtest.difficulty", "1000"]).args;
+        assert_eq!(args.regtest_difficulty, 1000);
+
+        // The difficulty only applies in regtest mode
+        assert!(CommandParser::try_parse_from(["permia", "--regtest.difficulty", "1000"]).is_err());
+    }
+
+    #[test]
+    fn test_regtest_refused_on_public_chains() {
+        use reth_chainspec::PERMIA_DEVNET_CHAIN_ID;
+
+        let regtest = CommandParser::parse_from(["permia", "--regtest"]).args;
+        assert!(regtest.validate_chain(PERMIA_MAINNET_CHAIN_ID).is_err());
+        assert!(regtest.validate_chain(PERMIA_TESTNET_CHAIN_ID).is_err());
+        assert!(regtest.validate_chain(PERMIA_DEVNET_CHAIN_ID).is_ok());
+
+        let args = CommandParser::parse_from(["permia"]).args;
+        assert!(args.validate_chain(PERMIA_MAINNET_CHAIN_ID).is_ok());
+    }
+
+    #[test]
+    fn test_max_mining_iterations() {
+        let args = CommandParser::parse_from(["permia"]).args;
+        assert_eq!(args.max_mining_iterations, None);
+
+        let args = CommandParser::parse_from(["permia", "--mining.max-iterations", "5000"]).args;
+        assert_eq!(args.max_mining_iterations, Some(5000));
+    }
+}
@@ -1,7 +1,9 @@
 //! Permia chain specification parser
 
+use permia_chainspec::PermiaChainSpec;
+use permia_consensus::permia_hash_config;
 use reth_chainspec::{
-    ChainSpec, PERMIA_DEV, PERMIA_MAINNET, PERMIA_TESTNET,
+    permia_block_time_ms, ChainSpec, PERMIA_DEV, PERMIA_MAINNET, PERMIA_TESTNET,
 };
 use reth_cli::chainspec::{parse_genesis, ChainSpecParser};
 use std::sync::Arc;
@@ -17,13 +19,52 @@ pub const SUPPORTED_CHAINS: &[&str] = &[
     "dev",
 ];
 
+/// A supported chain and its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainInfo {
+    /// Canonical chain name
+    pub name: &'static str,
+    /// Chain ID
+    pub chain_id: u64,
+    /// Target block time in milliseconds
+    pub block_time_ms: u64,
+    /// Whether this is a test or development network
+    pub is_testnet: bool,
+}
+
+/// Chains supported by Permia, one entry per network rather than per alias
+pub fn supported_chains() -> Vec<ChainInfo> {
+    [
+        ("permia-mainnet", &*PERMIA_MAINNET, false),
+        ("permia-testnet", &*PERMIA_TESTNET, true),
+        ("permia-dev", &*PERMIA_DEV, true),
+    ]
+    .into_iter()
+    .map(|(name, spec, is_testnet)| ChainInfo {
+        name,
+        chain_id: spec.chain.id(),
+        block_time_ms: permia_block_time_ms(spec),
+        is_testnet,
+    })
+    .collect()
+}
+
 /// Parse a chain specification string into a ChainSpec
+///
+/// Custom genesis files are rejected unless they fund the treasury they name, see
+/// [`PermiaChainSpec::validate_genesis_alloc`], or if they select an unknown DAG version,
+/// see [`permia_hash_config`].
 pub fn chain_value_parser(s: &str) -> eyre::Result<Arc<ChainSpec>, eyre::Error> {
     Ok(match s.to_lowercase().as_str() {
         "permia" | "permia-mainnet" | "mainnet" => PERMIA_MAINNET.clone(),
         "permia-testnet" | "testnet" => PERMIA_TESTNET.clone(),
         "permia-dev" | "dev" => PERMIA_DEV.clone(),
-        _ => Arc::new(parse_genesis(s)?.into()),
+        _ => {
+            let spec: Arc<ChainSpec> = Arc::new(parse_genesis(s)?.into());
+            PermiaChainSpec::from_reth(&spec)?;
+            permia_hash_config(&spec)?;
+            spec
+        }
     })
 }
 
@@ -65,4 +106,54 @@ mod tests {
         let spec = <PermiaChainSpecParser as ChainSpecParser>::parse("dev").unwrap();
         assert_eq!(spec.chain.id(), 42071);
     }
+
+    #[test]
+    fn parse_custom_genesis_checks_treasury() {
+        use alloy_primitives::Address;
+        use reth_chainspec::PERMIA_TREASURY_FIELD;
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.chain_id = 4242;
+        let json = serde_json::to_string(&genesis).unwrap();
+        assert_eq!(chain_value_parser(&json).unwrap().chain.id(), 4242);
+
+        // A treasury without its genesis allocation is refused
+        let treasury = Address::repeat_byte(7).to_string();
+        genesis.config.extra_fields.insert(PERMIA_TREASURY_FIELD.to_string(), treasury.into());
+        let json = serde_json::to_string(&genesis).unwrap();
+        assert!(chain_value_parser(&json).is_err());
+    }
+
+    #[test]
+    fn parse_custom_genesis_checks_dag_version() {
+        use reth_chainspec::{permia_dag_version, PERMIA_DAG_VERSION_FIELD};
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.chain_id = 4242;
+        genesis.config.extra_fields.insert(PERMIA_DAG_VERSION_FIELD.to_string(), 2.into());
+        let json = serde_json::to_string(&genesis).unwrap();
+        assert_eq!(permia_dag_version(&chain_value_parser(&json).unwrap()), Some(2));
+
+        genesis.config.extra_fields.insert(PERMIA_DAG_VERSION_FIELD.to_string(), 3.into());
+        let json = serde_json::to_string(&genesis).unwrap();
+        assert!(chain_value_parser(&json).is_err());
+    }
+
+    #[test]
+    fn supported_chains_metadata() {
+        let chains = supported_chains();
+        let ids: Vec<_> = chains.iter().map(|chain| chain.chain_id).collect();
+        assert_eq!(ids, [42069, 42070, 42071]);
+
+        for chain in &chains {
+            assert_eq!(chain.block_time_ms, 400);
+            assert_eq!(chain.is_testnet, chain.chain_id != 42069);
+        }
+
+        // Every alias resolves to one of the listed chains
+        for &alias in SUPPORTED_CHAINS {
+            let id = chain_value_parser(alias).unwrap().chain.id();
+            assert_eq!(chains.iter().filter(|chain| chain.chain_id == id).count(), 1, "{alias}");
+        }
+    }
 }
@@ -0,0 +1,108 @@
+//! Genesis subcommands
+//!
+//! `permia genesis export` prints the genesis of a Permia chain, so operators can
+//! configure new nodes with exactly the genesis the network uses. The same genesis is
+//! served by a running node over `permia_getGenesis`.
+
+use crate::PermiaChainSpecParser;
+use clap::{Args, Subcommand};
+use reth_chainspec::ChainSpec;
+use reth_cli::chainspec::ChainSpecParser;
+use reth_cli_runner::CliRunner;
+use reth_ethereum_cli::ExtendedCommand;
+use std::{path::PathBuf, sync::Arc};
+
+/// Permia subcommands, alongside reth's built-in ones
+#[derive(Debug, Subcommand)]
+pub enum PermiaCommands {
+    /// Genesis utilities
+    #[command(subcommand)]
+    Genesis(GenesisCommand),
+}
+
+impl ExtendedCommand for PermiaCommands {
+    fn execute(self, _runner: CliRunner) -> eyre::Result<()> {
+        match self {
+            Self::Genesis(GenesisCommand::Export(command)) => command.execute(),
+        }
+    }
+}
+
+/// `permia genesis` subcommands
+#[derive(Debug, Subcommand)]
+pub enum GenesisCommand {
+    /// Print the chain's genesis JSON
+    Export(ExportGenesisCommand),
+}
+
+/// Exports the genesis of a chain as JSON
+#[derive(Debug, Args)]
+pub struct ExportGenesisCommand {
+    /// The chain to export the genesis of.
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        long_help = PermiaChainSpecParser::help_message(),
+        default_value = PermiaChainSpecParser::default_value(),
+        value_parser = PermiaChainSpecParser::parser()
+    )]
+    chain: Arc<ChainSpec>,
+
+    /// Write the genesis to a file instead of stdout
+    #[arg(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+impl ExportGenesisCommand {
+    /// Execute the `genesis export` command
+    pub fn execute(self) -> eyre::Result<()> {
+        let json = export_genesis(&self.chain)?;
+        match self.output {
+            Some(path) => std::fs::write(path, json)?,
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+}
+
+/// Genesis of a chain as pretty-printed JSON
+pub fn export_genesis(chain_spec: &ChainSpec) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&chain_spec.genesis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_genesis::Genesis;
+    use alloy_primitives::U256;
+    use clap::Parser;
+    use reth_chainspec::PERMIA_DEV;
+
+    #[derive(Debug, Parser)]
+    struct CommandParser {
+        #[command(subcommand)]
+        command: PermiaCommands,
+    }
+
+    #[test]
+    fn test_export_devnet_genesis() {
+        let json = export_genesis(&PERMIA_DEV).unwrap();
+        let genesis: Genesis = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(genesis.config.chain_id, 42071);
+        assert_eq!(genesis.difficulty, U256::from(0x100000));
+
+        // Identical to the genesis embedded in the chain spec
+        let embedded: Genesis =
+            serde_json::from_str(include_str!("../../../chainspec/res/genesis/permia-dev.json")).unwrap();
+        assert_eq!(genesis, embedded);
+    }
+
+    #[test]
+    fn test_parse_export_command() {
+        let CommandParser { command: PermiaCommands::Genesis(GenesisCommand::Export(command)) } =
+            CommandParser::parse_from(["permia", "genesis", "export", "--chain", "dev"]);
+        assert_eq!(command.chain.chain.id(), 42071);
+        assert_eq!(command.output, None);
+    }
+}
@@ -1,7 +1,11 @@
 //! Permia CLI utilities
 //!
-//! Provides CLI parsing and chain specification handling for Permia nodes.
+//! Provides CLI parsing, node arguments, subcommands and chain specification handling for Permia nodes.
 
+pub mod args;
 pub mod chainspec;
+pub mod genesis;
 
-pub use chainspec::PermiaChainSpecParser;
+pub use args::{NodeRole, PermiaArgs, DEFAULT_REGTEST_DIFFICULTY};
+pub use chainspec::{supported_chains, ChainInfo, PermiaChainSpecParser};
+pub use genesis::{export_genesis, ExportGenesisCommand, GenesisCommand, PermiaCommands};
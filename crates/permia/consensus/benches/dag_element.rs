@@ -0,0 +1,25 @@
+#![allow(missing_docs)]
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use permia_consensus::pow::{compute_epoch_seed, DagVersion, PermiaHashConfig};
+use std::hint::black_box;
+
+fn dag_element(c: &mut Criterion) {
+    let epoch_seed = compute_epoch_seed(0, &PermiaHashConfig::default());
+    let mut group = c.benchmark_group("Generate 1000 DAG elements");
+    group.throughput(Throughput::Elements(1_000));
+
+    for (name, version) in [("v1 (2x SHA3-256)", DagVersion::V1), ("v2 (SHA3-512)", DagVersion::V2)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for index in 0..1_000 {
+                    black_box(version.element(&epoch_seed, index));
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, dag_element);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+#![allow(missing_docs)]
+use alloy_consensus::Header;
+use alloy_primitives::U256;
+use criterion::{criterion_group, criterion_main, Criterion};
+use permia_consensus::pow::{compute_seal_hash, permia_hash_with_epoch, verify_pow, verify_pow_batch};
+use std::hint::black_box;
+
+/// Headers of a single epoch, sealed at difficulty 1
+fn sealed_headers(count: u64) -> Vec<Header> {
+    (1..=count)
+        .map(|number| {
+            let mut header = Header { number, difficulty: U256::from(1u64), ..Default::default() };
+            header.nonce = number.to_be_bytes().into();
+            header.mix_hash =
+                permia_hash_with_epoch(&compute_seal_hash(&header), number, number).mix_digest;
+            header
+        })
+        .collect()
+}
+
+fn pow_batch(c: &mut Criterion) {
+    let headers = sealed_headers(1_000);
+    let mut group = c.benchmark_group("Verify PoW of 1000 headers");
+
+    group.bench_function("serial", |b| {
+        b.iter(|| {
+            for header in &headers {
+                black_box(verify_pow(header)).unwrap();
+            }
+        })
+    });
+
+    group.bench_function("batch", |b| b.iter(|| black_box(verify_pow_batch(&headers))));
+
+    group.finish();
+}
+
+criterion_group!(benches, pow_batch);
+criterion_main!(benches);
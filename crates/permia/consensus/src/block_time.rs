@@ -0,0 +1,228 @@
+//! Block time statistics
+//!
+//! Collects block times over a rolling window, for tuning difficulty adjustment against
+//! the observed block time distribution. The consensus records the time between each
+//! header and its parent from their timestamps while validating headers against their
+//! parent, see [`BlockTimeStats::record_header`]. Header timestamps have second
+//! resolution, so these block times are whole seconds: the mean tracks the target, while
+//! the median and percentiles show how blocks bunch into seconds.
+//!
+//! The same collector measures import latency, the time between canonical head updates
+//! as the node observes them, see [`BlockTimeStats::record_canonical`]. That reflects
+//! import, sync and restart timing rather than block production.
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// Default number of block times kept
+pub const DEFAULT_BLOCK_TIME_WINDOW: usize = 1000;
+
+/// Rolling histogram of block times in milliseconds
+#[derive(Debug, Clone)]
+pub struct BlockTimeStats {
+    /// Number of the highest block recorded from its header
+    last_header: Option<u64>,
+    /// Number of the latest canonical block and when it became canonical, in milliseconds
+    last_canonical: Option<(u64, u64)>,
+    /// Block times in arrival order, oldest first
+    samples: VecDeque<u64>,
+    /// Block time -> number of samples in the window
+    histogram: BTreeMap<u64, usize>,
+    /// Sum of the block times in the window
+    total: u64,
+    /// Maximum number of block times kept
+    window: usize,
+}
+
+impl Default for BlockTimeStats {
+    fn default() -> Self {
+        Self::new(DEFAULT_BLOCK_TIME_WINDOW)
+    }
+}
+
+impl BlockTimeStats {
+    /// Create a collector keeping the last `window` block times
+    pub fn new(window: usize) -> Self {
+        Self {
+            last_header: None,
+            last_canonical: None,
+            samples: VecDeque::with_capacity(window),
+            histogram: BTreeMap::new(),
+            total: 0,
+            window: window.max(1),
+        }
+    }
+
+    /// Record the block time of block `number` from its and its parent's header timestamps
+    /// (seconds)
+    ///
+    /// Only blocks above the highest one recorded add a block time, so validating a block
+    /// again or a competing block at the same height doesn't count twice.
+    pub fn record_header(&mut self, number: u64, parent_timestamp: u64, timestamp: u64) {
+        if self.last_header.is_some_and(|last| number <= last) {
+            return;
+        }
+        self.last_header = Some(number);
+        self.record(parent_timestamp.saturating_mul(1000), timestamp.saturating_mul(1000));
+    }
+
+    /// Record that block `number` became canonical at `at_ms` (milliseconds)
+    ///
+    /// Only a block directly on top of the previous canonical block adds a block time;
+    /// after a reorg or a jump of several blocks, e.g. while syncing, there is no time
+    /// between the block and its parent to measure.
+    pub fn record_canonical(&mut self, number: u64, at_ms: u64) {
+        if let Some((last_number, last_ms)) = self.last_canonical &&
+            number.checked_sub(1) == Some(last_number)
+        {
+            self.record(last_ms, at_ms);
+        }
+        self.last_canonical = Some((number, at_ms));
+    }
+
+    /// Record the time between a parent and child block, both in milliseconds
+    pub fn record(&mut self, parent_ms: u64, ms: u64) {
+        self.record_block_time(ms.saturating_sub(parent_ms));
+    }
+
+    /// Record a block time in milliseconds, evicting the oldest once the window is full
+    pub fn record_block_time(&mut self, block_time: u64) {
+        if self.samples.len() == self.window && let Some(oldest) = self.samples.pop_front() {
+            self.total -= oldest;
+            if let Some(count) = self.histogram.get_mut(&oldest) {
+                *count -= 1;
+                if *count == 0 {
+                    self.histogram.remove(&oldest);
+                }
+            }
+        }
+
+        self.samples.push_back(block_time);
+        *self.histogram.entry(block_time).or_default() += 1;
+        self.total += block_time;
+    }
+
+    /// Number of block times in the window
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether no block time has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Block time (ms) -> number of samples, over the window
+    pub fn histogram(&self) -> &BTreeMap<u64, usize> {
+        &self.histogram
+    }
+
+    /// Mean block time in milliseconds
+    pub fn mean(&self) -> Option<f64> {
+        (!self.is_empty()).then(|| self.total as f64 / self.samples.len() as f64)
+    }
+
+    /// Median block time in milliseconds
+    pub fn median(&self) -> Option<u64> {
+        self.percentile(50)
+    }
+
+    /// 95th percentile block time in milliseconds
+    pub fn p95(&self) -> Option<u64> {
+        self.percentile(95)
+    }
+
+    /// Block time in milliseconds at the given percentile (nearest rank)
+    pub fn percentile(&self, percentile: u8) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let percentile = percentile.clamp(1, 100) as usize;
+        let rank = (self.samples.len() * percentile).div_ceil(100);
+        let mut seen = 0;
+        self.histogram.iter().find_map(|(&block_time, &count)| {
+            seen += count;
+            (seen >= rank).then_some(block_time)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_time_stats() {
+        let mut stats = BlockTimeStats::default();
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.p95(), None);
+
+        // Block times 100..=2000ms in shuffled order
+        let mut at_ms = 1_700_000_000_000;
+        for block_time in (1..=20).rev().step_by(2).chain((1..=20).step_by(2)) {
+            stats.record(at_ms, at_ms + block_time * 100);
+            at_ms += block_time * 100;
+        }
+
+        assert_eq!(stats.len(), 20);
+        assert_eq!(stats.mean(), Some(1050.0));
+        assert_eq!(stats.median(), Some(1000));
+        assert_eq!(stats.p95(), Some(1900));
+        assert_eq!(stats.percentile(100), Some(2000));
+    }
+
+    #[test]
+    fn test_header_block_times() {
+        let mut stats = BlockTimeStats::default();
+
+        // Header timestamps are seconds
+        stats.record_header(1, 1_000, 1_000);
+        stats.record_header(2, 1_000, 1_001);
+        assert_eq!(stats.histogram(), &BTreeMap::from([(0, 1), (1_000, 1)]));
+
+        // Validating a block again or a competing block at the same height adds nothing
+        stats.record_header(2, 1_000, 1_001);
+        stats.record_header(2, 1_000, 1_003);
+        assert_eq!(stats.len(), 2);
+
+        stats.record_header(3, 1_001, 1_003);
+        assert_eq!(stats.mean(), Some(1_000.0));
+        assert_eq!(stats.p95(), Some(2_000));
+    }
+
+    #[test]
+    fn test_canonical_block_times() {
+        let mut stats = BlockTimeStats::default();
+
+        // The first block has nothing to be measured against
+        stats.record_canonical(10, 1_000);
+        assert!(stats.is_empty());
+
+        stats.record_canonical(11, 1_400);
+        stats.record_canonical(12, 1_750);
+        assert_eq!(stats.histogram(), &BTreeMap::from([(350, 1), (400, 1)]));
+
+        // Reorgs and jumps over several blocks add no block time
+        stats.record_canonical(12, 1_900);
+        stats.record_canonical(15, 2_000);
+        assert_eq!(stats.len(), 2);
+
+        stats.record_canonical(16, 2_500);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats.p95(), Some(500));
+    }
+
+    #[test]
+    fn test_window_rolls() {
+        let mut stats = BlockTimeStats::new(4);
+        for block_time in [9, 9, 0, 1, 0, 1] {
+            stats.record_block_time(block_time);
+        }
+
+        // Only the last four block times remain
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats.mean(), Some(0.5));
+        assert_eq!(stats.p95(), Some(1));
+        assert_eq!(stats.histogram(), &BTreeMap::from([(0, 2), (1, 2)]));
+    }
+}
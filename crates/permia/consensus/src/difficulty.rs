@@ -1,67 +1,231 @@
 //! Difficulty adjustment algorithm for Permia
+//!
+//! Header timestamps are seconds since the UNIX epoch, so with a sub-second target
+//! most blocks share their parent's timestamp. Each block scales difficulty by
+//! `exp(gain * (target - spacing) / target)`, where the spacing is the timestamp
+//! difference. The exponents of consecutive blocks add up, and so do their spacings, so
+//! over a run of blocks difficulty moves with how far the run's elapsed time is from
+//! the target: it is stable whenever blocks average the target spacing, whether they
+//! arrive evenly or at random. Increases and decreases are capped by the same factor.
+//!
+//! Every node must derive the same difficulty, so the adjustment is computed in integer
+//! fixed-point arithmetic rather than with floating point `exp`, whose results may
+//! differ in the last bit between platforms.
 
-use alloy_consensus::Header;
+use crate::{
+    PowHeader, BLOCK_TIME_MS, DEFAULT_MAX_FUTURE_DRIFT_SECS, PERMIA_DEVNET_CHAIN_ID,
+    PERMIA_TESTNET_CHAIN_ID,
+};
 use alloy_primitives::U256;
 
-/// Target block time in milliseconds
-const TARGET_BLOCK_TIME_MS: u64 = 400;
+/// Milliseconds per header timestamp unit
+const MS_PER_SEC: u64 = 1000;
+
+/// Block time, as a multiple of target, past which the emergency adjustment applies
+const EMERGENCY_THRESHOLD: u64 = 20;
+
+/// Shortest block time the emergency adjustment applies to, in milliseconds
+///
+/// Longer than a header may be dated ahead of the clock plus the second lost to timestamp
+/// rounding, so a miner cannot take the emergency drop by forward-dating its block within
+/// the allowed drift.
+const MIN_EMERGENCY_BLOCK_TIME_MS: u64 = (DEFAULT_MAX_FUTURE_DRIFT_SECS + 2) * MS_PER_SEC;
+
+/// Fixed-point scale of log-changes and multipliers, 1.0 is `WAD`
+const WAD: i128 = 1_000_000_000_000_000_000;
+
+/// `ln(2)` scaled by [`WAD`]
+const LN_2: i128 = 693_147_180_559_945_309;
+
+/// Parts per million, the unit of the adjustment caps
+const PPM: u64 = 1_000_000;
+
+/// Log-change in difficulty per block time of deviation from the target, 0.05 scaled
+/// by [`WAD`]
+///
+/// Low enough that a few seconds' gap stays within the per-block cap, which would
+/// otherwise swallow part of the drop and bias blocks slow.
+const ADJUSTMENT_GAIN: i128 = WAD / 20;
+
+/// Number of recent blocks to estimate hashrate over, see
+/// [`DifficultyCalculator::calculate_from_hashrate`]
+pub const HASHRATE_WINDOW: usize = 60;
+
+/// Minimum difficulty on mainnet
+pub const MAINNET_MIN_DIFFICULTY: u64 = 1 << 20;
+
+/// Minimum difficulty on testnet and devnet, low enough for CPU mining
+pub const TEST_NETWORK_MIN_DIFFICULTY: u64 = 1 << 16;
+
+/// Get the minimum difficulty enforced on a chain
+///
+/// Unknown chains use the mainnet minimum.
+pub fn min_difficulty_for_chain(chain_id: u64) -> U256 {
+    match chain_id {
+        PERMIA_TESTNET_CHAIN_ID | PERMIA_DEVNET_CHAIN_ID => U256::from(TEST_NETWORK_MIN_DIFFICULTY),
+        _ => U256::from(MAINNET_MIN_DIFFICULTY),
+    }
+}
 
 /// Difficulty adjustment calculator
 #[derive(Debug, Clone)]
 pub struct DifficultyCalculator {
     /// Target block time in milliseconds
     target_time_ms: u64,
-    /// Maximum adjustment per block (fraction)
-    max_adjustment: f64,
+    /// Maximum adjustment per block in parts per million, a decrease is capped at
+    /// dividing by `1 + max_adjustment`
+    max_adjustment_ppm: u64,
+    /// Maximum downward adjustment for blocks past the emergency threshold, in parts per
+    /// million
+    max_emergency_adjustment_ppm: u64,
     /// Minimum difficulty
     min_difficulty: U256,
+    /// Difficulty returned for every block, disabling adjustment
+    fixed: Option<U256>,
 }
 
 impl DifficultyCalculator {
     /// Create calculator with default parameters
     pub fn new() -> Self {
         Self {
-            target_time_ms: TARGET_BLOCK_TIME_MS,
-            max_adjustment: 0.25, // 25% max change per block
-            min_difficulty: U256::from(1u64 << 20),
+            target_time_ms: BLOCK_TIME_MS,
+            max_adjustment_ppm: 250_000, // 25% max change per block
+            max_emergency_adjustment_ppm: 750_000, // 75% max drop after a hashrate collapse
+            min_difficulty: U256::from(MAINNET_MIN_DIFFICULTY),
+            fixed: None,
         }
     }
     
+    /// Create a calculator that returns `difficulty` for every block, regardless of timestamps
+    ///
+    /// For regtest and integration tests that need reproducible block production. The
+    /// fixed difficulty is also the minimum difficulty.
+    pub fn fixed(difficulty: U256) -> Self {
+        Self { min_difficulty: difficulty, fixed: Some(difficulty), ..Self::new() }
+    }
+    
+    /// Get the fixed difficulty, if adjustment is disabled
+    pub fn fixed_difficulty(&self) -> Option<U256> {
+        self.fixed
+    }
+    
+    /// Create calculator with the minimum difficulty of the given chain
+    pub fn for_chain(chain_id: u64) -> Self {
+        Self::new().with_min_difficulty(min_difficulty_for_chain(chain_id))
+    }
+    
+    /// Set the target block time in milliseconds
+    pub fn with_target_block_time(mut self, target_time_ms: u64) -> Self {
+        self.target_time_ms = target_time_ms.max(1);
+        self
+    }
+    
+    /// Get the target block time in milliseconds
+    pub fn target_block_time_ms(&self) -> u64 {
+        self.target_time_ms
+    }
+    
+    /// Set the minimum difficulty
+    pub fn with_min_difficulty(mut self, min_difficulty: U256) -> Self {
+        self.min_difficulty = min_difficulty;
+        self
+    }
+    
     /// Get minimum difficulty
     pub fn min_difficulty(&self) -> U256 {
         self.min_difficulty
     }
     
     /// Calculate difficulty for next block
-    pub fn calculate(&self, parent: &Header, timestamp: u64) -> U256 {
-        // Time since parent block
-        let time_diff = timestamp.saturating_sub(parent.timestamp);
+    ///
+    /// `timestamp` and the parent's timestamp are header timestamps in seconds.
+    pub fn calculate<H: PowHeader>(&self, parent: &H, timestamp: u64) -> U256 {
+        if let Some(fixed) = self.fixed {
+            return fixed;
+        }
         
-        // If timestamps are same, increase difficulty slightly
-        if time_diff == 0 {
-            return self.apply_adjustment(parent.difficulty, 0.1);
+        // Time since parent block, header timestamps have second granularity. Blocks in the
+        // same second count as instant, which the blocks ticking the second over make up for
+        let time_diff_ms = timestamp.saturating_sub(parent.timestamp()).saturating_mul(MS_PER_SEC);
+        
+        self.retarget(parent.difficulty(), time_diff_ms)
+    }
+    
+    /// Calculate difficulty for a block whose parent, of `parent_difficulty`, took
+    /// `block_time_ms` to mine
+    ///
+    /// The adjustment [`Self::calculate`] applies, at millisecond precision, e.g. to
+    /// simulate retargeting off-chain.
+    pub fn retarget(&self, parent_difficulty: U256, block_time_ms: u64) -> U256 {
+        if let Some(fixed) = self.fixed {
+            return fixed;
         }
         
-        // Calculate adjustment based on actual vs target time
-        let target = self.target_time_ms as f64;
-        let actual = time_diff as f64;
+        self.apply_multiplier(parent_difficulty, self.multiplier_for(block_time_ms))
+    }
+    
+    /// Calculate difficulty for next block from the hashrate over recent blocks
+    ///
+    /// `recent` holds the `(timestamp, difficulty)` of the last blocks, oldest first and
+    /// ending with the parent, and `timestamp` is the new header's timestamp (seconds).
+    /// A block of difficulty `d` takes `d` hashes on average, so the window did
+    /// `sum(d)` hashes in `timestamp - recent[0].0` seconds, counting the new block at
+    /// its parent's difficulty. The result targets the block time at that hashrate and
+    /// moves at most the per-block maximum adjustment away from the parent difficulty.
+    ///
+    /// Returns the minimum difficulty if `recent` is empty.
+    pub fn calculate_from_hashrate(&self, recent: &[(u64, U256)], timestamp: u64) -> U256 {
+        if let Some(fixed) = self.fixed {
+            return fixed;
+        }
         
-        // adjustment = (target - actual) / target * 0.1
-        let raw_adjustment = (target - actual) / target * 0.1;
+        let (Some(&(first_timestamp, _)), Some(&(_, parent_difficulty))) = (recent.first(), recent.last())
+        else {
+            return self.min_difficulty;
+        };
         
-        // Clamp to max adjustment
-        let adjustment = raw_adjustment.clamp(-self.max_adjustment, self.max_adjustment);
+        let work = recent[1..].iter().fold(parent_difficulty, |work, (_, difficulty)| work.saturating_add(*difficulty));
+        let elapsed_ms = timestamp.saturating_sub(first_timestamp).max(1).saturating_mul(MS_PER_SEC);
+        let estimate = work.saturating_mul(U256::from(self.target_time_ms)) / U256::from(elapsed_ms);
         
-        self.apply_adjustment(parent.difficulty, adjustment)
+        let factor = U256::from(PPM + self.max_adjustment_ppm);
+        let scale = U256::from(PPM);
+        let lower = parent_difficulty * scale / factor;
+        let upper = parent_difficulty * factor / scale;
+        estimate.clamp(lower, upper).max(self.min_difficulty)
     }
     
-    /// Apply adjustment to difficulty
-    fn apply_adjustment(&self, difficulty: U256, adjustment: f64) -> U256 {
-        let multiplier = 1.0 + adjustment;
+    /// Difficulty multiplier, scaled by [`WAD`], for a block that arrived `time_diff_ms`
+    /// after its parent
+    ///
+    /// `exp` of the log-change, capped in multiplier space. `exp` is monotonic, so this is
+    /// the same as capping the log-change at the logarithms of the caps.
+    fn multiplier_for(&self, time_diff_ms: u64) -> i128 {
+        let target = i128::from(self.target_time_ms);
+        let actual = i128::from(time_diff_ms);
+        
+        let raw_multiplier = exp_wad((target - actual) * ADJUSTMENT_GAIN / target);
         
-        // Convert to fixed-point math
-        let multiplier_fixed = (multiplier * 1_000_000.0) as u64;
-        let new_difficulty = difficulty * U256::from(multiplier_fixed) / U256::from(1_000_000u64);
+        // Hashrate collapsed, allow a larger drop so the chain recovers quickly
+        let ppm = i128::from(PPM);
+        let emergency_threshold_ms = self
+            .target_time_ms
+            .saturating_mul(EMERGENCY_THRESHOLD)
+            .max(MIN_EMERGENCY_BLOCK_TIME_MS);
+        if time_diff_ms >= emergency_threshold_ms {
+            let floor = i128::from(PPM - self.max_emergency_adjustment_ppm);
+            return raw_multiplier.max(WAD * floor / ppm);
+        }
+        
+        // Clamp to max adjustment, the same factor either way
+        let factor = i128::from(PPM + self.max_adjustment_ppm);
+        raw_multiplier.clamp(WAD * ppm / factor, WAD * factor / ppm)
+    }
+    
+    /// Scale difficulty by a multiplier scaled by [`WAD`]
+    fn apply_multiplier(&self, difficulty: U256, multiplier: i128) -> U256 {
+        let multiplier = U256::from(multiplier.unsigned_abs());
+        let new_difficulty = difficulty.saturating_mul(multiplier) / U256::from(WAD.unsigned_abs());
         
         // Enforce minimum
         if new_difficulty < self.min_difficulty {
@@ -72,6 +236,32 @@ impl DifficultyCalculator {
     }
 }
 
+/// `exp(x)` in fixed point, `x` and the result scaled by [`WAD`]
+///
+/// Splits `x` into `k * ln(2) + r` with `|r| <= ln(2) / 2`, sums the Taylor series of
+/// `exp(r)` until its terms vanish and scales the sum by `2^k`. Integer arithmetic only,
+/// so the result is the same on every platform. `x` must be below `40 * WAD`; results
+/// that underflow the scale are 0.
+fn exp_wad(x: i128) -> i128 {
+    let half = if x < 0 { -LN_2 / 2 } else { LN_2 / 2 };
+    let k = (x + half) / LN_2;
+    let r = x - k * LN_2;
+    
+    let (mut term, mut sum) = (WAD, WAD);
+    let mut n = 1;
+    while term != 0 {
+        term = term * r / (n * WAD);
+        sum += term;
+        n += 1;
+    }
+    
+    if k >= 0 {
+        sum << k
+    } else {
+        u32::try_from(-k).ok().and_then(|shift| sum.checked_shr(shift)).unwrap_or(0)
+    }
+}
+
 impl Default for DifficultyCalculator {
     fn default() -> Self {
         Self::new()
@@ -81,6 +271,7 @@ impl Default for DifficultyCalculator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_consensus::Header;
     use alloy_primitives::{Address, B256, Bloom, Bytes};
     
     fn test_header(difficulty: U256, timestamp: u64) -> Header {
@@ -109,13 +300,28 @@ mod tests {
         }
     }
     
+    /// Mine `blocks` blocks at a fixed wall-clock spacing with second-granularity headers
+    fn simulate(calc: &DifficultyCalculator, start: U256, spacing_ms: u64, blocks: u64) -> U256 {
+        let mut parent = test_header(start, 0);
+        let mut clock_ms = 0;
+        
+        for _ in 0..blocks {
+            clock_ms += spacing_ms;
+            let timestamp = clock_ms / MS_PER_SEC;
+            let difficulty = calc.calculate(&parent, timestamp);
+            parent = test_header(difficulty, timestamp);
+        }
+        
+        parent.difficulty
+    }
+    
     #[test]
     fn test_difficulty_increase_on_fast_block() {
         let calc = DifficultyCalculator::new();
         let parent = test_header(U256::from(1_000_000u64), 1000);
         
-        // Block arrived 200ms after parent (faster than 400ms target)
-        let new_diff = calc.calculate(&parent, 1200);
+        // Block arrived within the same second as its parent
+        let new_diff = calc.calculate(&parent, 1000);
         
         // Difficulty should increase
         assert!(new_diff > parent.difficulty);
@@ -126,10 +332,303 @@ mod tests {
         let calc = DifficultyCalculator::new();
         let parent = test_header(U256::from(10_000_000u64), 1000);
         
-        // Block arrived 2000ms after parent (5x slower than 400ms target)
-        let new_diff = calc.calculate(&parent, 3000);
+        // Block arrived 2s after parent (5x slower than 400ms target)
+        let new_diff = calc.calculate(&parent, 1002);
         
         // Difficulty should decrease
         assert!(new_diff < parent.difficulty);
     }
+    
+    #[test]
+    fn test_retarget() {
+        let calc = DifficultyCalculator::new();
+        let parent = test_header(U256::from(10_000_000u64), 1000);
+        
+        // Matches the calculated difficulty for whole seconds
+        assert_eq!(calc.retarget(parent.difficulty, 2000), calc.calculate(&parent, 1002));
+        
+        // Sub-second blocks are retargeted by their actual spacing
+        assert!(calc.retarget(parent.difficulty, 100) > parent.difficulty);
+        assert_eq!(calc.retarget(parent.difficulty, calc.target_block_time_ms()), parent.difficulty);
+        assert!(calc.retarget(parent.difficulty, 700) < parent.difficulty);
+    }
+    
+    #[test]
+    fn test_exp_wad() {
+        // Within a few units of the last digit of exp(x) * 10^18
+        assert_eq!(exp_wad(0), WAD);
+        assert_eq!(exp_wad(WAD / 20), 1_051_271_096_376_024_035);
+        assert_eq!(exp_wad(-WAD / 20), 951_229_424_500_714_009);
+        assert_eq!(exp_wad(-WAD), 367_879_441_171_442_320);
+        assert_eq!(exp_wad(LN_2), 2 * WAD);
+        assert_eq!(exp_wad(7 * WAD / 10), 2_013_752_707_470_476_516);
+        assert_eq!(exp_wad(-3 * WAD), 49_787_068_367_863_942);
+        assert_eq!(exp_wad(-200 * WAD), 0);
+    }
+    
+    #[test]
+    fn test_retarget_exact_outputs() {
+        // Difficulty is consensus critical, so every node must compute these exact values
+        let calc = DifficultyCalculator::new();
+        let cases: [(u64, u64, u64); 12] = [
+            (1_000_000_000_000, 0, 1_051_271_096_376),
+            (1_000_000_000_000, 100, 1_038_211_997_081),
+            (1_000_000_000_000, 400, 1_000_000_000_000),
+            (1_000_000_000_000, 700, 963_194_417_720),
+            (1_000_000_000_000, 1_000, 927_743_486_328),
+            (1_000_000_000_000, 2_000, 818_730_753_077),
+            // Capped at the inverse of the largest increase
+            (1_000_000_000_000, 5_000, 800_000_000_000),
+            (1_000_000_000_000, 16_000, 800_000_000_000),
+            // Past the emergency threshold
+            (1_000_000_000_000, 17_000, 250_000_000_000),
+            (1_000_000_000_000, 60_000, 250_000_000_000),
+            (3_000_000, 0, 3_153_813),
+            (3_000_000, 1_000, 2_783_230),
+        ];
+        for (parent, block_time_ms, expected) in cases {
+            assert_eq!(
+                calc.retarget(U256::from(parent), block_time_ms),
+                U256::from(expected),
+                "{parent} after {block_time_ms}ms"
+            );
+        }
+    }
+    
+    #[test]
+    fn test_min_difficulty_per_chain() {
+        assert_eq!(DifficultyCalculator::new().min_difficulty(), U256::from(1u64 << 20));
+        assert_eq!(DifficultyCalculator::for_chain(crate::PERMIA_CHAIN_ID).min_difficulty(), U256::from(1u64 << 20));
+        assert_eq!(DifficultyCalculator::for_chain(PERMIA_DEVNET_CHAIN_ID).min_difficulty(), U256::from(1u64 << 16));
+        assert_eq!(DifficultyCalculator::for_chain(1).min_difficulty(), U256::from(1u64 << 20));
+        
+        // Slow blocks bottom out at the chain's floor
+        let calc = DifficultyCalculator::for_chain(PERMIA_DEVNET_CHAIN_ID);
+        let parent = test_header(U256::from(100_000u64), 1000);
+        assert_eq!(calc.calculate(&parent, 1060), U256::from(1u64 << 16));
+    }
+    
+    #[test]
+    fn test_emergency_adjustment_on_very_slow_block() {
+        let calc = DifficultyCalculator::new();
+        let start = U256::from(1_000_000_000_000u64);
+        let parent = test_header(start, 1000);
+        
+        // A minute without blocks drops past the normal cap
+        let new_diff = calc.calculate(&parent, 1060);
+        assert!(new_diff < start * U256::from(4u64) / U256::from(5u64), "only dropped to {new_diff}");
+        assert_eq!(new_diff, start / U256::from(4u64));
+        
+        // Just under the emergency threshold the normal cap applies, the inverse of the
+        // largest increase
+        let new_diff = calc.calculate(&parent, 1007);
+        assert_eq!(new_diff, start * U256::from(4u64) / U256::from(5u64));
+    }
+    
+    #[test]
+    fn test_forward_dated_block_cannot_trigger_emergency() {
+        let calc = DifficultyCalculator::new();
+        let start = U256::from(1_000_000_000_000u64);
+        let capped = start * U256::from(4u64) / U256::from(5u64);
+        
+        // Mined right after a parent stamped late in its second, and dated as far ahead
+        // as nodes accept: the normal cap still applies
+        let parent = test_header(start, 1000);
+        let forward_dated = 1000 + 1 + DEFAULT_MAX_FUTURE_DRIFT_SECS;
+        assert_eq!(calc.calculate(&parent, forward_dated), capped);
+        
+        // Whatever the target block time
+        for target_ms in [100, 400, 800] {
+            let calc = DifficultyCalculator::new().with_target_block_time(target_ms);
+            assert!(calc.calculate(&parent, forward_dated) >= capped, "target {target_ms}ms");
+        }
+        
+        // A gap longer than any forward dating does
+        assert_eq!(calc.calculate(&parent, forward_dated + 1), start / U256::from(4u64));
+    }
+    
+    #[test]
+    fn test_difficulty_stable_at_target_spacing() {
+        let calc = DifficultyCalculator::new();
+        let start = U256::from(1_000_000_000_000u64);
+        
+        let end = simulate(&calc, start, BLOCK_TIME_MS, 10_000);
+        
+        // Within 5% of the starting difficulty after 10k blocks
+        assert!(end > start * U256::from(95u64) / U256::from(100u64), "drifted down to {end}");
+        assert!(end < start * U256::from(105u64) / U256::from(100u64), "drifted up to {end}");
+    }
+    
+    #[test]
+    fn test_custom_target_block_time() {
+        let calc = DifficultyCalculator::new().with_target_block_time(1000);
+        assert_eq!(calc.target_block_time_ms(), 1000);
+        
+        // A block exactly on the 1s target keeps difficulty, the 400ms default would drop it
+        let parent = test_header(U256::from(10_000_000u64), 1000);
+        assert_eq!(calc.calculate(&parent, 1001), parent.difficulty);
+        assert!(DifficultyCalculator::new().calculate(&parent, 1001) < parent.difficulty);
+    }
+    
+    #[test]
+    fn test_difficulty_tracks_block_spacing() {
+        let calc = DifficultyCalculator::new();
+        let start = U256::from(1_000_000_000_000u64);
+        
+        // Blocks twice as fast as target raise difficulty
+        assert!(simulate(&calc, start, 200, 1_000) > start * U256::from(2u64));
+        
+        // Blocks twice as slow as target lower difficulty
+        assert!(simulate(&calc, start, 800, 1_000) < start / U256::from(2u64));
+    }
+    
+    /// Mine `blocks` blocks with a fixed hashrate, returning the wall-clock block times
+    ///
+    /// Block times are exponentially distributed around `difficulty / hashes_per_ms`,
+    /// drawn from a fixed-seed xorshift so the test is deterministic.
+    fn simulate_hashrate(
+        calc: &DifficultyCalculator,
+        start: U256,
+        hashes_per_ms: u64,
+        blocks: usize,
+    ) -> (Vec<f64>, Vec<U256>) {
+        let mut rng = 0x9e37_79b9_7f4a_7c15u64;
+        let mut parent = test_header(start, 0);
+        let mut clock_ms = 0.0;
+        let (mut times, mut difficulties) = (Vec::with_capacity(blocks), Vec::with_capacity(blocks));
+        
+        for _ in 0..blocks {
+            rng ^= rng << 13;
+            rng ^= rng >> 7;
+            rng ^= rng << 17;
+            let uniform = (rng >> 11) as f64 / (1u64 << 53) as f64;
+            
+            let expected_ms = f64::from(parent.difficulty) / hashes_per_ms as f64;
+            let block_time = -expected_ms * (1.0 - uniform).ln();
+            clock_ms += block_time;
+            
+            let timestamp = (clock_ms / MS_PER_SEC as f64) as u64;
+            let difficulty = calc.calculate(&parent, timestamp);
+            parent = test_header(difficulty, timestamp);
+            times.push(block_time);
+            difficulties.push(difficulty);
+        }
+        
+        (times, difficulties)
+    }
+    
+    #[test]
+    fn test_difficulty_converges_for_fixed_hashrate() {
+        let calc = DifficultyCalculator::new();
+        let hashes_per_ms = 1_000_000u64;
+        let equilibrium = U256::from(hashes_per_ms * BLOCK_TIME_MS);
+        
+        // Start 10x too hard and 10x too easy
+        for start in [equilibrium * U256::from(10u64), equilibrium / U256::from(10u64)] {
+            let (times, difficulties) = simulate_hashrate(&calc, start, hashes_per_ms, 40_000);
+            
+            // After settling, blocks average the target spacing
+            let settled = &times[20_000..];
+            let average_ms = settled.iter().sum::<f64>() / settled.len() as f64;
+            assert!(
+                (average_ms - BLOCK_TIME_MS as f64).abs() < BLOCK_TIME_MS as f64 * 0.02,
+                "start {start}: averaged {average_ms:.0}ms"
+            );
+            
+            // And difficulty stays near the equilibrium rather than oscillating or diverging
+            for chunk in difficulties[20_000..].chunks(1_000) {
+                let mean = chunk.iter().map(|d| f64::from(*d)).sum::<f64>() / chunk.len() as f64;
+                let ratio = mean / f64::from(equilibrium);
+                assert!((0.75..1.33).contains(&ratio), "start {start}: difficulty at {ratio:.2}x equilibrium");
+            }
+        }
+    }
+    
+    /// Mine `blocks` blocks evenly spaced at `difficulty / hashrate`, retargeting from the
+    /// hashrate over the last [`HASHRATE_WINDOW`] blocks
+    ///
+    /// The chain starts with a window of `start` difficulty blocks at the target spacing,
+    /// and `hashes_per_ms` gives the hashrate for each block number after it.
+    fn simulate_window(
+        calc: &DifficultyCalculator,
+        start: U256,
+        hashes_per_ms: impl Fn(usize) -> u64,
+        blocks: usize,
+    ) -> Vec<U256> {
+        let mut recent: Vec<_> =
+            (0..HASHRATE_WINDOW as u64).map(|i| (i * BLOCK_TIME_MS / MS_PER_SEC, start)).collect();
+        let mut clock_ms = (HASHRATE_WINDOW as u64 - 1) * BLOCK_TIME_MS;
+        
+        for number in 0..blocks {
+            let (_, parent_difficulty) = *recent.last().unwrap();
+            clock_ms += u64::try_from(parent_difficulty).unwrap() / hashes_per_ms(number);
+            let timestamp = clock_ms / MS_PER_SEC;
+            
+            let window = &recent[recent.len().saturating_sub(HASHRATE_WINDOW)..];
+            recent.push((timestamp, calc.calculate_from_hashrate(window, timestamp)));
+        }
+        
+        recent.into_iter().skip(HASHRATE_WINDOW).map(|(_, difficulty)| difficulty).collect()
+    }
+    
+    #[test]
+    fn test_hashrate_difficulty_stable_at_constant_hashrate() {
+        let calc = DifficultyCalculator::new();
+        let equilibrium = U256::from(1_000_000 * BLOCK_TIME_MS);
+        
+        let difficulties = simulate_window(&calc, equilibrium, |_| 1_000_000, 1_000);
+        for difficulty in &difficulties {
+            let ratio = f64::from(*difficulty) / f64::from(equilibrium);
+            assert!((0.9..1.1).contains(&ratio), "difficulty at {ratio:.2}x equilibrium");
+        }
+        
+        assert_eq!(calc.calculate_from_hashrate(&[], 10), calc.min_difficulty());
+    }
+    
+    #[test]
+    fn test_hashrate_difficulty_rises_smoothly() {
+        let calc = DifficultyCalculator::new();
+        let equilibrium = U256::from(1_000_000 * BLOCK_TIME_MS);
+        
+        // Hashrate doubles from block 500
+        let difficulties =
+            simulate_window(&calc, equilibrium, |number| if number < 500 { 1_000_000 } else { 2_000_000 }, 1_000);
+        let ratios: Vec<_> =
+            difficulties.iter().map(|difficulty| f64::from(*difficulty) / f64::from(equilibrium)).collect();
+        
+        // No single block jumps by more than the maximum adjustment
+        for pair in ratios.windows(2) {
+            assert!(pair[1] / pair[0] <= 1.0 + calc.max_adjustment_ppm as f64 / 1e6 + 1e-6);
+        }
+        
+        // The rise follows the moving average instead of happening at once
+        assert!(ratios[505] < 1.5, "already at {:.2}x", ratios[505]);
+        assert!(ratios[520] > ratios[505]);
+        
+        // And settles at twice the difficulty
+        for ratio in &ratios[700..] {
+            assert!((1.8..2.2).contains(ratio), "difficulty at {ratio:.2}x equilibrium");
+        }
+    }
+    
+    #[test]
+    fn test_fixed_difficulty() {
+        let difficulty = U256::from(1234u64);
+        let calc = DifficultyCalculator::fixed(difficulty);
+        assert_eq!(calc.fixed_difficulty(), Some(difficulty));
+        assert_eq!(calc.min_difficulty(), difficulty);
+        assert_eq!(DifficultyCalculator::new().fixed_difficulty(), None);
+        
+        // Same-second, on-target, slow and emergency-slow blocks all keep the difficulty
+        for parent_difficulty in [U256::from(1u64), difficulty, U256::from(MAINNET_MIN_DIFFICULTY)] {
+            let parent = test_header(parent_difficulty, 1000);
+            for timestamp in [0, 1000, 1001, 1010, 100_000, u64::MAX] {
+                assert_eq!(calc.calculate(&parent, timestamp), difficulty, "timestamp {timestamp}");
+            }
+        }
+        
+        let recent: Vec<_> = (0..10u64).map(|i| (1000 + i, U256::from(1_000_000u64))).collect();
+        assert_eq!(calc.calculate_from_hashrate(&recent, 1010), difficulty);
+        assert_eq!(calc.calculate_from_hashrate(&[], 1010), difficulty);
+    }
 }
@@ -0,0 +1,103 @@
+//! Header access for PermiaHash
+//!
+//! PermiaHash and difficulty retargeting only need a handful of header fields. The
+//! [`PowHeader`] trait exposes them, so the consensus is not tied to the alloy
+//! [`Header`] type and works with any header of the node's primitives.
+
+use alloy_consensus::Header;
+use alloy_primitives::{Address, B256, U256};
+
+/// Header fields committed to and checked by PermiaHash
+pub trait PowHeader {
+    /// Hash of the parent block
+    fn parent_hash(&self) -> B256;
+    /// Block beneficiary
+    fn beneficiary(&self) -> Address;
+    /// State root
+    fn state_root(&self) -> B256;
+    /// Transactions root
+    fn transactions_root(&self) -> B256;
+    /// Receipts root
+    fn receipts_root(&self) -> B256;
+    /// Block difficulty
+    fn difficulty(&self) -> U256;
+    /// Block number
+    fn number(&self) -> u64;
+    /// Gas limit
+    fn gas_limit(&self) -> u64;
+    /// Gas used
+    fn gas_used(&self) -> u64;
+    /// Timestamp in seconds
+    fn timestamp(&self) -> u64;
+    /// Extra data
+    fn extra_data(&self) -> &[u8];
+    /// PoW nonce
+    fn nonce(&self) -> u64;
+    /// PermiaHash mix digest
+    fn mix_hash(&self) -> B256;
+}
+
+impl PowHeader for Header {
+    fn parent_hash(&self) -> B256 {
+        self.parent_hash
+    }
+
+    fn beneficiary(&self) -> Address {
+        self.beneficiary
+    }
+
+    fn state_root(&self) -> B256 {
+        self.state_root
+    }
+
+    fn transactions_root(&self) -> B256 {
+        self.transactions_root
+    }
+
+    fn receipts_root(&self) -> B256 {
+        self.receipts_root
+    }
+
+    fn difficulty(&self) -> U256 {
+        self.difficulty
+    }
+
+    fn number(&self) -> u64 {
+        self.number
+    }
+
+    fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    fn extra_data(&self) -> &[u8] {
+        &self.extra_data
+    }
+
+    fn nonce(&self) -> u64 {
+        u64::from_be_bytes(self.nonce.0)
+    }
+
+    fn mix_hash(&self) -> B256 {
+        self.mix_hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_nonce_is_big_endian() {
+        let header = Header { nonce: 0x0102_u64.to_be_bytes().into(), ..Default::default() };
+        assert_eq!(PowHeader::nonce(&header), 0x0102);
+    }
+}
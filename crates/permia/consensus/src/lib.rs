@@ -6,25 +6,49 @@
 
 pub mod pow;
 pub mod difficulty;
+pub mod header;
+pub mod block_time;
 pub mod reth;
 
-pub use reth::PermiaPoWConsensus;
+pub use block_time::BlockTimeStats;
+pub use header::PowHeader;
+pub use reth::{permia_error, permia_hash_config, PermiaPoWConsensus};
 
-use alloy_consensus::Header;
-use alloy_primitives::U256;
+use alloy_consensus::Sealable;
+use alloy_primitives::{B256, U256};
+use reth_primitives_traits::SealedHeader;
 use std::sync::Arc;
 
 /// Permia chain ID
 pub const PERMIA_CHAIN_ID: u64 = 42069;
 
-/// Target block time in milliseconds
-pub const BLOCK_TIME_MS: u64 = 400;
+/// Permia testnet chain ID
+pub const PERMIA_TESTNET_CHAIN_ID: u64 = 42070;
+
+/// Permia devnet chain ID
+pub const PERMIA_DEVNET_CHAIN_ID: u64 = 42071;
+
+/// Default target block time in milliseconds
+///
+/// Chains may override it in their genesis, see [`reth_chainspec::permia_block_time_ms`].
+pub const BLOCK_TIME_MS: u64 = reth_chainspec::PERMIA_BLOCK_TIME_MS;
+
+/// Maximum allowed header extra data size in bytes
+pub const MAX_EXTRA_DATA_SIZE: usize = 32;
+
+/// Default maximum time a header timestamp may be ahead of the local clock, in seconds
+///
+/// Also the most a node may be configured to allow, see
+/// [`PermiaPoWConsensus::with_max_future_drift`].
+pub const DEFAULT_MAX_FUTURE_DRIFT_SECS: u64 = 15;
 
 /// Permia consensus implementation
 #[derive(Debug, Clone)]
 pub struct PermiaConsensus {
     /// Difficulty calculator
     difficulty_calc: Arc<difficulty::DifficultyCalculator>,
+    /// PermiaHash parameters headers are sealed with
+    hash_config: pow::PermiaHashConfig,
 }
 
 impl PermiaConsensus {
@@ -32,16 +56,68 @@ impl PermiaConsensus {
     pub fn new() -> Self {
         Self {
             difficulty_calc: Arc::new(difficulty::DifficultyCalculator::new()),
+            hash_config: pow::PermiaHashConfig::default(),
+        }
+    }
+    
+    /// Create a consensus instance with the parameters of the given chain
+    ///
+    /// See [`difficulty::min_difficulty_for_chain`].
+    pub fn for_chain(chain_id: u64) -> Self {
+        Self {
+            difficulty_calc: Arc::new(difficulty::DifficultyCalculator::for_chain(chain_id)),
+            hash_config: pow::PermiaHashConfig::default(),
         }
     }
     
+    /// Use the same difficulty for every block, see [`difficulty::DifficultyCalculator::fixed`]
+    pub fn with_fixed_difficulty(mut self, difficulty: U256) -> Self {
+        self.difficulty_calc = Arc::new(difficulty::DifficultyCalculator::fixed(difficulty));
+        self
+    }
+    
+    /// Verify headers under the given PermiaHash parameters, e.g. the chain's
+    /// [`permia_hash_config`]
+    pub fn with_hash_config(mut self, config: pow::PermiaHashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
+    
+    /// Get the PermiaHash parameters headers are sealed with
+    pub fn hash_config(&self) -> &pow::PermiaHashConfig {
+        &self.hash_config
+    }
+    
     /// Verify PermiaHash proof of work
-    pub fn verify_pow(&self, header: &Header) -> Result<(), PermiaConsensusError> {
-        pow::verify_pow(header).map_err(|_| PermiaConsensusError::InvalidProofOfWork)
+    pub fn verify_pow<H: PowHeader>(&self, header: &H) -> Result<(), PermiaConsensusError> {
+        pow::verify_pow_with_config(header, &self.hash_config)
+            .map_err(|_| PermiaConsensusError::InvalidProofOfWork)
     }
     
-    /// Calculate next block difficulty
-    pub fn calculate_difficulty(&self, parent: &Header, timestamp: u64) -> U256 {
+    /// Validate a sealed header's hash and PermiaHash seal in one check
+    ///
+    /// The sealed hash must be the hash of the header, the header's `mix_hash` must be the
+    /// mix digest of its seal hash and nonce, and the resulting hash must meet the target.
+    pub fn validate_sealed_header<H: PowHeader + Sealable>(
+        &self,
+        header: &SealedHeader<H>,
+    ) -> Result<(), PermiaConsensusError> {
+        let computed = header.header().hash_slow();
+        if computed != header.hash() {
+            return Err(PermiaConsensusError::BlockHashMismatch { sealed: header.hash(), computed });
+        }
+        self.verify_pow(header.header())
+    }
+    
+    /// Verify PermiaHash proof of work for many headers in parallel
+    ///
+    /// Returns one result per header, in order. See [`pow::verify_pow_batch_with_config`].
+    pub fn verify_pow_batch<H: PowHeader + Sync>(&self, headers: &[H]) -> Vec<Result<(), PermiaConsensusError>> {
+        pow::verify_pow_batch_with_config(headers, &self.hash_config)
+    }
+    
+    /// Calculate next block difficulty for a header timestamp in seconds
+    pub fn calculate_difficulty<H: PowHeader>(&self, parent: &H, timestamp: u64) -> U256 {
         self.difficulty_calc.calculate(parent, timestamp)
     }
     
@@ -49,6 +125,11 @@ impl PermiaConsensus {
     pub fn min_difficulty(&self) -> U256 {
         self.difficulty_calc.min_difficulty()
     }
+    
+    /// Get the fixed difficulty, if difficulty adjustment is disabled
+    pub fn fixed_difficulty(&self) -> Option<U256> {
+        self.difficulty_calc.fixed_difficulty()
+    }
 }
 
 impl Default for PermiaConsensus {
@@ -64,6 +145,18 @@ pub enum PermiaConsensusError {
     InvalidProofOfWork,
     #[error("invalid difficulty")]
     InvalidDifficulty,
+    /// Sealed hash that is not the hash of the header
+    #[error("sealed hash {sealed} does not match header hash {computed}")]
+    BlockHashMismatch { sealed: B256, computed: B256 },
+    /// PermiaHash parameters that cannot describe a DAG
+    #[error("invalid PermiaHash config: {0}")]
+    InvalidHashConfig(String),
+    /// Epoch DAG used to verify a block of another epoch
+    #[error("DAG of epoch {dag_epoch} used for a block of epoch {block_epoch}")]
+    EpochMismatch { block_epoch: u64, dag_epoch: u64 },
+    /// Difficulty outside the band allowed around the expected value
+    #[error("difficulty out of band: expected ~{expected}, got {actual}")]
+    DifficultyOutOfBand { expected: U256, actual: U256 },
     #[error("timestamp too old")]
     TimestampTooOld,
     #[error("block number mismatch")]
@@ -72,6 +165,18 @@ pub enum PermiaConsensusError {
     ExtraDataTooLarge,
     #[error("gas used exceeds limit")]
     GasUsedExceedsLimit,
+    /// Gas limit other than the one fixed by the chain spec
+    #[error("gas limit {actual} does not match the fixed gas limit {expected}")]
+    GasLimitMismatch { expected: u64, actual: u64 },
+    /// Zero difficulty, which only merged (PoS) headers carry
+    #[error("difficulty cannot be zero in PoW")]
+    ZeroDifficulty,
+    /// Header field set while the hardfork introducing it is inactive
+    #[error("Header field {0} is set before the hardfork introducing it")]
+    PosHeaderField(&'static str),
+    /// Header field unset while the hardfork introducing it is active
+    #[error("Header field {0} is missing after the hardfork introducing it")]
+    MissingForkHeaderField(&'static str),
 }
 
 #[cfg(test)]
@@ -83,4 +188,50 @@ mod tests {
         let consensus = PermiaConsensus::new();
         assert!(consensus.min_difficulty() > U256::ZERO);
     }
+    
+    #[test]
+    fn test_validate_sealed_header() {
+        use alloy_consensus::Header;
+        
+        let consensus = PermiaConsensus::new();
+        
+        // Difficulty 1 accepts any nonce, so only the mix hash has to be right
+        let mut header = Header { number: 3, difficulty: U256::from(1u64), ..Default::default() };
+        header.nonce = 21u64.to_be_bytes().into();
+        header.mix_hash = pow::permia_hash_with_epoch(&pow::compute_seal_hash(&header), 21, 3).mix_digest;
+        
+        let sealed = SealedHeader::seal_slow(header.clone());
+        assert!(consensus.validate_sealed_header(&sealed).is_ok());
+        
+        // Mix hash of another nonce, resealed so the hash itself is consistent
+        let mut swapped = header.clone();
+        swapped.mix_hash = pow::permia_hash_with_epoch(&pow::compute_seal_hash(&header), 22, 3).mix_digest;
+        assert!(matches!(
+            consensus.validate_sealed_header(&SealedHeader::seal_slow(swapped.clone())),
+            Err(PermiaConsensusError::InvalidProofOfWork)
+        ));
+        
+        // Swapped mix hash announced under the original hash
+        assert!(matches!(
+            consensus.validate_sealed_header(&SealedHeader::new(swapped, sealed.hash())),
+            Err(PermiaConsensusError::BlockHashMismatch { .. })
+        ));
+    }
+    
+    #[test]
+    fn test_hash_config() {
+        use alloy_consensus::Header;
+        
+        let v2 = pow::PermiaHashConfig { dag_version: pow::DagVersion::V2, ..Default::default() };
+        let consensus = PermiaConsensus::new().with_hash_config(v2.clone());
+        
+        let mut header = Header { number: 3, difficulty: U256::from(1u64), ..Default::default() };
+        header.nonce = 21u64.to_be_bytes().into();
+        let seal_hash = pow::compute_seal_hash(&header);
+        header.mix_hash = pow::permia_hash_with_config(&seal_hash, 21, 3, &v2).unwrap().mix_digest;
+        
+        assert!(consensus.verify_pow(&header).is_ok());
+        assert!(consensus.verify_pow_batch(&[header.clone()])[0].is_ok());
+        assert!(PermiaConsensus::new().verify_pow(&header).is_err());
+    }
 }
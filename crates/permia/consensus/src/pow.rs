@@ -10,24 +10,52 @@
 //!      b. mix = mix XOR DAG[index]
 //!      c. mix = BLAKE3(mix)
 //!   4. result = BLAKE3(mix)
+//!   5. mix_hash = BLAKE3("permia_mix_digest" || mix)
+//!
+//! The `mix_hash` committed in the header covers the full 64-byte mix, and is
+//! domain-separated from the final hash so the two are never equal.
 //!
 //! Hash Functions Used:
+//! - Keccak256: Seal hash of the header, see [`compute_seal_hash`]
 //! - BLAKE3: Primary hash (fast, cryptographically secure)
-//! - SHA3-256: DAG element generation (NIST standard, different construction)
+//! - SHA3-256: DAG element generation (NIST standard, different construction),
+//!   or a single SHA3-512 pass under [`DagVersion::V2`]
+//!
+//! # Domain separation
+//!
+//! Each stage hashes a distinct input layout, so no output of one stage can stand in
+//! for another:
+//! - The seal hash covers the pre-London header fields except the ommers hash, logs
+//!   bloom, nonce and mix hash, see [`compute_seal_hash`]. It is Keccak256, not SHA3-256:
+//!   the two only differ in padding, so they are easily confused but give different
+//!   outputs.
+//! - The PermiaHash seed is BLAKE3 over the 32-byte seal hash and the nonce, the final
+//!   hash BLAKE3 over the 64-byte mix.
+//! - The mix digest prefixes the mix with `permia_mix_digest`, and the epoch seed the
+//!   epoch with `permia_epoch_`, so neither equals the final hash.
+//! - DAG elements are SHA3 over the epoch seed and the element index.
+//!
+//! Swapping any of these functions changes every header hash, which the committed test
+//! vectors catch.
 //!
 //! Using both BLAKE3 and SHA3 provides defense-in-depth:
 //! - If BLAKE3 is compromised, SHA3 provides backup security
 //! - Different internal constructions (Merkle-Damgård vs sponge)
 //! - No known practical attack benefits from this combination
 
-use alloy_consensus::Header;
 use alloy_primitives::{B256, U256};
 use blake3::Hasher as Blake3;
-use sha3::{Digest, Sha3_256};
+use rayon::prelude::*;
+use sha3::{Digest, Sha3_256, Sha3_512};
+use std::collections::{BTreeMap, HashMap};
 
-use crate::PermiaConsensusError;
+use crate::{PermiaConsensusError, PowHeader};
 
 /// PermiaHash configuration
+///
+/// Every parameter of the hash is read from here, the default being the network
+/// parameters. Headers only verify under the configuration they were mined with.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PermiaHashConfig {
     /// Number of mixing rounds
     pub rounds: u32,
@@ -35,33 +63,119 @@ pub struct PermiaHashConfig {
     pub dag_size: usize,
     /// Epoch length in blocks (~3.5 days at 400ms blocks)
     pub epoch_length: u64,
+    /// DAG element derivation
+    pub dag_version: DagVersion,
 }
 
 impl Default for PermiaHashConfig {
     fn default() -> Self {
         Self {
-            rounds: 64,
-            dag_size: 4 * 1024 * 1024 * 1024, // 4 GB per spec
-            epoch_length: 30000,             // ~3.5 days
+            rounds: MIX_ROUNDS as u32,
+            dag_size: DAG_SIZE,
+            epoch_length: EPOCH_LENGTH,
+            dag_version: DagVersion::default(),
+        }
+    }
+}
+
+/// Derivation of DAG elements from the epoch seed
+///
+/// Changing the version changes every mix, so it is a consensus parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DagVersion {
+    /// Two chained SHA3-256 hashes per element
+    #[default]
+    V1,
+    /// One SHA3-512 hash per element, half the hashing of V1
+    V2,
+}
+
+impl DagVersion {
+    /// Generate the DAG element at `index` for an epoch seed
+    pub fn element(self, epoch_seed: &[u8; 32], index: u64) -> [u8; DAG_ELEMENT_SIZE] {
+        match self {
+            Self::V1 => generate_dag_element(epoch_seed, index),
+            Self::V2 => generate_dag_element_v2(epoch_seed, index),
+        }
+    }
+}
+
+impl TryFrom<u64> for DagVersion {
+    type Error = PermiaConsensusError;
+
+    /// Version by number, as selected in the genesis config
+    fn try_from(version: u64) -> Result<Self, Self::Error> {
+        match version {
+            1 => Ok(Self::V1),
+            2 => Ok(Self::V2),
+            _ => Err(PermiaConsensusError::InvalidHashConfig(format!(
+                "unknown DAG version {version}"
+            ))),
         }
     }
 }
 
+impl PermiaHashConfig {
+    /// Number of DAG elements
+    pub fn dag_elements(&self) -> u64 {
+        (self.dag_size / DAG_ELEMENT_SIZE) as u64
+    }
+
+    /// Epoch containing a block
+    pub fn epoch_of(&self, block_number: u64) -> u64 {
+        block_number / self.epoch_length
+    }
+
+    /// Check that the parameters describe a usable DAG
+    pub fn validate(&self) -> Result<(), PermiaConsensusError> {
+        if self.rounds == 0 || self.epoch_length == 0 {
+            return Err(PermiaConsensusError::InvalidHashConfig(
+                "rounds and epoch length must be non-zero".to_string(),
+            ));
+        }
+        if self.dag_size == 0 || !self.dag_size.is_multiple_of(DAG_ELEMENT_SIZE) {
+            return Err(PermiaConsensusError::InvalidHashConfig(format!(
+                "DAG size {} is not a non-zero multiple of the {DAG_ELEMENT_SIZE} byte element size",
+                self.dag_size
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 /// DAG element size in bytes (64 bytes = 512 bits)
-const DAG_ELEMENT_SIZE: usize = 64;
+pub const DAG_ELEMENT_SIZE: usize = 64;
+
+/// DAG size in bytes (4 GB per spec)
+const DAG_SIZE: usize = 4 * 1024 * 1024 * 1024;
+
+/// Number of mixing rounds
+const MIX_ROUNDS: u64 = 64;
 
-/// Number of DAG elements (4GB / 64 bytes)
-const DAG_ELEMENTS: u64 = (4 * 1024 * 1024 * 1024) / DAG_ELEMENT_SIZE as u64;
+/// Epoch length in blocks
+const EPOCH_LENGTH: u64 = 30000;
+
+/// Domain separator for the mix digest
+const MIX_DIGEST_DOMAIN: &[u8] = b"permia_mix_digest";
 
 /// Hash result from PermiaHash
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HashResult {
     /// The computed hash
     pub hash: B256,
-    /// Mix digest for verification
+    /// Mix digest for verification, committed as the header's `mix_hash`
     pub mix_digest: B256,
 }
 
+/// Compute the mix digest committed in the header from the full 64-byte mix
+pub fn compute_mix_digest(mix: &[u8; DAG_ELEMENT_SIZE]) -> B256 {
+    let mut hasher = Blake3::new();
+    hasher.update(MIX_DIGEST_DOMAIN);
+    hasher.update(mix);
+    B256::from_slice(hasher.finalize().as_bytes())
+}
+
 /// Generate a DAG element from epoch seed and index
 /// 
 /// In production, this would be cached in a 4GB DAG structure.
@@ -86,9 +200,107 @@ fn generate_dag_element(epoch_seed: &[u8; 32], index: u64) -> [u8; DAG_ELEMENT_S
     element
 }
 
-/// Compute epoch seed from block number
-pub fn compute_epoch_seed(block_number: u64) -> [u8; 32] {
-    let epoch = block_number / 30000;
+/// Generate a DAG element in a single SHA3-512 pass
+///
+/// Keeps SHA3 for element generation, so the mixing and the DAG still use
+/// different hash constructions.
+fn generate_dag_element_v2(epoch_seed: &[u8; 32], index: u64) -> [u8; DAG_ELEMENT_SIZE] {
+    let mut hasher = Sha3_512::new();
+    hasher.update(epoch_seed);
+    hasher.update(index.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// DAG index read in mixing round `round` for a seed byte
+fn dag_index(seed_byte: u8, round: u64, dag_elements: u64) -> u64 {
+    (seed_byte as u64 * (round + 1) * 31337) % dag_elements
+}
+
+/// The DAG elements of one epoch that PermiaHash can read
+///
+/// Indices depend only on a seed byte and the round, so an epoch has at most
+/// 256 × 64 reachable elements (~1 MB). Verifying many headers of the same epoch
+/// against one `EpochDag` avoids regenerating them per header.
+#[derive(Debug, Clone)]
+pub struct EpochDag {
+    /// Epoch number
+    epoch: u64,
+    /// Parameters the DAG was generated with
+    config: PermiaHashConfig,
+    /// Reachable elements by DAG index
+    elements: HashMap<u64, [u8; DAG_ELEMENT_SIZE]>,
+}
+
+impl EpochDag {
+    /// Generate the reachable elements of an epoch
+    pub fn new(epoch: u64) -> Self {
+        Self::generate(epoch, PermiaHashConfig::default())
+    }
+
+    /// Generate the reachable elements of an epoch under a custom configuration
+    pub fn with_config(epoch: u64, config: PermiaHashConfig) -> Result<Self, PermiaConsensusError> {
+        config.validate()?;
+        Ok(Self::generate(epoch, config))
+    }
+
+    fn generate(epoch: u64, config: PermiaHashConfig) -> Self {
+        let epoch_seed = epoch_seed(epoch);
+        let dag_elements = config.dag_elements();
+        let mut indices: Vec<u64> = (0..=u8::MAX)
+            .flat_map(|seed_byte| {
+                (0..config.rounds as u64).map(move |round| dag_index(seed_byte, round, dag_elements))
+            })
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let elements = indices
+            .into_par_iter()
+            .map(|index| (index, config.dag_version.element(&epoch_seed, index)))
+            .collect();
+
+        Self { epoch, config, elements }
+    }
+
+    /// Generate the DAG for the epoch containing a block, with the epochs of `config`
+    pub fn for_block(
+        block_number: u64,
+        config: PermiaHashConfig,
+    ) -> Result<Self, PermiaConsensusError> {
+        config.validate()?;
+        Ok(Self::generate(config.epoch_of(block_number), config))
+    }
+
+    /// Epoch number of this DAG
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Parameters this DAG was generated with
+    pub fn config(&self) -> &PermiaHashConfig {
+        &self.config
+    }
+
+    /// Get a DAG element
+    fn element(&self, index: u64) -> [u8; DAG_ELEMENT_SIZE] {
+        self.elements
+            .get(&index)
+            .copied()
+            .unwrap_or_else(|| self.config.dag_version.element(&epoch_seed(self.epoch), index))
+    }
+}
+
+/// Compute epoch seed from block number, with the epochs of `config`
+///
+/// # Panics
+///
+/// If `config` has a zero epoch length, see [`PermiaHashConfig::validate`].
+pub fn compute_epoch_seed(block_number: u64, config: &PermiaHashConfig) -> [u8; 32] {
+    epoch_seed(config.epoch_of(block_number))
+}
+
+/// Compute the seed of an epoch
+fn epoch_seed(epoch: u64) -> [u8; 32] {
     let mut hasher = Blake3::new();
     hasher.update(b"permia_epoch_");
     hasher.update(&epoch.to_le_bytes());
@@ -114,6 +326,54 @@ pub fn permia_hash(seal_hash: &B256, nonce: u64) -> HashResult {
 
 /// Compute PermiaHash with specific epoch
 pub fn permia_hash_with_epoch(seal_hash: &B256, nonce: u64, block_number: u64) -> HashResult {
+    hash_mix(&permia_mix(seal_hash, nonce, block_number, &PermiaHashConfig::default()))
+}
+
+/// Compute PermiaHash with specific epoch under a custom configuration
+pub fn permia_hash_with_config(
+    seal_hash: &B256,
+    nonce: u64,
+    block_number: u64,
+    config: &PermiaHashConfig,
+) -> Result<HashResult, PermiaConsensusError> {
+    config.validate()?;
+    Ok(hash_mix(&permia_mix(seal_hash, nonce, block_number, config)))
+}
+
+/// Compute the final hash and mix digest from the 64-byte mix (steps 4-5)
+fn hash_mix(mix: &[u8; DAG_ELEMENT_SIZE]) -> HashResult {
+    // Step 4: result = BLAKE3(mix)
+    let mut final_hasher = Blake3::new();
+    final_hasher.update(mix);
+    let final_hash = final_hasher.finalize();
+    
+    HashResult {
+        hash: B256::from_slice(final_hash.as_bytes()),
+        mix_digest: compute_mix_digest(mix),
+    }
+}
+
+/// Compute the 64-byte PermiaHash mix (steps 1-3)
+fn permia_mix(
+    seal_hash: &B256,
+    nonce: u64,
+    block_number: u64,
+    config: &PermiaHashConfig,
+) -> [u8; DAG_ELEMENT_SIZE] {
+    // Get epoch seed for DAG generation
+    let epoch_seed = epoch_seed(config.epoch_of(block_number));
+    
+    // DAG elements are computed on demand, see `EpochDag` for the cached variant
+    mix_with(seal_hash, nonce, config, |index| config.dag_version.element(&epoch_seed, index))
+}
+
+/// Compute the 64-byte mix reading DAG elements from `dag_element`
+fn mix_with(
+    seal_hash: &B256,
+    nonce: u64,
+    config: &PermiaHashConfig,
+    dag_element: impl Fn(u64) -> [u8; DAG_ELEMENT_SIZE],
+) -> [u8; DAG_ELEMENT_SIZE] {
     // Step 1: seed = BLAKE3(header || nonce)
     let mut blake = Blake3::new();
     blake.update(seal_hash.as_slice());
@@ -121,26 +381,23 @@ pub fn permia_hash_with_epoch(seal_hash: &B256, nonce: u64, block_number: u64) -
     let seed_hash = blake.finalize();
     let seed: [u8; 32] = *seed_hash.as_bytes();
     
-    // Get epoch seed for DAG generation
-    let epoch_seed = compute_epoch_seed(block_number);
-    
     // Initialize mix with seed (64 bytes)
     let mut mix = [0u8; DAG_ELEMENT_SIZE];
     mix[..32].copy_from_slice(&seed);
     mix[32..].copy_from_slice(&seed);
     
     // Step 2-3: 64 rounds of DAG access and mixing
-    for i in 0..64u64 {
+    let dag_elements = config.dag_elements();
+    for i in 0..config.rounds as u64 {
         // a. index = seed[i % 32] % DAG_SIZE
-        let seed_byte = seed[(i % 32) as usize] as u64;
-        let index = (seed_byte * (i + 1) * 31337) % DAG_ELEMENTS;
+        let index = dag_index(seed[(i % 32) as usize], i, dag_elements);
         
-        // b. Get DAG element (computed on-demand, would be cached in production)
-        let dag_element = generate_dag_element(&epoch_seed, index);
+        // b. Get DAG element
+        let element = dag_element(index);
         
         // c. mix = mix XOR dag_element
         for j in 0..DAG_ELEMENT_SIZE {
-            mix[j] ^= dag_element[j];
+            mix[j] ^= element[j];
         }
         
         // d. mix = BLAKE3(mix)
@@ -156,34 +413,98 @@ pub fn permia_hash_with_epoch(seal_hash: &B256, nonce: u64, block_number: u64) -
         mix[32..].copy_from_slice(mix_result2.as_bytes());
     }
     
-    // Step 4: result = BLAKE3(mix)
-    let mut final_hasher = Blake3::new();
-    final_hasher.update(&mix);
-    let final_hash = final_hasher.finalize();
-    
-    HashResult {
-        hash: B256::from_slice(final_hash.as_bytes()),
-        mix_digest: B256::from_slice(&mix[..32]),
-    }
+    mix
 }
 
 /// Verify PoW for a header
-pub fn verify_pow(header: &Header) -> Result<(), PermiaConsensusError> {
+pub fn verify_pow<H: PowHeader>(header: &H) -> Result<(), PermiaConsensusError> {
+    verify_pow_with_config(header, &PermiaHashConfig::default())
+}
+
+/// Verify PoW for a header under a custom configuration
+///
+/// Fails closed: an unusable configuration is an error, and a header mined under
+/// different parameters produces a different mix and is rejected.
+pub fn verify_pow_with_config<H: PowHeader>(
+    header: &H,
+    config: &PermiaHashConfig,
+) -> Result<(), PermiaConsensusError> {
+    config.validate()?;
     let seal_hash = compute_seal_hash(header);
     
-    // Extract nonce from header (FixedBytes<8> -> u64)
-    let nonce = u64::from_be_bytes(header.nonce.0);
-    
     // Use block number for epoch-based DAG calculation
-    let result = permia_hash_with_epoch(&seal_hash, nonce, header.number);
+    check_mix(header, &permia_mix(&seal_hash, header.nonce(), header.number(), config))
+}
+
+/// Verify PoW for a header, reading DAG elements from a cached epoch DAG
+///
+/// `dag` must be the DAG of the header's epoch, otherwise verification fails with
+/// [`PermiaConsensusError::EpochMismatch`].
+pub fn verify_pow_with_dag<H: PowHeader>(
+    header: &H,
+    dag: &EpochDag,
+) -> Result<(), PermiaConsensusError> {
+    let block_epoch = dag.config.epoch_of(header.number());
+    if dag.epoch() != block_epoch {
+        return Err(PermiaConsensusError::EpochMismatch { block_epoch, dag_epoch: dag.epoch() });
+    }
+    
+    let seal_hash = compute_seal_hash(header);
+    
+    check_mix(header, &mix_with(&seal_hash, header.nonce(), &dag.config, |index| dag.element(index)))
+}
+
+/// Verify PoW for many headers in parallel
+///
+/// Headers are grouped by epoch so every header of an epoch shares one [`EpochDag`].
+/// Results are returned in the order of `headers`.
+pub fn verify_pow_batch<H: PowHeader + Sync>(headers: &[H]) -> Vec<Result<(), PermiaConsensusError>> {
+    verify_pow_batch_with_config(headers, &PermiaHashConfig::default())
+}
+
+/// Verify PoW for many headers in parallel under a custom configuration
+///
+/// Every header fails if the configuration is unusable.
+pub fn verify_pow_batch_with_config<H: PowHeader + Sync>(
+    headers: &[H],
+    config: &PermiaHashConfig,
+) -> Vec<Result<(), PermiaConsensusError>> {
+    if let Err(err) = config.validate() {
+        return vec![Err(err); headers.len()];
+    }
+    
+    let mut by_epoch: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for (index, header) in headers.iter().enumerate() {
+        by_epoch.entry(config.epoch_of(header.number())).or_default().push(index);
+    }
+    
+    let mut results = vec![Ok(()); headers.len()];
+    for (epoch, indices) in by_epoch {
+        let dag = EpochDag::generate(epoch, config.clone());
+        let verified: Vec<_> = indices
+            .par_iter()
+            .map(|&index| (index, verify_pow_with_dag(&headers[index], &dag)))
+            .collect();
+        
+        for (index, result) in verified {
+            results[index] = result;
+        }
+    }
+    
+    results
+}
+
+/// Check a computed mix against the header's mix hash and difficulty target
+fn check_mix<H: PowHeader>(header: &H, mix: &[u8; DAG_ELEMENT_SIZE]) -> Result<(), PermiaConsensusError> {
+    let result = hash_mix(mix);
     
     // Check mix digest matches
-    if result.mix_digest != header.mix_hash {
+    if result.mix_digest != header.mix_hash() {
         return Err(PermiaConsensusError::InvalidProofOfWork);
     }
     
     // Check hash meets difficulty target
-    let target = difficulty_to_target(header.difficulty);
+    let target = difficulty_to_target(header.difficulty());
     let hash_value = U256::from_be_bytes(result.hash.0);
     
     if hash_value > target {
@@ -193,22 +514,30 @@ pub fn verify_pow(header: &Header) -> Result<(), PermiaConsensusError> {
     Ok(())
 }
 
-/// Compute seal hash (header hash without nonce/mix_hash)
-pub fn compute_seal_hash(header: &Header) -> B256 {
+/// Compute seal hash, the header commitment the proof of work is computed over
+///
+/// Covers the parent hash, beneficiary, state, transactions and receipts roots,
+/// difficulty, number, gas limit, gas used, timestamp and extra data. The ommers hash,
+/// logs bloom, base fee and the fields added since London (withdrawals root, blob gas,
+/// parent beacon block root, requests hash) are not committed to, nor of course the
+/// nonce and mix hash. The node miner seals only the committed fields and the node fills
+/// in the rest after mining (`mined_block` in `permia-node`). The proof of work doesn't
+/// bind those fields, they are only checked against the block by block validation.
+pub fn compute_seal_hash<H: PowHeader>(header: &H) -> B256 {
     use sha3::{Digest, Keccak256};
     
     let mut hasher = Keccak256::new();
-    hasher.update(header.parent_hash.as_slice());
-    hasher.update(header.beneficiary.as_slice());
-    hasher.update(header.state_root.as_slice());
-    hasher.update(header.transactions_root.as_slice());
-    hasher.update(header.receipts_root.as_slice());
-    hasher.update(&header.difficulty.to_be_bytes::<32>());
-    hasher.update(&header.number.to_be_bytes());
-    hasher.update(&header.gas_limit.to_be_bytes());
-    hasher.update(&header.gas_used.to_be_bytes());
-    hasher.update(&header.timestamp.to_be_bytes());
-    hasher.update(&header.extra_data);
+    hasher.update(header.parent_hash().as_slice());
+    hasher.update(header.beneficiary().as_slice());
+    hasher.update(header.state_root().as_slice());
+    hasher.update(header.transactions_root().as_slice());
+    hasher.update(header.receipts_root().as_slice());
+    hasher.update(&header.difficulty().to_be_bytes::<32>());
+    hasher.update(&header.number().to_be_bytes());
+    hasher.update(&header.gas_limit().to_be_bytes());
+    hasher.update(&header.gas_used().to_be_bytes());
+    hasher.update(&header.timestamp().to_be_bytes());
+    hasher.update(header.extra_data());
     
     B256::from_slice(&hasher.finalize())
 }
@@ -232,6 +561,7 @@ pub fn target_to_difficulty(target: U256) -> U256 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_consensus::Header;
     
     #[test]
     fn test_permia_hash() {
@@ -242,6 +572,147 @@ mod tests {
         assert_ne!(result.mix_digest, B256::ZERO);
     }
     
+    #[test]
+    fn test_mix_digest_commitment() {
+        let mut header = Header { number: 1, difficulty: U256::from(1u64), ..Default::default() };
+        let seal_hash = compute_seal_hash(&header);
+        let nonce = 42u64;
+        header.nonce = nonce.to_be_bytes().into();
+        
+        // Miner and verifier agree on the full-mix digest
+        let result = permia_hash_with_epoch(&seal_hash, nonce, header.number);
+        header.mix_hash = result.mix_digest;
+        assert!(verify_pow(&header).is_ok());
+        
+        let mix = permia_mix(&seal_hash, nonce, header.number, &PermiaHashConfig::default());
+        assert_eq!(result.mix_digest, compute_mix_digest(&mix));
+        assert_ne!(result.mix_digest, result.hash);
+        
+        // Old-style digest (first 32 bytes of the mix) is rejected
+        header.mix_hash = B256::from_slice(&mix[..32]);
+        assert!(matches!(verify_pow(&header), Err(PermiaConsensusError::InvalidProofOfWork)));
+        
+        // Changing only the second half of the mix changes the digest
+        let mut tampered = mix;
+        tampered[63] ^= 1;
+        assert_ne!(compute_mix_digest(&tampered), result.mix_digest);
+    }
+    
+    /// Header with a valid seal at difficulty 1, where every nonce meets the target
+    fn sealed_header(number: u64) -> Header {
+        let mut header = Header { number, difficulty: U256::from(1u64), ..Default::default() };
+        let nonce = number * 7;
+        header.nonce = nonce.to_be_bytes().into();
+        header.mix_hash = permia_hash_with_epoch(&compute_seal_hash(&header), nonce, number).mix_digest;
+        header
+    }
+    
+    #[test]
+    fn test_epoch_dag_matches_on_demand() {
+        let config = PermiaHashConfig::default();
+        let dag = EpochDag::for_block(EPOCH_LENGTH + 5, config.clone()).unwrap();
+        assert_eq!(dag.epoch(), 1);
+        
+        let epoch_seed = compute_epoch_seed(EPOCH_LENGTH + 5, &config);
+        for index in [0, dag_index(1, 0, config.dag_elements()), dag_index(255, 63, config.dag_elements())] {
+            assert_eq!(dag.element(index), generate_dag_element(&epoch_seed, index));
+        }
+        
+        let seal_hash = B256::repeat_byte(3);
+        assert_eq!(
+            mix_with(&seal_hash, 9, &config, |index| dag.element(index)),
+            permia_mix(&seal_hash, 9, EPOCH_LENGTH + 5, &config)
+        );
+    }
+    
+    #[test]
+    fn test_config_mismatch_fails_closed() {
+        // Small DAG and short epochs, as a test network might use
+        let small = PermiaHashConfig {
+            rounds: 16,
+            dag_size: 1024 * DAG_ELEMENT_SIZE,
+            epoch_length: 100,
+            dag_version: DagVersion::V1,
+        };
+        let mut header = Header { number: 250, difficulty: U256::from(1u64), ..Default::default() };
+        header.nonce = 5u64.to_be_bytes().into();
+        header.mix_hash = permia_hash_with_config(&compute_seal_hash(&header), 5, 250, &small)
+            .unwrap()
+            .mix_digest;
+        
+        assert!(verify_pow_with_config(&header, &small).is_ok());
+        assert!(matches!(verify_pow(&header), Err(PermiaConsensusError::InvalidProofOfWork)));
+        
+        // Each parameter on its own changes the result
+        for other in [
+            PermiaHashConfig { rounds: 17, ..small.clone() },
+            PermiaHashConfig { dag_size: 2048 * DAG_ELEMENT_SIZE, ..small.clone() },
+            PermiaHashConfig { epoch_length: 200, ..small.clone() },
+            PermiaHashConfig { dag_version: DagVersion::V2, ..small.clone() },
+        ] {
+            assert!(verify_pow_with_config(&header, &other).is_err(), "{other:?}");
+        }
+        
+        // The DAG of a block follows the configured epochs
+        let dag = EpochDag::for_block(250, small.clone()).unwrap();
+        assert_eq!(dag.epoch(), 2);
+        let seed = compute_epoch_seed(2 * EPOCH_LENGTH, &PermiaHashConfig::default());
+        assert_eq!(compute_epoch_seed(250, &small), seed);
+        
+        // A DAG of another epoch is rejected before hashing
+        let dag = EpochDag::with_config(1, small.clone()).unwrap();
+        assert!(matches!(
+            verify_pow_with_dag(&header, &dag),
+            Err(PermiaConsensusError::EpochMismatch { block_epoch: 2, dag_epoch: 1 })
+        ));
+        let dag = EpochDag::with_config(2, small.clone()).unwrap();
+        assert!(verify_pow_with_dag(&header, &dag).is_ok());
+        
+        // Batches share the configuration's epochs
+        let batch = [header.clone()];
+        assert!(verify_pow_batch_with_config(&batch, &small)[0].is_ok());
+        assert!(verify_pow_batch(&batch)[0].is_err());
+        
+        // Unusable parameters are an error, not a panic
+        let broken = PermiaHashConfig { dag_size: 100, ..small };
+        assert!(matches!(verify_pow_with_config(&header, &broken), Err(PermiaConsensusError::InvalidHashConfig(_))));
+        assert!(EpochDag::with_config(2, PermiaHashConfig { epoch_length: 0, ..Default::default() }).is_err());
+        let no_epochs = PermiaHashConfig { epoch_length: 0, ..Default::default() };
+        assert!(EpochDag::for_block(250, no_epochs).is_err());
+        assert!(verify_pow_batch_with_config(&batch, &broken)[0].is_err());
+    }
+    
+    #[test]
+    fn test_dag_version_from_number() {
+        assert_eq!(DagVersion::try_from(1).unwrap(), DagVersion::V1);
+        assert_eq!(DagVersion::try_from(2).unwrap(), DagVersion::V2);
+        for unknown in [0, 3, u64::MAX] {
+            let err = DagVersion::try_from(unknown).unwrap_err();
+            assert!(matches!(err, PermiaConsensusError::InvalidHashConfig(_)));
+        }
+    }
+    
+    #[test]
+    fn test_verify_pow_batch() {
+        // Headers spanning two epochs, out of order
+        let mut headers: Vec<_> =
+            [1, EPOCH_LENGTH + 1, 2, EPOCH_LENGTH + 2, 3].into_iter().map(sealed_header).collect();
+        headers[3].mix_hash = B256::repeat_byte(0xff);
+        
+        let results = verify_pow_batch(&headers);
+        assert_eq!(results.len(), headers.len());
+        for (index, result) in results.iter().enumerate() {
+            if index == 3 {
+                assert!(matches!(result, Err(PermiaConsensusError::InvalidProofOfWork)));
+            } else {
+                assert!(result.is_ok(), "header {index} rejected");
+            }
+            assert_eq!(result.is_ok(), verify_pow(&headers[index]).is_ok());
+        }
+        
+        assert!(verify_pow_batch::<Header>(&[]).is_empty());
+    }
+    
     #[test]
     fn test_difficulty_conversion() {
         let difficulty = U256::from(1_000_000u64);
@@ -252,4 +723,92 @@ mod tests {
         let diff = if back > difficulty { back - difficulty } else { difficulty - back };
         assert!(diff < U256::from(1000u64));
     }
+
+    #[test]
+    fn test_dag_element_vectors() {
+        use alloy_primitives::hex;
+
+        let seed = [0x11; 32];
+        assert_eq!(
+            DagVersion::V1.element(&seed, 0),
+            hex!("1572a621509df9141c8a1b6583111a68ec09fc3f20e2ff84825b9668d36837a15a5f7d71322d51c868cfd24893fa144ab4b3275085326296230c74b1cabc7cac")
+        );
+        assert_eq!(
+            DagVersion::V1.element(&seed, 42),
+            hex!("72294602ce27fc6de77c6c3dafe28047531154773e20873b11c61774b632b196fd7c9787db6c24f5cf79b28db38351e42dc6c46155d0554244cdf5c986bbc836")
+        );
+        assert_eq!(
+            DagVersion::V2.element(&seed, 0),
+            hex!("e92ec1999e7af7e4cb65ee1f401b078413f43a8afc0dcc4a0a3b7b2f29e345e1e911d4ad423e0bc1d142f2b64f17167222dc63c3d2e7c216f9f3ec25830c7958")
+        );
+        assert_eq!(
+            DagVersion::V2.element(&seed, 42),
+            hex!("dbd9fdf7c08b851c45faaebe266209a9d0b7955dc85cf251b272ae61a452e7d4d3a1479af5a25028d650c37e0ce4ed606b6a75ae3ac2b638fd5572a2c1e19f38")
+        );
+
+        // The default stays on V1 so existing chains keep verifying
+        assert_eq!(PermiaHashConfig::default().dag_version, DagVersion::V1);
+    }
+
+    /// Committed outputs of every stage of the pipeline, catching any change to the
+    /// hash functions or their inputs
+    #[test]
+    fn test_hash_pipeline_vectors() {
+        use alloy_primitives::{b256, hex};
+
+        // (block number, seal hash, DAG element 7 of its epoch, [(nonce, hash, mix digest)])
+        let vectors = [
+            (
+                1u64,
+                b256!("d341312139add5c569fa72e7ccd5314fb076a7817104413d0aa887c42c62bc0e"),
+                hex!("0be406325671ebe706b04c6457b704a50424705b76e722305885153b0a4f0912950c29b77a53f09009d44e422b04228adeaba9a4e5dcb98836f2a504166fc0da"),
+                [
+                    (
+                        0u64,
+                        b256!("297d0a01fc0191d7704467f32e8fc37698b8926dec21da7c164c183ea9b7b92d"),
+                        b256!("a68fc2f6eaa3c2854eb89f48202b57773979e36fecc734541225db447721a132"),
+                    ),
+                    (
+                        42,
+                        b256!("1d5cebd6e9282bb16e3c0074f50b63528b567629e3bfe3950be67d4ee1bc2076"),
+                        b256!("38a4807e32d8ae2bfb760a3389985990f083734330aabde02109f12261da4e2f"),
+                    ),
+                ],
+            ),
+            // Second epoch, so a different DAG
+            (
+                30_001,
+                b256!("60a567187383b5f4ff7078696f1afd53447470063a395a338da2cd8728c68655"),
+                hex!("e0fbeba454ecb4b6663b2bd01940c03ab5ec2a8f6dfa67bf7ede3e5ebc100aef63a401ee566bb66d9042ecac0329c296a195c62f8b251d6c8efadb2d2e57adbc"),
+                [
+                    (
+                        0,
+                        b256!("f22c41b0084709b56a08c6a8076d374a6dacd01eae2434da66452d4183f5162c"),
+                        b256!("a3785ca60c3415b235c84880e9a96829bc57c6602345d0a42d13b964ea9d13fc"),
+                    ),
+                    (
+                        42,
+                        b256!("8435786197baa5e847b6863eafe2ff38a31e62dd2f15a47ab16625adce0ea6a2"),
+                        b256!("48f951f4169653cafc7f60fb8297acf7492ce024f29c248e0d5d7509d0da3d66"),
+                    ),
+                ],
+            ),
+        ];
+
+        for (number, seal, dag_element, hashes) in vectors {
+            let header =
+                Header { number, difficulty: U256::from(1u64), timestamp: 1_000, ..Default::default() };
+            let seal_hash = compute_seal_hash(&header);
+            assert_eq!(seal_hash, seal, "seal hash of block {number}");
+            let epoch_seed = compute_epoch_seed(number, &PermiaHashConfig::default());
+            let element = DagVersion::V1.element(&epoch_seed, 7);
+            assert_eq!(element, dag_element, "DAG element of block {number}");
+
+            for (nonce, hash, mix_digest) in hashes {
+                let result = permia_hash_with_epoch(&seal_hash, nonce, number);
+                assert_eq!(result.hash, hash, "hash of block {number} nonce {nonce}");
+                assert_eq!(result.mix_digest, mix_digest, "mix digest of block {number} nonce {nonce}");
+            }
+        }
+    }
 }
@@ -1,20 +1,46 @@
 //! Reth Consensus Integration for Permia
 //!
 //! Implements the Reth Consensus traits for PermiaHash PoW.
+//!
+//! # Timestamps
+//!
+//! Header timestamps are **seconds** since the UNIX epoch, following the Ethereum
+//! convention. Sub-second block timing (the 400ms target) is measured outside of
+//! the header and is never committed to it.
 
-use crate::{difficulty::DifficultyCalculator, pow, PermiaConsensusError};
-use alloy_consensus::Header;
-use alloy_primitives::U256;
-use reth_chainspec::ChainSpec;
+use crate::{
+    difficulty::DifficultyCalculator,
+    pow::{self, DagVersion, PermiaHashConfig},
+    BlockTimeStats, PermiaConsensusError, PowHeader, DEFAULT_MAX_FUTURE_DRIFT_SECS,
+    MAX_EXTRA_DATA_SIZE,
+};
+use alloy_primitives::{Address, U256};
+use parking_lot::Mutex;
+use permia_services::{
+    base_block_reward, block_reward, epoch_at, reward_split, ProofStore, ServiceProofBundle,
+    UptimeAttestation, DEFAULT_TREASURY_SHARE_BPS, MAX_SERVICE_PROOFS_PER_BLOCK,
+};
+use reth_chainspec::{
+    permia_block_time_ms, permia_dag_version, permia_max_service_proofs, permia_treasury_address,
+    ChainSpec, EthereumHardforks,
+};
 use reth_consensus::{Consensus, ConsensusError, FullConsensus, HeaderValidator};
 use reth_consensus_common::validation::{
     validate_against_parent_gas_limit, validate_against_parent_hash_number,
     validate_against_parent_timestamp, validate_block_pre_execution, validate_body_against_header,
     validate_header_extra_data, validate_header_gas,
 };
-use reth_primitives_traits::{Block, BlockHeader, NodePrimitives, RecoveredBlock, SealedBlock, SealedHeader};
+use reth_ethereum_consensus::validate_block_post_execution;
+use reth_primitives_traits::{
+    Block, BlockHeader, GotExpected, NodePrimitives, RecoveredBlock, SealedBlock, SealedHeader,
+};
 use reth_execution_types::BlockExecutionResult;
-use std::{error::Error, fmt::Debug, sync::Arc};
+use std::{
+    error::Error,
+    fmt::Debug,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 /// Custom error for Permia consensus
 #[derive(Debug, Clone)]
@@ -32,8 +58,41 @@ fn custom_error(msg: impl Into<String>) -> ConsensusError {
     ConsensusError::Custom(Arc::new(PermiaError(msg.into())))
 }
 
-/// Maximum allowed extra data size in bytes
-const MAX_EXTRA_DATA_SIZE: usize = 32;
+impl From<PermiaConsensusError> for ConsensusError {
+    fn from(err: PermiaConsensusError) -> Self {
+        Self::Custom(Arc::new(err))
+    }
+}
+
+/// Get the [`PermiaConsensusError`] carried by a consensus error, if any
+///
+/// Lets callers tell apart e.g. a bad PoW from a wrong difficulty.
+pub fn permia_error(err: &ConsensusError) -> Option<&PermiaConsensusError> {
+    match err {
+        ConsensusError::Custom(err) => err.downcast_ref::<PermiaConsensusError>(),
+        _ => None,
+    }
+}
+
+/// PermiaHash configuration of a chain
+///
+/// The DAG version is selected by the genesis, see [`permia_dag_version`]; the other
+/// parameters are the network defaults. Fails if the genesis names an unknown version.
+pub fn permia_hash_config(
+    chain_spec: &ChainSpec,
+) -> Result<PermiaHashConfig, PermiaConsensusError> {
+    let mut config = PermiaHashConfig::default();
+    if let Some(version) = permia_dag_version(chain_spec) {
+        config.dag_version = DagVersion::try_from(version)?;
+    }
+    Ok(config)
+}
+
+/// Default allowed deviation of a header's difficulty from the expected one, in basis points
+pub const DEFAULT_DIFFICULTY_TOLERANCE_BPS: u32 = 500;
+
+/// Denominator of the difficulty tolerance (basis points, 10_000 = 100%)
+const DIFFICULTY_TOLERANCE_DENOMINATOR: u32 = 10_000;
 
 /// Permia Proof-of-Work Consensus
 ///
@@ -46,50 +105,324 @@ pub struct PermiaPoWConsensus {
     difficulty_calc: DifficultyCalculator,
     /// Maximum extra data size
     max_extra_data_size: usize,
+    /// Maximum allowed drift of a header timestamp into the future, in seconds
+    max_future_drift_secs: u64,
+    /// Gas limit every block must use, fixed by the genesis
+    gas_limit: u64,
+    /// Block times from the timestamps of headers validated against their parent
+    block_times: Arc<Mutex<BlockTimeStats>>,
+    /// Time between canonical head updates observed by the node
+    import_latency: Arc<Mutex<BlockTimeStats>>,
+    /// Treasury paid a share of every block reward, if the chain has one
+    treasury: Option<Address>,
+    /// Share of the block reward paid to the treasury, in basis points
+    treasury_share_bps: u32,
+    /// Allowed deviation of a header's difficulty from the expected one, in basis points
+    difficulty_tolerance_bps: u32,
+    /// Maximum number of service proofs in a block
+    max_service_proofs: usize,
+    /// PermiaHash parameters headers are sealed with
+    hash_config: PermiaHashConfig,
 }
 
 impl PermiaPoWConsensus {
     /// Create a new instance
+    ///
+    /// # Panics
+    ///
+    /// If the genesis selects an unknown DAG version, see [`permia_hash_config`]. Chain
+    /// specs parsed by the CLI are checked for this up front.
     pub fn new(chain_spec: Arc<ChainSpec>) -> Self {
+        let hash_config = permia_hash_config(&chain_spec)
+            .unwrap_or_else(|err| panic!("invalid chain spec: {err}"));
         Self {
-            chain_spec,
-            difficulty_calc: DifficultyCalculator::new(),
+            difficulty_calc: DifficultyCalculator::for_chain(chain_spec.chain.id())
+                .with_target_block_time(permia_block_time_ms(&chain_spec)),
             max_extra_data_size: MAX_EXTRA_DATA_SIZE,
+            max_future_drift_secs: DEFAULT_MAX_FUTURE_DRIFT_SECS,
+            gas_limit: chain_spec.genesis().gas_limit,
+            block_times: Default::default(),
+            import_latency: Default::default(),
+            treasury: permia_treasury_address(&chain_spec),
+            treasury_share_bps: DEFAULT_TREASURY_SHARE_BPS,
+            difficulty_tolerance_bps: DEFAULT_DIFFICULTY_TOLERANCE_BPS,
+            max_service_proofs: permia_max_service_proofs(&chain_spec)
+                .unwrap_or(MAX_SERVICE_PROOFS_PER_BLOCK),
+            hash_config,
+            chain_spec,
         }
     }
 
+    /// Set the share of the block reward paid to the treasury, in basis points
+    pub fn with_treasury_share_bps(mut self, bps: u32) -> Self {
+        self.treasury_share_bps = bps;
+        self
+    }
+
+    /// Get the share of the block reward paid to the treasury, in basis points
+    ///
+    /// Always zero on chains without a treasury.
+    pub fn treasury_share_bps(&self) -> u32 {
+        self.treasury.map_or(0, |_| self.treasury_share_bps)
+    }
+
+    /// Set the treasury paid a share of every block reward, `None` for no treasury
+    pub fn with_treasury(mut self, treasury: Option<Address>) -> Self {
+        self.treasury = treasury;
+        self
+    }
+
+    /// Get the treasury paid a share of every block reward, read from the chain spec
+    pub fn treasury(&self) -> Option<Address> {
+        self.treasury
+    }
+
+    /// Set the allowed deviation of a header's difficulty from the expected one, in basis points
+    pub fn with_difficulty_tolerance_bps(mut self, bps: u32) -> Self {
+        self.difficulty_tolerance_bps = bps.min(DIFFICULTY_TOLERANCE_DENOMINATOR);
+        self
+    }
+
+    /// Get the allowed difficulty deviation, in basis points
+    pub fn difficulty_tolerance_bps(&self) -> u32 {
+        self.difficulty_tolerance_bps
+    }
+
+    /// Set the maximum number of service proofs in a block
+    pub fn with_max_service_proofs(mut self, max: usize) -> Self {
+        self.max_service_proofs = max;
+        self
+    }
+
+    /// Get the maximum number of service proofs in a block
+    pub fn max_service_proofs(&self) -> usize {
+        self.max_service_proofs
+    }
+
+    /// Get the PermiaHash parameters headers are sealed with, read from the chain spec
+    pub fn hash_config(&self) -> &PermiaHashConfig {
+        &self.hash_config
+    }
+
+    /// Use the same difficulty for every block, see [`DifficultyCalculator::fixed`]
+    pub fn with_fixed_difficulty(mut self, difficulty: U256) -> Self {
+        let target_block_time_ms = self.difficulty_calc.target_block_time_ms();
+        self.difficulty_calc = DifficultyCalculator::fixed(difficulty).with_target_block_time(target_block_time_ms);
+        self
+    }
+
+    /// Set the maximum allowed drift of a header timestamp into the future, in seconds
+    ///
+    /// Capped at [`DEFAULT_MAX_FUTURE_DRIFT_SECS`]: the difficulty adjustment only treats
+    /// blocks as an emergency past that drift, so a larger one would let miners trigger it
+    /// by forward-dating their blocks.
+    pub fn with_max_future_drift(mut self, secs: u64) -> Self {
+        self.max_future_drift_secs = secs.min(DEFAULT_MAX_FUTURE_DRIFT_SECS);
+        self
+    }
+
+    /// Get the maximum allowed future drift, in seconds
+    pub fn max_future_drift_secs(&self) -> u64 {
+        self.max_future_drift_secs
+    }
+
+    /// Reject headers whose timestamp is further ahead of `now` than the allowed drift
+    ///
+    /// Both `timestamp` and `now` are seconds since the UNIX epoch.
+    pub fn validate_timestamp_drift(&self, timestamp: u64, now: u64) -> Result<(), ConsensusError> {
+        if timestamp > now.saturating_add(self.max_future_drift_secs) {
+            return Err(ConsensusError::TimestampIsInFuture {
+                timestamp,
+                present_timestamp: now,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get the gas limit every block must use
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    /// Reject a gas limit other than the one fixed by the chain spec
+    ///
+    /// Unlike Ethereum, miners cannot adjust the gas limit between blocks.
+    pub fn validate_gas_limit(&self, gas_limit: u64) -> Result<(), ConsensusError> {
+        if gas_limit != self.gas_limit {
+            return Err(PermiaConsensusError::GasLimitMismatch {
+                expected: self.gas_limit,
+                actual: gas_limit,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Snapshot of the block times recorded from header timestamps while validating headers
+    /// against their parent, see [`BlockTimeStats::record_header`]
+    pub fn block_time_stats(&self) -> BlockTimeStats {
+        self.block_times.lock().clone()
+    }
+
+    /// Record that block `number` became canonical at `at_ms` (milliseconds since the
+    /// UNIX epoch), see [`BlockTimeStats::record_canonical`]
+    ///
+    /// Called by the node as its canonical head advances. This measures import latency,
+    /// not block production: blocks imported in a burst while syncing count as instant,
+    /// and a node that was down sees a long gap.
+    pub fn record_canonical_import(&self, number: u64, at_ms: u64) {
+        self.import_latency.lock().record_canonical(number, at_ms);
+    }
+
+    /// Snapshot of the time between canonical head updates observed by the node
+    pub fn import_latency_stats(&self) -> BlockTimeStats {
+        self.import_latency.lock().clone()
+    }
+
+    /// Reject headers that look like merged (PoS) headers
+    ///
+    /// The difficulty must be non-zero, and header fields introduced by Shanghai, Cancun
+    /// and Prague must be set exactly while the chain spec has the respective fork active.
+    /// Permia chains activate them from genesis to run the current EVM, so there the
+    /// field values are left to the regular pre-execution checks.
+    pub fn validate_pow_header_fields<H: BlockHeader>(&self, header: &H) -> Result<(), ConsensusError> {
+        let timestamp = header.timestamp();
+        let shanghai = self.chain_spec.is_shanghai_active_at_timestamp(timestamp);
+        let cancun = self.chain_spec.is_cancun_active_at_timestamp(timestamp);
+        let prague = self.chain_spec.is_prague_active_at_timestamp(timestamp);
+        let fork_fields = [
+            ("withdrawals_root", header.withdrawals_root().is_some(), shanghai),
+            ("parent_beacon_block_root", header.parent_beacon_block_root().is_some(), cancun),
+            ("blob_gas_used", header.blob_gas_used().is_some(), cancun),
+            ("excess_blob_gas", header.excess_blob_gas().is_some(), cancun),
+            ("requests_hash", header.requests_hash().is_some(), prague),
+        ];
+        for (field, set, active) in fork_fields {
+            match (set, active) {
+                (true, false) => return Err(PermiaConsensusError::PosHeaderField(field).into()),
+                (false, true) => {
+                    return Err(PermiaConsensusError::MissingForkHeaderField(field).into())
+                }
+                _ => {}
+            }
+        }
+
+        if header.difficulty().is_zero() {
+            return Err(PermiaConsensusError::ZeroDifficulty.into());
+        }
+
+        Ok(())
+    }
+
     /// Get the chain spec
     pub fn chain_spec(&self) -> &Arc<ChainSpec> {
         &self.chain_spec
     }
 
+    /// Get the target block time of this chain in milliseconds
+    pub fn target_block_time_ms(&self) -> u64 {
+        self.difficulty_calc.target_block_time_ms()
+    }
+
+    /// Get the minimum difficulty enforced on this chain
+    pub fn min_difficulty(&self) -> U256 {
+        self.difficulty_calc.min_difficulty()
+    }
+
+    /// Amounts of the block reward the block at `block_number` credits to the coinbase and
+    /// the treasury
+    ///
+    /// The [`reward_split`] of the [`base_block_reward`]. Blocks don't commit to service
+    /// proofs, so this is what every block pays on-chain, see [`Self::block_reward_credits`]
+    /// for the reward with service proofs.
+    pub fn base_block_reward_credits(&self, block_number: u64) -> (u128, u128) {
+        reward_split(base_block_reward(block_number), self.treasury_share_bps())
+    }
+
+    /// Estimated amounts of the block reward owed to the coinbase and the treasury for
+    /// service proofs
+    ///
+    /// The [`reward_split`] of the [`block_reward`] for the service proofs in `bundle`, of
+    /// the block at `timestamp` (seconds). Proofs `store` has credited before earn no bonus,
+    /// and the bundle's proofs are credited to it. Bundles of more than
+    /// [`Self::max_service_proofs`] proofs are rejected.
+    ///
+    /// An off-chain estimate only: blocks don't carry service proofs, so they are executed
+    /// with [`Self::base_block_reward_credits`] and the multiplier, proof deduplication,
+    /// caps and replay protection of the bundle do not apply on-chain.
+    pub fn block_reward_credits(
+        &self,
+        bundle: &ServiceProofBundle,
+        store: &mut ProofStore,
+        timestamp: u64,
+        uptime: Option<&UptimeAttestation>,
+    ) -> Result<(u128, u128), ConsensusError> {
+        bundle.check_size(self.max_service_proofs).map_err(|err| custom_error(err.to_string()))?;
+
+        let total = block_reward(bundle, store, epoch_at(timestamp), uptime);
+        Ok(reward_split(total, self.treasury_share_bps()))
+    }
+
+    /// Check that the header's base fee follows EIP-1559 from its parent
+    ///
+    /// The expected base fee is derived from the parent's gas used, gas limit and base fee
+    /// with the chain spec's [`BaseFeeParams`](reth_chainspec::BaseFeeParams), so it works
+    /// for any header type. London is active from genesis on every Permia chain, so both
+    /// headers must carry a base fee.
+    fn validate_base_fee<H: BlockHeader>(
+        &self,
+        header: &H,
+        parent: &H,
+    ) -> Result<(), ConsensusError> {
+        let base_fee = header.base_fee_per_gas().ok_or(ConsensusError::BaseFeeMissing)?;
+        let parent_base_fee = parent.base_fee_per_gas().ok_or(ConsensusError::BaseFeeMissing)?;
+
+        let expected = self
+            .chain_spec
+            .base_fee_params_at_timestamp(header.timestamp())
+            .next_block_base_fee(parent.gas_used(), parent.gas_limit(), parent_base_fee);
+        if base_fee != expected {
+            return Err(ConsensusError::BaseFeeDiff(GotExpected { got: base_fee, expected }));
+        }
+
+        Ok(())
+    }
+
     /// Validate PoW for a header
-    fn validate_pow(&self, header: &Header) -> Result<(), ConsensusError> {
-        pow::verify_pow(header).map_err(|e| match e {
-            PermiaConsensusError::InvalidProofOfWork => {
-                custom_error("Invalid PermiaHash proof of work")
-            }
-            _ => custom_error(format!("{}", e)),
-        })
+    fn validate_pow<H: PowHeader>(&self, header: &H) -> Result<(), ConsensusError> {
+        pow::verify_pow_with_config(header, &self.hash_config).map_err(Into::into)
+    }
+
+    /// Difficulty of the block after `parent` with the given timestamp
+    ///
+    /// This is the difficulty [`Self::validate_difficulty`] expects, so blocks mined at
+    /// it are accepted by nodes of the same chain.
+    pub fn calculate_difficulty<H: PowHeader>(&self, parent: &H, timestamp: u64) -> U256 {
+        self.difficulty_calc.calculate(parent, timestamp)
     }
 
     /// Validate difficulty
-    fn validate_difficulty(
+    fn validate_difficulty<H: PowHeader>(
         &self,
-        header: &Header,
-        parent: &Header,
+        header: &H,
+        parent: &H,
     ) -> Result<(), ConsensusError> {
-        let expected = self.difficulty_calc.calculate(parent, header.timestamp);
+        let expected = self.calculate_difficulty(parent, header.timestamp());
         
         // Allow some tolerance for difficulty
-        let min_allowed = expected * U256::from(95u64) / U256::from(100u64);
-        let max_allowed = expected * U256::from(105u64) / U256::from(100u64);
+        let denominator = U256::from(DIFFICULTY_TOLERANCE_DENOMINATOR);
+        let tolerance = U256::from(self.difficulty_tolerance_bps);
+        let min_allowed = expected.saturating_mul(denominator - tolerance) / denominator;
+        let max_allowed = expected.saturating_mul(denominator + tolerance) / denominator;
         
-        if header.difficulty < min_allowed || header.difficulty > max_allowed {
-            return Err(custom_error(format!(
-                "Invalid difficulty: expected ~{}, got {}",
-                expected, header.difficulty
-            )));
+        if header.difficulty() < min_allowed || header.difficulty() > max_allowed {
+            return Err(PermiaConsensusError::DifficultyOutOfBand {
+                expected,
+                actual: header.difficulty(),
+            }
+            .into());
         }
         
         Ok(())
@@ -98,7 +431,7 @@ impl PermiaPoWConsensus {
 
 impl<H> HeaderValidator<H> for PermiaPoWConsensus
 where
-    H: BlockHeader + AsRef<Header>,
+    H: BlockHeader + PowHeader,
 {
     fn validate_header(&self, header: &SealedHeader<H>) -> Result<(), ConsensusError> {
         let h = header.header();
@@ -108,14 +441,20 @@ where
         
         // Validate gas
         validate_header_gas(h)?;
+        self.validate_gas_limit(PowHeader::gas_limit(h))?;
         
-        // Validate PoW
-        self.validate_pow(h.as_ref())?;
+        // Reject merged (PoS) headers and fork fields not matching the chain spec
+        self.validate_pow_header_fields(h)?;
         
-        // Validate difficulty is non-zero
-        if h.difficulty().is_zero() {
-            return Err(custom_error("Difficulty cannot be zero in PoW"));
-        }
+        // Reject far-future timestamps before doing the expensive PoW check
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.validate_timestamp_drift(PowHeader::timestamp(h), now)?;
+        
+        // Validate PoW
+        self.validate_pow(h)?;
         
         Ok(())
     }
@@ -129,9 +468,16 @@ where
         validate_against_parent_hash_number(header.header(), parent)?;
         validate_against_parent_timestamp(header.header(), parent.header())?;
         validate_against_parent_gas_limit(header, parent, &*self.chain_spec)?;
+        self.validate_base_fee(header.header(), parent.header())?;
         
         // Validate difficulty adjustment
-        self.validate_difficulty(header.header().as_ref(), parent.header().as_ref())?;
+        self.validate_difficulty(header.header(), parent.header())?;
+        
+        self.block_times.lock().record_header(
+            PowHeader::number(header.header()),
+            PowHeader::timestamp(parent.header()),
+            PowHeader::timestamp(header.header()),
+        );
         
         Ok(())
     }
@@ -140,7 +486,7 @@ where
 impl<B> Consensus<B> for PermiaPoWConsensus
 where
     B: Block,
-    B::Header: AsRef<Header>,
+    B::Header: PowHeader,
 {
     fn validate_body_against_header(
         &self,
@@ -158,22 +504,27 @@ where
 impl<N> FullConsensus<N> for PermiaPoWConsensus
 where
     N: NodePrimitives,
-    N::BlockHeader: AsRef<Header>,
+    N::BlockHeader: PowHeader,
 {
     fn validate_block_post_execution(
         &self,
-        _block: &RecoveredBlock<N::Block>,
-        _result: &BlockExecutionResult<N::Receipt>,
+        block: &RecoveredBlock<N::Block>,
+        result: &BlockExecutionResult<N::Receipt>,
     ) -> Result<(), ConsensusError> {
-        // For PoW, we don't have additional post-execution validation
-        // The PoW validation happens in header validation
-        Ok(())
+        // The PoW is checked with the header, the gas used and receipts like on Ethereum.
+        // The block reward is credited by the block executor with the amounts from
+        // `base_block_reward_credits`, so a block paying any other reward fails the state
+        // root check after execution. Blocks carry no service proofs, so there is no
+        // proof-dependent reward to check here.
+        validate_block_post_execution(block, &*self.chain_spec, &result.receipts, &result.requests)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_consensus::Header;
+    use alloy_primitives::{Address, B256};
     use reth_chainspec::PERMIA_DEV;
 
     #[test]
@@ -181,4 +532,736 @@ mod tests {
         let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
         assert_eq!(consensus.chain_spec().chain.id(), 42071);
     }
+
+    #[test]
+    fn test_import_latency() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        consensus.record_canonical_import(1, 10_000);
+        consensus.record_canonical_import(2, 10_400);
+
+        // Clones share the statistics, which are kept apart from the block times
+        consensus.clone().record_canonical_import(3, 10_750);
+        let stats = consensus.import_latency_stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.mean(), Some(375.0));
+        assert!(consensus.block_time_stats().is_empty());
+    }
+
+    #[test]
+    fn test_block_time_from_chain_spec() {
+        use reth_chainspec::{permia_chain_spec_from_genesis, PERMIA_BLOCK_TIME_FIELD};
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.extra_fields.insert(PERMIA_BLOCK_TIME_FIELD.into(), 1000.into());
+        let chain_spec = Arc::new(permia_chain_spec_from_genesis(PERMIA_DEV.chain.id(), genesis));
+
+        let consensus = PermiaPoWConsensus::new(chain_spec);
+        assert_eq!(consensus.target_block_time_ms(), 1000);
+        assert_eq!(PermiaPoWConsensus::new(PERMIA_DEV.clone()).target_block_time_ms(), 400);
+
+        // Retargeting uses the chain's block time: a block 1s after its parent is on target
+        let parent = Header { difficulty: U256::from(10_000_000u64), timestamp: 1000, ..Default::default() };
+        let header = Header {
+            difficulty: U256::from(10_000_000u64),
+            timestamp: 1001,
+            ..Default::default()
+        };
+        assert!(consensus.validate_difficulty(&header, &parent).is_ok());
+        assert!(PermiaPoWConsensus::new(PERMIA_DEV.clone()).validate_difficulty(&header, &parent).is_err());
+    }
+
+    #[test]
+    fn test_base_fee() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        let validate =
+            |header: &Header, parent: &Header| consensus.validate_base_fee(header, parent);
+        let gas_limit = PERMIA_DEV.genesis().gas_limit;
+        let parent = Header {
+            number: 1,
+            gas_limit,
+            // Twice the gas target, the base fee rises by the maximum 12.5%
+            gas_used: gas_limit,
+            base_fee_per_gas: Some(1_000_000_000),
+            ..Default::default()
+        };
+        let header = |base_fee| Header {
+            number: 2,
+            gas_limit,
+            timestamp: 1,
+            base_fee_per_gas: base_fee,
+            ..Default::default()
+        };
+
+        assert!(validate(&header(Some(1_125_000_000)), &parent).is_ok());
+
+        // An empty parent lowers it by the maximum 12.5%
+        let empty = Header { gas_used: 0, ..parent.clone() };
+        assert!(validate(&header(Some(875_000_000)), &empty).is_ok());
+
+        // A base fee the miner picked, or none at all, is rejected
+        let err = validate(&header(Some(1_000_000_000)), &parent).unwrap_err();
+        assert!(matches!(
+            err,
+            ConsensusError::BaseFeeDiff(GotExpected { got: 1_000_000_000, expected: 1_125_000_000 })
+        ));
+        assert!(validate(&header(Some(1)), &parent).is_err());
+        assert!(matches!(
+            validate(&header(None), &parent),
+            Err(ConsensusError::BaseFeeMissing)
+        ));
+    }
+
+    #[test]
+    fn test_genesis_difficulty_above_min() {
+        for chain_spec in [PERMIA_DEV.clone(), reth_chainspec::PERMIA_TESTNET.clone(), reth_chainspec::PERMIA_MAINNET.clone()] {
+            let consensus = PermiaPoWConsensus::new(chain_spec.clone());
+            assert!(chain_spec.genesis_header().difficulty >= consensus.min_difficulty());
+        }
+    }
+
+    #[test]
+    fn test_block_reward_credits() {
+        use permia_services::{ServiceProof, BASE_BLOCK_REWARD};
+
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        let miner = Address::repeat_byte(1);
+        let timestamp = 100 * 3600;
+        let storage = ServiceProof::new_storage(miner, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        let bundle = ServiceProofBundle::with_proofs(5, miner, vec![storage]);
+
+        // Base reward with the 1.2x storage multiplier, 10% of it to the treasury
+        let total = BASE_BLOCK_REWARD / 10 * 12;
+        let credits =
+            consensus.block_reward_credits(&bundle, &mut ProofStore::new(), timestamp, None);
+        assert_eq!(credits.unwrap(), (total / 10 * 9, total / 10));
+
+        // Without a treasury share the miner gets everything
+        let no_treasury = consensus.clone().with_treasury_share_bps(0);
+        let mut store = ProofStore::new();
+        assert_eq!(
+            no_treasury.block_reward_credits(&bundle, &mut store, timestamp, None).unwrap(),
+            (total, 0)
+        );
+
+        // A proof the store credited before earns no bonus
+        assert_eq!(
+            no_treasury.block_reward_credits(&bundle, &mut store, timestamp, None).unwrap(),
+            (BASE_BLOCK_REWARD, 0)
+        );
+
+        // Chains without a treasury, like the testnet, pay the miner everything
+        let testnet = PermiaPoWConsensus::new(reth_chainspec::PERMIA_TESTNET.clone());
+        assert_eq!(testnet.treasury(), None);
+        assert_eq!(testnet.treasury_share_bps(), 0);
+        assert_eq!(
+            testnet.block_reward_credits(&bundle, &mut ProofStore::new(), timestamp, None).unwrap(),
+            (total, 0)
+        );
+        assert_eq!(consensus.treasury(), Some(Address::with_last_byte(1)));
+
+        // Blocks without proofs earn the base reward
+        let empty = ServiceProofBundle::new(5, miner);
+        let credits =
+            no_treasury.block_reward_credits(&empty, &mut ProofStore::new(), timestamp, None);
+        assert_eq!(credits.unwrap(), (BASE_BLOCK_REWARD, 0));
+
+        // Which is what every block pays on-chain
+        let credits =
+            consensus.block_reward_credits(&empty, &mut ProofStore::new(), timestamp, None);
+        assert_eq!(consensus.base_block_reward_credits(5), credits.unwrap());
+        assert_eq!(no_treasury.base_block_reward_credits(5), (BASE_BLOCK_REWARD, 0));
+    }
+
+    #[test]
+    fn test_max_service_proofs() {
+        use permia_services::ServiceProof;
+        use reth_chainspec::{permia_chain_spec_from_genesis, PERMIA_MAX_SERVICE_PROOFS_FIELD};
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.extra_fields.insert(PERMIA_MAX_SERVICE_PROOFS_FIELD.into(), 2.into());
+        let chain_spec = Arc::new(permia_chain_spec_from_genesis(PERMIA_DEV.chain.id(), genesis));
+        let consensus = PermiaPoWConsensus::new(chain_spec).with_treasury_share_bps(0);
+        assert_eq!(consensus.max_service_proofs(), 2);
+        assert_eq!(
+            PermiaPoWConsensus::new(PERMIA_DEV.clone()).max_service_proofs(),
+            MAX_SERVICE_PROOFS_PER_BLOCK
+        );
+
+        let miner = Address::repeat_byte(1);
+        let timestamp = 100 * 3600;
+        let storage =
+            |i: u8| ServiceProof::new_storage(miner, 100, B256::repeat_byte(i), vec![], B256::ZERO);
+
+        let at_limit = ServiceProofBundle::with_proofs(5, miner, vec![storage(1), storage(2)]);
+        let reward = block_reward(&at_limit, &mut ProofStore::new(), epoch_at(timestamp), None);
+        let credits = consensus
+            .block_reward_credits(&at_limit, &mut ProofStore::new(), timestamp, None)
+            .unwrap();
+        assert_eq!(credits, (reward, 0));
+
+        let mut over_limit = at_limit.clone();
+        over_limit.push(storage(3));
+        let err = consensus
+            .block_reward_credits(&over_limit, &mut ProofStore::new(), timestamp, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("Too many service proofs"), "{err}");
+    }
+
+    /// Header type of alternate primitives, exposing only what PermiaHash needs
+    #[derive(Debug, Default)]
+    struct MinimalHeader {
+        number: u64,
+        difficulty: U256,
+        timestamp: u64,
+        nonce: u64,
+        mix_hash: B256,
+    }
+
+    impl PowHeader for MinimalHeader {
+        fn parent_hash(&self) -> B256 {
+            B256::ZERO
+        }
+        fn beneficiary(&self) -> Address {
+            Address::ZERO
+        }
+        fn state_root(&self) -> B256 {
+            B256::ZERO
+        }
+        fn transactions_root(&self) -> B256 {
+            B256::ZERO
+        }
+        fn receipts_root(&self) -> B256 {
+            B256::ZERO
+        }
+        fn difficulty(&self) -> U256 {
+            self.difficulty
+        }
+        fn number(&self) -> u64 {
+            self.number
+        }
+        fn gas_limit(&self) -> u64 {
+            30_000_000
+        }
+        fn gas_used(&self) -> u64 {
+            0
+        }
+        fn timestamp(&self) -> u64 {
+            self.timestamp
+        }
+        fn extra_data(&self) -> &[u8] {
+            &[]
+        }
+        fn nonce(&self) -> u64 {
+            self.nonce
+        }
+        fn mix_hash(&self) -> B256 {
+            self.mix_hash
+        }
+    }
+
+    #[test]
+    fn test_validate_custom_header() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+
+        // Difficulty 1 accepts any nonce with the right mix digest
+        let mut header =
+            MinimalHeader { number: 1, difficulty: U256::from(1u64), timestamp: 1000, nonce: 7, ..Default::default() };
+        let seal_hash = pow::compute_seal_hash(&header);
+        header.mix_hash = pow::permia_hash_with_epoch(&seal_hash, header.nonce, header.number).mix_digest;
+        assert!(consensus.validate_pow(&header).is_ok());
+
+        // Sealing matches the alloy header with the same fields
+        let alloy_header = Header {
+            number: 1,
+            difficulty: U256::from(1u64),
+            gas_limit: 30_000_000,
+            timestamp: 1000,
+            nonce: 7u64.to_be_bytes().into(),
+            mix_hash: header.mix_hash,
+            ..Default::default()
+        };
+        assert_eq!(pow::compute_seal_hash(&alloy_header), seal_hash);
+        assert!(consensus.validate_pow(&alloy_header).is_ok());
+
+        header.mix_hash = B256::repeat_byte(1);
+        assert!(consensus.validate_pow(&header).is_err());
+
+        // Difficulty retargeting reads the custom header too
+        let parent = MinimalHeader { difficulty: U256::from(10_000_000u64), timestamp: 1000, ..Default::default() };
+        let expected = consensus.difficulty_calc.calculate(&parent, 1001);
+        let child = MinimalHeader { difficulty: expected, timestamp: 1001, ..Default::default() };
+        assert!(consensus.validate_difficulty(&child, &parent).is_ok());
+
+        let child = MinimalHeader { difficulty: U256::from(1u64), timestamp: 1001, ..Default::default() };
+        assert!(consensus.validate_difficulty(&child, &parent).is_err());
+    }
+
+    /// Non-alloy header type of alternate primitives, wrapping the alloy header
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    #[serde(transparent)]
+    struct WrappedHeader(Header);
+
+    impl alloy_rlp::Encodable for WrappedHeader {
+        fn encode(&self, out: &mut dyn alloy_rlp::BufMut) {
+            alloy_rlp::Encodable::encode(&self.0, out)
+        }
+        fn length(&self) -> usize {
+            alloy_rlp::Encodable::length(&self.0)
+        }
+    }
+
+    impl alloy_rlp::Decodable for WrappedHeader {
+        fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+            <Header as alloy_rlp::Decodable>::decode(buf).map(Self)
+        }
+    }
+
+    impl alloy_consensus::Sealable for WrappedHeader {
+        fn hash_slow(&self) -> B256 {
+            alloy_consensus::Sealable::hash_slow(&self.0)
+        }
+    }
+
+    impl reth_primitives_traits::InMemorySize for WrappedHeader {
+        fn size(&self) -> usize {
+            reth_primitives_traits::InMemorySize::size(&self.0)
+        }
+    }
+
+    impl AsRef<Self> for WrappedHeader {
+        fn as_ref(&self) -> &Self {
+            self
+        }
+    }
+
+    impl alloy_consensus::BlockHeader for WrappedHeader {
+        fn parent_hash(&self) -> B256 {
+            self.0.parent_hash
+        }
+        fn ommers_hash(&self) -> B256 {
+            self.0.ommers_hash
+        }
+        fn beneficiary(&self) -> Address {
+            self.0.beneficiary
+        }
+        fn state_root(&self) -> B256 {
+            self.0.state_root
+        }
+        fn transactions_root(&self) -> B256 {
+            self.0.transactions_root
+        }
+        fn receipts_root(&self) -> B256 {
+            self.0.receipts_root
+        }
+        fn withdrawals_root(&self) -> Option<B256> {
+            self.0.withdrawals_root
+        }
+        fn logs_bloom(&self) -> alloy_primitives::Bloom {
+            self.0.logs_bloom
+        }
+        fn difficulty(&self) -> U256 {
+            self.0.difficulty
+        }
+        fn number(&self) -> u64 {
+            self.0.number
+        }
+        fn gas_limit(&self) -> u64 {
+            self.0.gas_limit
+        }
+        fn gas_used(&self) -> u64 {
+            self.0.gas_used
+        }
+        fn timestamp(&self) -> u64 {
+            self.0.timestamp
+        }
+        fn mix_hash(&self) -> Option<B256> {
+            Some(self.0.mix_hash)
+        }
+        fn nonce(&self) -> Option<alloy_primitives::B64> {
+            Some(self.0.nonce)
+        }
+        fn base_fee_per_gas(&self) -> Option<u64> {
+            self.0.base_fee_per_gas
+        }
+        fn blob_gas_used(&self) -> Option<u64> {
+            self.0.blob_gas_used
+        }
+        fn excess_blob_gas(&self) -> Option<u64> {
+            self.0.excess_blob_gas
+        }
+        fn parent_beacon_block_root(&self) -> Option<B256> {
+            self.0.parent_beacon_block_root
+        }
+        fn requests_hash(&self) -> Option<B256> {
+            self.0.requests_hash
+        }
+        fn extra_data(&self) -> &alloy_primitives::Bytes {
+            &self.0.extra_data
+        }
+    }
+
+    impl PowHeader for WrappedHeader {
+        fn parent_hash(&self) -> B256 {
+            self.0.parent_hash
+        }
+        fn beneficiary(&self) -> Address {
+            self.0.beneficiary
+        }
+        fn state_root(&self) -> B256 {
+            self.0.state_root
+        }
+        fn transactions_root(&self) -> B256 {
+            self.0.transactions_root
+        }
+        fn receipts_root(&self) -> B256 {
+            self.0.receipts_root
+        }
+        fn difficulty(&self) -> U256 {
+            self.0.difficulty
+        }
+        fn number(&self) -> u64 {
+            self.0.number
+        }
+        fn gas_limit(&self) -> u64 {
+            self.0.gas_limit
+        }
+        fn gas_used(&self) -> u64 {
+            self.0.gas_used
+        }
+        fn timestamp(&self) -> u64 {
+            self.0.timestamp
+        }
+        fn extra_data(&self) -> &[u8] {
+            &self.0.extra_data
+        }
+        fn nonce(&self) -> u64 {
+            PowHeader::nonce(&self.0)
+        }
+        fn mix_hash(&self) -> B256 {
+            self.0.mix_hash
+        }
+    }
+
+    impl reth_primitives_traits::serde_bincode_compat::RlpBincode for WrappedHeader {}
+
+    impl BlockHeader for WrappedHeader {}
+
+    #[test]
+    fn test_header_validator_with_custom_header() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        let gas_limit = consensus.gas_limit();
+
+        // Seals a header of the wrapped type with the chain's PermiaHash parameters
+        let seal = |header: Header| {
+            let mut header = WrappedHeader(header);
+            let seal_hash = pow::compute_seal_hash(&header);
+            let nonce = PowHeader::nonce(&header);
+            let result = pow::permia_hash_with_config(
+                &seal_hash,
+                nonce,
+                header.0.number,
+                consensus.hash_config(),
+            );
+            header.0.mix_hash = result.unwrap().mix_digest;
+            SealedHeader::seal_slow(header)
+        };
+
+        let parent = seal(Header {
+            number: 1,
+            difficulty: U256::from(1u64),
+            gas_limit,
+            gas_used: gas_limit / 2,
+            timestamp: 1000,
+            base_fee_per_gas: Some(1_000_000_000),
+            nonce: 7u64.to_be_bytes().into(),
+            withdrawals_root: Some(B256::ZERO),
+            parent_beacon_block_root: Some(B256::ZERO),
+            blob_gas_used: Some(0),
+            excess_blob_gas: Some(0),
+            requests_hash: Some(B256::ZERO),
+            ..Default::default()
+        });
+        assert!(HeaderValidator::<WrappedHeader>::validate_header(&consensus, &parent).is_ok());
+
+        // The same header with a wrong mix digest fails the PoW
+        let mut forged = parent.header().clone();
+        forged.0.mix_hash = B256::repeat_byte(1);
+        let forged = SealedHeader::seal_slow(forged);
+        let err =
+            HeaderValidator::<WrappedHeader>::validate_header(&consensus, &forged).unwrap_err();
+        assert!(matches!(permia_error(&err), Some(PermiaConsensusError::InvalidProofOfWork)));
+
+        // A child at the retargeted difficulty and the EIP-1559 base fee follows the parent
+        let child = |base_fee| {
+            let timestamp = 1001;
+            seal(Header {
+                parent_hash: parent.hash(),
+                number: 2,
+                difficulty: consensus.calculate_difficulty(parent.header(), timestamp),
+                timestamp,
+                base_fee_per_gas: Some(base_fee),
+                ..parent.header().0.clone()
+            })
+        };
+        let validate = |header: &SealedHeader<WrappedHeader>| {
+            HeaderValidator::<WrappedHeader>::validate_header_against_parent(
+                &consensus,
+                header,
+                &parent,
+            )
+        };
+        assert!(validate(&child(1_000_000_000)).is_ok());
+        assert!(matches!(validate(&child(1_125_000_000)), Err(ConsensusError::BaseFeeDiff(_))));
+
+        // The valid child recorded its block time from the header timestamps, once
+        assert!(validate(&child(1_000_000_000)).is_ok());
+        let stats = consensus.block_time_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats.mean(), Some(1_000.0));
+    }
+
+    #[test]
+    fn test_difficulty_tolerance() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        assert_eq!(consensus.difficulty_tolerance_bps(), DEFAULT_DIFFICULTY_TOLERANCE_BPS);
+
+        let parent = Header { difficulty: U256::from(10_000_000u64), timestamp: 1000, ..Default::default() };
+        let expected = consensus.difficulty_calc.calculate(&parent, 1001);
+
+        // 3% off the expected difficulty, in either direction
+        let percent_of_expected = |percent: u64| expected * U256::from(percent) / U256::from(100);
+        for difficulty in [percent_of_expected(103), percent_of_expected(97)] {
+            let header = Header { difficulty, timestamp: 1001, ..Default::default() };
+            assert!(consensus.clone().with_difficulty_tolerance_bps(500).validate_difficulty(&header, &parent).is_ok());
+            let err = consensus
+                .clone()
+                .with_difficulty_tolerance_bps(100)
+                .validate_difficulty(&header, &parent)
+                .unwrap_err();
+            assert!(matches!(permia_error(&err), Some(PermiaConsensusError::DifficultyOutOfBand { .. })));
+        }
+    }
+
+    #[test]
+    fn test_fixed_difficulty() {
+        let difficulty = U256::from(1_000u64);
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone()).with_fixed_difficulty(difficulty);
+        assert_eq!(consensus.min_difficulty(), difficulty);
+        assert_eq!(consensus.target_block_time_ms(), permia_block_time_ms(&PERMIA_DEV));
+
+        // The parent's difficulty and spacing no longer matter
+        let parent = Header { difficulty: U256::from(10_000_000u64), timestamp: 1000, ..Default::default() };
+        for timestamp in [1000, 1001, 1100] {
+            let header = Header { difficulty, timestamp, ..Default::default() };
+            assert!(consensus.validate_difficulty(&header, &parent).is_ok());
+            let header = Header { difficulty: parent.difficulty, timestamp, ..Default::default() };
+            assert!(consensus.validate_difficulty(&header, &parent).is_err());
+        }
+    }
+
+    #[test]
+    fn test_structured_errors() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+
+        let parent = Header { difficulty: U256::from(10_000_000u64), timestamp: 1000, ..Default::default() };
+        let header = Header { difficulty: U256::from(1_000_000u64), timestamp: 1001, ..Default::default() };
+        let err = consensus.validate_difficulty(&header, &parent).unwrap_err();
+        assert!(matches!(
+            permia_error(&err),
+            Some(PermiaConsensusError::DifficultyOutOfBand { actual, .. }) if *actual == header.difficulty
+        ));
+
+        // Wrong mix digest for the nonce
+        let header = Header { number: 1, difficulty: U256::from(1u64), nonce: 7u64.to_be_bytes().into(), ..Default::default() };
+        let err = consensus.validate_pow(&header).unwrap_err();
+        assert!(matches!(permia_error(&err), Some(PermiaConsensusError::InvalidProofOfWork)));
+
+        assert!(permia_error(&ConsensusError::BaseFeeMissing).is_none());
+    }
+
+    #[test]
+    fn test_timestamp_drift() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone()).with_max_future_drift(15);
+        let now = 1_700_000_000;
+
+        // Past, present and slightly-future blocks pass
+        assert!(consensus.validate_timestamp_drift(now - 10, now).is_ok());
+        assert!(consensus.validate_timestamp_drift(now, now).is_ok());
+        assert!(consensus.validate_timestamp_drift(now + 15, now).is_ok());
+
+        // Far-future blocks are rejected
+        assert!(matches!(
+            consensus.validate_timestamp_drift(now + 16, now),
+            Err(ConsensusError::TimestampIsInFuture { timestamp, present_timestamp })
+                if timestamp == now + 16 && present_timestamp == now
+        ));
+
+        // Drifts past the emergency difficulty threshold are capped
+        let consensus = consensus.with_max_future_drift(60);
+        assert_eq!(consensus.max_future_drift_secs(), DEFAULT_MAX_FUTURE_DRIFT_SECS);
+        assert!(consensus.validate_timestamp_drift(now + 16, now).is_err());
+    }
+
+    /// `header` with the fields of the forks the Permia chains activate at genesis
+    fn with_fork_fields(header: Header) -> Header {
+        Header {
+            withdrawals_root: Some(B256::ZERO),
+            parent_beacon_block_root: Some(B256::ZERO),
+            blob_gas_used: Some(0),
+            excess_blob_gas: Some(0),
+            requests_hash: Some(B256::ZERO),
+            ..header
+        }
+    }
+
+    #[test]
+    fn test_validate_header_rejects_far_future() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        let header = with_fork_fields(Header {
+            difficulty: U256::from(1u64),
+            gas_limit: consensus.gas_limit(),
+            timestamp: u64::MAX,
+            ..Default::default()
+        });
+
+        let result = HeaderValidator::<Header>::validate_header(
+            &consensus,
+            &SealedHeader::seal_slow(header),
+        );
+        assert!(matches!(result, Err(ConsensusError::TimestampIsInFuture { .. })));
+    }
+
+    #[test]
+    fn test_dag_version_from_chain_spec() {
+        use reth_chainspec::{permia_chain_spec_from_genesis, PERMIA_DAG_VERSION_FIELD};
+
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        assert_eq!(consensus.hash_config().dag_version, DagVersion::V1);
+
+        let mut genesis = PERMIA_DEV.genesis.clone();
+        genesis.config.extra_fields.insert(PERMIA_DAG_VERSION_FIELD.into(), 2.into());
+        let chain_spec = permia_chain_spec_from_genesis(PERMIA_DEV.chain.id(), genesis.clone());
+        let consensus = PermiaPoWConsensus::new(Arc::new(chain_spec));
+        assert_eq!(consensus.hash_config().dag_version, DagVersion::V2);
+
+        // Headers are validated with the chain's DAG version
+        let sealed = |config: &PermiaHashConfig| {
+            let mut header = with_fork_fields(Header {
+                number: 1,
+                difficulty: U256::from(1u64),
+                gas_limit: consensus.gas_limit(),
+                nonce: 7u64.to_be_bytes().into(),
+                ..Default::default()
+            });
+            let seal_hash = pow::compute_seal_hash(&header);
+            let result = pow::permia_hash_with_config(&seal_hash, 7, 1, config).unwrap();
+            header.mix_hash = result.mix_digest;
+            SealedHeader::seal_slow(header)
+        };
+        let validate = |header: SealedHeader<Header>| {
+            HeaderValidator::<Header>::validate_header(&consensus, &header)
+        };
+        assert!(validate(sealed(consensus.hash_config())).is_ok());
+        assert!(validate(sealed(&PermiaHashConfig::default())).is_err());
+
+        // Unknown versions are rejected
+        genesis.config.extra_fields.insert(PERMIA_DAG_VERSION_FIELD.into(), 3.into());
+        let chain_spec = permia_chain_spec_from_genesis(PERMIA_DEV.chain.id(), genesis);
+        let err = permia_hash_config(&chain_spec).unwrap_err();
+        assert!(matches!(err, PermiaConsensusError::InvalidHashConfig(_)));
+    }
+
+    #[test]
+    fn test_gas_limit_fixed_by_chain_spec() {
+        let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+        assert_eq!(consensus.gas_limit(), 60_000_000);
+
+        let sealed = |gas_limit: u64| {
+            let mut header = with_fork_fields(Header {
+                number: 1,
+                difficulty: U256::from(1u64),
+                gas_limit,
+                nonce: 7u64.to_be_bytes().into(),
+                ..Default::default()
+            });
+            header.mix_hash = pow::permia_hash_with_epoch(&pow::compute_seal_hash(&header), 7, 1).mix_digest;
+            SealedHeader::seal_slow(header)
+        };
+
+        assert!(HeaderValidator::<Header>::validate_header(&consensus, &sealed(60_000_000)).is_ok());
+
+        // A validly sealed header with a manipulated gas limit
+        let err = HeaderValidator::<Header>::validate_header(&consensus, &sealed(60_000_001)).unwrap_err();
+        assert!(matches!(
+            permia_error(&err),
+            Some(PermiaConsensusError::GasLimitMismatch { expected: 60_000_000, actual: 60_000_001 })
+        ));
+    }
+
+    #[test]
+    fn test_pos_header_fields_rejected() {
+        use reth_chainspec::{EthereumHardfork, ForkCondition};
+
+        // Shanghai, Cancun and Prague activate at timestamp 1000
+        let mut chain_spec = (**PERMIA_DEV).clone();
+        for fork in
+            [EthereumHardfork::Shanghai, EthereumHardfork::Cancun, EthereumHardfork::Prague]
+        {
+            chain_spec.hardforks.insert(fork, ForkCondition::Timestamp(1000));
+        }
+        let consensus = PermiaPoWConsensus::new(Arc::new(chain_spec));
+        let sealed = |header: Header| {
+            let mut header = Header {
+                number: 1,
+                difficulty: U256::from(1u64),
+                gas_limit: consensus.gas_limit(),
+                nonce: 7u64.to_be_bytes().into(),
+                ..header
+            };
+            header.mix_hash = pow::permia_hash_with_epoch(&pow::compute_seal_hash(&header), 7, 1).mix_digest;
+            SealedHeader::seal_slow(header)
+        };
+        let validate = |header: Header| HeaderValidator::<Header>::validate_header(&consensus, &sealed(header));
+
+        // A plain PoW header passes
+        assert!(validate(Header::default()).is_ok());
+
+        // A validly sealed header carrying a beacon block root does not
+        let err = validate(Header { parent_beacon_block_root: Some(B256::ZERO), ..Default::default() }).unwrap_err();
+        assert!(matches!(
+            permia_error(&err),
+            Some(PermiaConsensusError::PosHeaderField("parent_beacon_block_root"))
+        ));
+
+        let err = validate(Header { withdrawals_root: Some(B256::ZERO), ..Default::default() }).unwrap_err();
+        assert!(matches!(permia_error(&err), Some(PermiaConsensusError::PosHeaderField("withdrawals_root"))));
+
+        // Once the forks are active the fields are required, as on the Permia chains
+        let post_fork = with_fork_fields(Header {
+            difficulty: U256::from(1u64),
+            timestamp: 1000,
+            ..Default::default()
+        });
+        assert!(consensus.validate_pow_header_fields(&post_fork).is_ok());
+        let err = validate(Header { timestamp: 1000, ..Default::default() }).unwrap_err();
+        assert!(matches!(
+            permia_error(&err),
+            Some(PermiaConsensusError::MissingForkHeaderField("withdrawals_root"))
+        ));
+        let pre_prague = Header { requests_hash: None, ..post_fork };
+        let err = consensus.validate_pow_header_fields(&pre_prague).unwrap_err();
+        assert!(matches!(
+            permia_error(&err),
+            Some(PermiaConsensusError::MissingForkHeaderField("requests_hash"))
+        ));
+        for chain_spec in [PERMIA_DEV.clone(), reth_chainspec::PERMIA_MAINNET.clone()] {
+            let consensus = PermiaPoWConsensus::new(chain_spec.clone());
+            assert!(consensus.validate_pow_header_fields(chain_spec.genesis_header()).is_ok());
+        }
+
+        // Nor does a merged-style zero difficulty
+        let merged = Header { gas_limit: consensus.gas_limit(), ..Default::default() };
+        let err = consensus.validate_pow_header_fields(&merged).unwrap_err();
+        assert!(matches!(permia_error(&err), Some(PermiaConsensusError::ZeroDifficulty)));
+    }
 }
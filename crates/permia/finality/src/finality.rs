@@ -1,9 +1,11 @@
 //! Block finality tracking
 
+use alloy_eips::BlockNumHash;
 use alloy_primitives::B256;
+use metrics::{gauge, Gauge};
 use std::collections::HashMap;
 
-use crate::{config, ValidatorSet, VoteAggregator};
+use crate::{config, FinalityError, ValidatorSet, Vote, VoteAggregator};
 
 /// Status of a block's finality
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,7 +22,7 @@ pub enum FinalityStatus {
         /// Number of votes that finalized the block
         votes: usize,
     },
-    /// Block is final via depth (3+ confirmations)
+    /// Block is final via depth (implicit finality depth, 3 by default)
     FinalizedDepth {
         /// Current depth (confirmations)
         depth: u64,
@@ -45,6 +47,14 @@ pub struct FinalityTracker {
     chain: Vec<B256>,
     /// Maximum chain length to track
     max_chain_length: usize,
+    /// Confirmations after which a block is implicitly final
+    implicit_finality_depth: u64,
+    /// Maximum number of blocks a reorg may revert
+    max_reorg_depth: u64,
+    /// Number of the chain head, once anchored by [`add_block_at`](Self::add_block_at)
+    head_number: Option<u64>,
+    /// Finality gauges
+    metrics: FinalityMetrics,
 }
 
 impl Default for FinalityTracker {
@@ -61,18 +71,66 @@ impl FinalityTracker {
             depths: HashMap::new(),
             chain: Vec::new(),
             max_chain_length: 1000,
+            implicit_finality_depth: config::IMPLICIT_FINALITY_DEPTH,
+            max_reorg_depth: config::MAX_REORG_DEPTH,
+            head_number: None,
+            metrics: FinalityMetrics::default(),
         }
     }
 
+    /// Set the confirmations after which a block is implicitly final
+    ///
+    /// Defaults to [`config::IMPLICIT_FINALITY_DEPTH`].
+    pub fn with_implicit_finality_depth(mut self, depth: u64) -> Self {
+        self.implicit_finality_depth = depth;
+        self
+    }
+
+    /// Get the confirmations after which a block is implicitly final
+    pub fn implicit_finality_depth(&self) -> u64 {
+        self.implicit_finality_depth
+    }
+
+    /// Set the maximum number of blocks a reorg may revert
+    ///
+    /// Defaults to [`config::MAX_REORG_DEPTH`].
+    pub fn with_max_reorg_depth(mut self, depth: u64) -> Self {
+        self.max_reorg_depth = depth;
+        self
+    }
+
+    /// Get the maximum number of blocks a reorg may revert
+    pub fn max_reorg_depth(&self) -> u64 {
+        self.max_reorg_depth
+    }
+
     /// Add a new block to the chain
+    ///
+    /// The head number, if known, advances by one.
     pub fn add_block(&mut self, block_hash: B256) {
+        self.push_block(block_hash);
+        self.head_number = self.head_number.map(|number| number + 1);
+        self.on_new_head();
+    }
+
+    /// Add a new block with a known number to the chain
+    pub fn add_block_at(&mut self, block_hash: B256, block_number: u64) {
+        self.push_block(block_hash);
+        self.head_number = Some(block_number);
+        self.on_new_head();
+    }
+
+    /// Number of the chain head, if known
+    pub fn head_number(&self) -> Option<u64> {
+        self.head_number
+    }
+
+    fn push_block(&mut self, block_hash: B256) {
         // Add to front of chain (most recent)
         self.chain.insert(0, block_hash);
         
         // Update depths
-        for (i, hash) in self.chain.iter().enumerate() {
-            self.depths.insert(*hash, i as u64);
-        }
+        self.update_depths();
         
         // Prune old entries
         if self.chain.len() > self.max_chain_length {
@@ -83,6 +141,86 @@ impl FinalityTracker {
         }
     }
 
+    /// Revert blocks from the chain and append their replacements
+    ///
+    /// `new` is ordered oldest first, matching [`add_block`](Self::add_block) call order.
+    /// Reorgs reverting more than [`max_reorg_depth`](Self::max_reorg_depth) blocks are
+    /// refused with [`FinalityError::ReorgTooDeep`], leaving the tracker unchanged.
+    pub fn reorg(&mut self, reverted: &[B256], new: &[B256]) -> Result<(), FinalityError> {
+        let depth = reverted.len() as u64;
+        if depth > self.max_reorg_depth {
+            return Err(FinalityError::ReorgTooDeep { depth, max: self.max_reorg_depth });
+        }
+
+        let tracked = self.chain.len();
+        self.chain.retain(|hash| !reverted.contains(hash));
+        for hash in reverted {
+            self.depths.remove(hash);
+        }
+        self.update_depths();
+
+        let removed = (tracked - self.chain.len()) as u64;
+        self.head_number = self.head_number.map(|number| number.saturating_sub(removed));
+        for hash in new {
+            self.push_block(*hash);
+            self.head_number = self.head_number.map(|number| number + 1);
+        }
+        self.on_new_head();
+        Ok(())
+    }
+
+    /// Replace the tracked chain with the given canonical blocks (oldest first)
+    ///
+    /// Used when the canonical chain moved in a way [`reorg`](Self::reorg) refuses to
+    /// apply, so the tracker follows the chain again instead of keeping reverted blocks.
+    /// Blocks below `blocks` are forgotten and regain depth as the chain grows.
+    pub fn resync(&mut self, blocks: &[BlockNumHash]) {
+        self.chain.clear();
+        self.depths.clear();
+        for block in blocks {
+            self.push_block(block.hash);
+        }
+        self.head_number = blocks.last().map(|block| block.number);
+        self.on_new_head();
+    }
+
+    /// Evict votes that fell behind the new head and publish the finality gauges
+    fn on_new_head(&mut self) {
+        if let Some(head) = self.head_number {
+            self.votes.set_head(head);
+        }
+        self.update_metrics();
+    }
+
+    /// Publish the finality gauges for the current head
+    ///
+    /// The gauges read zero while nothing is finalized.
+    fn update_metrics(&self) {
+        let parent_votes = self.chain.get(1).map_or(0, |parent| self.votes.vote_count(parent));
+        self.metrics.validator_votes_last_block.set(parent_votes as f64);
+
+        let lag = self.latest_finalized_depth();
+        self.metrics.finality_lag.set(lag.unwrap_or_default() as f64);
+        let finalized_number =
+            lag.zip(self.head_number).map_or(0, |(lag, head)| head.saturating_sub(lag));
+        self.metrics.finalized_block_number.set(finalized_number as f64);
+    }
+
+    /// Recompute depths from the chain order
+    fn update_depths(&mut self) {
+        for (i, hash) in self.chain.iter().enumerate() {
+            self.depths.insert(*hash, i as u64);
+        }
+    }
+
+    /// Blocks built on top of `block_hash` in the tracked chain (most recent first)
+    ///
+    /// Returns `None` if the block is not tracked.
+    pub fn descendants(&self, block_hash: &B256) -> Option<&[B256]> {
+        let index = self.chain.iter().position(|hash| hash == block_hash)?;
+        Some(&self.chain[..index])
+    }
+
     /// Get the depth (confirmations) of a block
     pub fn depth(&self, block_hash: &B256) -> Option<u64> {
         self.depths.get(block_hash).copied()
@@ -99,7 +237,7 @@ impl FinalityTracker {
 
         // Check depth finality
         if let Some(depth) = self.depth(block_hash) {
-            if depth >= config::IMPLICIT_FINALITY_DEPTH {
+            if depth >= self.implicit_finality_depth {
                 return FinalityStatus::FinalizedDepth { depth };
             }
         }
@@ -116,6 +254,21 @@ impl FinalityTracker {
         self.status(block_hash, validator_set).is_final()
     }
 
+    /// Add a validator vote, returns true if it finalized the block
+    ///
+    /// Same as [`VoteAggregator::add_vote`], but also publishes the finality gauges.
+    pub fn add_vote(
+        &mut self,
+        vote: Vote,
+        validator_set: &ValidatorSet,
+    ) -> Result<bool, FinalityError> {
+        let result = self.votes.add_vote(vote, validator_set);
+        if result.is_ok() {
+            self.update_metrics();
+        }
+        result
+    }
+
     /// Get mutable access to the vote aggregator
     pub fn votes_mut(&mut self) -> &mut VoteAggregator {
         &mut self.votes
@@ -128,23 +281,29 @@ impl FinalityTracker {
 
     /// Get the latest finalized block
     pub fn latest_finalized(&self, validator_set: &ValidatorSet) -> Option<B256> {
+        self.latest_finalized_depth().map(|depth| self.chain[depth as usize])
+    }
+
+    /// Get the number of the latest finalized block
+    ///
+    /// Requires the head number to be known, see [`Self::add_block_at`].
+    pub fn latest_finalized_number(&self) -> Option<u64> {
+        let depth = self.latest_finalized_depth()?;
+        self.head_number()?.checked_sub(depth)
+    }
+
+    /// Depth of the latest finalized block, i.e. how far finality lags the head
+    fn latest_finalized_depth(&self) -> Option<u64> {
         // First check for BFT finalized blocks
-        for hash in &self.chain {
-            if self.votes.is_finalized(hash) {
-                return Some(*hash);
-            }
+        if let Some(index) = self.chain.iter().position(|hash| self.votes.is_finalized(hash)) {
+            return Some(index as u64);
         }
 
         // Then check for depth finalized
-        for hash in &self.chain {
-            if let Some(depth) = self.depth(hash) {
-                if depth >= config::IMPLICIT_FINALITY_DEPTH {
-                    return Some(*hash);
-                }
-            }
-        }
-
-        None
+        self.chain
+            .iter()
+            .filter_map(|hash| self.depth(hash))
+            .find(|&depth| depth >= self.implicit_finality_depth)
     }
 
     /// Prune data for blocks older than the given depth
@@ -167,23 +326,37 @@ impl FinalityTracker {
     }
 }
 
+/// Gauges published by the [`FinalityTracker`]
+#[derive(Clone)]
+struct FinalityMetrics {
+    /// Blocks between the head and the latest finalized block
+    finality_lag: Gauge,
+    /// Number of the latest finalized block
+    finalized_block_number: Gauge,
+    /// Votes received by the block preceding the head
+    validator_votes_last_block: Gauge,
+}
+
+impl Default for FinalityMetrics {
+    fn default() -> Self {
+        Self {
+            finality_lag: gauge!("permia_finality_lag"),
+            finalized_block_number: gauge!("permia_finalized_block_number"),
+            validator_votes_last_block: gauge!("permia_validator_votes_last_block"),
+        }
+    }
+}
+
+impl std::fmt::Debug for FinalityMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FinalityMetrics").finish_non_exhaustive()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Validator, Vote};
-    use alloy_primitives::{Address, U256};
-
-    fn create_test_validator_set(count: usize) -> ValidatorSet {
-        let validators: Vec<_> = (0..count)
-            .map(|i| Validator::new(
-                Address::repeat_byte(i as u8),
-                U256::from(100u64),
-                10,
-            ))
-            .collect();
-        
-        ValidatorSet::from_validators(validators, 1, 0)
-    }
+    use crate::test_utils::{create_test_validator_set, signed_vote};
 
     #[test]
     fn test_depth_finality() {
@@ -206,6 +379,29 @@ mod tests {
         assert!(!tracker.is_final(&blocks[3], &validator_set));
     }
 
+    #[test]
+    fn test_custom_finality_depth() {
+        let validator_set = create_test_validator_set(100);
+        let mut tracker = FinalityTracker::new().with_implicit_finality_depth(6);
+        
+        let blocks: Vec<_> = (0..7).map(|i| B256::repeat_byte(i)).collect();
+        for block in &blocks {
+            tracker.add_block(*block);
+        }
+        
+        // Depth 5 is final by default but still pending here
+        assert_eq!(tracker.depth(&blocks[1]), Some(5));
+        assert!(matches!(tracker.status(&blocks[1], &validator_set), FinalityStatus::Pending { .. }));
+        
+        assert_eq!(tracker.status(&blocks[0], &validator_set), FinalityStatus::FinalizedDepth { depth: 6 });
+        assert_eq!(tracker.latest_finalized(&validator_set), Some(blocks[0]));
+
+        // Unknown without a numbered block
+        assert_eq!(tracker.latest_finalized_number(), None);
+        tracker.add_block_at(B256::repeat_byte(7), 107);
+        assert_eq!(tracker.latest_finalized_number(), Some(101));
+    }
+
     #[test]
     fn test_bft_finality() {
         let validator_set = create_test_validator_set(100);
@@ -219,7 +415,7 @@ mod tests {
         
         // Add 67 votes (threshold)
         for i in 0..67u8 {
-            let vote = Vote::new_unsigned(block_hash, 100, Address::repeat_byte(i));
+            let vote = signed_vote(block_hash, 100, i);
             tracker.votes_mut().add_vote(vote, &validator_set).unwrap();
         }
         
@@ -242,11 +438,133 @@ mod tests {
         
         // Add some votes
         for i in 0..30u8 {
-            let vote = Vote::new_unsigned(block_hash, 100, Address::repeat_byte(i));
+            let vote = signed_vote(block_hash, 100, i);
             tracker.votes_mut().add_vote(vote, &validator_set).unwrap();
         }
         
         let status = tracker.status(&block_hash, &validator_set);
         assert!(matches!(status, FinalityStatus::Pending { votes: 30, .. }));
     }
+
+    #[test]
+    fn test_reorg() {
+        let mut tracker = FinalityTracker::new();
+
+        let blocks: Vec<_> = (0..4).map(|i| B256::repeat_byte(i)).collect();
+        for block in &blocks {
+            tracker.add_block(*block);
+        }
+
+        // Replace the two newest blocks with a single competing block
+        let replacement = B256::repeat_byte(0xaa);
+        tracker.reorg(&blocks[2..], &[replacement]).unwrap();
+
+        assert_eq!(tracker.depth(&blocks[2]), None);
+        assert_eq!(tracker.depth(&blocks[3]), None);
+        assert_eq!(tracker.depth(&replacement), Some(0));
+        assert_eq!(tracker.depth(&blocks[1]), Some(1));
+        assert_eq!(tracker.depth(&blocks[0]), Some(2));
+    }
+
+    #[test]
+    fn test_deep_reorg_refused() {
+        let mut tracker = FinalityTracker::new().with_max_reorg_depth(2);
+
+        let blocks: Vec<_> = (0..5).map(|i| B256::repeat_byte(i)).collect();
+        for block in &blocks {
+            tracker.add_block(*block);
+        }
+
+        // Reverting three blocks is too deep, and changes nothing
+        assert!(matches!(
+            tracker.reorg(&blocks[2..], &[B256::repeat_byte(0xaa)]),
+            Err(FinalityError::ReorgTooDeep { depth: 3, max: 2 })
+        ));
+        assert_eq!(tracker.depth(&blocks[4]), Some(0));
+        assert_eq!(tracker.depth(&B256::repeat_byte(0xaa)), None);
+
+        // Reverting two is processed
+        tracker.reorg(&blocks[3..], &[B256::repeat_byte(0xbb)]).unwrap();
+        assert_eq!(tracker.depth(&B256::repeat_byte(0xbb)), Some(0));
+        assert_eq!(tracker.depth(&blocks[2]), Some(1));
+    }
+
+    #[test]
+    fn test_descendants() {
+        let mut tracker = FinalityTracker::new();
+
+        let blocks: Vec<_> = (0..3).map(|i| B256::repeat_byte(i)).collect();
+        for block in &blocks {
+            tracker.add_block(*block);
+        }
+
+        assert_eq!(tracker.descendants(&blocks[2]), Some(&[][..]));
+        assert_eq!(tracker.descendants(&blocks[0]), Some(&[blocks[2], blocks[1]][..]));
+        assert_eq!(tracker.descendants(&B256::repeat_byte(0xaa)), None);
+    }
+
+    #[test]
+    fn test_finality_lag_metrics() {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        // Taking a snapshot resets the recorded values, so read all gauges at once
+        let gauges = || -> HashMap<String, f64> {
+            snapshotter
+                .snapshot()
+                .into_vec()
+                .into_iter()
+                .map(|(key, .., value)| match value {
+                    DebugValue::Gauge(value) => (key.key().name().to_string(), value.into_inner()),
+                    other => panic!("{key:?} is not a gauge: {other:?}"),
+                })
+                .collect()
+        };
+
+        metrics::with_local_recorder(&recorder, || {
+            let validator_set = create_test_validator_set(100);
+            let mut tracker = FinalityTracker::new().with_implicit_finality_depth(50);
+
+            // Nothing finalized yet
+            let finalized = B256::repeat_byte(100);
+            tracker.add_block_at(B256::repeat_byte(99), 99);
+            tracker.add_block_at(finalized, 100);
+            let values = gauges();
+            assert_eq!(values["permia_finality_lag"], 0.0);
+            assert_eq!(values["permia_finalized_block_number"], 0.0);
+
+            // Block 100 is finalized by votes, the gauges follow without a new block
+            for i in 0..67u8 {
+                let vote = signed_vote(finalized, 100, i);
+                tracker.add_vote(vote, &validator_set).unwrap();
+            }
+            let values = gauges();
+            assert_eq!(values["permia_finality_lag"], 0.0);
+            assert_eq!(values["permia_finalized_block_number"], 100.0);
+
+            tracker.add_block(B256::repeat_byte(101));
+            let values = gauges();
+            assert_eq!(values["permia_finality_lag"], 1.0);
+            assert_eq!(values["permia_finalized_block_number"], 100.0);
+            assert_eq!(values["permia_validator_votes_last_block"], 67.0);
+
+            // The head keeps moving while finality stalls
+            for number in 102..=110u8 {
+                tracker.add_block(B256::repeat_byte(number));
+            }
+            assert_eq!(tracker.head_number(), Some(110));
+            let values = gauges();
+            assert_eq!(values["permia_finality_lag"], 10.0);
+            assert_eq!(values["permia_finalized_block_number"], 100.0);
+            assert_eq!(values["permia_validator_votes_last_block"], 0.0);
+
+            // Reorging out the tip shortens the lag
+            tracker
+                .reorg(&[B256::repeat_byte(109), B256::repeat_byte(110)], &[B256::repeat_byte(0xaa)])
+                .unwrap();
+            assert_eq!(tracker.head_number(), Some(109));
+            assert_eq!(gauges()["permia_finality_lag"], 9.0);
+        });
+    }
 }
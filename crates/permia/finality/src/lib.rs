@@ -24,13 +24,22 @@
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
+pub mod signer;
 pub mod validator;
 pub mod vote;
 pub mod finality;
+pub mod updater;
 
-pub use validator::{Validator, ValidatorSet, ValidatorSetUpdate};
+pub use signer::ValidatorSigner;
+pub use validator::{
+    GenesisValidator, SharedValidatorSet, SlashReason, SlashingRecord, Validator, ValidatorSet,
+    ValidatorSetUpdate,
+};
 pub use vote::{Vote, VoteMessage, VoteAggregator};
 pub use finality::{FinalityTracker, FinalityStatus};
+pub use updater::{
+    run_finality_updater, spawn_finality_updater, FinalityUpdate, SharedFinalityTracker,
+};
 
 use alloy_primitives::{Address, B256, U256};
 use thiserror::Error;
@@ -43,6 +52,12 @@ pub mod config {
     /// Blocks per epoch for validator set updates
     pub const EPOCH_LENGTH: u64 = 3600; // ~24 minutes at 400ms blocks
     
+    /// Highest service score credited to a validator per epoch
+    ///
+    /// A score point weighs as much as 1 MIA of stake, so service can add at most 10x the
+    /// minimum stake to a validator's weight.
+    pub const MAX_SERVICE_SCORE: u64 = 100_000;
+    
     /// Minimum stake required to be a validator (in wei)
     /// 10,000 MIA = 10_000 * 10^18 wei
     pub const MIN_STAKE: u128 = 10_000_000_000_000_000_000_000; // 10,000 MIA
@@ -52,6 +67,21 @@ pub mod config {
     
     /// Blocks required for implicit finality
     pub const IMPLICIT_FINALITY_DEPTH: u64 = 3;
+    
+    /// Deepest reorg (in reverted blocks) the node follows
+    pub const MAX_REORG_DEPTH: u64 = 64;
+    
+    /// Blocks behind or ahead of the head the vote aggregator accepts votes for
+    pub const MAX_VOTE_DISTANCE: u64 = 1024;
+    
+    /// Denominator of slashing fractions (basis points, 10_000 = 100%)
+    pub const SLASH_FRACTION_DENOMINATOR: u32 = 10_000;
+    
+    /// Fraction of stake slashed for equivocation, in basis points
+    pub const EQUIVOCATION_SLASH_FRACTION: u32 = SLASH_FRACTION_DENOMINATOR;
+    
+    /// Genesis config field listing the initial validators
+    pub const GENESIS_VALIDATORS_FIELD: &str = "permiaValidators";
 }
 
 /// Finality errors
@@ -69,6 +99,34 @@ pub enum FinalityError {
     #[error("Duplicate vote from {0} for block {1}")]
     DuplicateVote(Address, B256),
     
+    /// Validator voted for two different blocks at the same height
+    #[error(
+        "Validator {} equivocated at block {}: {} and {}",
+        first.validator,
+        first.block_number,
+        first.block_hash,
+        second.block_hash
+    )]
+    Equivocation {
+        /// The first vote
+        first: Box<Vote>,
+        /// The conflicting vote
+        second: Box<Vote>,
+    },
+    
+    /// Slashing evidence does not prove the offence
+    #[error("Invalid slashing evidence: {0}")]
+    InvalidEvidence(String),
+    
+    /// Validator was already slashed for an offence at this height
+    #[error("Validator {validator} already slashed at block {block_number}")]
+    AlreadySlashed {
+        /// Slashed validator
+        validator: Address,
+        /// Height of the offence
+        block_number: u64,
+    },
+    
     /// Block not found
     #[error("Block {0} not found")]
     BlockNotFound(B256),
@@ -76,6 +134,58 @@ pub enum FinalityError {
     /// Invalid block for voting
     #[error("Cannot vote on block: {0}")]
     InvalidBlock(String),
+    
+    /// Malformed vote on the wire
+    #[error("Invalid vote encoding: {0}")]
+    InvalidEncoding(String),
+    
+    /// Validator signing key could not be loaded
+    #[error("Invalid validator key: {0}")]
+    InvalidKey(String),
+    
+    /// Genesis config can't be read
+    #[error("Invalid genesis config: {0}")]
+    InvalidGenesis(String),
+    
+    /// Reorg reverts more blocks than allowed
+    #[error("Reorg of {depth} blocks exceeds the maximum reorg depth {max}")]
+    ReorgTooDeep {
+        /// Number of reverted blocks
+        depth: u64,
+        /// Maximum reorg depth
+        max: u64,
+    },
+}
+
+/// Validators with deterministic keys for tests
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use crate::{Validator, ValidatorSet, Vote};
+    use alloy_primitives::{Address, B256};
+    use k256::ecdsa::SigningKey;
+
+    /// Signing key of test validator `i`
+    pub(crate) fn validator_key(i: u8) -> SigningKey {
+        SigningKey::from_bytes(&[i + 1; 32].into()).expect("valid key")
+    }
+
+    /// Address of test validator `i`
+    pub(crate) fn validator_address(i: u8) -> Address {
+        Address::from_private_key(&validator_key(i))
+    }
+
+    /// Vote for a block signed by test validator `i`
+    pub(crate) fn signed_vote(block_hash: B256, block_number: u64, i: u8) -> Vote {
+        Vote::sign(block_hash, block_number, &validator_key(i)).expect("signed vote")
+    }
+
+    /// Set of test validators `0..count` with minimum stake
+    pub(crate) fn create_test_validator_set(count: usize) -> ValidatorSet {
+        let validators = (0..count)
+            .map(|i| Validator::new(validator_address(i as u8), Validator::min_stake(), 10))
+            .collect();
+        ValidatorSet::from_validators(validators, 1, 0)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,110 @@
+//! Validator vote signing
+//!
+//! A [`ValidatorSigner`] holds a validator's secp256k1 key and produces signed
+//! [`Vote`]s. Keys are loaded from raw hex or from a key file holding the hex-encoded
+//! secret, the same format reth uses for its p2p secret key file.
+
+use alloy_primitives::{hex, Address, B256};
+use k256::ecdsa::SigningKey;
+use std::path::Path;
+
+use crate::{FinalityError, Vote};
+
+/// Signs finality votes with a validator key
+#[derive(Clone)]
+pub struct ValidatorSigner {
+    /// Validator signing key
+    key: SigningKey,
+    /// Address derived from the key
+    address: Address,
+}
+
+impl std::fmt::Debug for ValidatorSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidatorSigner").field("address", &self.address).finish_non_exhaustive()
+    }
+}
+
+impl ValidatorSigner {
+    /// Create a signer from a signing key
+    pub fn new(key: SigningKey) -> Self {
+        let address = Address::from_private_key(&key);
+        Self { key, address }
+    }
+
+    /// Load a signer from a hex-encoded secret key, with or without `0x` prefix
+    pub fn from_hex(secret: &str) -> Result<Self, FinalityError> {
+        let bytes = hex::decode(secret.trim())
+            .map_err(|e| FinalityError::InvalidKey(format!("invalid hex: {e}")))?;
+        if bytes.len() != B256::len_bytes() {
+            return Err(FinalityError::InvalidKey(format!(
+                "expected 32 bytes, got {}",
+                bytes.len()
+            )));
+        }
+
+        let key = SigningKey::from_slice(&bytes)
+            .map_err(|e| FinalityError::InvalidKey(e.to_string()))?;
+        Ok(Self::new(key))
+    }
+
+    /// Load a signer from a key file containing the hex-encoded secret key
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FinalityError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| FinalityError::InvalidKey(format!("{}: {e}", path.display())))?;
+        Self::from_hex(&contents)
+    }
+
+    /// Address of the validator
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Produce a signed vote for a block
+    pub fn sign_vote(&self, block_hash: B256, block_number: u64) -> Result<Vote, FinalityError> {
+        Vote::sign(block_hash, block_number, &self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    /// Secret key `1` and its well-known address
+    const KEY_ONE: &str = "0x0000000000000000000000000000000000000000000000000000000000000001";
+    const KEY_ONE_ADDRESS: Address = address!("7E5F4552091A69125d5DfCb7b8C2659029395Bdf");
+
+    #[test]
+    fn test_signer_from_known_key() {
+        let signer = ValidatorSigner::from_hex(KEY_ONE).unwrap();
+        assert_eq!(signer.address(), KEY_ONE_ADDRESS);
+
+        let vote = signer.sign_vote(B256::repeat_byte(1), 100).unwrap();
+        assert_eq!(vote.validator, KEY_ONE_ADDRESS);
+        assert_eq!(vote.recover_signer().unwrap(), KEY_ONE_ADDRESS);
+    }
+
+    #[test]
+    fn test_signer_from_file() {
+        let path = std::env::temp_dir().join(format!("permia-validator-key-{}", std::process::id()));
+        std::fs::write(&path, format!("{}\n", &KEY_ONE[2..])).unwrap();
+
+        let signer = ValidatorSigner::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(signer.unwrap().address(), KEY_ONE_ADDRESS);
+
+        assert!(ValidatorSigner::from_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_invalid_keys_rejected() {
+        assert!(matches!(ValidatorSigner::from_hex("0xzz"), Err(FinalityError::InvalidKey(_))));
+        assert!(matches!(ValidatorSigner::from_hex("0x01"), Err(FinalityError::InvalidKey(_))));
+
+        // Zero is not a valid secp256k1 secret
+        let zero = B256::ZERO.to_string();
+        assert!(matches!(ValidatorSigner::from_hex(&zero), Err(FinalityError::InvalidKey(_))));
+    }
+}
@@ -0,0 +1,187 @@
+//! Canonical chain driven finality updates
+//!
+//! Feeds canonical chain changes into a shared [`FinalityTracker`] so that
+//! depth-based finality follows the live chain.
+
+use crate::FinalityTracker;
+use alloy_eips::BlockNumHash;
+use alloy_primitives::B256;
+use parking_lot::RwLock;
+use reth_chain_state::{CanonStateNotification, CanonStateSubscriptions};
+use reth_primitives_traits::NodePrimitives;
+use std::{future::Future, sync::Arc};
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, info, warn};
+
+/// A [`FinalityTracker`] shared between the updater task and its readers
+pub type SharedFinalityTracker = Arc<RwLock<FinalityTracker>>;
+
+/// A canonical chain change relevant to finality tracking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinalityUpdate {
+    /// Blocks appended to the canonical chain (oldest first)
+    Commit(Vec<BlockNumHash>),
+    /// Blocks reverted from the canonical chain and their replacements
+    Reorg {
+        /// Hashes of the reverted blocks
+        reverted: Vec<B256>,
+        /// The new canonical blocks (oldest first)
+        new: Vec<BlockNumHash>,
+    },
+}
+
+impl<N: NodePrimitives> From<&CanonStateNotification<N>> for FinalityUpdate {
+    fn from(notification: &CanonStateNotification<N>) -> Self {
+        match notification {
+            CanonStateNotification::Commit { new } => {
+                Self::Commit(new.blocks_iter().map(|block| block.num_hash()).collect())
+            }
+            CanonStateNotification::Reorg { old, new } => Self::Reorg {
+                reverted: old.blocks_iter().map(|block| block.hash()).collect(),
+                new: new.blocks_iter().map(|block| block.num_hash()).collect(),
+            },
+        }
+    }
+}
+
+/// Apply a stream of [`FinalityUpdate`]s to the shared tracker until the stream ends
+pub async fn run_finality_updater<S>(tracker: SharedFinalityTracker, updates: S)
+where
+    S: Stream<Item = FinalityUpdate>,
+{
+    info!(target: "permia::finality", "Finality updater started");
+
+    let mut updates = std::pin::pin!(updates);
+    while let Some(update) = updates.next().await {
+        match update {
+            FinalityUpdate::Commit(blocks) => {
+                let mut tracker = tracker.write();
+                for block in blocks {
+                    tracker.add_block_at(block.hash, block.number);
+                }
+            }
+            FinalityUpdate::Reorg { reverted, new } => {
+                debug!(
+                    target: "permia::finality",
+                    reverted_blocks = reverted.len(),
+                    new_blocks = new.len(),
+                    "Applying chain reorg to finality tracker"
+                );
+                // The engine already made `new` canonical, so a reorg the tracker refuses
+                // must not leave it following the reverted blocks
+                let hashes: Vec<_> = new.iter().map(|block| block.hash).collect();
+                let mut tracker = tracker.write();
+                if let Err(e) = tracker.reorg(&reverted, &hashes) {
+                    warn!(
+                        target: "permia::finality",
+                        error = %e,
+                        "Chain reorged past the finality limit, resyncing tracker"
+                    );
+                    tracker.resync(&new);
+                }
+            }
+        }
+    }
+
+    info!(target: "permia::finality", "Finality updater stopped");
+}
+
+/// Spawn the finality updater, driven by the provider's canonical state notifications
+pub fn spawn_finality_updater<P>(
+    provider: P,
+    tracker: SharedFinalityTracker,
+) -> impl Future<Output = ()>
+where
+    P: CanonStateSubscriptions + 'static,
+{
+    let updates =
+        provider.canonical_state_stream().map(|notification| FinalityUpdate::from(&notification));
+    async move {
+        run_finality_updater(tracker, updates).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Validator, ValidatorSet};
+
+    #[tokio::test]
+    async fn test_updater_tracks_commits() {
+        let validator_set = ValidatorSet::from_validators(
+            vec![Validator::new(Default::default(), Validator::min_stake(), 0)],
+            1,
+            0,
+        );
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+
+        let blocks: Vec<_> = (0..4).map(|i| B256::repeat_byte(i)).collect();
+        let at = |i: usize| BlockNumHash::new(i as u64, blocks[i]);
+        let updates = tokio_stream::iter(vec![
+            FinalityUpdate::Commit(vec![at(0)]),
+            FinalityUpdate::Commit(vec![at(1)]),
+            FinalityUpdate::Commit(vec![at(2), at(3)]),
+        ]);
+
+        run_finality_updater(Arc::clone(&tracker), updates).await;
+
+        let tracker = tracker.read();
+        assert_eq!(tracker.depth(&blocks[0]), Some(3));
+        assert!(tracker.is_final(&blocks[0], &validator_set));
+        assert!(!tracker.is_final(&blocks[3], &validator_set));
+        assert_eq!(tracker.head_number(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_updater_applies_reorg() {
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+
+        let updates = tokio_stream::iter(vec![
+            FinalityUpdate::Commit(vec![
+                BlockNumHash::new(1, B256::repeat_byte(1)),
+                BlockNumHash::new(2, B256::repeat_byte(2)),
+            ]),
+            FinalityUpdate::Reorg {
+                reverted: vec![B256::repeat_byte(2)],
+                new: vec![BlockNumHash::new(2, B256::repeat_byte(3))],
+            },
+        ]);
+
+        run_finality_updater(Arc::clone(&tracker), updates).await;
+
+        let tracker = tracker.read();
+        assert_eq!(tracker.depth(&B256::repeat_byte(2)), None);
+        assert_eq!(tracker.depth(&B256::repeat_byte(3)), Some(0));
+        assert_eq!(tracker.depth(&B256::repeat_byte(1)), Some(1));
+        assert_eq!(tracker.head_number(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_updater_resyncs_on_deep_reorg() {
+        let tracker: SharedFinalityTracker =
+            Arc::new(RwLock::new(FinalityTracker::new().with_max_reorg_depth(1)));
+
+        let old: Vec<_> =
+            (1..=3).map(|i| BlockNumHash::new(i, B256::repeat_byte(i as u8))).collect();
+        let new: Vec<_> =
+            (2..=4).map(|i| BlockNumHash::new(i, B256::repeat_byte(0x10 + i as u8))).collect();
+        let updates = tokio_stream::iter(vec![
+            FinalityUpdate::Commit(old.clone()),
+            FinalityUpdate::Reorg {
+                reverted: old[1..].iter().map(|block| block.hash).collect(),
+                new: new.clone(),
+            },
+        ]);
+
+        run_finality_updater(Arc::clone(&tracker), updates).await;
+
+        // The tracker follows the new chain rather than the reverted blocks
+        let tracker = tracker.read();
+        for block in &old[1..] {
+            assert_eq!(tracker.depth(&block.hash), None);
+        }
+        assert_eq!(tracker.depth(&new[0].hash), Some(2));
+        assert_eq!(tracker.depth(&new[2].hash), Some(0));
+        assert_eq!(tracker.head_number(), Some(4));
+    }
+}
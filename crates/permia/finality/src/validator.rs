@@ -2,9 +2,14 @@
 //!
 //! Validators are the top 100 miners by stake + service score.
 
+use alloy_genesis::Genesis;
 use alloy_primitives::{Address, U256};
+use permia_services::{epoch_service_score, ServiceProof};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{FinalityError, Vote};
 
 /// A validator in the active set
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,30 +18,39 @@ pub struct Validator {
     pub address: Address,
     /// Staked amount in wei
     pub stake: U256,
-    /// Service score (from service proofs)
+    /// Service score (from service proofs), at most [`MAX_SERVICE_SCORE`]
+    ///
+    /// [`MAX_SERVICE_SCORE`]: crate::config::MAX_SERVICE_SCORE
     pub service_score: u64,
     /// Combined weight for selection
     pub weight: U256,
-    /// Whether currently active
+    /// Whether currently active, `false` while jailed
     pub active: bool,
 }
 
 impl Validator {
     /// Create a new validator
+    ///
+    /// The service score is capped at [`MAX_SERVICE_SCORE`](crate::config::MAX_SERVICE_SCORE).
     pub fn new(address: Address, stake: U256, service_score: u64) -> Self {
-        // Weight = stake + (service_score * 1e18)
-        let service_weight = U256::from(service_score) * U256::from(1_000_000_000_000_000_000u64);
-        let weight = stake.saturating_add(service_weight);
-        
+        let service_score = service_score.min(crate::config::MAX_SERVICE_SCORE);
         Self {
             address,
             stake,
             service_score,
-            weight,
+            weight: Self::compute_weight(stake, service_score),
             active: true,
         }
     }
 
+    /// Weight = stake + (min(service_score, MAX_SERVICE_SCORE) * 1e18), saturating
+    fn compute_weight(stake: U256, service_score: u64) -> U256 {
+        let service_score = service_score.min(crate::config::MAX_SERVICE_SCORE);
+        let service_weight =
+            U256::from(service_score).saturating_mul(U256::from(1_000_000_000_000_000_000u64));
+        stake.saturating_add(service_weight)
+    }
+
     /// Check if validator meets minimum stake requirement
     pub fn meets_minimum_stake(&self) -> bool {
         self.stake >= U256::from(crate::config::MIN_STAKE)
@@ -48,17 +62,91 @@ impl Validator {
     }
 }
 
+/// A validator listed in the genesis config under
+/// [`GENESIS_VALIDATORS_FIELD`](crate::config::GENESIS_VALIDATORS_FIELD)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisValidator {
+    /// Validator address
+    pub address: Address,
+    /// Staked amount in wei
+    pub stake: U256,
+}
+
+/// Reason a validator was slashed, with the evidence for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlashReason {
+    /// Voted for two different blocks at the same height
+    Equivocation {
+        /// The first vote
+        first: Vote,
+        /// The conflicting vote
+        second: Vote,
+    },
+}
+
+impl SlashReason {
+    /// Check that the evidence proves an offence by `validator` at `block_number`
+    pub fn verify(&self, validator: &Address, block_number: u64) -> Result<(), FinalityError> {
+        match self {
+            Self::Equivocation { first, second } => {
+                if first.validator != *validator || second.validator != *validator {
+                    return Err(FinalityError::InvalidEvidence(format!(
+                        "votes are not from {validator}"
+                    )));
+                }
+                if first.block_number != block_number || second.block_number != block_number {
+                    return Err(FinalityError::InvalidEvidence(format!(
+                        "votes are not for block {block_number}"
+                    )));
+                }
+                if first.block_hash == second.block_hash {
+                    return Err(FinalityError::InvalidEvidence("votes do not conflict".into()));
+                }
+                first.verify()?;
+                second.verify()
+            }
+        }
+    }
+}
+
+/// A slash applied to a validator's stake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashingRecord {
+    /// Slashed validator
+    pub validator: Address,
+    /// Block number of the offence
+    pub block_number: u64,
+    /// Why the validator was slashed
+    pub reason: SlashReason,
+    /// Stake removed, in wei
+    pub slashed_amount: U256,
+}
+
+/// A [`ValidatorSet`] shared between finality components
+pub type SharedValidatorSet = Arc<RwLock<ValidatorSet>>;
+
 /// The active validator set
-#[derive(Debug, Clone, Default)]
+///
+/// Serializes the known validators, slashes and epoch; the active ordering is derived
+/// state and recomputed on deserialize.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(into = "ValidatorSetState", from = "ValidatorSetState")]
 pub struct ValidatorSet {
     /// Validators indexed by address
     validators: HashMap<Address, Validator>,
     /// Ordered list of validator addresses by weight
     ordered: Vec<Address>,
+    /// Validators excluded from the active set for being below minimum stake
+    rejected_below_min: Vec<Address>,
+    /// Every slash applied to this set, oldest first
+    slashes: Vec<SlashingRecord>,
     /// Current epoch
     pub epoch: u64,
     /// Block number when this set became active
     pub active_from_block: u64,
+    /// Number of times the ordering has been recomputed
+    #[cfg(test)]
+    reorder_count: usize,
 }
 
 impl ValidatorSet {
@@ -67,8 +155,12 @@ impl ValidatorSet {
         Self {
             validators: HashMap::new(),
             ordered: Vec::new(),
+            rejected_below_min: Vec::new(),
+            slashes: Vec::new(),
             epoch,
             active_from_block,
+            #[cfg(test)]
+            reorder_count: 0,
         }
     }
 
@@ -84,6 +176,24 @@ impl ValidatorSet {
         set
     }
 
+    /// Create the epoch 0 validator set listed in the genesis config
+    ///
+    /// Chains without [`GENESIS_VALIDATORS_FIELD`](crate::config::GENESIS_VALIDATORS_FIELD)
+    /// start with an empty set.
+    pub fn from_genesis(genesis: &Genesis) -> Result<Self, FinalityError> {
+        let field = crate::config::GENESIS_VALIDATORS_FIELD;
+        let validators = match genesis.config.extra_fields.get_deserialized(field) {
+            Some(validators) => validators
+                .map_err(|e| FinalityError::InvalidGenesis(format!("{field}: {e}")))?,
+            None => Vec::new(),
+        };
+        let validators = validators
+            .into_iter()
+            .map(|GenesisValidator { address, stake }| Validator::new(address, stake, 0))
+            .collect();
+        Ok(Self::from_validators(validators, 0, 0))
+    }
+
     /// Add or update a validator
     pub fn upsert(&mut self, validator: Validator) {
         self.validators.insert(validator.address, validator);
@@ -96,11 +206,129 @@ impl ValidatorSet {
         self.reorder();
     }
 
+    /// Apply a batch of additions and removals, reordering only once at the end
+    pub fn apply_batch<'a>(
+        &mut self,
+        additions: impl IntoIterator<Item = Validator>,
+        removals: impl IntoIterator<Item = &'a Address>,
+    ) {
+        for validator in additions {
+            self.validators.insert(validator.address, validator);
+        }
+
+        for address in removals {
+            self.validators.remove(address);
+        }
+
+        self.reorder();
+    }
+
+    /// Jail a validator, excluding it from the active set until unjailed
+    ///
+    /// Returns `false` if the address is not a known validator.
+    pub fn jail(&mut self, address: &Address) -> bool {
+        self.set_active(address, false)
+    }
+
+    /// Unjail a validator, making it eligible for the active set again
+    ///
+    /// Returns `false` if the address is not a known validator.
+    pub fn unjail(&mut self, address: &Address) -> bool {
+        self.set_active(address, true)
+    }
+
+    fn set_active(&mut self, address: &Address, active: bool) -> bool {
+        let Some(validator) = self.validators.get_mut(address) else { return false };
+        if validator.active != active {
+            validator.active = active;
+            self.reorder();
+        }
+        true
+    }
+
+    /// Slash a fraction of a validator's stake
+    ///
+    /// `fraction` is in basis points of [`SLASH_FRACTION_DENOMINATOR`] and capped at
+    /// 100%. The evidence in `reason` must verify, and a validator is slashed at most once
+    /// per height. The validator's weight is recomputed and the set reordered, which ejects
+    /// it if it falls below the minimum stake.
+    ///
+    /// [`SLASH_FRACTION_DENOMINATOR`]: crate::config::SLASH_FRACTION_DENOMINATOR
+    pub fn slash(
+        &mut self,
+        address: &Address,
+        fraction: u32,
+        block_number: u64,
+        reason: SlashReason,
+    ) -> Result<SlashingRecord, FinalityError> {
+        reason.verify(address, block_number)?;
+        if self.slashes.iter().any(|r| r.validator == *address && r.block_number == block_number) {
+            return Err(FinalityError::AlreadySlashed { validator: *address, block_number });
+        }
+
+        let denominator = crate::config::SLASH_FRACTION_DENOMINATOR;
+        let validator =
+            self.validators.get_mut(address).ok_or(FinalityError::NotValidator(*address))?;
+
+        let slashed_amount =
+            validator.stake * U256::from(fraction.min(denominator)) / U256::from(denominator);
+        validator.stake -= slashed_amount;
+        validator.weight = Validator::compute_weight(validator.stake, validator.service_score);
+
+        let record = SlashingRecord { validator: *address, block_number, reason, slashed_amount };
+        self.slashes.push(record.clone());
+        self.reorder();
+
+        Ok(record)
+    }
+
+    /// Slash a validator that cast both votes, by [`EQUIVOCATION_SLASH_FRACTION`]
+    ///
+    /// [`EQUIVOCATION_SLASH_FRACTION`]: crate::config::EQUIVOCATION_SLASH_FRACTION
+    pub fn slash_equivocation(
+        &mut self,
+        first: Vote,
+        second: Vote,
+    ) -> Result<SlashingRecord, FinalityError> {
+        let (validator, block_number) = (first.validator, first.block_number);
+        self.slash(
+            &validator,
+            crate::config::EQUIVOCATION_SLASH_FRACTION,
+            block_number,
+            SlashReason::Equivocation { first, second },
+        )
+    }
+
+    /// Get every slash applied to this set, oldest first
+    pub fn slashes(&self) -> &[SlashingRecord] {
+        &self.slashes
+    }
+
+    /// Check if a known validator is jailed
+    pub fn is_jailed(&self, address: &Address) -> bool {
+        self.validators.get(address).is_some_and(|v| !v.active)
+    }
+
     /// Reorder validators by weight
+    ///
+    /// Jailed validators and validators below the minimum stake are excluded before
+    /// taking the top N.
     fn reorder(&mut self) {
-        let mut validators: Vec<_> = self.validators.values().collect();
+        #[cfg(test)]
+        {
+            self.reorder_count += 1;
+        }
+
+        let (mut validators, rejected): (Vec<_>, Vec<_>) = self
+            .validators
+            .values()
+            .filter(|v| v.active)
+            .partition(|v| v.meets_minimum_stake());
         validators.sort_by(|a, b| b.weight.cmp(&a.weight));
-        
+
+        self.rejected_below_min = rejected.into_iter().map(|v| v.address).collect();
+        self.rejected_below_min.sort();
+
         // Keep only top N validators
         self.ordered = validators
             .into_iter()
@@ -109,6 +337,11 @@ impl ValidatorSet {
             .collect();
     }
 
+    /// Get the addresses rejected from the active set for being below minimum stake
+    pub fn rejected_below_min(&self) -> &[Address] {
+        &self.rejected_below_min
+    }
+
     /// Check if an address is an active validator
     pub fn is_validator(&self, address: &Address) -> bool {
         self.ordered.contains(address)
@@ -131,6 +364,26 @@ impl ValidatorSet {
             .collect()
     }
 
+    /// Get the validator at the given rank (0 = highest weight)
+    pub fn validator_at_rank(&self, rank: usize) -> Option<&Validator> {
+        self.ordered.get(rank).and_then(|addr| self.validators.get(addr))
+    }
+
+    /// Get a page of active validators in rank order
+    pub fn validators_page(&self, offset: usize, limit: usize) -> Vec<&Validator> {
+        self.ordered
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|addr| self.validators.get(addr))
+            .collect()
+    }
+
+    /// Get the rank of an active validator (0 = highest weight)
+    pub fn rank_of(&self, address: &Address) -> Option<usize> {
+        self.ordered.iter().position(|addr| addr == address)
+    }
+
     /// Get the number of active validators
     pub fn len(&self) -> usize {
         self.ordered.len()
@@ -141,8 +394,14 @@ impl ValidatorSet {
         self.ordered.is_empty()
     }
 
-    /// Get the finality threshold (2/3 + 1)
+    /// Get the finality threshold (2/3 + 1) over the active validators
+    ///
+    /// An empty set has no validators to vote, so its threshold is unreachable
+    /// (`usize::MAX`) and nothing finalizes by votes.
     pub fn finality_threshold(&self) -> usize {
+        if self.is_empty() {
+            return usize::MAX;
+        }
         (self.len() * 2 / 3) + 1
     }
 
@@ -152,6 +411,72 @@ impl ValidatorSet {
             .iter()
             .fold(U256::ZERO, |acc, v| acc.saturating_add(v.stake))
     }
+
+    /// Update for the next epoch, re-scoring validators by their service proofs
+    ///
+    /// `epoch` is the service epoch that just ended. Each validator's service score
+    /// becomes the [`epoch_service_score`] of its own proofs of that epoch, so validators
+    /// that stopped serving lose weight. Proofs of other epochs and proofs that fail
+    /// verification are ignored.
+    pub fn next_epoch_update(
+        &self,
+        from_block: u64,
+        epoch: u64,
+        proofs: &[ServiceProof],
+    ) -> ValidatorSetUpdate {
+        let mut by_miner: HashMap<Address, Vec<ServiceProof>> = HashMap::new();
+        for proof in proofs {
+            by_miner.entry(proof.miner).or_default().push(proof.clone());
+        }
+
+        let additions = self
+            .validators
+            .values()
+            .map(|validator| {
+                let score = by_miner
+                    .get(&validator.address)
+                    .map_or(0, |proofs| epoch_service_score(proofs, epoch));
+                Validator {
+                    active: validator.active,
+                    ..Validator::new(validator.address, validator.stake, score)
+                }
+            })
+            .collect();
+
+        ValidatorSetUpdate { epoch: self.epoch + 1, from_block, additions, removals: Vec::new() }
+    }
+}
+
+/// Serialized form of a [`ValidatorSet`]
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidatorSetState {
+    epoch: u64,
+    active_from_block: u64,
+    /// Sorted by address, so equal sets serialize identically
+    validators: Vec<Validator>,
+    slashes: Vec<SlashingRecord>,
+}
+
+impl From<ValidatorSet> for ValidatorSetState {
+    fn from(set: ValidatorSet) -> Self {
+        let mut validators: Vec<_> = set.validators.into_values().collect();
+        validators.sort_by_key(|v| v.address);
+        Self {
+            epoch: set.epoch,
+            active_from_block: set.active_from_block,
+            validators,
+            slashes: set.slashes,
+        }
+    }
+}
+
+impl From<ValidatorSetState> for ValidatorSet {
+    fn from(state: ValidatorSetState) -> Self {
+        let mut set = Self::from_validators(state.validators, state.epoch, state.active_from_block);
+        set.slashes = state.slashes;
+        set
+    }
 }
 
 /// Update to the validator set
@@ -172,20 +497,15 @@ impl ValidatorSetUpdate {
     pub fn apply(&self, set: &mut ValidatorSet) {
         set.epoch = self.epoch;
         set.active_from_block = self.from_block;
-        
-        for validator in &self.additions {
-            set.upsert(validator.clone());
-        }
-        
-        for address in &self.removals {
-            set.remove(address);
-        }
+        set.apply_batch(self.additions.iter().cloned(), &self.removals);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_primitives::B256;
+    use permia_services::ServiceProofType;
 
     #[test]
     fn test_validator_creation() {
@@ -197,12 +517,30 @@ mod tests {
         assert!(validator.weight > stake); // Service score adds weight
     }
 
+    #[test]
+    fn test_service_score_capped() {
+        let max = crate::config::MAX_SERVICE_SCORE;
+        let stake = Validator::min_stake();
+        let one_mia = U256::from(1_000_000_000_000_000_000u64);
+
+        // An enormous score is capped instead of overflowing
+        let farmer = Validator::new(Address::repeat_byte(1), stake, u64::MAX);
+        assert_eq!(farmer.service_score, max);
+        assert_eq!(farmer.weight, stake + U256::from(max) * one_mia);
+        assert_eq!(Validator::compute_weight(U256::MAX, u64::MAX), U256::MAX);
+
+        // ...so it can't outweigh a much larger stake
+        let whale = Validator::new(Address::repeat_byte(2), stake * U256::from(20), 0);
+        let set = ValidatorSet::from_validators(vec![farmer, whale.clone()], 0, 0);
+        assert_eq!(set.validator_at_rank(0), Some(&whale));
+    }
+
     #[test]
     fn test_validator_set() {
         let validators = vec![
-            Validator::new(Address::repeat_byte(1), U256::from(100u64), 10),
-            Validator::new(Address::repeat_byte(2), U256::from(200u64), 20),
-            Validator::new(Address::repeat_byte(3), U256::from(150u64), 15),
+            Validator::new(Address::repeat_byte(1), Validator::min_stake() + U256::from(100u64), 10),
+            Validator::new(Address::repeat_byte(2), Validator::min_stake() + U256::from(200u64), 20),
+            Validator::new(Address::repeat_byte(3), Validator::min_stake() + U256::from(150u64), 15),
         ];
         
         let set = ValidatorSet::from_validators(validators, 1, 0);
@@ -221,7 +559,7 @@ mod tests {
         for i in 0..100u8 {
             validators.push(Validator::new(
                 Address::repeat_byte(i),
-                U256::from(100u64),
+                Validator::min_stake(),
                 10,
             ));
         }
@@ -229,4 +567,343 @@ mod tests {
         let set = ValidatorSet::from_validators(validators, 1, 0);
         assert_eq!(set.finality_threshold(), 67); // 2/3 + 1
     }
+
+    #[test]
+    fn test_finality_threshold_small_sets() {
+        let set_of = |n: u8| {
+            let validators = (1..=n)
+                .map(|i| Validator::new(Address::repeat_byte(i), Validator::min_stake(), 10))
+                .collect();
+            ValidatorSet::from_validators(validators, 1, 0)
+        };
+
+        // No validators: unreachable
+        assert_eq!(ValidatorSet::new(1, 0).finality_threshold(), usize::MAX);
+        assert_eq!(set_of(0).finality_threshold(), usize::MAX);
+
+        // Every validator must vote in sets too small to tolerate a fault
+        assert_eq!(set_of(1).finality_threshold(), 1);
+        assert_eq!(set_of(2).finality_threshold(), 2);
+        assert_eq!(set_of(3).finality_threshold(), 3);
+        assert_eq!(set_of(4).finality_threshold(), 3);
+    }
+
+    #[test]
+    fn test_rank_and_pagination() {
+        let validators: Vec<_> = (0..100u8)
+            .map(|i| {
+                Validator::new(Address::repeat_byte(i), Validator::min_stake() + U256::from(i as u64 * 10), 0)
+            })
+            .collect();
+        let set = ValidatorSet::from_validators(validators, 1, 0);
+
+        // Rank 0 is the highest weight
+        let top = set.validator_at_rank(0).unwrap();
+        assert_eq!(top.address, Address::repeat_byte(99));
+        assert!(set.active_validators().iter().all(|v| v.weight <= top.weight));
+        assert_eq!(set.validator_at_rank(99).unwrap().address, Address::repeat_byte(0));
+        assert!(set.validator_at_rank(100).is_none());
+
+        // Pagination boundaries
+        assert_eq!(set.validators_page(0, 10).len(), 10);
+        assert_eq!(set.validators_page(95, 10).len(), 5);
+        assert!(set.validators_page(100, 10).is_empty());
+        assert_eq!(set.validators_page(10, 1)[0].address, Address::repeat_byte(89));
+
+        // rank_of round-trips
+        for rank in 0..set.len() {
+            let validator = set.validator_at_rank(rank).unwrap();
+            assert_eq!(set.rank_of(&validator.address), Some(rank));
+        }
+        assert!(set.rank_of(&Address::repeat_byte(200)).is_none());
+    }
+
+    #[test]
+    fn test_batch_update_reorders_once() {
+        let initial: Vec<_> = (0..20u8)
+            .map(|i| {
+                Validator::new(Address::repeat_byte(i), Validator::min_stake() + U256::from(i as u64 * 10), 1)
+            })
+            .collect();
+
+        let update = ValidatorSetUpdate {
+            epoch: 2,
+            from_block: 3600,
+            additions: (20..50u8)
+                .map(|i| {
+                    Validator::new(Address::repeat_byte(i), Validator::min_stake() + U256::from(i as u64 * 7), 2)
+                })
+                .chain(std::iter::once(Validator::new(
+                    Address::repeat_byte(3),
+                    Validator::min_stake() + U256::from(10_000u64),
+                    0,
+                )))
+                .collect(),
+            removals: vec![Address::repeat_byte(5), Address::repeat_byte(25)],
+        };
+
+        // Apply each change individually
+        let mut individual = ValidatorSet::from_validators(initial.clone(), 1, 0);
+        for validator in &update.additions {
+            individual.upsert(validator.clone());
+        }
+        for address in &update.removals {
+            individual.remove(address);
+        }
+
+        // Apply as a batch
+        let mut batched = ValidatorSet::from_validators(initial, 1, 0);
+        let before = batched.reorder_count;
+        update.apply(&mut batched);
+
+        assert_eq!(batched.reorder_count - before, 1);
+        assert_eq!(batched.ordered, individual.ordered);
+        assert_eq!(batched.epoch, 2);
+        assert_eq!(batched.active_from_block, 3600);
+    }
+
+    #[test]
+    fn test_min_stake_enforced() {
+        let min = Validator::min_stake();
+        let validators = vec![
+            Validator::new(Address::repeat_byte(1), min, 0),
+            Validator::new(Address::repeat_byte(2), min + U256::from(1u64), 0),
+            // Large service score must not compensate for insufficient stake
+            Validator::new(Address::repeat_byte(3), min - U256::from(1u64), 1_000_000),
+            Validator::new(Address::repeat_byte(4), U256::ZERO, 10),
+        ];
+
+        let mut set = ValidatorSet::from_validators(validators, 1, 0);
+
+        assert_eq!(set.len(), 2);
+        assert!(set.active_validators().iter().all(|v| v.meets_minimum_stake()));
+        assert!(!set.is_validator(&Address::repeat_byte(3)));
+        assert!(set.get(&Address::repeat_byte(4)).is_none());
+        assert_eq!(set.rejected_below_min(), &[Address::repeat_byte(3), Address::repeat_byte(4)]);
+
+        // Upserting an under-staked validator keeps it out of the active set
+        set.upsert(Validator::new(Address::repeat_byte(5), U256::from(1u64), 0));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.rejected_below_min().len(), 3);
+
+        // Topping up stake promotes it
+        set.upsert(Validator::new(Address::repeat_byte(5), min, 0));
+        assert!(set.is_validator(&Address::repeat_byte(5)));
+        assert_eq!(set.rejected_below_min().len(), 2);
+    }
+
+    #[test]
+    fn test_jailed_validators_are_inactive() {
+        let validators: Vec<_> =
+            (0..10u8).map(|i| Validator::new(Address::repeat_byte(i), Validator::min_stake(), 1)).collect();
+        let mut set = ValidatorSet::from_validators(validators, 1, 0);
+        assert_eq!(set.finality_threshold(), 7);
+
+        let jailed = Address::repeat_byte(3);
+        assert!(set.jail(&jailed));
+        assert!(set.is_jailed(&jailed));
+        assert!(!set.is_validator(&jailed));
+        assert!(set.active_validators().iter().all(|v| v.address != jailed));
+        assert_eq!(set.len(), 9);
+
+        // Three jailed out of ten lowers the threshold
+        set.jail(&Address::repeat_byte(4));
+        set.jail(&Address::repeat_byte(5));
+        assert_eq!(set.len(), 7);
+        assert_eq!(set.finality_threshold(), 5);
+
+        // Jailed validators are not rejected for stake
+        assert!(set.rejected_below_min().is_empty());
+
+        assert!(set.unjail(&jailed));
+        assert!(set.is_validator(&jailed));
+        assert_eq!(set.len(), 8);
+
+        assert!(!set.jail(&Address::repeat_byte(200)));
+    }
+
+    #[test]
+    fn test_equivocating_validator_slashed() {
+        use crate::{
+            test_utils::{create_test_validator_set, signed_vote, validator_address},
+            FinalityError, VoteAggregator,
+        };
+
+        let mut set = create_test_validator_set(4);
+        let total_stake = set.total_stake();
+
+        let offender = validator_address(2);
+        let mut aggregator = VoteAggregator::new();
+        aggregator.add_vote(signed_vote(B256::repeat_byte(1), 10, 2), &set).unwrap();
+        let Err(FinalityError::Equivocation { first, second }) =
+            aggregator.add_vote(signed_vote(B256::repeat_byte(2), 10, 2), &set)
+        else {
+            panic!("equivocation not detected");
+        };
+
+        let record = set.slash_equivocation(*first.clone(), *second.clone()).unwrap();
+
+        assert_eq!(record.slashed_amount, Validator::min_stake());
+        assert_eq!(set.slashes(), &[record]);
+        assert!(!set.is_validator(&offender));
+        assert_eq!(set.rejected_below_min(), &[offender]);
+        assert_eq!(set.total_stake(), total_stake - Validator::min_stake());
+        assert_eq!(set.finality_threshold(), 3);
+
+        // The same offence is only slashed once
+        assert!(matches!(
+            set.slash_equivocation(*first.clone(), *second.clone()),
+            Err(FinalityError::AlreadySlashed { block_number: 10, .. })
+        ));
+
+        // Partial slashes reduce stake and weight proportionally
+        let other = validator_address(1);
+        let weight = set.get(&other).unwrap().weight;
+        let reason = SlashReason::Equivocation {
+            first: signed_vote(B256::repeat_byte(1), 11, 1),
+            second: signed_vote(B256::repeat_byte(2), 11, 1),
+        };
+        let record = set.slash(&other, 1_000, 11, reason).unwrap();
+        assert_eq!(record.slashed_amount, Validator::min_stake() / U256::from(10u64));
+        assert_eq!(set.validators[&other].weight, weight - record.slashed_amount);
+        assert_eq!(set.slashes().len(), 2);
+
+        // Evidence must be signed by the slashed validator
+        let forge = |block_hash| Vote {
+            validator: validator_address(3),
+            ..signed_vote(block_hash, 12, 0)
+        };
+        let forged = SlashReason::Equivocation {
+            first: forge(B256::repeat_byte(1)),
+            second: forge(B256::repeat_byte(2)),
+        };
+        assert!(matches!(
+            set.slash(&validator_address(3), 1, 12, forged),
+            Err(FinalityError::InvalidSignature)
+        ));
+
+        // Votes for the same block are not an offence
+        let same = SlashReason::Equivocation { first: *first.clone(), second: *first };
+        assert!(matches!(
+            set.slash(&offender, 1, 10, same),
+            Err(FinalityError::InvalidEvidence(_))
+        ));
+
+        let unknown = SlashReason::Equivocation {
+            first: signed_vote(B256::repeat_byte(1), 13, 200),
+            second: signed_vote(B256::repeat_byte(2), 13, 200),
+        };
+        assert!(matches!(
+            set.slash(&validator_address(200), 1, 13, unknown),
+            Err(FinalityError::NotValidator(_))
+        ));
+        assert_eq!(set.slashes().len(), 2);
+    }
+
+    #[test]
+    fn test_next_epoch_rescored_by_service_proofs() {
+        let server = Address::repeat_byte(1);
+        let idle = Address::repeat_byte(2);
+        let set = ValidatorSet::from_validators(
+            vec![
+                Validator::new(server, Validator::min_stake(), 0),
+                // Slightly more stake and a stale score from earlier epochs
+                Validator::new(idle, Validator::min_stake() + U256::from(1u64), 50),
+            ],
+            1,
+            0,
+        );
+        assert_eq!(set.rank_of(&idle), Some(0));
+
+        let mut proofs = vec![
+            ServiceProof::new_storage(server, 10, B256::repeat_byte(1), vec![], B256::repeat_byte(2)),
+            ServiceProof::new_compute(server, 10, B256::repeat_byte(3), B256::ZERO, B256::ZERO, 3_000_000_000),
+            ServiceProof::new_cdn(idle, 10, B256::repeat_byte(4), 1_000, 1_000, vec![]),
+        ];
+        // Neither proofs of an earlier epoch nor invalid proofs score
+        let big = |epoch, cid| {
+            ServiceProof::new_compute(idle, epoch, cid, B256::ZERO, B256::ZERO, 9_000_000_000)
+        };
+        proofs.push(big(9, B256::repeat_byte(5)));
+        proofs.push(ServiceProof {
+            proof_type: ServiceProofType::CdnDelivery,
+            ..big(10, B256::repeat_byte(6))
+        });
+
+        let update = set.next_epoch_update(3600, 10, &proofs);
+        assert_eq!(update.epoch, 2);
+
+        let mut next = set.clone();
+        update.apply(&mut next);
+        assert_eq!(next.get(&server).unwrap().service_score, 4);
+        assert_eq!(next.get(&idle).unwrap().service_score, 1);
+        assert_eq!(next.rank_of(&server), Some(0));
+        assert_eq!(next.active_from_block, 3600);
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let stake = Validator::min_stake();
+        let offender = crate::test_utils::validator_address(4);
+        let validators: Vec<_> = (1..=5u8)
+            .map(|i| {
+                let address = if i == 4 { offender } else { Address::repeat_byte(i) };
+                Validator::new(address, stake * U256::from(i), 0)
+            })
+            .collect();
+        let mut set = ValidatorSet::from_validators(validators, 3, 300);
+        set.jail(&Address::repeat_byte(5));
+        set.slash_equivocation(
+            crate::test_utils::signed_vote(B256::ZERO, 299, 4),
+            crate::test_utils::signed_vote(B256::repeat_byte(1), 299, 4),
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&set).unwrap();
+        let decoded: ValidatorSet = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(decoded.epoch, 3);
+        assert_eq!(decoded.active_from_block, 300);
+        assert_eq!(decoded.slashes(), set.slashes());
+        assert_eq!(decoded.active_validators(), set.active_validators());
+        assert_eq!(decoded.rejected_below_min(), set.rejected_below_min());
+        assert!(decoded.is_jailed(&Address::repeat_byte(5)));
+
+        // Highest stake first, jailed and fully slashed validators left out
+        let ranked: Vec<_> =
+            (0..decoded.len()).map(|rank| decoded.validator_at_rank(rank).unwrap().address).collect();
+        assert_eq!(ranked, [3, 2, 1].map(Address::repeat_byte));
+
+        // Serialization doesn't depend on map iteration order
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+
+    #[test]
+    fn test_from_genesis() {
+        let field = crate::config::GENESIS_VALIDATORS_FIELD;
+        let mut genesis = Genesis::default();
+        assert!(ValidatorSet::from_genesis(&genesis).unwrap().is_empty());
+
+        let stake = Validator::min_stake();
+        let validators = [
+            GenesisValidator { address: Address::repeat_byte(1), stake },
+            GenesisValidator { address: Address::repeat_byte(2), stake: stake * U256::from(2) },
+            GenesisValidator { address: Address::repeat_byte(3), stake: U256::from(1) },
+        ];
+        genesis.config.extra_fields.insert(field.into(), serde_json::to_value(validators).unwrap());
+
+        let set = ValidatorSet::from_genesis(&genesis).unwrap();
+        assert_eq!((set.epoch, set.active_from_block), (0, 0));
+        assert_eq!(set.get(&Address::repeat_byte(2)).unwrap().stake, stake * U256::from(2));
+        assert_eq!(set.rank_of(&Address::repeat_byte(2)), Some(0));
+        // Validators below the minimum stake are listed but not active
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.rejected_below_min(), [Address::repeat_byte(3)]);
+
+        genesis.config.extra_fields.insert(field.into(), "validators".into());
+        assert!(matches!(
+            ValidatorSet::from_genesis(&genesis),
+            Err(FinalityError::InvalidGenesis(_))
+        ));
+    }
 }
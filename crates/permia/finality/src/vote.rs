@@ -1,13 +1,14 @@
 //! Vote messages and aggregation for BFT finality
 
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{Address, Signature, B256};
+use k256::ecdsa::SigningKey;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use crate::{FinalityError, ValidatorSet};
+use crate::{config, FinalityError, ValidatorSet};
 
 /// A vote for a block
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Vote {
     /// The block being voted on
     pub block_hash: B256,
@@ -15,12 +16,14 @@ pub struct Vote {
     pub block_number: u64,
     /// Validator who cast the vote
     pub validator: Address,
-    /// ECDSA signature (v, r, s concatenated)
+    /// ECDSA signature (r, s, v concatenated)
     pub signature: Vec<u8>,
 }
 
 impl Vote {
-    /// Create a new vote (without signature - for testing)
+    /// Create a vote with a placeholder signature
+    ///
+    /// The vote fails [`Self::verify`], so it is never counted by a [`VoteAggregator`].
     pub fn new_unsigned(block_hash: B256, block_number: u64, validator: Address) -> Self {
         Self {
             block_hash,
@@ -30,6 +33,31 @@ impl Vote {
         }
     }
 
+    /// Create a vote for a block signed with the validator's key
+    pub fn sign(block_hash: B256, block_number: u64, key: &SigningKey) -> Result<Self, FinalityError> {
+        let mut vote = Self {
+            block_hash,
+            block_number,
+            validator: Address::from_private_key(key),
+            signature: Vec::new(),
+        };
+        
+        let signature: Signature = key
+            .sign_prehash_recoverable(vote.signing_message().as_slice())
+            .map_err(|_| FinalityError::InvalidSignature)?
+            .into();
+        vote.signature = signature.as_bytes().to_vec();
+        
+        Ok(vote)
+    }
+
+    /// Recover the address that signed the vote
+    pub fn recover_signer(&self) -> Result<Address, FinalityError> {
+        Signature::from_raw(&self.signature)
+            .and_then(|signature| signature.recover_address_from_prehash(&self.signing_message()))
+            .map_err(|_| FinalityError::InvalidSignature)
+    }
+
     /// Get the message that should be signed
     pub fn signing_message(&self) -> B256 {
         use alloy_primitives::keccak256;
@@ -42,16 +70,23 @@ impl Vote {
         keccak256(&data)
     }
 
-    /// Verify the vote signature
+    /// Verify that the vote is signed by its validator
     pub fn verify(&self) -> Result<(), FinalityError> {
-        // TODO: Implement ECDSA signature verification
-        // For now, accept all votes (will be implemented with proper crypto)
+        if self.recover_signer()? != self.validator {
+            return Err(FinalityError::InvalidSignature);
+        }
         Ok(())
     }
 }
 
+/// Wire format version of an encoded [`VoteMessage`]
+pub const VOTE_WIRE_VERSION: u8 = 1;
+
+/// Size of the fixed-length prefix of an encoded [`VoteMessage`]
+const VOTE_HEADER_LEN: usize = 1 + 32 + 8 + 20 + 8 + 2;
+
 /// Message containing a vote for network propagation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VoteMessage {
     /// The vote
     pub vote: Vote,
@@ -69,21 +104,126 @@ impl VoteMessage {
         
         Self { vote, timestamp }
     }
+
+    /// Encode the message for the wire
+    ///
+    /// Layout (integers big-endian):
+    /// `version (1) | block_hash (32) | block_number (8) | validator (20) | timestamp (8) |
+    /// signature_len (2) | signature`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(VOTE_HEADER_LEN + self.vote.signature.len());
+        out.push(VOTE_WIRE_VERSION);
+        out.extend_from_slice(self.vote.block_hash.as_slice());
+        out.extend_from_slice(&self.vote.block_number.to_be_bytes());
+        out.extend_from_slice(self.vote.validator.as_slice());
+        out.extend_from_slice(&self.timestamp.to_be_bytes());
+        out.extend_from_slice(&(self.vote.signature.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.vote.signature);
+        out
+    }
+
+    /// Decode a message produced by [`encode`](Self::encode)
+    pub fn decode(buf: &[u8]) -> Result<Self, FinalityError> {
+        if buf.len() < VOTE_HEADER_LEN {
+            return Err(FinalityError::InvalidEncoding(format!(
+                "message too short: {} bytes",
+                buf.len()
+            )));
+        }
+        if buf[0] != VOTE_WIRE_VERSION {
+            return Err(FinalityError::InvalidEncoding(format!("unsupported version {}", buf[0])));
+        }
+
+        let block_hash = B256::from_slice(&buf[1..33]);
+        let block_number = u64::from_be_bytes(buf[33..41].try_into().expect("8 bytes"));
+        let validator = Address::from_slice(&buf[41..61]);
+        let timestamp = u64::from_be_bytes(buf[61..69].try_into().expect("8 bytes"));
+        let sig_len = u16::from_be_bytes(buf[69..71].try_into().expect("2 bytes")) as usize;
+
+        let signature = &buf[VOTE_HEADER_LEN..];
+        if signature.len() != sig_len {
+            return Err(FinalityError::InvalidEncoding(format!(
+                "signature length mismatch: expected {sig_len}, got {}",
+                signature.len()
+            )));
+        }
+
+        Ok(Self {
+            vote: Vote { block_hash, block_number, validator, signature: signature.to_vec() },
+            timestamp,
+        })
+    }
 }
 
 /// Aggregates votes for blocks
-#[derive(Debug, Default)]
+///
+/// Once the head is known, see [`Self::set_head`], only votes within
+/// [`config::MAX_VOTE_DISTANCE`] blocks of it are accepted, and votes for non-finalized
+/// blocks that fall further behind are evicted. The votes a validator cast are kept as
+/// equivocation evidence for twice that distance.
+#[derive(Debug)]
 pub struct VoteAggregator {
     /// Votes per block hash
     votes: HashMap<B256, HashMap<Address, Vote>>,
     /// Blocks that have reached finality
     finalized: HashSet<B256>,
+    /// Vote cast by each validator per height, kept as evidence of equivocation
+    voted_at: HashMap<(Address, u64), Vote>,
+    /// Non-finalized blocks with votes, by block number
+    pending: BTreeSet<(u64, B256)>,
+    /// Number of the chain head, if known
+    head: Option<u64>,
+    /// Maximum distance from the head of blocks to accept votes for
+    max_vote_distance: u64,
+}
+
+impl Default for VoteAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VoteAggregator {
     /// Create a new vote aggregator
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            votes: HashMap::new(),
+            finalized: HashSet::new(),
+            voted_at: HashMap::new(),
+            pending: BTreeSet::new(),
+            head: None,
+            max_vote_distance: config::MAX_VOTE_DISTANCE,
+        }
+    }
+
+    /// Set the maximum distance from the head of blocks to accept votes for
+    ///
+    /// Defaults to [`config::MAX_VOTE_DISTANCE`].
+    pub fn with_max_vote_distance(mut self, distance: u64) -> Self {
+        self.max_vote_distance = distance;
+        self
+    }
+
+    /// Update the number of the chain head, evicting votes that fell too far behind
+    pub fn set_head(&mut self, head: u64) {
+        self.head = Some(head);
+
+        let cutoff = head.saturating_sub(self.max_vote_distance);
+        while let Some(&(block_number, block_hash)) = self.pending.first() {
+            if block_number >= cutoff {
+                break
+            }
+            self.pending.pop_first();
+            self.votes.remove(&block_hash);
+        }
+
+        let evidence_cutoff = head.saturating_sub(self.max_vote_distance.saturating_mul(2));
+        self.voted_at.retain(|(_, number), _| *number >= evidence_cutoff);
+    }
+
+    /// Number of non-finalized blocks with votes
+    pub fn pending_blocks(&self) -> usize {
+        self.pending.len()
     }
 
     /// Add a vote, returns true if this vote contributed to finality
@@ -103,6 +243,28 @@ impl VoteAggregator {
         let block_hash = vote.block_hash;
         let validator = vote.validator;
 
+        // Check for a conflicting vote at the same height
+        match self.voted_at.get(&(validator, vote.block_number)) {
+            Some(first) if first.block_hash != block_hash => {
+                return Err(FinalityError::Equivocation {
+                    first: Box::new(first.clone()),
+                    second: Box::new(vote),
+                });
+            }
+            _ => {}
+        }
+
+        // Only accept votes near the head
+        if let Some(head) = self.head {
+            let distance = vote.block_number.abs_diff(head);
+            if distance > self.max_vote_distance {
+                return Err(FinalityError::InvalidBlock(format!(
+                    "block {} is {distance} blocks from head {head}",
+                    vote.block_number
+                )));
+            }
+        }
+
         // Check for duplicate
         let block_votes = self.votes.entry(block_hash).or_default();
         if block_votes.contains_key(&validator) {
@@ -110,6 +272,8 @@ impl VoteAggregator {
         }
 
         // Add vote
+        let block_number = vote.block_number;
+        self.voted_at.insert((validator, block_number), vote.clone());
         block_votes.insert(validator, vote);
 
         // Check if we've reached finality threshold
@@ -118,9 +282,14 @@ impl VoteAggregator {
 
         if vote_count >= threshold && !self.finalized.contains(&block_hash) {
             self.finalized.insert(block_hash);
+            self.pending.remove(&(block_number, block_hash));
             return Ok(true);
         }
 
+        if !self.finalized.contains(&block_hash) {
+            self.pending.insert((block_number, block_hash));
+        }
+
         Ok(false)
     }
 
@@ -155,26 +324,15 @@ impl VoteAggregator {
         self.votes.retain(|_, votes| {
             votes.values().any(|v| v.block_number >= block_number)
         });
+        self.voted_at.retain(|(_, number), _| *number >= block_number);
+        self.pending.retain(|(_, hash)| self.votes.contains_key(hash));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Validator;
-    use alloy_primitives::U256;
-
-    fn create_test_validator_set(count: usize) -> ValidatorSet {
-        let validators: Vec<_> = (0..count)
-            .map(|i| Validator::new(
-                Address::repeat_byte(i as u8),
-                U256::from(100u64),
-                10,
-            ))
-            .collect();
-        
-        ValidatorSet::from_validators(validators, 1, 0)
-    }
+    use crate::test_utils::{create_test_validator_set, signed_vote, validator_address};
 
     #[test]
     fn test_vote_creation() {
@@ -187,6 +345,23 @@ mod tests {
         assert_eq!(vote.block_number, 100);
     }
 
+    #[test]
+    fn test_signed_vote_recovers_validator() {
+        let key = SigningKey::from_bytes(&[7u8; 32].into()).unwrap();
+        let vote = Vote::sign(B256::repeat_byte(1), 100, &key).unwrap();
+        
+        assert_eq!(vote.validator, Address::from_private_key(&key));
+        assert_eq!(vote.signature.len(), 65);
+        assert_eq!(vote.recover_signer().unwrap(), vote.validator);
+        
+        // Signature does not carry over to another block
+        let moved = Vote { block_number: 101, ..vote };
+        assert_ne!(moved.recover_signer().ok(), Some(moved.validator));
+        
+        // Placeholder signatures recover nobody
+        assert!(Vote::new_unsigned(B256::ZERO, 1, Address::ZERO).recover_signer().is_err());
+    }
+
     #[test]
     fn test_vote_aggregation() {
         let validator_set = create_test_validator_set(100);
@@ -196,7 +371,7 @@ mod tests {
         
         // Add 66 votes (not enough for finality)
         for i in 0..66u8 {
-            let vote = Vote::new_unsigned(block_hash, 100, Address::repeat_byte(i));
+            let vote = signed_vote(block_hash, 100, i);
             let result = aggregator.add_vote(vote, &validator_set);
             assert!(result.is_ok());
             assert!(!result.unwrap()); // Not finalized yet
@@ -206,7 +381,7 @@ mod tests {
         assert!(!aggregator.is_finalized(&block_hash));
         
         // Add 67th vote (triggers finality)
-        let vote = Vote::new_unsigned(block_hash, 100, Address::repeat_byte(66));
+        let vote = signed_vote(block_hash, 100, 66);
         let result = aggregator.add_vote(vote, &validator_set).unwrap();
         assert!(result); // Finalized!
         assert!(aggregator.is_finalized(&block_hash));
@@ -218,7 +393,7 @@ mod tests {
         let mut aggregator = VoteAggregator::new();
         
         let block_hash = B256::repeat_byte(1);
-        let vote = Vote::new_unsigned(block_hash, 100, Address::repeat_byte(1));
+        let vote = signed_vote(block_hash, 100, 1);
         
         // First vote succeeds
         assert!(aggregator.add_vote(vote.clone(), &validator_set).is_ok());
@@ -228,6 +403,47 @@ mod tests {
         assert!(matches!(result, Err(FinalityError::DuplicateVote(_, _))));
     }
 
+    #[test]
+    fn test_equivocation_detected() {
+        let validator_set = create_test_validator_set(10);
+        let mut aggregator = VoteAggregator::new();
+        aggregator.add_vote(signed_vote(B256::repeat_byte(1), 100, 1), &validator_set).unwrap();
+
+        // Another block at the same height
+        let result = aggregator.add_vote(signed_vote(B256::repeat_byte(2), 100, 1), &validator_set);
+        let Err(FinalityError::Equivocation { first, second }) = result else {
+            panic!("equivocation not detected");
+        };
+        assert_eq!(*first, signed_vote(B256::repeat_byte(1), 100, 1));
+        assert_eq!(*second, signed_vote(B256::repeat_byte(2), 100, 1));
+        assert_eq!(aggregator.vote_count(&B256::repeat_byte(2)), 0);
+
+        // A different height is fine
+        aggregator.add_vote(signed_vote(B256::repeat_byte(2), 101, 1), &validator_set).unwrap();
+    }
+
+    #[test]
+    fn test_bad_signature_rejected() {
+        let validator_set = create_test_validator_set(10);
+        let mut aggregator = VoteAggregator::new();
+        let block_hash = B256::repeat_byte(1);
+
+        // Unsigned vote
+        let unsigned = Vote::new_unsigned(block_hash, 100, validator_address(1));
+        assert!(matches!(aggregator.add_vote(unsigned, &validator_set), Err(FinalityError::InvalidSignature)));
+
+        // Signed by validator 2 on behalf of validator 1
+        let forged = Vote { validator: validator_address(1), ..signed_vote(block_hash, 100, 2) };
+        assert!(matches!(aggregator.add_vote(forged, &validator_set), Err(FinalityError::InvalidSignature)));
+
+        // Signature moved to another block
+        let moved = Vote { block_hash: B256::repeat_byte(2), ..signed_vote(block_hash, 100, 1) };
+        assert!(matches!(aggregator.add_vote(moved, &validator_set), Err(FinalityError::InvalidSignature)));
+
+        assert_eq!(aggregator.vote_count(&block_hash), 0);
+        assert!(aggregator.add_vote(signed_vote(block_hash, 100, 1), &validator_set).is_ok());
+    }
+
     #[test]
     fn test_non_validator_rejected() {
         let validator_set = create_test_validator_set(10);
@@ -243,4 +459,90 @@ mod tests {
         let result = aggregator.add_vote(vote, &validator_set);
         assert!(matches!(result, Err(FinalityError::NotValidator(_))));
     }
+
+    #[test]
+    fn test_vote_message_wire_roundtrip() {
+        let vote = Vote::new_unsigned(B256::repeat_byte(7), 42, Address::repeat_byte(9));
+        let msg = VoteMessage { vote, timestamp: 1_700_000_000_000 };
+
+        let encoded = msg.encode();
+        assert_eq!(encoded.len(), VOTE_HEADER_LEN + 65);
+        assert_eq!(VoteMessage::decode(&encoded).unwrap(), msg);
+
+        // Truncated signature
+        assert!(matches!(
+            VoteMessage::decode(&encoded[..encoded.len() - 1]),
+            Err(FinalityError::InvalidEncoding(_))
+        ));
+
+        // Unknown version
+        let mut bad = encoded;
+        bad[0] = 0xff;
+        assert!(VoteMessage::decode(&bad).is_err());
+    }
+
+    #[test]
+    fn test_pending_votes_evicted() {
+        let validator_set = create_test_validator_set(10);
+        let mut aggregator = VoteAggregator::new().with_max_vote_distance(3);
+        aggregator.set_head(100);
+        let blocks: Vec<_> = (1..=5u8).map(B256::repeat_byte).collect();
+        for (number, block) in (100..).zip(&blocks[..4]) {
+            aggregator.add_vote(signed_vote(*block, number, 1), &validator_set).unwrap();
+        }
+
+        // Votes far ahead of the head are refused, so they can't crowd out recent blocks
+        assert!(matches!(
+            aggregator.add_vote(signed_vote(blocks[4], 104, 1), &validator_set),
+            Err(FinalityError::InvalidBlock(_))
+        ));
+        assert_eq!(aggregator.pending_blocks(), 4);
+
+        // The head moves on, the two oldest blocks are evicted, the most recent remain
+        aggregator.set_head(105);
+        assert_eq!(aggregator.pending_blocks(), 2);
+        assert_eq!(aggregator.vote_count(&blocks[0]), 0);
+        assert_eq!(aggregator.vote_count(&blocks[1]), 0);
+        for block in &blocks[2..4] {
+            assert_eq!(aggregator.vote_count(block), 1);
+        }
+
+        // Evicted votes still count towards equivocation, but are not taken again
+        assert!(matches!(
+            aggregator.add_vote(signed_vote(B256::repeat_byte(0xaa), 100, 1), &validator_set),
+            Err(FinalityError::Equivocation { .. })
+        ));
+        assert!(matches!(
+            aggregator.add_vote(signed_vote(blocks[0], 100, 1), &validator_set),
+            Err(FinalityError::InvalidBlock(_))
+        ));
+
+        // Once finalized, a block is no longer pending or evicted
+        let finalized = B256::repeat_byte(0xf0);
+        for i in 0..validator_set.finality_threshold() as u8 {
+            aggregator.add_vote(signed_vote(finalized, 104, i), &validator_set).unwrap();
+        }
+        assert!(aggregator.is_finalized(&finalized));
+        assert_eq!(aggregator.pending_blocks(), 2);
+        aggregator.set_head(107);
+        assert_eq!(aggregator.pending_blocks(), 0);
+        assert_eq!(aggregator.vote_count(&finalized), validator_set.finality_threshold());
+
+        // Equivocation evidence is dropped after twice the distance
+        assert!(aggregator.voted_at.contains_key(&(validator_address(1), 101)));
+        aggregator.set_head(108);
+        assert!(!aggregator.voted_at.contains_key(&(validator_address(1), 101)));
+    }
+
+    #[test]
+    fn test_empty_validator_set_never_finalizes() {
+        let validator_set = ValidatorSet::new(1, 0);
+        let mut aggregator = VoteAggregator::new();
+        let block_hash = B256::repeat_byte(1);
+
+        let vote = Vote::new_unsigned(block_hash, 100, Address::repeat_byte(1));
+        assert!(matches!(aggregator.add_vote(vote, &validator_set), Err(FinalityError::NotValidator(_))));
+        assert!(!aggregator.is_finalized(&block_hash));
+        assert_eq!(aggregator.vote_count(&block_hash), 0);
+    }
 }
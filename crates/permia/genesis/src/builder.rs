@@ -77,7 +77,7 @@ impl GenesisBuilder {
 
     /// Build the chain configuration
     fn build_chain_config(&self) -> alloy_genesis::ChainConfig {
-        alloy_genesis::ChainConfig {
+        let mut config = alloy_genesis::ChainConfig {
             chain_id: self.config.chain_id(),
             homestead_block: Some(0),
             eip150_block: Some(0),
@@ -95,7 +95,15 @@ impl GenesisBuilder {
             terminal_total_difficulty: Some(U256::ZERO),
             terminal_total_difficulty_passed: true,
             ..Default::default()
-        }
+        };
+        
+        // Block time read back by `reth_chainspec::permia_block_time_ms`
+        config.extra_fields.insert(
+            reth_chainspec::PERMIA_BLOCK_TIME_FIELD.to_string(),
+            constants::TARGET_BLOCK_TIME_MS.into(),
+        );
+        
+        config
     }
 
     /// Write genesis to a JSON file
@@ -126,6 +134,14 @@ mod tests {
         assert!(genesis.alloc.is_empty());
     }
 
+    #[test]
+    fn test_devnet_genesis_difficulty_above_consensus_min() {
+        let genesis = GenesisBuilder::devnet().build().unwrap();
+        let calc = permia_consensus::difficulty::DifficultyCalculator::for_chain(genesis.config.chain_id);
+        
+        assert!(genesis.difficulty >= calc.min_difficulty());
+    }
+
     #[test]
     fn test_mainnet_genesis() {
         let builder = GenesisBuilder::mainnet(
@@ -1,7 +1,9 @@
 //! Genesis configuration types
 
 use alloy_primitives::{Address, U256};
+use permia_consensus::difficulty::min_difficulty_for_chain;
 use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, path::Path};
 
 use crate::constants;
 
@@ -35,6 +37,20 @@ impl NetworkType {
             NetworkType::Devnet => 100_000, // Very easy for local dev
         }
     }
+
+    /// Get the minimum difficulty consensus enforces on this network
+    pub fn min_difficulty(&self) -> U256 {
+        min_difficulty_for_chain(self.chain_id())
+    }
+
+    /// Get the fixed genesis timestamp of this network (0 for devnet)
+    pub fn genesis_timestamp(&self) -> u64 {
+        match self {
+            NetworkType::Mainnet => reth_chainspec::PERMIA_MAINNET_GENESIS_TIMESTAMP,
+            NetworkType::Testnet => reth_chainspec::PERMIA_TESTNET_GENESIS_TIMESTAMP,
+            NetworkType::Devnet => 0,
+        }
+    }
 }
 
 impl Default for NetworkType {
@@ -51,8 +67,10 @@ pub struct Allocation {
     /// Initial balance in wei
     pub balance: U256,
     /// Vesting period in blocks (0 = no vesting)
+    #[serde(default)]
     pub vesting_blocks: u64,
     /// Description/purpose
+    #[serde(default)]
     pub description: String,
 }
 
@@ -72,6 +90,32 @@ impl Allocation {
         self.vesting_blocks = blocks;
         self
     }
+
+    /// Parse an `address,balance,vesting_blocks,description` CSV row
+    ///
+    /// Balances are decimal or `0x`-prefixed hex wei. Vesting and description may be
+    /// omitted; the description is the rest of the row and may contain commas.
+    fn from_csv_row(row: &str) -> Result<Self, String> {
+        let mut fields = row.splitn(4, ',').map(str::trim);
+
+        let address = fields.next().unwrap_or_default();
+        let address: Address =
+            address.parse().map_err(|e| format!("invalid address '{address}': {e}"))?;
+
+        let balance = fields.next().ok_or("missing balance")?;
+        let balance: U256 =
+            balance.parse().map_err(|e| format!("invalid balance '{balance}': {e}"))?;
+
+        let vesting_blocks = match fields.next() {
+            Some(blocks) if !blocks.is_empty() => blocks
+                .parse()
+                .map_err(|e| format!("invalid vesting blocks '{blocks}': {e}"))?,
+            _ => 0,
+        };
+
+        let description = fields.next().unwrap_or_default();
+        Ok(Self::new(address, balance, description).with_vesting(vesting_blocks))
+    }
 }
 
 /// Genesis configuration
@@ -109,15 +153,11 @@ impl Default for GenesisConfig {
 
 impl GenesisConfig {
     /// Create a new config for the specified network
+    ///
+    /// The timestamp is the network's fixed genesis timestamp, never the wall clock, so
+    /// the genesis is reproducible.
     pub fn new(network: NetworkType) -> Self {
-        Self {
-            network,
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-            ..Default::default()
-        }
+        Self { network, timestamp: network.genesis_timestamp(), ..Default::default() }
     }
 
     /// Create a devnet config (no allocations, easy mining)
@@ -179,6 +219,55 @@ impl GenesisConfig {
         self.network.initial_difficulty()
     }
 
+    /// Load allocations from a JSON or CSV file and append them
+    ///
+    /// Files ending in `.json` hold an array of [`Allocation`]s, anything else is read as
+    /// CSV rows of `address,balance,vesting_blocks,description`, with an optional header
+    /// row and `#` comments. Nothing is appended if any row is malformed or an address is
+    /// allocated twice. Returns the number of allocations loaded.
+    pub fn load_allocations(&mut self, path: impl AsRef<Path>) -> Result<usize, crate::GenesisError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+
+        let loaded: Vec<Allocation> = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents)?
+        } else {
+            let mut loaded = Vec::new();
+            for (index, row) in contents.lines().enumerate() {
+                let row = row.trim();
+                if row.is_empty() || row.starts_with('#') {
+                    continue;
+                }
+                if index == 0 && row.to_ascii_lowercase().starts_with("address") {
+                    continue;
+                }
+
+                let allocation = Allocation::from_csv_row(row).map_err(|reason| {
+                    crate::GenesisError::InvalidConfig(format!(
+                        "{}:{}: {reason}",
+                        path.display(),
+                        index + 1
+                    ))
+                })?;
+                loaded.push(allocation);
+            }
+            loaded
+        };
+
+        let mut seen: HashSet<_> = self.allocations.iter().map(|alloc| alloc.address).collect();
+        if let Some(alloc) = loaded.iter().find(|alloc| !seen.insert(alloc.address)) {
+            return Err(crate::GenesisError::InvalidConfig(format!(
+                "{}: Duplicate allocation address: {}",
+                path.display(),
+                alloc.address
+            )));
+        }
+
+        let count = loaded.len();
+        self.allocations.extend(loaded);
+        Ok(count)
+    }
+
     /// Calculate total allocated
     pub fn total_allocated(&self) -> U256 {
         self.allocations.iter().fold(U256::ZERO, |acc, a| acc + a.balance)
@@ -187,7 +276,7 @@ impl GenesisConfig {
     /// Validate the configuration
     pub fn validate(&self) -> Result<(), crate::GenesisError> {
         // Check for duplicate addresses
-        let mut seen = std::collections::HashSet::new();
+        let mut seen = HashSet::new();
         for alloc in &self.allocations {
             if !seen.insert(alloc.address) {
                 return Err(crate::GenesisError::InvalidConfig(
@@ -196,6 +285,16 @@ impl GenesisConfig {
             }
         }
 
+        // The chain must be able to adjust from its genesis difficulty
+        let min_difficulty = self.network.min_difficulty();
+        if U256::from(self.initial_difficulty()) < min_difficulty {
+            return Err(crate::GenesisError::InvalidConfig(format!(
+                "Initial difficulty {} below consensus minimum {}",
+                self.initial_difficulty(),
+                min_difficulty
+            )));
+        }
+
         Ok(())
     }
 }
@@ -211,6 +310,24 @@ mod tests {
         assert_eq!(NetworkType::Devnet.chain_id(), 42071);
     }
 
+    #[test]
+    fn test_initial_difficulty_above_consensus_min() {
+        for network in [NetworkType::Mainnet, NetworkType::Testnet, NetworkType::Devnet] {
+            assert!(U256::from(network.initial_difficulty()) >= network.min_difficulty(), "{network:?}");
+        }
+        
+        assert!(GenesisConfig::devnet().validate().is_ok());
+    }
+
+    #[test]
+    fn test_genesis_timestamps_are_fixed() {
+        let mainnet = GenesisConfig::new(NetworkType::Mainnet);
+        assert_ne!(mainnet.timestamp, 0);
+        assert_ne!(mainnet.timestamp, NetworkType::Testnet.genesis_timestamp());
+        assert_eq!(GenesisConfig::new(NetworkType::Testnet).timestamp, NetworkType::Testnet.genesis_timestamp());
+        assert_eq!(GenesisConfig::devnet().timestamp, 0);
+    }
+
     #[test]
     fn test_devnet_config() {
         let config = GenesisConfig::devnet();
@@ -242,4 +359,91 @@ mod tests {
         
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_load_allocations() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let csv = dir.path().join("airdrop.csv");
+        std::fs::write(
+            &csv,
+            "address,balance,vesting_blocks,description\n\
+             0x0101010101010101010101010101010101010101,1000,0,Faucet\n\
+             # comment\n\
+             \n\
+             0x0202020202020202020202020202020202020202,0x10,500,Airdrop, batch 1\n\
+             0x0303030303030303030303030303030303030303,7\n",
+        )
+        .unwrap();
+
+        let json = dir.path().join("grants.json");
+        std::fs::write(
+            &json,
+            r#"[{"address":"0x0404040404040404040404040404040404040404","balance":"0x64","description":"Grant"}]"#,
+        )
+        .unwrap();
+
+        let mut config = GenesisConfig::devnet();
+        assert_eq!(config.load_allocations(&csv).unwrap(), 3);
+        assert_eq!(config.load_allocations(&json).unwrap(), 1);
+        assert!(config.validate().is_ok());
+
+        let airdrop = &config.allocations[1];
+        assert_eq!(airdrop.address, Address::repeat_byte(2));
+        assert_eq!(airdrop.balance, U256::from(16));
+        assert_eq!(airdrop.vesting_blocks, 500);
+        assert_eq!(airdrop.description, "Airdrop, batch 1");
+        assert_eq!(config.allocations[3].balance, U256::from(100));
+        assert_eq!(config.total_allocated(), U256::from(1000 + 16 + 7 + 100));
+    }
+
+    #[test]
+    fn test_load_allocations_rejects_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dup.csv");
+        std::fs::write(
+            &path,
+            "0x0101010101010101010101010101010101010101,1000\n\
+             0x0101010101010101010101010101010101010101,2000\n",
+        )
+        .unwrap();
+
+        let mut config = GenesisConfig::devnet();
+        let err = config.load_allocations(&path).unwrap_err();
+        assert!(err.to_string().contains("Duplicate allocation address"), "{err}");
+        assert!(config.allocations.is_empty());
+
+        // Addresses already allocated in the config are duplicates too
+        let path = dir.path().join("single.csv");
+        std::fs::write(&path, "0x0101010101010101010101010101010101010101,1000\n").unwrap();
+        config.allocations.push(Allocation::new(Address::repeat_byte(1), U256::from(1), "Existing"));
+        assert!(config.load_allocations(&path).is_err());
+        assert_eq!(config.allocations.len(), 1);
+    }
+
+    #[test]
+    fn test_load_allocations_malformed_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bad.csv");
+        std::fs::write(
+            &path,
+            "0x0101010101010101010101010101010101010101,1000\n\
+             0x0202020202020202020202020202020202020202,lots\n",
+        )
+        .unwrap();
+
+        let mut config = GenesisConfig::devnet();
+        let err = config.load_allocations(&path).unwrap_err().to_string();
+        assert!(err.contains("bad.csv:2"), "{err}");
+        assert!(err.contains("invalid balance 'lots'"), "{err}");
+        assert!(config.allocations.is_empty());
+
+        std::fs::write(&path, "0x01,1000\n").unwrap();
+        let err = config.load_allocations(&path).unwrap_err().to_string();
+        assert!(err.contains("invalid address '0x01'"), "{err}");
+
+        std::fs::write(&path, "0x0101010101010101010101010101010101010101\n").unwrap();
+        let err = config.load_allocations(&path).unwrap_err().to_string();
+        assert!(err.contains("missing balance"), "{err}");
+    }
 }
@@ -59,7 +59,7 @@ pub mod constants {
     pub const DEVNET_CHAIN_ID: u64 = 42071;
     
     /// Target block time in milliseconds
-    pub const TARGET_BLOCK_TIME_MS: u64 = 400;
+    pub const TARGET_BLOCK_TIME_MS: u64 = reth_chainspec::PERMIA_BLOCK_TIME_MS;
     
     /// Initial mining difficulty (2^20)
     pub const INITIAL_DIFFICULTY: u64 = 1_048_576;
@@ -68,10 +68,10 @@ pub mod constants {
     pub const BASE_BLOCK_REWARD: u128 = 10_000_000_000_000_000_000;
     
     /// Blocks per day at 400ms
-    pub const BLOCKS_PER_DAY: u64 = 216_000; // 86400 * 1000 / 400
+    pub const BLOCKS_PER_DAY: u64 = 86_400_000 / TARGET_BLOCK_TIME_MS; // 216,000
     
     /// Blocks per year
-    pub const BLOCKS_PER_YEAR: u64 = 78_840_000; // 365 * BLOCKS_PER_DAY
+    pub const BLOCKS_PER_YEAR: u64 = 365 * BLOCKS_PER_DAY; // 78,840,000
     
     /// Foundation allocation (10% of year 1 mining)
     pub fn foundation_allocation() -> U256 {
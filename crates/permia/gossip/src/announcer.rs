@@ -2,16 +2,107 @@
 //!
 //! This module provides the block announcement service that broadcasts
 //! newly mined blocks to peers via the P2P network.
+//!
+//! Canonical heads arriving within the announce window of the previous announcement
+//! are coalesced: only the latest head is announced once the window closes. The skipped
+//! blocks are ancestors of that head, so peers importing it fetch them from us.
 
-use alloy_primitives::U128;
+use crate::error::PermiaGossipError;
+use alloy_primitives::{U128, U256};
+use permia_finality::config::MAX_REORG_DEPTH;
 use reth_chain_state::{CanonStateNotification, CanonStateSubscriptions};
 use reth_eth_wire::{NetworkPrimitives, NewBlock};
 use reth_ethereum_primitives::EthPrimitives;
 use reth_network::NetworkHandle;
 use reth_primitives_traits::RecoveredBlock;
 use std::future::Future;
+use std::time::{Duration, Instant};
 use tokio_stream::StreamExt;
-use tracing::{debug, info};
+use tracing::{debug, info, span, warn, Level};
+
+/// Default window over which bursts of canonical heads are coalesced
+pub const DEFAULT_ANNOUNCE_WINDOW: Duration = Duration::from_millis(100);
+
+/// Coalesces bursts of canonical heads into a single announcement
+///
+/// The first head after a quiet period is announced immediately. Heads arriving within
+/// the window of the last announcement replace each other, and the latest one is
+/// announced when the window closes.
+#[derive(Debug)]
+pub struct AnnounceThrottle<B> {
+    /// Coalescing window
+    window: Duration,
+    /// When the last announcement was made
+    last_announce: Option<Instant>,
+    /// Latest head waiting for the window to close
+    pending: Option<B>,
+}
+
+impl<B> AnnounceThrottle<B> {
+    /// Create a throttle with the given coalescing window
+    pub fn new(window: Duration) -> Self {
+        Self { window, last_announce: None, pending: None }
+    }
+
+    /// Record a new canonical head, returning it if it should be announced right away
+    pub fn on_head(&mut self, head: B, now: Instant) -> Option<B> {
+        if self.last_announce.is_some_and(|last| now < last + self.window) {
+            self.pending = Some(head);
+            return None;
+        }
+
+        // Any pending head is an ancestor of this one or was reorged out
+        self.pending = None;
+        self.last_announce = Some(now);
+        Some(head)
+    }
+
+    /// When the pending head is due, if any
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref()?;
+        self.last_announce.map(|last| last + self.window)
+    }
+
+    /// Take the pending head if its window has closed
+    pub fn poll(&mut self, now: Instant) -> Option<B> {
+        if self.deadline()? > now {
+            return None;
+        }
+
+        self.last_announce = Some(now);
+        self.pending.take()
+    }
+
+    /// Take the pending head regardless of the window
+    pub fn flush(&mut self) -> Option<B> {
+        self.pending.take()
+    }
+}
+
+/// Cumulative difficulty of a chain segment
+pub fn branch_difficulty(difficulties: impl IntoIterator<Item = U256>) -> U256 {
+    difficulties.into_iter().fold(U256::ZERO, |td, difficulty| td.saturating_add(difficulty))
+}
+
+/// Whether a reorg from the `old` branch onto the `new` branch should be announced
+///
+/// Both branches fork from the same ancestor, so comparing their cumulative difficulty
+/// compares the total difficulty of the two tips. Only a strictly heavier branch is
+/// announced; peers that already have the old branch would reject anything else.
+pub fn should_announce_reorg(new_td: U256, old_td: U256) -> bool {
+    new_td > old_td
+}
+
+/// Check that a reorg reverting `depth` blocks is within `max_depth`
+///
+/// Re-announcing an arbitrarily deep reorg is costly, and anything that deep reverts
+/// blocks the network already treats as final.
+pub fn check_reorg_depth(depth: u64, max_depth: u64) -> Result<(), PermiaGossipError> {
+    if depth > max_depth {
+        return Err(PermiaGossipError::ReorgTooDeep { depth, max: max_depth });
+    }
+    Ok(())
+}
 
 /// Permia Block Announcer
 ///
@@ -20,6 +111,10 @@ use tracing::{debug, info};
 pub struct PermiaBlockAnnouncer<N: NetworkPrimitives> {
     /// Network handle for announcing blocks
     network: NetworkHandle<N>,
+    /// Window over which bursts of canonical heads are coalesced
+    announce_window: Duration,
+    /// Maximum number of blocks a reorg may revert to be announced
+    max_reorg_depth: u64,
 }
 
 impl<N> PermiaBlockAnnouncer<N>
@@ -28,7 +123,23 @@ where
 {
     /// Create a new block announcer
     pub fn new(network: NetworkHandle<N>) -> Self {
-        Self { network }
+        Self { network, announce_window: DEFAULT_ANNOUNCE_WINDOW, max_reorg_depth: MAX_REORG_DEPTH }
+    }
+
+    /// Set the window over which bursts of canonical heads are coalesced
+    ///
+    /// A zero window disables coalescing and announces every new canonical block.
+    pub fn with_announce_window(mut self, window: Duration) -> Self {
+        self.announce_window = window;
+        self
+    }
+
+    /// Set the maximum number of blocks a reorg may revert to be announced
+    ///
+    /// Defaults to [`MAX_REORG_DEPTH`].
+    pub fn with_max_reorg_depth(mut self, depth: u64) -> Self {
+        self.max_reorg_depth = depth;
+        self
     }
 
     /// Run the block announcer, listening for new blocks and announcing them
@@ -39,28 +150,83 @@ where
         info!(target: "permia::announcer", "Block announcer started");
         
         let mut stream = provider.canonical_state_stream();
+        let mut throttle = AnnounceThrottle::new(self.announce_window);
         
-        while let Some(notification) = stream.next().await {
-            match notification {
-                CanonStateNotification::Commit { new } => {
-                    // Announce all new blocks - blocks() returns (number, block) tuples
-                    for (_number, block) in new.blocks() {
-                        self.announce_block(block);
+        loop {
+            // Wake up to announce a coalesced head once its window closes
+            let deadline = throttle.deadline();
+            let sleep = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into());
+            let notification = tokio::select! {
+                notification = stream.next() => notification,
+                _ = sleep, if deadline.is_some() => {
+                    if let Some(block) = throttle.poll(Instant::now()) {
+                        self.announce_block(&block);
                     }
+                    continue;
                 }
+            };
+            let Some(notification) = notification else { break };
+
+            let new = match notification {
+                CanonStateNotification::Commit { new } => new,
                 CanonStateNotification::Reorg { new, old } => {
+                    let new_td = branch_difficulty(new.blocks_iter().map(|b| b.header().difficulty));
+                    let old_td = branch_difficulty(old.blocks_iter().map(|b| b.header().difficulty));
                     debug!(
                         target: "permia::announcer",
                         reverted_blocks = old.len(),
                         new_blocks = new.len(),
+                        %new_td,
+                        %old_td,
                         "Chain reorg detected"
                     );
-                    // Announce new blocks after reorg
-                    for (_number, block) in new.blocks() {
-                        self.announce_block(block);
+
+                    if let Err(e) = check_reorg_depth(old.len() as u64, self.max_reorg_depth) {
+                        warn!(
+                            target: "permia::announcer",
+                            error = %e,
+                            "Reorg too deep, not announcing"
+                        );
+                        continue;
                     }
+
+                    if !should_announce_reorg(new_td, old_td) {
+                        warn!(
+                            target: "permia::announcer",
+                            reverted_blocks = old.len(),
+                            new_blocks = new.len(),
+                            %new_td,
+                            %old_td,
+                            "Reorg onto a branch that is not heavier, not announcing"
+                        );
+                        continue;
+                    }
+
+                    new
+                }
+            };
+
+            if self.announce_window.is_zero() {
+                // Announce all new blocks - blocks() returns (number, block) tuples
+                for (_number, block) in new.blocks() {
+                    self.announce_block(block);
                 }
+                continue;
             }
+
+            if let Some(block) = throttle.on_head(new.tip().clone(), Instant::now()) {
+                self.announce_block(&block);
+            } else {
+                debug!(
+                    target: "permia::announcer",
+                    block_number = new.tip().header().number,
+                    "Coalescing block announcement"
+                );
+            }
+        }
+
+        if let Some(block) = throttle.flush() {
+            self.announce_block(&block);
         }
         
         info!(target: "permia::announcer", "Block announcer stopped");
@@ -72,6 +238,14 @@ where
         let hash = block.hash();
         let number = header.number;
         let difficulty = header.difficulty;
+        let _span = span!(
+            target: "permia::announcer",
+            Level::INFO,
+            "announce",
+            block_number = number,
+            block_hash = %hash
+        )
+        .entered();
         
         // Create NewBlock message with total difficulty
         // For PoW, TD is cumulative difficulty up to this block
@@ -109,8 +283,80 @@ where
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_module_exists() {
         // Module compiles
     }
+
+    #[test]
+    fn test_reorg_announced_only_when_heavier() {
+        let old_td = branch_difficulty([1_000, 1_000, 1_000].map(U256::from));
+
+        // Longer but lighter branch is not announced
+        let lighter = branch_difficulty([500, 500, 500, 500, 500].map(U256::from));
+        assert_eq!(lighter, U256::from(2_500));
+        assert!(!should_announce_reorg(lighter, old_td));
+
+        // Equal work keeps the branch peers already have
+        assert!(!should_announce_reorg(old_td, old_td));
+
+        // Shorter but heavier branch is announced
+        let heavier = branch_difficulty([2_000, 1_500].map(U256::from));
+        assert!(should_announce_reorg(heavier, old_td));
+    }
+
+    #[test]
+    fn test_deep_reorg_not_announced() {
+        assert!(check_reorg_depth(0, 2).is_ok());
+        assert!(check_reorg_depth(2, 2).is_ok());
+        assert!(matches!(
+            check_reorg_depth(3, 2),
+            Err(PermiaGossipError::ReorgTooDeep { depth: 3, max: 2 })
+        ));
+        assert!(check_reorg_depth(MAX_REORG_DEPTH + 1, MAX_REORG_DEPTH).is_err());
+    }
+
+    #[test]
+    fn test_burst_is_coalesced() {
+        let window = Duration::from_millis(100);
+        let mut throttle = AnnounceThrottle::new(window);
+        let start = Instant::now();
+
+        // Five heads within the window: the first is announced immediately
+        let mut announced: Vec<u64> = Vec::new();
+        for (i, head) in (1..=5).enumerate() {
+            let now = start + Duration::from_millis(10 * i as u64);
+            announced.extend(throttle.on_head(head, now));
+            announced.extend(throttle.poll(now));
+        }
+        assert_eq!(announced, vec![1]);
+
+        // The latest head is announced once the window closes
+        assert_eq!(throttle.deadline(), Some(start + window));
+        assert_eq!(throttle.poll(start + window - Duration::from_millis(1)), None);
+        assert_eq!(throttle.poll(start + window), Some(5));
+        assert_eq!(throttle.deadline(), None);
+
+        // A head after a quiet period goes out right away
+        assert_eq!(throttle.on_head(6, start + window * 3), Some(6));
+        assert_eq!(throttle.flush(), None);
+    }
+
+    #[test]
+    fn test_flush_announces_pending_head() {
+        let mut throttle = AnnounceThrottle::new(Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert_eq!(throttle.on_head(1, now), Some(1));
+        assert_eq!(throttle.on_head(2, now), None);
+        assert_eq!(throttle.flush(), Some(2));
+    }
+
+    #[test]
+    fn test_branch_difficulty_saturates() {
+        assert_eq!(branch_difficulty([]), U256::ZERO);
+        assert_eq!(branch_difficulty([U256::MAX, U256::from(1)]), U256::MAX);
+    }
 }
@@ -3,9 +3,15 @@
 //! This module implements the `BlockImport` trait for Permia's PermiaHash PoW consensus.
 //! It validates incoming block announcements and submits valid blocks to the Engine API.
 
-use crate::error::PermiaGossipError;
-use alloy_primitives::{B256, U256};
-use permia_consensus::PermiaConsensus;
+use crate::{
+    error::PermiaGossipError,
+    p2p_importer::P2PBlockSender,
+    peer_scoring::{InvalidBlockTracker, PeerBanSender},
+};
+use alloy_primitives::B256;
+use permia_consensus::{
+    pow::PermiaHashConfig, PermiaConsensus, PermiaConsensusError, PERMIA_DEVNET_CHAIN_ID,
+};
 use reth_eth_wire::NewBlock;
 use reth_network::import::{
     BlockImport, BlockImportError, BlockImportEvent, BlockImportOutcome, BlockValidation,
@@ -13,13 +19,13 @@ use reth_network::import::{
 };
 use reth_network::message::NewBlockMessage;
 use reth_network_peers::PeerId;
-use reth_primitives_traits::Block as BlockTrait;
+use reth_primitives_traits::{Block as BlockTrait, BlockHeader, Header};
 use reth_provider::BlockReaderIdExt;
 use std::{
     collections::VecDeque,
     fmt::Debug,
     sync::Arc,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
 };
 use tracing::{debug, info, trace, warn};
 
@@ -29,75 +35,121 @@ use tracing::{debug, info, trace, warn};
 /// and submits valid blocks to the local Engine API for import.
 #[derive(Debug)]
 pub struct PermiaPoWBlockImport<Provider> {
+    /// Chain the blocks are validated for
+    chain_id: u64,
     /// PermiaHash consensus for PoW validation
     consensus: Arc<PermiaConsensus>,
     /// Provider for checking existing blocks
     provider: Provider,
     /// Pending import results
     pending_results: VecDeque<BlockImportEvent<NewBlock>>,
+    /// Waker of the network task, woken when a result is queued
+    waker: Option<Waker>,
+    /// Invalid blocks received per peer
+    invalid_blocks: InvalidBlockTracker,
+    /// Channel for banning peers that cross the invalid block threshold
+    ban_tx: Option<PeerBanSender>,
+    /// Channel forwarding valid blocks to the importer
+    import_tx: Option<P2PBlockSender>,
 }
 
 impl<Provider> PermiaPoWBlockImport<Provider>
 where
     Provider: BlockReaderIdExt + Clone + Debug + 'static,
 {
-    /// Create a new PermiaPoWBlockImport
-    pub fn new(provider: Provider) -> Self {
-        let consensus = Arc::new(PermiaConsensus::new());
+    /// Create a new PermiaPoWBlockImport validating blocks of the given chain
+    ///
+    /// The chain selects the consensus parameters, e.g. the minimum difficulty.
+    pub fn new(provider: Provider, chain_id: u64) -> Self {
+        let consensus = Arc::new(PermiaConsensus::for_chain(chain_id));
         Self {
+            chain_id,
             consensus,
             provider,
             pending_results: VecDeque::new(),
+            waker: None,
+            invalid_blocks: InvalidBlockTracker::default(),
+            ban_tx: None,
+            import_tx: None,
         }
     }
 
+    /// Chain the blocks are validated for
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Send ban signals for peers that repeatedly send invalid blocks on the given channel
+    ///
+    /// See [`apply_peer_bans`](crate::apply_peer_bans).
+    pub fn with_ban_sender(mut self, ban_tx: PeerBanSender) -> Self {
+        self.ban_tx = Some(ban_tx);
+        self
+    }
+
+    /// Forward valid blocks on the given channel for import into the local chain
+    ///
+    /// See [`PermiaP2PImporter`](crate::PermiaP2PImporter).
+    pub fn with_importer(mut self, import_tx: P2PBlockSender) -> Self {
+        self.import_tx = Some(import_tx);
+        self
+    }
+
+    /// Set the number of invalid blocks after which a peer is banned
+    pub fn with_invalid_block_threshold(mut self, threshold: u32) -> Self {
+        self.invalid_blocks = InvalidBlockTracker::new(threshold);
+        self
+    }
+
+    /// Validate blocks under the chain's PermiaHash parameters
+    ///
+    /// See [`permia_hash_config`](permia_consensus::permia_hash_config).
+    pub fn with_hash_config(mut self, config: PermiaHashConfig) -> Self {
+        self.consensus = Arc::new((*self.consensus).clone().with_hash_config(config));
+        self
+    }
+
     /// Validate a block's PermiaHash proof-of-work
     fn validate_pow(&self, block: &NewBlock) -> Result<(), PermiaGossipError> {
+        validate_pow(&self.consensus, self.chain_id, block.block.header())
+    }
+
+    /// Validate that a block's number follows its parent's
+    ///
+    /// Blocks whose parent is not known locally can't be checked here and pass.
+    fn validate_block_number(&self, block: &NewBlock) -> Result<(), PermiaGossipError> {
         let header = block.block.header();
-        let difficulty = header.difficulty;
-        
-        // Dev mode / PoS blocks have difficulty=0, skip PoW validation for these
-        // This allows sync nodes to accept blocks from dev mode miners
-        if difficulty.is_zero() {
-            debug!(
-                target: "permia::gossip",
-                block_number = %header.number,
-                "Accepting dev mode block (difficulty=0)"
-            );
-            return Ok(());
+        match self.provider.header(header.parent_hash) {
+            Ok(Some(parent)) => validate_block_number(parent.number(), header),
+            _ => Ok(()),
         }
-        
-        // Check minimum difficulty for PoW blocks
-        let min_difficulty = self.consensus.min_difficulty();
-        if difficulty < min_difficulty {
-            return Err(PermiaGossipError::DifficultyTooLow {
-                difficulty,
-                minimum: min_difficulty,
-            });
+    }
+
+    /// Count an invalid block against a peer, signalling a ban at the threshold
+    fn on_invalid_block(&mut self, peer_id: PeerId) {
+        let Some(ban) = self.invalid_blocks.record_invalid(peer_id) else { return };
+
+        warn!(
+            target: "permia::gossip",
+            %peer_id,
+            invalid_blocks = ban.invalid_blocks,
+            "Peer crossed the invalid block threshold"
+        );
+        if let Some(tx) = &self.ban_tx {
+            let _ = tx.send(ban);
         }
+    }
 
-        // Verify the PermiaHash PoW using the header
-        match self.consensus.verify_pow(header) {
-            Ok(()) => {
-                debug!(
-                    target: "permia::gossip",
-                    difficulty = %difficulty,
-                    nonce = %header.nonce,
-                    "PermiaHash PoW validated"
-                );
-                Ok(())
-            }
-            Err(e) => {
-                warn!(
-                    target: "permia::gossip",
-                    error = %e,
-                    "PermiaHash PoW validation failed"
-                );
-                Err(PermiaGossipError::InvalidPoW {
-                    expected: difficulty,
-                    actual: U256::ZERO,
-                })
-            }
+    /// Hand a valid block to the importer, if one is attached
+    fn forward_to_importer(&self, block_hash: B256, block: &NewBlock) {
+        let Some(tx) = &self.import_tx else { return };
+        if let Err(e) = tx.try_send(block.clone()) {
+            warn!(
+                target: "permia::gossip",
+                %block_hash,
+                error = %e,
+                "Dropping valid block, importer unavailable"
+            );
         }
     }
 
@@ -107,11 +159,14 @@ where
     }
 
     /// Process a new block announcement
+    ///
+    /// Already known blocks are benign and produce no outcome, since any error outcome
+    /// costs the peer reputation.
     fn process_new_block(
         &mut self,
         peer_id: PeerId,
         block: NewBlockMessage<NewBlock>,
-    ) -> BlockImportOutcome<NewBlock> {
+    ) -> Option<BlockImportOutcome<NewBlock>> {
         // Compute block hash from header
         let block_hash = block.block.block.header().hash_slow();
         
@@ -122,16 +177,11 @@ where
                 %block_hash,
                 "Block already known, skipping"
             );
-            return BlockImportOutcome {
-                peer: peer_id,
-                result: Err(BlockImportError::Other(Box::new(
-                    PermiaGossipError::AlreadyKnown { hash: block_hash },
-                ))),
-            };
+            return None;
         }
 
-        // Validate PermiaHash PoW
-        match self.validate_pow(&block.block) {
+        // Validate the block number, then PermiaHash PoW
+        match self.validate_block_number(&block.block).and_then(|()| self.validate_pow(&block.block)) {
             Ok(()) => {
                 info!(
                     target: "permia::gossip",
@@ -139,30 +189,99 @@ where
                     %peer_id,
                     "Valid PermiaHash block received from peer"
                 );
+                self.forward_to_importer(block_hash, &block.block);
                 
                 // Return valid header for relay
-                BlockImportOutcome {
+                Some(BlockImportOutcome {
                     peer: peer_id,
                     result: Ok(BlockValidation::ValidHeader { block }),
-                }
+                })
             }
             Err(e) => {
                 warn!(
                     target: "permia::gossip",
                     %block_hash,
                     %peer_id,
+                    chain_id = self.chain_id,
                     error = %e,
                     "Invalid block received from peer"
                 );
-                BlockImportOutcome {
+                self.on_invalid_block(peer_id);
+                Some(BlockImportOutcome {
                     peer: peer_id,
                     result: Err(BlockImportError::Other(Box::new(e))),
-                }
+                })
             }
         }
     }
 }
 
+/// Validate that a header's number is exactly one above its parent's
+///
+/// Mirrors [`PermiaConsensusError::BlockNumberMismatch`], with the offending numbers.
+fn validate_block_number(parent_number: u64, header: &Header) -> Result<(), PermiaGossipError> {
+    if parent_number.checked_add(1) != Some(header.number) {
+        return Err(PermiaGossipError::BlockNumberMismatch { parent_number, number: header.number });
+    }
+    Ok(())
+}
+
+/// Validate a header's PermiaHash proof-of-work
+///
+/// A difficulty below the consensus minimum is reported as
+/// [`PermiaConsensusError::DifficultyOutOfBand`] and a seal that does not meet the target as
+/// [`PermiaConsensusError::InvalidProofOfWork`], so callers can tell the two apart.
+///
+/// Only the dev chain accepts unsealed blocks of difficulty zero, from dev mode miners. On
+/// every other chain they are below the minimum difficulty.
+fn validate_pow(
+    consensus: &PermiaConsensus,
+    chain_id: u64,
+    header: &Header,
+) -> Result<(), PermiaGossipError> {
+    let difficulty = header.difficulty;
+
+    if difficulty.is_zero() && chain_id == PERMIA_DEVNET_CHAIN_ID {
+        debug!(
+            target: "permia::gossip",
+            block_number = %header.number,
+            "Accepting dev mode block (difficulty=0)"
+        );
+        return Ok(());
+    }
+
+    // Check minimum difficulty for PoW blocks
+    let min_difficulty = consensus.min_difficulty();
+    if difficulty < min_difficulty {
+        return Err(PermiaConsensusError::DifficultyOutOfBand {
+            expected: min_difficulty,
+            actual: difficulty,
+        }
+        .into());
+    }
+
+    // Verify the PermiaHash PoW using the header
+    match consensus.verify_pow(header) {
+        Ok(()) => {
+            debug!(
+                target: "permia::gossip",
+                difficulty = %difficulty,
+                nonce = %header.nonce,
+                "PermiaHash PoW validated"
+            );
+            Ok(())
+        }
+        Err(e) => {
+            warn!(
+                target: "permia::gossip",
+                error = %e,
+                "PermiaHash PoW validation failed"
+            );
+            Err(e.into())
+        }
+    }
+}
+
 impl<Provider> BlockImport<NewBlock> for PermiaPoWBlockImport<Provider>
 where
     Provider: BlockReaderIdExt + Clone + Debug + Send + Sync + 'static,
@@ -176,8 +295,13 @@ where
         
         match incoming_block {
             NewBlockEvent::Block(block) => {
-                let outcome = self.process_new_block(peer_id, block);
-                self.pending_results.push_back(BlockImportEvent::Outcome(outcome));
+                if let Some(outcome) = self.process_new_block(peer_id, block) {
+                    self.pending_results.push_back(BlockImportEvent::Outcome(outcome));
+                    // The network only polls for results when woken
+                    if let Some(waker) = self.waker.take() {
+                        waker.wake();
+                    }
+                }
             }
             NewBlockEvent::Hashes(hashes) => {
                 // For hash announcements, we need to request the full block
@@ -191,12 +315,13 @@ where
         }
     }
 
-    fn poll(&mut self, _cx: &mut Context<'_>) -> Poll<BlockImportEvent<NewBlock>> {
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<BlockImportEvent<NewBlock>> {
         // Return any pending results
         if let Some(event) = self.pending_results.pop_front() {
             return Poll::Ready(event);
         }
         
+        self.waker = Some(cx.waker().clone());
         Poll::Pending
     }
 }
@@ -204,6 +329,98 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        p2p_block_channel,
+        peer_scoring::{peer_ban_channel, PeerBan},
+        EngineClient, P2PBlockReceiver, PermiaP2PImporter,
+    };
+    use alloy_primitives::U256;
+    use alloy_rpc_types_engine::PayloadStatusEnum;
+    use permia_consensus::{pow, PERMIA_CHAIN_ID, PERMIA_TESTNET_CHAIN_ID};
+    use reth_network::{
+        config::rng_secret_key, NetworkConfigBuilder, NetworkEvent, NetworkEventListenerProvider,
+        NetworkHandle, NetworkManager, Peers, PeersInfo,
+    };
+    use reth_provider::{test_utils::MockEthProvider, BlockReader};
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        time::Duration,
+    };
+    use tokio::time::{sleep, timeout};
+    use tokio_stream::StreamExt;
+
+    fn new_block(header: Header) -> NewBlockMessage<NewBlock> {
+        let block = reth_ethereum_primitives::Block { header, body: Default::default() };
+        NewBlockMessage {
+            hash: block.header.hash_slow(),
+            block: Arc::new(NewBlock { block, td: Default::default() }),
+        }
+    }
+
+    /// Header of block 1 sealed at the given testnet minimum difficulty (nonce found by search)
+    fn sealed_testnet_header(min_difficulty: U256) -> Header {
+        let mut header = Header {
+            number: 1,
+            difficulty: min_difficulty,
+            nonce: 8423u64.to_be_bytes().into(),
+            ..Default::default()
+        };
+        header.mix_hash = pow::permia_hash_with_epoch(&pow::compute_seal_hash(&header), 8423, 1).mix_digest;
+        header
+    }
+
+    /// Engine adopting imported blocks into the peer's provider
+    struct ProviderEngine(MockEthProvider);
+
+    impl EngineClient for ProviderEngine {
+        async fn new_payload(&self, block: &NewBlock) -> Result<PayloadStatusEnum, PermiaGossipError> {
+            self.0.add_block(block.block.header.hash_slow(), block.block.clone());
+            Ok(PayloadStatusEnum::Valid)
+        }
+
+        async fn forkchoice_updated(&self, _head: B256) -> Result<PayloadStatusEnum, PermiaGossipError> {
+            Ok(PayloadStatusEnum::Valid)
+        }
+    }
+
+    /// Launch a local PoW network peer validating blocks with [`PermiaPoWBlockImport`]
+    ///
+    /// Returns the peer's handle and the channel its valid blocks are forwarded on.
+    async fn launch_peer(provider: MockEthProvider) -> (NetworkHandle, P2PBlockReceiver) {
+        let (import_tx, import_rx) = p2p_block_channel(8);
+        let block_import =
+            PermiaPoWBlockImport::new(provider.clone(), PERMIA_TESTNET_CHAIN_ID).with_importer(import_tx);
+        let config = NetworkConfigBuilder::eth(rng_secret_key())
+            .listener_addr(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .disable_discovery()
+            .with_pow()
+            .block_import(Box::new(block_import))
+            .build(provider);
+        let network = NetworkManager::new(config).await.unwrap();
+        let handle = network.handle().clone();
+        tokio::spawn(network);
+        (handle, import_rx)
+    }
+
+    /// Connect two peers and wait until both sides have an active session
+    async fn connect(a: &NetworkHandle, b: &NetworkHandle) {
+        async fn established(events: impl tokio_stream::Stream<Item = NetworkEvent>, peer: PeerId) {
+            let mut events = std::pin::pin!(events);
+            while let Some(event) = events.next().await {
+                if matches!(event, NetworkEvent::ActivePeerSession { info, .. } if info.peer_id == peer) {
+                    return;
+                }
+            }
+        }
+
+        let (events_a, events_b) = (a.event_listener(), b.event_listener());
+        a.add_peer(*b.peer_id(), b.local_addr());
+        let sessions = async {
+            established(events_a, *b.peer_id()).await;
+            established(events_b, *a.peer_id()).await;
+        };
+        timeout(Duration::from_secs(10), sessions).await.expect("peers connect");
+    }
 
     #[test]
     fn test_permia_gossip_error_display() {
@@ -213,4 +430,194 @@ mod tests {
         };
         assert!(err.to_string().contains("Invalid PermiaHash PoW"));
     }
+
+    #[test]
+    fn test_validate_pow_errors() {
+        let consensus = PermiaConsensus::new();
+
+        // Below the minimum difficulty
+        let header = Header { difficulty: U256::from(1u64), ..Default::default() };
+        assert!(matches!(
+            validate_pow(&consensus, PERMIA_CHAIN_ID, &header),
+            Err(PermiaGossipError::Pow(PermiaConsensusError::DifficultyOutOfBand { actual, .. }))
+                if actual == U256::from(1u64)
+        ));
+
+        // Enough difficulty but a bad nonce
+        let header = Header {
+            difficulty: consensus.min_difficulty(),
+            nonce: 7u64.to_be_bytes().into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            validate_pow(&consensus, PERMIA_CHAIN_ID, &header),
+            Err(PermiaGossipError::Pow(PermiaConsensusError::InvalidProofOfWork))
+        ));
+
+        // Unsealed blocks of difficulty zero only pass on the dev chain
+        let header = Header::default();
+        assert!(validate_pow(&consensus, PERMIA_DEVNET_CHAIN_ID, &header).is_ok());
+        for chain_id in [PERMIA_CHAIN_ID, PERMIA_TESTNET_CHAIN_ID] {
+            let err = validate_pow(&consensus, chain_id, &header).unwrap_err();
+            assert!(matches!(
+                err,
+                PermiaGossipError::Pow(PermiaConsensusError::DifficultyOutOfBand { actual, .. })
+                    if actual.is_zero()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_invalid_blocks_ban_peer() {
+        let provider = MockEthProvider::default();
+        let (ban_tx, mut ban_rx) = peer_ban_channel();
+        let mut import = PermiaPoWBlockImport::new(provider.clone(), PERMIA_CHAIN_ID)
+            .with_ban_sender(ban_tx)
+            .with_invalid_block_threshold(3);
+        let min_difficulty = import.consensus.min_difficulty();
+
+        // Blocks with bad seals are rejected, and the third one bans the peer
+        let spammer = PeerId::repeat_byte(1);
+        for nonce in 0..3u64 {
+            let header =
+                Header { difficulty: min_difficulty, nonce: nonce.to_be_bytes().into(), ..Default::default() };
+            import.on_new_block(spammer, NewBlockEvent::Block(new_block(header)));
+            if nonce < 2 {
+                assert!(ban_rx.try_recv().is_err());
+            }
+        }
+        assert_eq!(ban_rx.try_recv().unwrap(), PeerBan { peer_id: spammer, invalid_blocks: 3 });
+        assert_eq!(import.pending_results.len(), 3);
+        assert!(import.pending_results.iter().all(|event| matches!(
+            event,
+            BlockImportEvent::Outcome(BlockImportOutcome { result: Err(_), .. })
+        )));
+
+        // Re-announcing a known block is benign
+        let known = new_block(Header { number: 1, difficulty: min_difficulty, ..Default::default() });
+        provider.add_block(known.hash, known.block.block.clone());
+        let peer = PeerId::repeat_byte(2);
+        for _ in 0..5 {
+            import.on_new_block(peer, NewBlockEvent::Block(known.clone()));
+        }
+        assert_eq!(import.pending_results.len(), 3);
+        assert_eq!(import.invalid_blocks.invalid_blocks(&peer), 0);
+        assert!(ban_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_gapped_block_number_rejected() {
+        let provider = MockEthProvider::default();
+        let mut import = PermiaPoWBlockImport::new(provider.clone(), PERMIA_DEVNET_CHAIN_ID);
+        let parent = Header { number: 10, ..Default::default() };
+        let parent_hash = parent.hash_slow();
+        provider.add_header(parent_hash, parent);
+
+        // The number check runs before PoW, so dev chain blocks of difficulty 0 isolate it
+        let sequential = new_block(Header { number: 11, parent_hash, ..Default::default() });
+        assert!(import.validate_block_number(&sequential.block).is_ok());
+        import.on_new_block(PeerId::repeat_byte(1), NewBlockEvent::Block(sequential));
+
+        let gapped = new_block(Header { number: 13, parent_hash, ..Default::default() });
+        assert!(matches!(
+            import.validate_block_number(&gapped.block),
+            Err(PermiaGossipError::BlockNumberMismatch { parent_number: 10, number: 13 })
+        ));
+        import.on_new_block(PeerId::repeat_byte(2), NewBlockEvent::Block(gapped));
+
+        assert!(matches!(
+            import.pending_results.pop_front(),
+            Some(BlockImportEvent::Outcome(BlockImportOutcome { result: Ok(_), .. }))
+        ));
+        assert!(matches!(
+            import.pending_results.pop_front(),
+            Some(BlockImportEvent::Outcome(BlockImportOutcome { result: Err(_), .. }))
+        ));
+        assert_eq!(import.invalid_blocks.invalid_blocks(&PeerId::repeat_byte(2)), 1);
+    }
+
+    #[test]
+    fn test_min_difficulty_follows_chain() {
+        let provider = MockEthProvider::default();
+        let testnet = PermiaPoWBlockImport::new(provider.clone(), PERMIA_TESTNET_CHAIN_ID);
+        let mainnet = PermiaPoWBlockImport::new(provider, PERMIA_CHAIN_ID);
+        assert_eq!(testnet.chain_id(), PERMIA_TESTNET_CHAIN_ID);
+
+        // Sealed at the testnet minimum difficulty
+        let block = new_block(sealed_testnet_header(testnet.consensus.min_difficulty())).block;
+
+        assert!(testnet.validate_pow(&block).is_ok());
+        assert!(matches!(
+            mainnet.validate_pow(&block),
+            Err(PermiaGossipError::Pow(PermiaConsensusError::DifficultyOutOfBand { expected, .. }))
+                if expected == mainnet.consensus.min_difficulty()
+        ));
+
+        // The mainnet minimum is above the testnet one, so mainnet blocks are never too
+        // easy for a testnet importer
+        assert!(mainnet.consensus.min_difficulty() > testnet.consensus.min_difficulty());
+    }
+
+    #[test]
+    fn test_dag_version_follows_config() {
+        let v2 = PermiaHashConfig { dag_version: pow::DagVersion::V2, ..Default::default() };
+        let provider = MockEthProvider::default();
+        let v1_import = PermiaPoWBlockImport::new(provider.clone(), PERMIA_TESTNET_CHAIN_ID);
+        let v2_import = PermiaPoWBlockImport::new(provider, PERMIA_TESTNET_CHAIN_ID)
+            .with_hash_config(v2.clone());
+
+        // Sealed under V2 at the testnet minimum difficulty (nonce found by search)
+        let mut header = Header {
+            number: 1,
+            difficulty: v1_import.consensus.min_difficulty(),
+            nonce: 54381u64.to_be_bytes().into(),
+            ..Default::default()
+        };
+        let result = pow::permia_hash_with_config(&pow::compute_seal_hash(&header), 54381, 1, &v2);
+        header.mix_hash = result.unwrap().mix_digest;
+        let block = new_block(header).block;
+
+        assert!(v2_import.validate_pow(&block).is_ok());
+        assert!(matches!(
+            v1_import.validate_pow(&block),
+            Err(PermiaGossipError::Pow(PermiaConsensusError::InvalidProofOfWork))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_exchanged_between_peers() {
+        // M mines and announces, B validates and imports, A is only connected to B
+        let provider_m = MockEthProvider::default();
+        let provider_b = MockEthProvider::default();
+        let (peer_m, mut import_m) = launch_peer(provider_m.clone()).await;
+        let (peer_b, import_b) = launch_peer(provider_b.clone()).await;
+        let (peer_a, mut import_a) = launch_peer(MockEthProvider::default()).await;
+        connect(&peer_m, &peer_b).await;
+        connect(&peer_a, &peer_b).await;
+        tokio::spawn(PermiaP2PImporter::new(import_b, ProviderEngine(provider_b.clone())).run());
+
+        let min_difficulty = PermiaConsensus::for_chain(PERMIA_TESTNET_CHAIN_ID).min_difficulty();
+        let header = sealed_testnet_header(min_difficulty);
+        let block = reth_ethereum_primitives::Block { header, body: Default::default() };
+        let hash = block.header.hash_slow();
+        provider_m.add_block(hash, block.clone());
+        peer_m.announce_block(NewBlock { block, td: Default::default() }, hash);
+
+        // B validates the block and adopts it through the importer
+        let imported = async {
+            while provider_b.block_by_hash(hash).unwrap().is_none() {
+                sleep(Duration::from_millis(10)).await;
+            }
+        };
+        timeout(Duration::from_secs(10), imported).await.expect("peer B imports the block");
+
+        // B relays the validated block to the peers that haven't seen it: A receives the
+        // relay, M doesn't get its own block back
+        let relayed = timeout(Duration::from_secs(10), import_a.recv())
+            .await
+            .expect("peer A receives the relay")
+            .unwrap();
+        assert_eq!(relayed.block.header.hash_slow(), hash);
+        assert!(import_m.try_recv().is_err());
+    }
 }
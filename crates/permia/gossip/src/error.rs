@@ -31,6 +31,15 @@ pub enum PermiaGossipError {
         parent_hash: B256,
     },
 
+    /// Block number does not follow its parent's
+    #[error("Block number {number} does not follow parent block number {parent_number}")]
+    BlockNumberMismatch {
+        /// Number of the parent block
+        parent_number: u64,
+        /// Number claimed by the block
+        number: u64,
+    },
+
     /// Difficulty too low
     #[error("Difficulty too low: {difficulty} < minimum {minimum}")]
     DifficultyTooLow {
@@ -47,15 +56,54 @@ pub enum PermiaGossipError {
         hash: B256,
     },
 
+    /// Reorg reverts more blocks than allowed
+    #[error("Reorg of {depth} blocks exceeds the maximum reorg depth {max}")]
+    ReorgTooDeep {
+        /// Number of reverted blocks
+        depth: u64,
+        /// Maximum reorg depth
+        max: u64,
+    },
+
+    /// Fork choice refused to revert canonical blocks, e.g. finalized ones
+    #[error("Fork choice rejected: {0}")]
+    ForkChoiceRejected(String),
+
     /// Engine API error
     #[error("Engine API error: {0}")]
     EngineApi(String),
 
+    /// Engine rejected the block as INVALID
+    #[error("Engine rejected block {hash} as invalid: {reason}")]
+    InvalidPayload {
+        /// Hash of the rejected block
+        hash: B256,
+        /// Validation error reported by the engine
+        reason: String,
+    },
+
+    /// Engine API call kept failing until the attempt limit
+    #[error("Engine API {method} failed after {attempts} attempts")]
+    EngineRetriesExhausted {
+        /// Engine API method that failed
+        method: &'static str,
+        /// Number of attempts made
+        attempts: u32,
+    },
+
     /// Provider error
     #[error("Provider error: {0}")]
     Provider(String),
 
+    /// Malformed vote gossip message
+    #[error("Invalid vote message: {0}")]
+    InvalidVote(String),
+
     /// Consensus error
     #[error("Consensus error: {0}")]
     Consensus(#[from] reth_consensus::ConsensusError),
+
+    /// PermiaHash validation error, e.g. a bad seal or an out-of-band difficulty
+    #[error("PermiaHash validation failed: {0}")]
+    Pow(#[from] permia_consensus::PermiaConsensusError),
 }
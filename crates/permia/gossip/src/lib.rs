@@ -29,11 +29,7 @@
 //! ```ignore
 //! use permia_gossip::PermiaPoWBlockImport;
 //!
-//! let block_import = PermiaPoWBlockImport::new(
-//!     consensus,
-//!     engine_handle,
-//!     provider,
-//! );
+//! let block_import = PermiaPoWBlockImport::new(provider, chain_spec.chain().id());
 //! ```
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
@@ -42,11 +38,30 @@ mod announcer;
 mod block_import;
 mod error;
 mod p2p_importer;
+mod peer_scoring;
+mod vote_gossip;
 
-pub use announcer::{PermiaBlockAnnouncer, spawn_block_announcer};
+pub use announcer::{
+    branch_difficulty, check_reorg_depth, should_announce_reorg, spawn_block_announcer, AnnounceThrottle,
+    PermiaBlockAnnouncer, DEFAULT_ANNOUNCE_WINDOW,
+};
 pub use block_import::PermiaPoWBlockImport;
 pub use error::PermiaGossipError;
-pub use p2p_importer::{p2p_block_channel, P2PBlockReceiver, P2PBlockSender, PermiaP2PImporter};
+pub use p2p_importer::{
+    p2p_block_channel, EngineClient, EngineRetryConfig, ForkChoiceRule, HeaviestChainRule,
+    P2PBlockReceiver, P2PBlockSender, PermiaP2PImporter, ReorgGuard,
+    DEFAULT_ENGINE_INITIAL_BACKOFF, DEFAULT_ENGINE_MAX_ATTEMPTS, DEFAULT_ENGINE_MAX_BACKOFF,
+    DEFAULT_RECENT_HEADERS,
+};
+pub use peer_scoring::{
+    apply_peer_bans, peer_ban_channel, InvalidBlockTracker, PeerBan, PeerBanReceiver,
+    PeerBanSender, DEFAULT_INVALID_BLOCK_THRESHOLD, MAX_TRACKED_PEERS,
+};
+pub use vote_gossip::{
+    decode_vote_frame, encode_vote_frame, install_vote_gossip, spawn_vote_producer,
+    vote_capability, vote_gossip, vote_protocol, PermiaVoteProtocol, VoteBroadcaster,
+    VoteConnection, VoteConnectionHandler, VoteProducer, VoteReceiver,
+};
 
 /// Re-export core types
 pub use reth_network::import::{BlockImport, BlockImportEvent, BlockValidation, NewBlockEvent};
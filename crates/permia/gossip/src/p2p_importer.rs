@@ -1,20 +1,35 @@
-//! P2P Block Importer (Stub)
+//! P2P Block Importer
 //!
-//! This module provides the foundation for P2P block import via Engine API.
-//! Currently serves as a placeholder - full implementation requires complex
-//! type conversions between P2P block types and Engine API payload types.
+//! Submits validated P2P blocks to the local chain through an [`EngineClient`]:
+//! `newPayload` for the block, then `forkchoiceUpdated` to make it the head if the
+//! [`ForkChoiceRule`] picks it. The node's [`ConsensusEngineHandle`] is the production
+//! client, and [`HeaviestChainRule`] the production rule.
 //!
-//! The P2P gossip infrastructure (validation + announcement) is fully functional.
-//! Actual chain import for sync nodes will be implemented in a future phase.
+//! The engine may answer SYNCING while it catches up, or fail transiently, so both
+//! calls are retried with exponential backoff per [`EngineRetryConfig`]. INVALID
+//! responses are final and never retried.
 
+use crate::{announcer::check_reorg_depth, PermiaGossipError};
+use alloy_primitives::{B256, U256};
+use alloy_rpc_types_engine::{ForkchoiceState, PayloadStatusEnum};
+use parking_lot::Mutex;
+use permia_finality::config::MAX_REORG_DEPTH;
+use reth_engine_primitives::ConsensusEngineHandle;
 use reth_eth_wire::NewBlock;
-use reth_primitives_traits::Block as BlockTrait;
+use reth_payload_primitives::{BuiltPayload, EngineApiMessageVersion, PayloadTypes};
+use reth_primitives_traits::{Block as BlockTrait, BlockHeader, Header, NodePrimitives, SealedBlock};
+use reth_provider::BlockReaderIdExt;
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    time::Duration,
+};
 use tokio::sync::mpsc;
-use tracing::info;
+use tracing::{debug, info, span, warn, Instrument, Level};
 
 /// Channel for submitting validated P2P blocks for import
 pub type P2PBlockSender = mpsc::Sender<NewBlock>;
-/// Receiver for validated P2P blocks  
+/// Receiver for validated P2P blocks
 pub type P2PBlockReceiver = mpsc::Receiver<NewBlock>;
 
 /// Creates a channel for P2P block import
@@ -22,45 +37,627 @@ pub fn p2p_block_channel(buffer: usize) -> (P2PBlockSender, P2PBlockReceiver) {
     mpsc::channel(buffer)
 }
 
-/// P2P Block Importer (stub implementation)
-/// 
-/// In the current implementation, P2P blocks are:
-/// - Validated via PermiaPoWBlockImport (working)
-/// - Announced via PermiaBlockAnnouncer (working)
-/// - NOT imported to local chain (requires Engine API integration)
+/// Default number of attempts per Engine API call
+pub const DEFAULT_ENGINE_MAX_ATTEMPTS: u32 = 5;
+
+/// Default delay before the first retry
+pub const DEFAULT_ENGINE_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Default upper bound on the delay between retries
+pub const DEFAULT_ENGINE_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Local Engine API used to import P2P blocks
+pub trait EngineClient: Send + Sync {
+    /// Submit a block via `engine_newPayload`
+    fn new_payload(
+        &self,
+        block: &NewBlock,
+    ) -> impl Future<Output = Result<PayloadStatusEnum, PermiaGossipError>> + Send;
+
+    /// Make the given block the head via `engine_forkchoiceUpdated`
+    fn forkchoice_updated(
+        &self,
+        head: B256,
+    ) -> impl Future<Output = Result<PayloadStatusEnum, PermiaGossipError>> + Send;
+}
+
+impl<T> EngineClient for ConsensusEngineHandle<T>
+where
+    T: PayloadTypes<
+        BuiltPayload: BuiltPayload<
+            Primitives: NodePrimitives<Block = reth_ethereum_primitives::Block>,
+        >,
+    >,
+{
+    async fn new_payload(&self, block: &NewBlock) -> Result<PayloadStatusEnum, PermiaGossipError> {
+        let payload = T::block_to_payload(SealedBlock::seal_slow(block.block.clone()));
+        Self::new_payload(self, payload)
+            .await
+            .map(|status| status.status)
+            .map_err(|e| PermiaGossipError::EngineApi(e.to_string()))
+    }
+
+    async fn forkchoice_updated(&self, head: B256) -> Result<PayloadStatusEnum, PermiaGossipError> {
+        let state = ForkchoiceState { head_block_hash: head, ..Default::default() };
+        self.fork_choice_updated(state, None, EngineApiMessageVersion::default())
+            .await
+            .map(|updated| updated.payload_status.status)
+            .map_err(|e| PermiaGossipError::EngineApi(e.to_string()))
+    }
+}
+
+/// Decides whether an imported block becomes the new head
+pub trait ForkChoiceRule: Send + Sync {
+    /// Whether `block`, already accepted by the engine, should replace the current head
+    fn is_new_head(&self, block: &NewBlock) -> Result<bool, PermiaGossipError>;
+}
+
+/// Decides whether canonical blocks may be reverted by a fork choice update
+pub trait ReorgGuard: Send + Sync {
+    /// Check that reverting `reverted` (oldest first) is allowed
+    fn check_reorg(&self, reverted: &[B256]) -> Result<(), PermiaGossipError>;
+}
+
+/// Number of recently imported headers [`HeaviestChainRule`] remembers
+pub const DEFAULT_RECENT_HEADERS: usize = 1024;
+
+/// Fork choice by total difficulty
 ///
-/// For full sync node support, this component needs to:
-/// 1. Convert NewBlock to ExecutionPayload
-/// 2. Submit via Engine API newPayload
-/// 3. Update forkchoice state
+/// A block extending the canonical head always becomes the new head. A block on another
+/// branch only does if its branch, from the fork point, is heavier than the canonical
+/// blocks it would revert, and the [`ReorgGuard`], if any, allows reverting them.
+/// Branches forking more than the maximum reorg depth below the head are refused before
+/// the engine is ever asked to switch to them.
+pub struct HeaviestChainRule<Provider> {
+    provider: Provider,
+    /// Headers of recently imported blocks, to walk branches that are not canonical
+    recent: Mutex<RecentHeaders>,
+    /// Check on the canonical blocks a switch would revert
+    reorg_guard: Option<Box<dyn ReorgGuard>>,
+    /// Maximum number of canonical blocks a switch may revert
+    max_reorg_depth: u64,
+}
+
+impl<Provider: std::fmt::Debug> std::fmt::Debug for HeaviestChainRule<Provider> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeaviestChainRule")
+            .field("provider", &self.provider)
+            .field("reorg_guard", &self.reorg_guard.is_some())
+            .field("max_reorg_depth", &self.max_reorg_depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Provider> HeaviestChainRule<Provider>
+where
+    Provider: BlockReaderIdExt + Send + Sync,
+{
+    /// Create a rule comparing imported blocks against the provider's canonical chain
+    pub fn new(provider: Provider) -> Self {
+        Self {
+            provider,
+            recent: Mutex::new(RecentHeaders::new(DEFAULT_RECENT_HEADERS)),
+            reorg_guard: None,
+            max_reorg_depth: MAX_REORG_DEPTH,
+        }
+    }
+
+    /// Set the maximum number of canonical blocks a switch may revert
+    ///
+    /// Defaults to [`MAX_REORG_DEPTH`].
+    pub fn with_max_reorg_depth(mut self, depth: u64) -> Self {
+        self.max_reorg_depth = depth;
+        self
+    }
+
+    /// Only switch branches if the guard allows reverting the canonical blocks
+    pub fn with_reorg_guard(mut self, guard: impl ReorgGuard + 'static) -> Self {
+        self.reorg_guard = Some(Box::new(guard));
+        self
+    }
+
+    /// Walk from `header` down to the canonical chain
+    fn branch(&self, header: &Header) -> Result<Branch, PermiaGossipError> {
+        let recent = self.recent.lock();
+        let head = self.provider.best_block_number().map_err(provider_error)?;
+        let mut new_td = header.difficulty;
+        let mut parent_hash = header.parent_hash;
+        let ancestor = loop {
+            if let Some(number) = self.canonical_number(parent_hash)? {
+                break number;
+            }
+            let parent =
+                recent.get(&parent_hash).ok_or(PermiaGossipError::ParentNotFound { parent_hash })?;
+            // Stop walking as soon as the fork point is known to be too deep
+            check_reorg_depth(head.saturating_sub(parent.number), self.max_reorg_depth)?;
+            new_td = new_td.saturating_add(parent.difficulty);
+            parent_hash = parent.parent_hash;
+        };
+        check_reorg_depth(head.saturating_sub(ancestor), self.max_reorg_depth)?;
+
+        let mut reverted = Vec::new();
+        let mut old_td = U256::ZERO;
+        for number in ancestor + 1..=head {
+            let header = self
+                .provider
+                .sealed_header(number)
+                .map_err(provider_error)?
+                .ok_or_else(|| PermiaGossipError::Provider(format!("missing header {number}")))?;
+            old_td = old_td.saturating_add(header.header().difficulty());
+            reverted.push(header.hash());
+        }
+
+        Ok(Branch { reverted, old_td, new_td })
+    }
+
+    /// Number of a block if it is on the canonical chain
+    fn canonical_number(&self, hash: B256) -> Result<Option<u64>, PermiaGossipError> {
+        let Some(number) = self.provider.block_number(hash).map_err(provider_error)? else {
+            return Ok(None)
+        };
+        let canonical = self.provider.block_hash(number).map_err(provider_error)?;
+        Ok((canonical == Some(hash)).then_some(number))
+    }
+}
+
+impl<Provider> ForkChoiceRule for HeaviestChainRule<Provider>
+where
+    Provider: BlockReaderIdExt + Send + Sync,
+{
+    fn is_new_head(&self, block: &NewBlock) -> Result<bool, PermiaGossipError> {
+        let header = block.block.header();
+        let hash = header.hash_slow();
+        self.recent.lock().insert(hash, header.clone());
+
+        let branch = self.branch(header)?;
+        if branch.reverted.is_empty() {
+            return Ok(true);
+        }
+
+        debug!(
+            target: "permia::p2p_importer",
+            %hash,
+            reverted = branch.reverted.len(),
+            new_td = %branch.new_td,
+            old_td = %branch.old_td,
+            "Block forks off the canonical chain"
+        );
+        if branch.new_td <= branch.old_td {
+            return Ok(false);
+        }
+        if let Some(guard) = &self.reorg_guard {
+            guard.check_reorg(&branch.reverted)?;
+        }
+        Ok(true)
+    }
+}
+
+/// A branch from the canonical chain to an imported block
+#[derive(Debug)]
+struct Branch {
+    /// Canonical blocks above the fork point, oldest first
+    reverted: Vec<B256>,
+    /// Difficulty of the reverted blocks
+    old_td: U256,
+    /// Difficulty of the branch up to and including the imported block
+    new_td: U256,
+}
+
+/// Bounded map of headers, evicting the oldest insert first
 #[derive(Debug)]
-pub struct PermiaP2PImporter {
+struct RecentHeaders {
+    headers: HashMap<B256, Header>,
+    order: VecDeque<B256>,
+    capacity: usize,
+}
+
+impl RecentHeaders {
+    fn new(capacity: usize) -> Self {
+        Self { headers: HashMap::new(), order: VecDeque::new(), capacity }
+    }
+
+    fn get(&self, hash: &B256) -> Option<&Header> {
+        self.headers.get(hash)
+    }
+
+    fn insert(&mut self, hash: B256, header: Header) {
+        if self.headers.insert(hash, header).is_some() {
+            return;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.headers.remove(&oldest);
+            }
+        }
+    }
+}
+
+fn provider_error(e: impl std::fmt::Display) -> PermiaGossipError {
+    PermiaGossipError::Provider(e.to_string())
+}
+
+/// Retry policy for Engine API calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineRetryConfig {
+    /// Maximum number of attempts per call, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled on every further retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl Default for EngineRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_ENGINE_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_ENGINE_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_ENGINE_MAX_BACKOFF,
+        }
+    }
+}
+
+impl EngineRetryConfig {
+    /// Set the maximum number of attempts per call
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the upper bound on the delay between retries
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Delay after the given failed attempt (1-based)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// P2P Block Importer
+///
+/// P2P blocks are:
+/// - Validated via PermiaPoWBlockImport
+/// - Announced via PermiaBlockAnnouncer
+/// - Imported to the local chain via the [`EngineClient`]
+pub struct PermiaP2PImporter<E> {
     block_rx: P2PBlockReceiver,
+    engine: E,
+    retry: EngineRetryConfig,
+    fork_choice: Option<Box<dyn ForkChoiceRule>>,
 }
 
-impl PermiaP2PImporter {
-    /// Create a new P2P block importer
-    pub fn new(block_rx: P2PBlockReceiver) -> Self {
-        Self { block_rx }
+impl<E: std::fmt::Debug> std::fmt::Debug for PermiaP2PImporter<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PermiaP2PImporter")
+            .field("engine", &self.engine)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: EngineClient> PermiaP2PImporter<E> {
+    /// Create a new P2P block importer submitting to the given engine
+    ///
+    /// Without a [`ForkChoiceRule`] every imported block becomes the head.
+    pub fn new(block_rx: P2PBlockReceiver, engine: E) -> Self {
+        Self { block_rx, engine, retry: EngineRetryConfig::default(), fork_choice: None }
+    }
+
+    /// Only make imported blocks the head if the rule picks them
+    pub fn with_fork_choice(mut self, rule: impl ForkChoiceRule + 'static) -> Self {
+        self.fork_choice = Some(Box::new(rule));
+        self
+    }
+
+    /// Set the retry policy for Engine API calls
+    pub fn with_retry_config(mut self, retry: EngineRetryConfig) -> Self {
+        self.retry = retry;
+        self
     }
 
-    /// Run the P2P importer loop (logs validated blocks for now)
+    /// Run the P2P importer loop until the block channel closes
     pub async fn run(mut self) {
-        info!(target: "permia::p2p_importer", "P2P block importer started (stub mode)");
+        info!(target: "permia::p2p_importer", "P2P block importer started");
 
         while let Some(block) = self.block_rx.recv().await {
-            let header = block.block.header();
-            let block_hash = header.hash_slow();
-            let block_number = header.number;
-
-            info!(
+            let block_hash = block.block.header().hash_slow();
+            let block_number = block.block.header().number;
+            let span = span!(
                 target: "permia::p2p_importer",
-                number = %block_number,
-                hash = %block_hash,
-                "Received validated P2P block (import not yet implemented)"
+                Level::INFO,
+                "import",
+                block_number,
+                block_hash = %block_hash
             );
+
+            async {
+                match self.import(&block).await {
+                    Ok(()) => info!(
+                        target: "permia::p2p_importer",
+                        number = %block_number,
+                        hash = %block_hash,
+                        "Imported P2P block"
+                    ),
+                    Err(e) => warn!(
+                        target: "permia::p2p_importer",
+                        number = %block_number,
+                        hash = %block_hash,
+                        error = %e,
+                        "Failed to import P2P block"
+                    ),
+                }
+            }
+            .instrument(span)
+            .await;
         }
 
         info!(target: "permia::p2p_importer", "P2P block importer stopped");
     }
+
+    /// Import a block: submit its payload, then make it the head if the fork choice rule
+    /// picks it
+    pub async fn import(&self, block: &NewBlock) -> Result<(), PermiaGossipError> {
+        let hash = block.block.header().hash_slow();
+        self.with_retry("newPayload", hash, || self.engine.new_payload(block)).await?;
+
+        if let Some(rule) = &self.fork_choice {
+            if !rule.is_new_head(block)? {
+                debug!(target: "permia::p2p_importer", %hash, "Keeping the heavier canonical head");
+                return Ok(());
+            }
+        }
+        self.with_retry("forkchoiceUpdated", hash, || self.engine.forkchoice_updated(hash)).await
+    }
+
+    /// Call the engine until it accepts, rejects, or the attempts run out
+    async fn with_retry<F, Fut>(
+        &self,
+        method: &'static str,
+        hash: B256,
+        mut call: F,
+    ) -> Result<(), PermiaGossipError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<PayloadStatusEnum, PermiaGossipError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let reason = match call().await {
+                Ok(PayloadStatusEnum::Valid | PayloadStatusEnum::Accepted) => return Ok(()),
+                Ok(PayloadStatusEnum::Invalid { validation_error }) => {
+                    return Err(PermiaGossipError::InvalidPayload { hash, reason: validation_error })
+                }
+                Ok(PayloadStatusEnum::Syncing) => "engine syncing".to_string(),
+                Err(e) => e.to_string(),
+            };
+
+            if attempt >= self.retry.max_attempts {
+                warn!(
+                    target: "permia::p2p_importer",
+                    method,
+                    %hash,
+                    attempt,
+                    %reason,
+                    "Giving up on Engine API call"
+                );
+                return Err(PermiaGossipError::EngineRetriesExhausted { method, attempts: attempt });
+            }
+
+            let backoff = self.retry.backoff(attempt);
+            debug!(
+                target: "permia::p2p_importer",
+                method,
+                %hash,
+                attempt,
+                ?backoff,
+                %reason,
+                "Retrying Engine API call"
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use reth_primitives_traits::Header;
+    use std::collections::VecDeque;
+
+    /// Engine answering `newPayload` from a script, `forkchoiceUpdated` from its own script
+    /// or VALID once that runs out
+    #[derive(Default)]
+    struct MockEngine {
+        new_payload: Mutex<VecDeque<PayloadStatusEnum>>,
+        new_payload_calls: Mutex<u32>,
+        forkchoice: Mutex<VecDeque<PayloadStatusEnum>>,
+        heads: Mutex<Vec<B256>>,
+    }
+
+    impl MockEngine {
+        fn scripted(responses: impl IntoIterator<Item = PayloadStatusEnum>) -> Self {
+            Self { new_payload: Mutex::new(responses.into_iter().collect()), ..Default::default() }
+        }
+    }
+
+    impl EngineClient for &MockEngine {
+        async fn new_payload(&self, _block: &NewBlock) -> Result<PayloadStatusEnum, PermiaGossipError> {
+            *self.new_payload_calls.lock() += 1;
+            self.new_payload
+                .lock()
+                .pop_front()
+                .ok_or_else(|| PermiaGossipError::EngineApi("engine unavailable".into()))
+        }
+
+        async fn forkchoice_updated(&self, head: B256) -> Result<PayloadStatusEnum, PermiaGossipError> {
+            self.heads.lock().push(head);
+            Ok(self.forkchoice.lock().pop_front().unwrap_or(PayloadStatusEnum::Valid))
+        }
+    }
+
+    fn new_block() -> NewBlock {
+        block(Header { number: 1, ..Default::default() })
+    }
+
+    fn block(header: Header) -> NewBlock {
+        let block = reth_ethereum_primitives::Block { header, body: Default::default() };
+        NewBlock { block, td: Default::default() }
+    }
+
+    /// Header of a child of `parent` with the given difficulty
+    fn child(parent: &Header, difficulty: u64, extra: u8) -> Header {
+        Header {
+            number: parent.number + 1,
+            parent_hash: parent.hash_slow(),
+            difficulty: U256::from(difficulty),
+            extra_data: vec![extra].into(),
+            ..Default::default()
+        }
+    }
+
+    fn retry() -> EngineRetryConfig {
+        EngineRetryConfig::default().with_initial_backoff(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let retry = EngineRetryConfig::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500));
+        let delays: Vec<_> = (1..=5).map(|attempt| retry.backoff(attempt).as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 500, 500]);
+        assert_eq!(retry.backoff(u32::MAX), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn test_syncing_is_retried_until_valid() {
+        let engine = MockEngine::scripted([
+            PayloadStatusEnum::Syncing,
+            PayloadStatusEnum::Syncing,
+            PayloadStatusEnum::Valid,
+        ]);
+        let (_tx, rx) = p2p_block_channel(1);
+        let importer = PermiaP2PImporter::new(rx, &engine).with_retry_config(retry());
+
+        let block = new_block();
+        importer.import(&block).await.unwrap();
+        assert_eq!(*engine.new_payload_calls.lock(), 3);
+        assert_eq!(*engine.heads.lock(), [block.block.header().hash_slow()]);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_exhausted_are_not_imported() {
+        let invalid = MockEngine::scripted([
+            PayloadStatusEnum::Invalid { validation_error: "bad state root".into() },
+            PayloadStatusEnum::Valid,
+        ]);
+        let (_tx, rx) = p2p_block_channel(1);
+        let importer = PermiaP2PImporter::new(rx, &invalid).with_retry_config(retry());
+        let err = importer.import(&new_block()).await.unwrap_err();
+        assert!(matches!(err, PermiaGossipError::InvalidPayload { .. }), "{err}");
+        assert_eq!(*invalid.new_payload_calls.lock(), 1);
+        assert!(invalid.heads.lock().is_empty());
+
+        // Transient errors are retried up to the attempt limit
+        let unavailable = MockEngine::default();
+        let (_tx, rx) = p2p_block_channel(1);
+        let importer =
+            PermiaP2PImporter::new(rx, &unavailable).with_retry_config(retry().with_max_attempts(3));
+        let err = importer.import(&new_block()).await.unwrap_err();
+        assert!(matches!(err, PermiaGossipError::EngineRetriesExhausted { attempts: 3, .. }), "{err}");
+        assert_eq!(*unavailable.new_payload_calls.lock(), 3);
+        assert!(unavailable.heads.lock().is_empty());
+
+        // An INVALID fork choice update is not retried either
+        let engine = MockEngine::scripted([PayloadStatusEnum::Valid]);
+        engine
+            .forkchoice
+            .lock()
+            .push_back(PayloadStatusEnum::Invalid { validation_error: "bad ancestor".into() });
+        let (_tx, rx) = p2p_block_channel(1);
+        let importer = PermiaP2PImporter::new(rx, &engine).with_retry_config(retry());
+        let err = importer.import(&new_block()).await.unwrap_err();
+        assert!(matches!(err, PermiaGossipError::InvalidPayload { .. }), "{err}");
+        assert_eq!(engine.heads.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fork_choice_follows_heaviest_chain() {
+        use reth_provider::test_utils::MockEthProvider;
+
+        // Canonical chain: genesis <- a1 <- a2, difficulty 10 each
+        let provider = MockEthProvider::default();
+        let genesis = Header::default();
+        let a1 = child(&genesis, 10, 0xa);
+        let a2 = child(&a1, 10, 0xa);
+        for header in [&genesis, &a1, &a2] {
+            provider.add_header(header.hash_slow(), header.clone());
+        }
+
+        let engine = MockEngine::scripted(vec![PayloadStatusEnum::Valid; 4]);
+        let (_tx, rx) = p2p_block_channel(1);
+        let importer = PermiaP2PImporter::new(rx, &engine)
+            .with_retry_config(retry())
+            .with_fork_choice(HeaviestChainRule::new(provider));
+
+        // A lighter fork is imported but does not become the head
+        let b1 = child(&genesis, 15, 0xb);
+        importer.import(&block(b1.clone())).await.unwrap();
+        assert!(engine.heads.lock().is_empty());
+
+        // Once its branch outweighs the canonical blocks it reverts, it does
+        let b2 = child(&b1, 10, 0xb);
+        importer.import(&block(b2.clone())).await.unwrap();
+        assert_eq!(*engine.heads.lock(), [b2.hash_slow()]);
+
+        // Extending the canonical head is always taken
+        let a3 = child(&a2, 1, 0xa);
+        importer.import(&block(a3.clone())).await.unwrap();
+        assert_eq!(engine.heads.lock().last(), Some(&a3.hash_slow()));
+
+        // Blocks whose branch can't be traced are not made the head
+        let orphan = Header { number: 9, parent_hash: B256::repeat_byte(9), ..Default::default() };
+        let err = importer.import(&block(orphan)).await.unwrap_err();
+        assert!(matches!(err, PermiaGossipError::ParentNotFound { .. }), "{err}");
+        assert_eq!(engine.heads.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fork_choice_refuses_deep_reorg() {
+        use reth_provider::test_utils::MockEthProvider;
+
+        // Canonical chain: genesis <- a1 <- a2
+        let provider = MockEthProvider::default();
+        let genesis = Header::default();
+        let a1 = child(&genesis, 10, 0xa);
+        let a2 = child(&a1, 10, 0xa);
+        for header in [&genesis, &a1, &a2] {
+            provider.add_header(header.hash_slow(), header.clone());
+        }
+
+        let engine = MockEngine::scripted(vec![PayloadStatusEnum::Valid; 2]);
+        let (_tx, rx) = p2p_block_channel(1);
+        let importer = PermiaP2PImporter::new(rx, &engine)
+            .with_retry_config(retry())
+            .with_fork_choice(HeaviestChainRule::new(provider).with_max_reorg_depth(1));
+
+        // Heavier, but reverting two blocks is deeper than allowed
+        let b1 = child(&genesis, 100, 0xb);
+        let err = importer.import(&block(b1)).await.unwrap_err();
+        assert!(matches!(err, PermiaGossipError::ReorgTooDeep { depth: 2, max: 1 }), "{err}");
+        assert!(engine.heads.lock().is_empty());
+    }
 }
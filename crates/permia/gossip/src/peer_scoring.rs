@@ -0,0 +1,177 @@
+//! Peer scoring for invalid block gossip
+//!
+//! Every invalid block already costs the sending peer a `BadBlock` reputation change in
+//! the network manager. On top of that, [`InvalidBlockTracker`] counts invalid blocks per
+//! peer and produces a [`PeerBan`] once a peer crosses the configured threshold, which
+//! [`apply_peer_bans`] turns into a ban through the network layer. A banned peer is
+//! forgotten, and at most [`MAX_TRACKED_PEERS`] peers are counted at once.
+
+use reth_network::{
+    types::{peers::reputation::BANNED_REPUTATION, ReputationChangeKind},
+    Peers,
+};
+use reth_network_peers::PeerId;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Default number of invalid blocks after which a peer is banned
+pub const DEFAULT_INVALID_BLOCK_THRESHOLD: u32 = 5;
+
+/// Maximum number of peers whose invalid blocks are counted at once
+pub const MAX_TRACKED_PEERS: usize = 1024;
+
+/// Sender of peer ban signals
+pub type PeerBanSender = mpsc::UnboundedSender<PeerBan>;
+/// Receiver of peer ban signals
+pub type PeerBanReceiver = mpsc::UnboundedReceiver<PeerBan>;
+
+/// Creates a channel for peer ban signals
+pub fn peer_ban_channel() -> (PeerBanSender, PeerBanReceiver) {
+    mpsc::unbounded_channel()
+}
+
+/// Signal to ban a peer that sent too many invalid blocks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerBan {
+    /// Peer to ban
+    pub peer_id: PeerId,
+    /// Invalid blocks received from the peer
+    pub invalid_blocks: u32,
+}
+
+/// Counts invalid blocks per peer
+#[derive(Debug)]
+pub struct InvalidBlockTracker {
+    /// Invalid blocks received per peer
+    counts: HashMap<PeerId, u32>,
+    /// Invalid blocks at which a peer is banned
+    threshold: u32,
+}
+
+impl Default for InvalidBlockTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_INVALID_BLOCK_THRESHOLD)
+    }
+}
+
+impl InvalidBlockTracker {
+    /// Create a tracker banning peers after `threshold` invalid blocks
+    pub fn new(threshold: u32) -> Self {
+        Self { counts: HashMap::new(), threshold: threshold.max(1) }
+    }
+
+    /// Get the ban threshold
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Record an invalid block from a peer, returning a ban once it reaches the threshold
+    ///
+    /// The peer is forgotten once banned. When [`MAX_TRACKED_PEERS`] peers are already
+    /// counted, the one with the fewest invalid blocks makes room for a new peer.
+    pub fn record_invalid(&mut self, peer_id: PeerId) -> Option<PeerBan> {
+        if !self.counts.contains_key(&peer_id) && self.counts.len() >= MAX_TRACKED_PEERS {
+            self.evict_least_invalid();
+        }
+
+        let count = self.counts.entry(peer_id).or_default();
+        *count = count.saturating_add(1);
+        let invalid_blocks = *count;
+        if invalid_blocks < self.threshold {
+            return None
+        }
+
+        self.counts.remove(&peer_id);
+        Some(PeerBan { peer_id, invalid_blocks })
+    }
+
+    /// Number of invalid blocks received from a peer
+    pub fn invalid_blocks(&self, peer_id: &PeerId) -> u32 {
+        self.counts.get(peer_id).copied().unwrap_or_default()
+    }
+
+    /// Forget a peer, e.g. once it was banned and disconnected
+    pub fn remove(&mut self, peer_id: &PeerId) {
+        self.counts.remove(peer_id);
+    }
+
+    /// Number of peers currently counted
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Whether no peer is currently counted
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Forget the peer with the fewest invalid blocks
+    fn evict_least_invalid(&mut self) {
+        let least = self.counts.iter().min_by_key(|(_, count)| **count).map(|(peer, _)| *peer);
+        if let Some(peer_id) = least {
+            self.counts.remove(&peer_id);
+        }
+    }
+}
+
+/// Ban every peer received on the channel through the network
+pub async fn apply_peer_bans<N: Peers>(network: N, mut bans: PeerBanReceiver) {
+    while let Some(ban) = bans.recv().await {
+        warn!(
+            target: "permia::gossip",
+            peer_id = %ban.peer_id,
+            invalid_blocks = ban.invalid_blocks,
+            "Banning peer for repeated invalid blocks"
+        );
+        network.reputation_change(ban.peer_id, ReputationChangeKind::Other(BANNED_REPUTATION));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ban_at_threshold() {
+        let mut tracker = InvalidBlockTracker::new(3);
+        let peer = PeerId::repeat_byte(1);
+
+        assert_eq!(tracker.record_invalid(peer), None);
+        assert_eq!(tracker.record_invalid(peer), None);
+        assert_eq!(tracker.record_invalid(peer), Some(PeerBan { peer_id: peer, invalid_blocks: 3 }));
+
+        // A banned peer is forgotten
+        assert_eq!(tracker.invalid_blocks(&peer), 0);
+        assert!(tracker.is_empty());
+
+        // Other peers are tracked separately
+        let other = PeerId::repeat_byte(2);
+        tracker.record_invalid(other);
+        assert_eq!(tracker.invalid_blocks(&other), 1);
+        assert_eq!(tracker.invalid_blocks(&peer), 0);
+
+        tracker.remove(&other);
+        assert_eq!(tracker.invalid_blocks(&other), 0);
+    }
+
+    #[test]
+    fn test_tracked_peers_bounded() {
+        let mut tracker = InvalidBlockTracker::new(3);
+        let repeat = PeerId::repeat_byte(0xff);
+        tracker.record_invalid(repeat);
+        tracker.record_invalid(repeat);
+
+        for i in 0..MAX_TRACKED_PEERS as u64 {
+            tracker.record_invalid(PeerId::left_padding_from(&i.to_be_bytes()));
+        }
+        assert_eq!(tracker.len(), MAX_TRACKED_PEERS);
+
+        // Peers with a single invalid block made room, the repeat offender is still counted
+        assert_eq!(tracker.invalid_blocks(&repeat), 2);
+        assert_eq!(
+            tracker.record_invalid(repeat),
+            Some(PeerBan { peer_id: repeat, invalid_blocks: 3 })
+        );
+    }
+}
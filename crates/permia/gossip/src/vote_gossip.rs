@@ -0,0 +1,649 @@
+//! Permia Vote Gossip
+//!
+//! This module propagates BFT finality votes between validators over a dedicated
+//! `RLPx` sub-protocol (`pvote/1`) registered on the same network handle used by
+//! the block announcer. Received votes are fed into the shared finality tracker.
+//!
+//! # Wire Format
+//!
+//! Every `pvote` message is a single frame:
+//!
+//! ```text
+//! message_id (1 byte, 0x00 = vote) | VoteMessage::encode()
+//! ```
+//!
+//! Votes are only counted if signed by their validator. Frames to each peer and votes
+//! received from peers are buffered in bounded channels; when a buffer is full, further
+//! votes are dropped rather than queued, so a flooding peer can't exhaust memory.
+//!
+//! # Vote Production
+//!
+//! A node running as a validator signs a vote for every block appended to its
+//! canonical chain, see [`VoteProducer`]. Blocks only become canonical once the node
+//! has fully validated them. Replacement blocks arriving through a reorg are voted for
+//! above the highest height the validator already voted at; below it a second vote at
+//! the same height would be equivocation.
+
+use crate::error::PermiaGossipError;
+use alloy_primitives::{
+    bytes::{BufMut, BytesMut},
+    Address, B256,
+};
+use parking_lot::Mutex;
+use permia_finality::{
+    FinalityError, FinalityUpdate, SharedFinalityTracker, SharedValidatorSet, ValidatorSigner,
+    VoteMessage,
+};
+use reth_chain_state::CanonStateSubscriptions;
+use reth_eth_wire::{
+    capability::SharedCapabilities, multiplex::ProtocolConnection, protocol::Protocol, Capability,
+};
+use reth_network::{
+    protocol::{ConnectionHandler, IntoRlpxSubProtocol, OnNotSupported, ProtocolHandler},
+    Direction, NetworkProtocols,
+};
+use reth_network_peers::PeerId;
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tracing::{debug, info, trace, warn};
+
+/// Message ID of a vote within the `pvote` sub-protocol
+const VOTE_MESSAGE_ID: u8 = 0x00;
+
+/// Frames buffered for each peer before further votes to it are dropped
+const PEER_FRAME_BUFFER: usize = 256;
+
+/// Votes received from peers buffered before further votes are dropped
+const INCOMING_VOTE_BUFFER: usize = 4096;
+
+/// Outgoing frame senders for every connected peer
+type PeerSenders = Arc<Mutex<HashMap<PeerId, mpsc::Sender<BytesMut>>>>;
+
+/// Returns the `pvote/1` capability
+pub fn vote_capability() -> Capability {
+    Capability::new_static("pvote", 1)
+}
+
+/// Returns the `pvote` protocol
+pub fn vote_protocol() -> Protocol {
+    Protocol::new(vote_capability(), 1)
+}
+
+/// Frame a vote for the `pvote` sub-protocol
+pub fn encode_vote_frame(msg: &VoteMessage) -> BytesMut {
+    let payload = msg.encode();
+    let mut buf = BytesMut::with_capacity(1 + payload.len());
+    buf.put_u8(VOTE_MESSAGE_ID);
+    buf.put_slice(&payload);
+    buf
+}
+
+/// Decode a `pvote` frame produced by [`encode_vote_frame`]
+pub fn decode_vote_frame(buf: &[u8]) -> Result<VoteMessage, PermiaGossipError> {
+    match buf.split_first() {
+        Some((&VOTE_MESSAGE_ID, payload)) => {
+            VoteMessage::decode(payload).map_err(|e| PermiaGossipError::InvalidVote(e.to_string()))
+        }
+        Some((id, _)) => {
+            Err(PermiaGossipError::InvalidVote(format!("unknown message id {id:#04x}")))
+        }
+        None => Err(PermiaGossipError::InvalidVote("empty message".to_string())),
+    }
+}
+
+/// State shared between the protocol handler and all vote connections
+#[derive(Debug, Clone)]
+struct VoteGossipState {
+    /// Outgoing frame senders per connected peer
+    peers: PeerSenders,
+    /// Decoded votes received from peers
+    incoming: mpsc::Sender<(PeerId, VoteMessage)>,
+}
+
+impl VoteGossipState {
+    /// Register a connected peer, returning the stream of frames to send to it
+    fn register_peer(&self, peer_id: PeerId) -> ReceiverStream<BytesMut> {
+        let (tx, rx) = mpsc::channel(PEER_FRAME_BUFFER);
+        self.peers.lock().insert(peer_id, tx);
+        ReceiverStream::new(rx)
+    }
+
+    /// Forget a disconnected peer
+    fn remove_peer(&self, peer_id: &PeerId) {
+        self.peers.lock().remove(peer_id);
+    }
+
+    /// Handle a raw frame received from a peer
+    fn on_frame(&self, peer_id: PeerId, frame: &[u8]) {
+        match decode_vote_frame(frame) {
+            Ok(msg) => {
+                if let Err(mpsc::error::TrySendError::Full(_)) =
+                    self.incoming.try_send((peer_id, msg))
+                {
+                    debug!(target: "permia::vote_gossip", %peer_id, "Vote buffer full, dropping vote");
+                }
+            }
+            Err(e) => {
+                warn!(
+                    target: "permia::vote_gossip",
+                    %peer_id,
+                    error = %e,
+                    "Dropping malformed vote from peer"
+                );
+            }
+        }
+    }
+}
+
+/// `RLPx` sub-protocol handler for vote gossip
+#[derive(Debug, Clone)]
+pub struct PermiaVoteProtocol {
+    state: VoteGossipState,
+}
+
+impl ProtocolHandler for PermiaVoteProtocol {
+    type ConnectionHandler = VoteConnectionHandler;
+
+    fn on_incoming(&self, _socket_addr: SocketAddr) -> Option<Self::ConnectionHandler> {
+        Some(VoteConnectionHandler { state: self.state.clone() })
+    }
+
+    fn on_outgoing(
+        &self,
+        _socket_addr: SocketAddr,
+        _peer_id: PeerId,
+    ) -> Option<Self::ConnectionHandler> {
+        Some(VoteConnectionHandler { state: self.state.clone() })
+    }
+}
+
+/// Per-connection handler for the `pvote` sub-protocol
+#[derive(Debug)]
+pub struct VoteConnectionHandler {
+    state: VoteGossipState,
+}
+
+impl ConnectionHandler for VoteConnectionHandler {
+    type Connection = VoteConnection;
+
+    fn protocol(&self) -> Protocol {
+        vote_protocol()
+    }
+
+    fn on_unsupported_by_peer(
+        self,
+        _supported: &SharedCapabilities,
+        _direction: Direction,
+        _peer_id: PeerId,
+    ) -> OnNotSupported {
+        // Non-validator peers may not speak `pvote`, keep the eth connection alive
+        OnNotSupported::KeepAlive
+    }
+
+    fn into_connection(
+        self,
+        _direction: Direction,
+        peer_id: PeerId,
+        conn: ProtocolConnection,
+    ) -> Self::Connection {
+        let outgoing = self.state.register_peer(peer_id);
+        debug!(target: "permia::vote_gossip", %peer_id, "Vote gossip connection established");
+        VoteConnection { peer_id, conn, outgoing, state: self.state }
+    }
+}
+
+/// An established `pvote` connection to a single peer
+///
+/// Yields frames to send to the peer and dispatches frames received from it.
+#[derive(Debug)]
+pub struct VoteConnection {
+    peer_id: PeerId,
+    conn: ProtocolConnection,
+    outgoing: ReceiverStream<BytesMut>,
+    state: VoteGossipState,
+}
+
+impl Stream for VoteConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if let Poll::Ready(Some(frame)) = Pin::new(&mut this.outgoing).poll_next(cx) {
+                return Poll::Ready(Some(frame));
+            }
+
+            let Some(frame) = ready!(Pin::new(&mut this.conn).poll_next(cx)) else {
+                this.state.remove_peer(&this.peer_id);
+                return Poll::Ready(None);
+            };
+
+            this.state.on_frame(this.peer_id, &frame);
+        }
+    }
+}
+
+/// Broadcasts locally produced votes to all connected `pvote` peers
+#[derive(Debug, Clone)]
+pub struct VoteBroadcaster {
+    peers: PeerSenders,
+}
+
+impl VoteBroadcaster {
+    /// Broadcast a vote to all connected peers, returning the number of peers reached
+    ///
+    /// Peers whose frame buffer is full miss the vote; disconnected peers are forgotten.
+    pub fn broadcast(&self, msg: &VoteMessage) -> usize {
+        let frame = encode_vote_frame(msg);
+        let mut peers = self.peers.lock();
+        let mut reached = 0;
+        peers.retain(|peer_id, tx| match tx.try_send(frame.clone()) {
+            Ok(()) => {
+                reached += 1;
+                true
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                debug!(target: "permia::vote_gossip", %peer_id, "Peer vote buffer full, dropping vote");
+                true
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        });
+
+        trace!(
+            target: "permia::vote_gossip",
+            block_hash = %msg.vote.block_hash,
+            peers = reached,
+            "Broadcast vote"
+        );
+
+        reached
+    }
+
+    /// Number of peers currently connected over `pvote`
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().len()
+    }
+}
+
+/// Feeds votes received from peers into the shared finality tracker
+#[derive(Debug)]
+pub struct VoteReceiver {
+    rx: mpsc::Receiver<(PeerId, VoteMessage)>,
+    tracker: SharedFinalityTracker,
+    validator_set: SharedValidatorSet,
+}
+
+impl VoteReceiver {
+    /// Run until all senders (the protocol handler and its connections) are dropped
+    pub async fn run(mut self) {
+        info!(target: "permia::vote_gossip", "Vote receiver started");
+
+        while let Some((peer_id, msg)) = self.rx.recv().await {
+            self.on_vote(peer_id, msg);
+        }
+
+        info!(target: "permia::vote_gossip", "Vote receiver stopped");
+    }
+
+    fn on_vote(&self, peer_id: PeerId, msg: VoteMessage) {
+        let block_hash = msg.vote.block_hash;
+        let result = {
+            let validator_set = self.validator_set.read();
+            self.tracker.write().add_vote(msg.vote, &validator_set)
+        };
+
+        match result {
+            Ok(true) => {
+                info!(
+                    target: "permia::vote_gossip",
+                    %block_hash,
+                    "Block finalized by validator votes"
+                );
+            }
+            Ok(false) => {
+                trace!(target: "permia::vote_gossip", %block_hash, %peer_id, "Vote counted");
+            }
+            Err(FinalityError::DuplicateVote(..)) => {
+                trace!(target: "permia::vote_gossip", %block_hash, %peer_id, "Duplicate vote");
+            }
+            Err(FinalityError::Equivocation { first, second }) => {
+                match self.validator_set.write().slash_equivocation(*first, *second) {
+                    Ok(record) => {
+                        warn!(
+                            target: "permia::vote_gossip",
+                            validator = %record.validator,
+                            block_number = record.block_number,
+                            slashed = %record.slashed_amount,
+                            "Slashed equivocating validator"
+                        );
+                    }
+                    Err(e) => {
+                        debug!(target: "permia::vote_gossip", %peer_id, error = %e, "Not slashed");
+                    }
+                }
+            }
+            Err(e) => {
+                debug!(
+                    target: "permia::vote_gossip",
+                    %block_hash,
+                    %peer_id,
+                    error = %e,
+                    "Rejected vote from peer"
+                );
+            }
+        }
+    }
+}
+
+/// Create the vote gossip components
+///
+/// The returned [`PermiaVoteProtocol`] must be registered on the network, see
+/// [`install_vote_gossip`].
+pub fn vote_gossip(
+    tracker: SharedFinalityTracker,
+    validator_set: SharedValidatorSet,
+) -> (PermiaVoteProtocol, VoteBroadcaster, VoteReceiver) {
+    let (incoming, rx) = mpsc::channel(INCOMING_VOTE_BUFFER);
+    let peers = PeerSenders::default();
+
+    let state = VoteGossipState { peers: Arc::clone(&peers), incoming };
+    let protocol = PermiaVoteProtocol { state };
+    let broadcaster = VoteBroadcaster { peers };
+    let receiver = VoteReceiver { rx, tracker, validator_set };
+
+    (protocol, broadcaster, receiver)
+}
+
+/// Register the `pvote` sub-protocol on the network and return the gossip endpoints
+pub fn install_vote_gossip<N>(
+    network: &N,
+    tracker: SharedFinalityTracker,
+    validator_set: SharedValidatorSet,
+) -> (VoteBroadcaster, VoteReceiver)
+where
+    N: NetworkProtocols,
+{
+    let (protocol, broadcaster, receiver) = vote_gossip(tracker, validator_set);
+    network.add_rlpx_sub_protocol(protocol.into_rlpx_sub_protocol());
+    (broadcaster, receiver)
+}
+
+/// Signs and broadcasts votes for new canonical blocks
+///
+/// Blocks made canonical by a reorg are voted on too, except at heights the producer
+/// already voted for: a second vote there would be an equivocation.
+#[derive(Debug)]
+pub struct VoteProducer {
+    signer: ValidatorSigner,
+    broadcaster: VoteBroadcaster,
+    tracker: SharedFinalityTracker,
+    validator_set: SharedValidatorSet,
+    /// Highest block number voted for
+    last_voted: Option<u64>,
+}
+
+impl VoteProducer {
+    /// Create a vote producer
+    ///
+    /// Votes are counted in `tracker` as well as broadcast, so the local node's own
+    /// vote contributes to finality.
+    pub fn new(
+        signer: ValidatorSigner,
+        broadcaster: VoteBroadcaster,
+        tracker: SharedFinalityTracker,
+        validator_set: SharedValidatorSet,
+    ) -> Self {
+        Self { signer, broadcaster, tracker, validator_set, last_voted: None }
+    }
+
+    /// Address the votes are signed with
+    pub fn validator(&self) -> Address {
+        self.signer.address()
+    }
+
+    /// Vote on a stream of [`FinalityUpdate`]s until the stream ends
+    pub async fn run<S>(mut self, updates: S)
+    where
+        S: Stream<Item = FinalityUpdate>,
+    {
+        info!(target: "permia::vote_gossip", validator = %self.validator(), "Vote producer started");
+
+        let mut updates = std::pin::pin!(updates);
+        while let Some(update) = updates.next().await {
+            let blocks = match update {
+                FinalityUpdate::Commit(blocks) => blocks,
+                FinalityUpdate::Reorg { new, .. } => new,
+            };
+            for block in blocks {
+                if self.last_voted.is_some_and(|last| block.number <= last) {
+                    debug!(
+                        target: "permia::vote_gossip",
+                        block_hash = %block.hash,
+                        block_number = block.number,
+                        "Already voted at this height, not voting for reorged block"
+                    );
+                    continue;
+                }
+                self.vote(block.hash, block.number);
+            }
+        }
+
+        info!(target: "permia::vote_gossip", "Vote producer stopped");
+    }
+
+    fn vote(&mut self, block_hash: B256, block_number: u64) {
+        let vote = match self.signer.sign_vote(block_hash, block_number) {
+            Ok(vote) => vote,
+            Err(e) => {
+                warn!(target: "permia::vote_gossip", %block_hash, error = %e, "Failed to sign vote");
+                return;
+            }
+        };
+        self.last_voted = Some(block_number);
+
+        let msg = VoteMessage::new(vote.clone());
+        let peers = self.broadcaster.broadcast(&msg);
+
+        let validator_set = self.validator_set.read();
+        match self.tracker.write().add_vote(vote, &validator_set) {
+            Ok(true) => {
+                info!(target: "permia::vote_gossip", %block_hash, "Block finalized by validator votes");
+            }
+            Ok(false) | Err(FinalityError::DuplicateVote(..)) => {}
+            Err(e) => {
+                // Still broadcast: peers may already see this validator in their set
+                debug!(target: "permia::vote_gossip", %block_hash, error = %e, "Own vote not counted");
+            }
+        }
+
+        trace!(target: "permia::vote_gossip", %block_hash, block_number, peers, "Produced vote");
+    }
+}
+
+/// Spawn a vote producer driven by the provider's canonical state notifications
+pub fn spawn_vote_producer<P>(provider: P, producer: VoteProducer) -> impl Future<Output = ()>
+where
+    P: CanonStateSubscriptions + 'static,
+{
+    let updates =
+        provider.canonical_state_stream().map(|notification| FinalityUpdate::from(&notification));
+    async move {
+        producer.run(updates).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_eips::BlockNumHash;
+    use parking_lot::RwLock;
+    use permia_finality::{FinalityTracker, SlashReason, Validator, ValidatorSet, Vote};
+
+    const VALIDATOR_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    /// Signer of test validator `i`
+    fn signer(i: u8) -> ValidatorSigner {
+        ValidatorSigner::from_hex(&B256::repeat_byte(i).to_string()).unwrap()
+    }
+
+    fn shared_validator_set() -> SharedValidatorSet {
+        let validators = (1..=3u8)
+            .map(|i| Validator::new(signer(i).address(), Validator::min_stake(), 0))
+            .collect();
+        Arc::new(RwLock::new(ValidatorSet::from_validators(validators, 1, 0)))
+    }
+
+    #[test]
+    fn test_vote_frame_roundtrip() {
+        let msg = VoteMessage::new(Vote::new_unsigned(B256::repeat_byte(1), 10, Address::ZERO));
+        let frame = encode_vote_frame(&msg);
+
+        assert_eq!(frame[0], VOTE_MESSAGE_ID);
+        assert_eq!(decode_vote_frame(&frame).unwrap(), msg);
+        assert!(decode_vote_frame(&[]).is_err());
+        assert!(decode_vote_frame(&[0x7f, 0x00]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_vote_gossip_between_aggregators() {
+        let validator_set = shared_validator_set();
+        let tracker_a: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+        let tracker_b: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+
+        let (protocol_a, broadcaster_a, _receiver_a) =
+            vote_gossip(Arc::clone(&tracker_a), Arc::clone(&validator_set));
+        let (protocol_b, _broadcaster_b, receiver_b) =
+            vote_gossip(Arc::clone(&tracker_b), Arc::clone(&validator_set));
+
+        // Simulate an established connection between the two nodes
+        let peer_a = PeerId::repeat_byte(0xa);
+        let peer_b = PeerId::repeat_byte(0xb);
+        let mut frames_to_b = protocol_a.state.register_peer(peer_b);
+
+        let block_hash = B256::repeat_byte(0x42);
+        let msg = VoteMessage::new(signer(1).sign_vote(block_hash, 100).unwrap());
+        assert_eq!(broadcaster_a.broadcast(&msg), 1);
+
+        let frame = frames_to_b.next().await.expect("frame sent to peer");
+        protocol_b.state.on_frame(peer_a, &frame);
+
+        // Dropping the protocol closes the incoming channel so the receiver drains and exits
+        drop(protocol_b);
+        receiver_b.run().await;
+
+        assert_eq!(tracker_b.read().votes().vote_count(&block_hash), 1);
+        assert_eq!(tracker_b.read().votes().get_voters(&block_hash), vec![signer(1).address()]);
+        assert_eq!(tracker_a.read().votes().vote_count(&block_hash), 0);
+    }
+
+    #[tokio::test]
+    async fn test_forged_and_flooded_votes_not_counted() {
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+        let (protocol, _broadcaster, receiver) =
+            vote_gossip(Arc::clone(&tracker), shared_validator_set());
+        let peer = PeerId::repeat_byte(0xa);
+        let block_hash = B256::repeat_byte(0x42);
+
+        // Signed by another key on behalf of validator 1
+        let forged = Vote {
+            validator: signer(1).address(),
+            ..signer(9).sign_vote(block_hash, 100).unwrap()
+        };
+        protocol.state.on_frame(peer, &encode_vote_frame(&VoteMessage::new(forged)));
+
+        // A flooding peer fills the buffer, the excess is dropped
+        let spam = encode_vote_frame(&VoteMessage::new(Vote::new_unsigned(
+            block_hash,
+            100,
+            Address::ZERO,
+        )));
+        for _ in 0..INCOMING_VOTE_BUFFER {
+            protocol.state.on_frame(peer, &spam);
+        }
+        assert_eq!(receiver.rx.len(), INCOMING_VOTE_BUFFER);
+
+        drop(protocol);
+        receiver.run().await;
+        assert_eq!(tracker.read().votes().vote_count(&block_hash), 0);
+    }
+
+    #[tokio::test]
+    async fn test_equivocating_validator_slashed() {
+        let validator_set = shared_validator_set();
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+        let (protocol, _broadcaster, receiver) =
+            vote_gossip(Arc::clone(&tracker), Arc::clone(&validator_set));
+        let peer = PeerId::repeat_byte(0xa);
+
+        for block_hash in [B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3)] {
+            let vote = signer(2).sign_vote(block_hash, 100).unwrap();
+            protocol.state.on_frame(peer, &encode_vote_frame(&VoteMessage::new(vote)));
+        }
+
+        drop(protocol);
+        receiver.run().await;
+
+        // Slashed once, with both signed votes as evidence
+        let set = validator_set.read();
+        let [record] = set.slashes() else { panic!("expected one slash") };
+        assert_eq!((record.validator, record.block_number), (signer(2).address(), 100));
+        let SlashReason::Equivocation { first, second } = &record.reason;
+        assert_eq!(first.block_hash, B256::repeat_byte(1));
+        assert_eq!(second.block_hash, B256::repeat_byte(2));
+        assert!(!set.is_validator(&signer(2).address()));
+    }
+
+    #[tokio::test]
+    async fn test_canonical_block_produces_signed_vote() {
+        let signer = ValidatorSigner::from_hex(VALIDATOR_KEY).unwrap();
+        let validator_set: SharedValidatorSet =
+            Arc::new(RwLock::new(ValidatorSet::from_validators(
+                vec![Validator::new(signer.address(), Validator::min_stake(), 0)],
+                1,
+                0,
+            )));
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new()));
+
+        let (protocol, broadcaster, _receiver) =
+            vote_gossip(Arc::clone(&tracker), Arc::clone(&validator_set));
+        let mut frames = protocol.state.register_peer(PeerId::repeat_byte(0xb));
+
+        let block = BlockNumHash::new(7, B256::repeat_byte(7));
+        let reorged = BlockNumHash::new(8, B256::repeat_byte(9));
+        let updates = tokio_stream::iter([
+            FinalityUpdate::Commit(vec![block]),
+            FinalityUpdate::Reorg {
+                reverted: vec![block.hash],
+                new: vec![BlockNumHash::new(7, B256::repeat_byte(8)), reorged],
+            },
+        ]);
+        VoteProducer::new(signer.clone(), broadcaster, Arc::clone(&tracker), validator_set)
+            .run(updates)
+            .await;
+
+        // One vote for the committed block, signed by the validator
+        let msg = decode_vote_frame(&frames.next().await.unwrap()).unwrap();
+        assert_eq!((msg.vote.block_hash, msg.vote.block_number), (block.hash, block.number));
+        assert_eq!(msg.vote.recover_signer().unwrap(), signer.address());
+
+        // The reorged block at the same height is skipped rather than equivocating on, the
+        // one above it is voted for
+        let msg = decode_vote_frame(&frames.next().await.unwrap()).unwrap();
+        assert_eq!((msg.vote.block_hash, msg.vote.block_number), (reorged.hash, reorged.number));
+        drop(protocol);
+        assert!(frames.next().await.is_none());
+
+        // The own votes count locally, and finalize with a single validator
+        assert!(tracker.read().votes().is_finalized(&block.hash));
+        assert!(tracker.read().votes().is_finalized(&reorged.hash));
+    }
+}
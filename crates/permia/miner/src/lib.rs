@@ -36,10 +36,22 @@
 pub mod worker;
 pub mod template;
 pub mod node_miner;
+pub mod work;
+pub mod tune;
 
-pub use worker::{MiningWorker, MiningResult, MiningConfig};
-pub use template::BlockTemplate;
-pub use node_miner::{NodeMiner, NodeMinerConfig, NodeMinerHandle, MinedBlock, spawn_node_miner};
+pub use worker::{
+    BatchTuning, MiningWorker, MiningResult, MiningConfig, MiningProgress, DEFAULT_BATCH_INTERVAL,
+};
+pub use template::{
+    current_timestamp, next_base_fee, next_timestamp, validate_extra_data, BlockTemplate,
+    DEFAULT_EXTRA_DATA, MAX_EXTRA_DATA_SIZE,
+};
+pub use node_miner::{
+    NodeMiner, NodeMinerConfig, NodeMinerHandle, MinedBlock, spawn_node_miner,
+    MAX_DAG_CACHE_MEMORY_PERCENT,
+};
+pub use work::{TemplateRegistry, WorkPackage, WorkSlot, WorkSolution, TEMPLATE_RETENTION_BLOCKS};
+pub use tune::{measure_hashrate, DifficultyTuner, DEFAULT_CALIBRATION_TIME};
 
 use alloy_primitives::U256;
 use thiserror::Error;
@@ -59,6 +71,52 @@ pub enum MiningError {
     #[error("Invalid block template: {0}")]
     InvalidTemplate(String),
     
+    /// No template is currently being mined
+    #[error("No mining work available")]
+    NoWork,
+    
+    /// Submitted solution is for a template that is no longer current
+    #[error("Stale work: current seal hash {current}, submitted {submitted}")]
+    StaleWork {
+        /// Seal hash of the current template
+        current: alloy_primitives::B256,
+        /// Seal hash the solution was submitted for
+        submitted: alloy_primitives::B256,
+    },
+    
+    /// Solution names a template that is not (or no longer) registered
+    #[error("Unknown or evicted template {0}")]
+    UnknownTemplate(alloy_primitives::B256),
+    
+    /// Mined block pays someone other than the configured miner
+    #[error("Beneficiary mismatch: expected {expected}, block pays {actual}")]
+    BeneficiaryMismatch {
+        /// Configured beneficiary
+        expected: alloy_primitives::Address,
+        /// Beneficiary of the mined block
+        actual: alloy_primitives::Address,
+    },
+    
+    /// DAG cache does not fit in the available memory
+    #[error(
+        "DAG cache of {required} bytes exceeds {}% of the {available} bytes of available memory",
+        crate::node_miner::MAX_DAG_CACHE_MEMORY_PERCENT
+    )]
+    InsufficientMemory {
+        /// Configured DAG cache size
+        required: u64,
+        /// Memory available on this machine
+        available: u64,
+    },
+    
+    /// Solution could not be handed to the node
+    #[error("Failed to submit solution: {0}")]
+    SubmissionFailed(String),
+    
+    /// Block template (de)serialization failed
+    #[error("Template serialization error: {0}")]
+    Serialization(String),
+    
     /// Consensus error
     #[error("Consensus error: {0}")]
     Consensus(#[from] permia_consensus::PermiaConsensusError),
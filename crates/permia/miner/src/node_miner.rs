@@ -3,13 +3,22 @@
 //! This module provides a miner that integrates with the Reth node,
 //! automatically mining blocks when the node is running.
 
-use crate::{BlockTemplate, MiningConfig, MiningError, MiningResult, MiningWorker};
-use alloy_primitives::{Address, B256, U256};
+use crate::{
+    next_base_fee, next_timestamp, validate_extra_data, BatchTuning, BlockTemplate, MiningConfig,
+    MiningError, MiningResult, MiningWorker, TemplateRegistry, WorkPackage, WorkSlot, WorkSolution,
+    DEFAULT_EXTRA_DATA,
+};
+use alloy_consensus::Header;
+use alloy_primitives::{Address, Bytes, B256, U256};
+use permia_consensus::pow::PermiaHashConfig;
+use permia_finality::SharedFinalityTracker;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::mpsc;
-use tracing::{debug, error, info, warn};
+use tokio::{sync::mpsc, time::Instant};
+use tracing::{debug, error, field, info, span, warn, Instrument, Level, Span};
 
 /// Configuration for the node-integrated miner
 #[derive(Debug, Clone)]
@@ -24,16 +33,37 @@ pub struct NodeMinerConfig {
     pub mine_empty_blocks: bool,
     /// Maximum time to spend mining a single block
     pub max_mining_time: Duration,
+    /// Maximum nonces to try for a single block (None = no limit)
+    pub max_mining_iterations: Option<u64>,
+    /// Extra data stamped into mined headers, e.g. a pool tag
+    pub extra_data: Bytes,
+    /// Fixed interval between mined blocks, regardless of hashrate (dev mode)
+    pub block_interval: Option<Duration>,
+    /// Bytes of DAG to keep cached in memory (None = derive elements on the fly)
+    pub dag_cache_size: Option<u64>,
+    /// Finality state; blocks at or below the finalized height are not mined
+    pub finality: Option<SharedFinalityTracker>,
+    /// PermiaHash parameters of the chain, for mining and verifying external solutions
+    pub hash_config: PermiaHashConfig,
 }
 
+/// Share of available memory the DAG cache may take, in percent
+pub const MAX_DAG_CACHE_MEMORY_PERCENT: u64 = 75;
+
 impl Default for NodeMinerConfig {
     fn default() -> Self {
         Self {
             beneficiary: Address::ZERO,
             threads: num_cpus::get(),
-            target_block_time_ms: 400, // Permia target block time
+            target_block_time_ms: permia_consensus::BLOCK_TIME_MS,
             mine_empty_blocks: true,
             max_mining_time: Duration::from_secs(60),
+            max_mining_iterations: None,
+            extra_data: Bytes::from_static(DEFAULT_EXTRA_DATA),
+            block_interval: None,
+            dag_cache_size: None,
+            finality: None,
+            hash_config: PermiaHashConfig::default(),
         }
     }
 }
@@ -50,6 +80,107 @@ impl NodeMinerConfig {
         self.threads = threads.max(1);
         self
     }
+
+    /// Create config with the chain's target block time
+    ///
+    /// See [`reth_chainspec::permia_block_time_ms`].
+    pub fn with_target_block_time(mut self, ms: u64) -> Self {
+        self.target_block_time_ms = ms;
+        self
+    }
+
+    /// Create config that gives up on a block after trying `iterations` nonces
+    ///
+    /// Like [`Self::max_mining_time`], the block is then left unmined until the next
+    /// [`NodeMinerHandle::start_mining`].
+    pub fn with_max_mining_iterations(mut self, iterations: u64) -> Self {
+        self.max_mining_iterations = Some(iterations);
+        self
+    }
+
+    /// Create config that paces mined blocks to a fixed interval
+    ///
+    /// A block found early is held back until `interval` has passed since the previous
+    /// one, so a devnet produces blocks at a steady rate whatever the hashrate.
+    pub fn with_block_interval(mut self, interval: Duration) -> Self {
+        self.block_interval = Some(interval);
+        self
+    }
+
+    /// Create config caching `bytes` of the DAG in memory
+    ///
+    /// The full DAG is [`PermiaHashConfig::dag_size`](permia_consensus::pow::PermiaHashConfig)
+    /// bytes; see [`Self::check_memory`].
+    pub fn with_dag_cache_size(mut self, bytes: u64) -> Self {
+        self.dag_cache_size = Some(bytes);
+        self
+    }
+
+    /// Create config that stops mining blocks the given tracker has finalized
+    ///
+    /// Checked before a block is started and between nonce batches while mining it, so
+    /// work on a height that finalized in the meantime is abandoned.
+    pub fn with_finality(mut self, tracker: SharedFinalityTracker) -> Self {
+        self.finality = Some(tracker);
+        self
+    }
+
+    /// Create config mining with the chain's PermiaHash parameters
+    ///
+    /// See [`permia_consensus::permia_hash_config`].
+    pub fn with_hash_config(mut self, config: PermiaHashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
+
+    /// Check that the DAG cache fits in the memory available on this machine
+    ///
+    /// Passes if there is no cache, or the available memory can't be determined.
+    pub fn check_memory(&self) -> Result<(), MiningError> {
+        match available_memory() {
+            Some(available) => self.check_memory_with(available),
+            None => {
+                warn!(target: "permia::node_miner", "Available memory unknown, not checking DAG cache size");
+                Ok(())
+            }
+        }
+    }
+
+    /// Check that the DAG cache fits in [`MAX_DAG_CACHE_MEMORY_PERCENT`] of `available`
+    /// bytes of memory
+    pub fn check_memory_with(&self, available: u64) -> Result<(), MiningError> {
+        let Some(required) = self.dag_cache_size else { return Ok(()) };
+        let limit = available / 100 * MAX_DAG_CACHE_MEMORY_PERCENT;
+        if required > limit {
+            return Err(MiningError::InsufficientMemory { required, available });
+        }
+        Ok(())
+    }
+
+    /// Create config with extra data for mined headers
+    ///
+    /// Fails if the extra data exceeds [`MAX_EXTRA_DATA_SIZE`](crate::MAX_EXTRA_DATA_SIZE).
+    pub fn with_extra_data(mut self, extra_data: Bytes) -> Result<Self, MiningError> {
+        validate_extra_data(&extra_data)?;
+        self.extra_data = extra_data;
+        Ok(self)
+    }
+}
+
+/// Memory available for new allocations, in bytes
+///
+/// Read from `MemAvailable` in `/proc/meminfo`, so only known on Linux.
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kib = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    kib.checked_mul(1024)
 }
 
 /// A mined block ready for submission
@@ -67,8 +198,46 @@ pub struct MinedBlock {
     pub mix_hash: B256,
     /// Difficulty
     pub difficulty: U256,
+    /// Address the block reward is paid to
+    pub beneficiary: Address,
     /// Mining result with stats
     pub mining_result: MiningResult,
+    /// Template the block was mined from
+    pub template: BlockTemplate,
+}
+
+impl MinedBlock {
+    /// Create a mined block from a template and the result that solves it
+    ///
+    /// `hash` is the hash of the sealed header; the PermiaHash output stays in
+    /// `mining_result`.
+    pub fn new(template: BlockTemplate, mining_result: MiningResult) -> Self {
+        let header = template.to_sealed_header(mining_result.nonce, mining_result.mix_hash);
+        Self {
+            number: template.number,
+            parent_hash: template.parent_hash,
+            hash: header.hash_slow(),
+            nonce: mining_result.nonce,
+            mix_hash: mining_result.mix_hash,
+            difficulty: template.difficulty,
+            beneficiary: template.beneficiary,
+            mining_result,
+            template,
+        }
+    }
+
+    /// Check that the block pays the `expected` beneficiary
+    pub fn check_beneficiary(&self, expected: Address) -> Result<(), MiningError> {
+        if self.beneficiary != expected || self.template.beneficiary != expected {
+            return Err(MiningError::BeneficiaryMismatch { expected, actual: self.template.beneficiary });
+        }
+        Ok(())
+    }
+
+    /// Convert into the sealed header, whose hash is `self.hash`
+    pub fn into_header(self) -> Header {
+        self.template.to_sealed_header(self.nonce, self.mix_hash)
+    }
 }
 
 /// Messages sent to the node miner
@@ -76,14 +245,14 @@ pub struct MinedBlock {
 pub enum MinerMessage {
     /// Start mining a new block
     StartMining {
-        /// Parent block hash
-        parent_hash: B256,
-        /// Parent block number
-        parent_number: u64,
+        /// Parent header, the block's hash, number and base fee derive from it
+        parent: Box<Header>,
         /// State root after pending transactions
         state_root: B256,
         /// Transactions root
         transactions_root: B256,
+        /// EIP-2718 encoded transactions of the block, checked against `transactions_root`
+        transactions: Vec<Bytes>,
         /// Receipts root
         receipts_root: B256,
         /// Difficulty for this block
@@ -91,8 +260,21 @@ pub enum MinerMessage {
         /// Gas used
         gas_used: u64,
     },
+    /// Submit an externally found solution
+    SubmitSolution {
+        /// Id of the solved template, its seal hash
+        template_id: B256,
+        /// The winning nonce
+        nonce: u64,
+        /// Mix hash produced by PermiaHash for the nonce
+        mix_hash: B256,
+    },
     /// Stop current mining
     Stop,
+    /// Pause mining, keeping the latest new block until resumed
+    Pause,
+    /// Resume mining, starting the block kept while paused
+    Resume,
     /// Shutdown the miner
     Shutdown,
 }
@@ -102,31 +284,85 @@ pub enum MinerMessage {
 pub struct NodeMinerHandle {
     tx: mpsc::Sender<MinerMessage>,
     running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// Template currently being mined
+    work: WorkSlot,
+    /// Recently mined templates, for solutions to work the miner moved on from
+    templates: TemplateRegistry,
+    /// Sender for externally mined blocks
+    mined_tx: mpsc::Sender<MinedBlock>,
+    /// Address the mined blocks pay
+    beneficiary: Address,
 }
 
 impl NodeMinerHandle {
+    /// Get the address the mined blocks pay
+    pub fn beneficiary(&self) -> Address {
+        self.beneficiary
+    }
+
     /// Check if the miner is currently running
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
     }
 
+    /// Check if mining is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Get the work package of the block currently being mined
+    pub fn work(&self) -> Option<WorkPackage> {
+        self.work.work()
+    }
+
+    /// Get the slot holding the template currently being mined
+    pub fn work_slot(&self) -> &WorkSlot {
+        &self.work
+    }
+
+    /// Get the registry of recently mined templates
+    pub fn templates(&self) -> &TemplateRegistry {
+        &self.templates
+    }
+
+    /// Submit an externally found solution
+    ///
+    /// A valid solution claims its template and is delivered on the mined block channel
+    /// like a locally mined block. Claiming the current work also stops the local search,
+    /// which checks the work slot between nonce batches; a solution for an earlier, still
+    /// registered template leaves it running.
+    pub fn submit_work(&self, solution: WorkSolution) -> Result<MinedBlock, MiningError> {
+        let block = accept_solution(&self.work, &self.templates, &solution)?;
+
+        self.mined_tx
+            .try_send(block.clone())
+            .map_err(|e| MiningError::SubmissionFailed(e.to_string()))?;
+
+        Ok(block)
+    }
+
     /// Start mining a new block
+    ///
+    /// `transactions` are the EIP-2718 encoded transactions of the block; the block is not
+    /// mined unless they match `transactions_root`.
+    #[allow(clippy::too_many_arguments)]
     pub async fn start_mining(
         &self,
-        parent_hash: B256,
-        parent_number: u64,
+        parent: Header,
         state_root: B256,
         transactions_root: B256,
+        transactions: Vec<Bytes>,
         receipts_root: B256,
         difficulty: U256,
         gas_used: u64,
     ) -> Result<(), mpsc::error::SendError<MinerMessage>> {
         self.tx
             .send(MinerMessage::StartMining {
-                parent_hash,
-                parent_number,
+                parent: Box::new(parent),
                 state_root,
                 transactions_root,
+                transactions,
                 receipts_root,
                 difficulty,
                 gas_used,
@@ -134,24 +370,75 @@ impl NodeMinerHandle {
             .await
     }
 
+    /// Submit an externally found solution through the miner loop
+    ///
+    /// Like [`Self::submit_work`], but checked by the miner itself, also while it is mining:
+    /// a valid solution is delivered on the mined block channel and, if it solves the block
+    /// being mined, stops the local search. Invalid solutions are logged and dropped.
+    pub async fn submit_solution(
+        &self,
+        template_id: B256,
+        nonce: u64,
+        mix_hash: B256,
+    ) -> Result<(), mpsc::error::SendError<MinerMessage>> {
+        self.tx.send(MinerMessage::SubmitSolution { template_id, nonce, mix_hash }).await
+    }
+
     /// Stop current mining
     pub async fn stop(&self) -> Result<(), mpsc::error::SendError<MinerMessage>> {
         self.tx.send(MinerMessage::Stop).await
     }
 
+    /// Pause mining without stopping the miner loop
+    ///
+    /// A block already being mined is finished; of the blocks started while paused only
+    /// the latest is kept, as it supersedes the earlier ones.
+    pub async fn pause(&self) -> Result<(), mpsc::error::SendError<MinerMessage>> {
+        self.paused.store(true, Ordering::SeqCst);
+        self.tx.send(MinerMessage::Pause).await
+    }
+
+    /// Resume mining, starting the latest block started while paused
+    pub async fn resume(&self) -> Result<(), mpsc::error::SendError<MinerMessage>> {
+        self.paused.store(false, Ordering::SeqCst);
+        self.tx.send(MinerMessage::Resume).await
+    }
+
     /// Shutdown the miner completely
     pub async fn shutdown(&self) -> Result<(), mpsc::error::SendError<MinerMessage>> {
         self.tx.send(MinerMessage::Shutdown).await
     }
 }
 
+/// A solved block held back until the fixed block interval has passed
+struct HeldBlock {
+    block: MinedBlock,
+    /// When the block may be delivered
+    release_at: Instant,
+    /// Span of the block, delivery is reported in it
+    span: Span,
+}
+
 /// Node-integrated miner for Permia PoW
 pub struct NodeMiner {
     config: NodeMinerConfig,
     rx: mpsc::Receiver<MinerMessage>,
     mined_tx: mpsc::Sender<MinedBlock>,
     running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    /// Latest block started while paused
+    queued: Option<MinerMessage>,
+    /// Messages received while mining, handled once it stops
+    deferred: VecDeque<MinerMessage>,
     worker: MiningWorker,
+    /// Template currently being mined, shared with external miners
+    work: WorkSlot,
+    /// Recently mined templates, shared with external miners
+    templates: TemplateRegistry,
+    /// Local clock reading of the last mined block, used to measure sub-second block times
+    last_mined_at: Option<Instant>,
+    /// Solved block waiting for the fixed block interval
+    held: Option<HeldBlock>,
 }
 
 impl NodeMiner {
@@ -162,31 +449,81 @@ impl NodeMiner {
         let (tx, rx) = mpsc::channel(16);
         let (mined_tx, mined_rx) = mpsc::channel(16);
         let running = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
 
         let mining_config = MiningConfig {
             threads: config.threads,
-            batch_size: 10_000,
+            // Grown by the batch tuning; starting small keeps the first cancellation and
+            // finality checks prompt on slow hardware
+            batch_size: 100,
+            batch_tuning: Some(BatchTuning::default()),
             max_duration: Some(config.max_mining_time),
+            start_nonce: None,
+            max_iterations: config.max_mining_iterations,
+            hash_config: config.hash_config.clone(),
+        };
+
+        let worker = MiningWorker::new(mining_config);
+        let work = WorkSlot::new().with_hash_config(config.hash_config.clone());
+        let templates = TemplateRegistry::new().with_hash_config(config.hash_config.clone());
+
+        let handle = NodeMinerHandle {
+            tx,
+            running: Arc::clone(&running),
+            paused: Arc::clone(&paused),
+            work: work.clone(),
+            templates: templates.clone(),
+            mined_tx: mined_tx.clone(),
+            beneficiary: config.beneficiary,
         };
 
         let miner = Self {
             config,
             rx,
             mined_tx,
-            running: Arc::clone(&running),
-            worker: MiningWorker::new(mining_config),
-        };
-
-        let handle = NodeMinerHandle {
-            tx,
             running,
+            paused,
+            queued: None,
+            deferred: VecDeque::new(),
+            worker,
+            work,
+            templates,
+            last_mined_at: None,
+            held: None,
         };
 
         (miner, handle, mined_rx)
     }
 
+    /// Create an empty template for the child of `parent`, with this miner's beneficiary
+    /// and extra data and the base fee that follows the parent's
+    fn template(&self, parent: &Header, difficulty: U256) -> BlockTemplate {
+        let mut template = BlockTemplate::new(
+            parent.hash_slow(),
+            parent.number + 1,
+            next_timestamp(parent),
+            self.config.beneficiary,
+            difficulty,
+        );
+        template.extra_data = self.config.extra_data.clone();
+        template.base_fee_per_gas = Some(next_base_fee(parent));
+        template
+    }
+
+    /// Latest finalized height, if a block at `number` would be at or below it
+    fn finalized_at_or_above(&self, number: u64) -> Option<u64> {
+        finalized_at_or_above(self.config.finality.as_ref(), number)
+    }
+
     /// Run the miner loop
+    ///
+    /// Refuses to start if the DAG cache does not fit in memory.
     pub async fn run(mut self) {
+        if let Err(e) = self.config.check_memory() {
+            error!(target: "permia::node_miner", error = %e, "Not starting node miner");
+            return;
+        }
+
         info!(
             target: "permia::node_miner",
             beneficiary = %self.config.beneficiary,
@@ -194,101 +531,61 @@ impl NodeMiner {
             "Node miner started"
         );
 
-        while let Some(msg) = self.rx.recv().await {
-            match msg {
-                MinerMessage::StartMining {
-                    parent_hash,
-                    parent_number,
-                    state_root,
-                    transactions_root,
-                    receipts_root,
-                    difficulty,
-                    gas_used,
-                } => {
-                    self.running.store(true, Ordering::SeqCst);
-
-                    let block_number = parent_number + 1;
-                    info!(
-                        target: "permia::node_miner",
-                        block = block_number,
-                        parent = %parent_hash,
-                        difficulty = %difficulty,
-                        "Starting to mine block"
-                    );
-
-                    // Create block template
-                    let timestamp = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64;
-
-                    let mut template = BlockTemplate::new(
-                        parent_hash,
-                        block_number,
-                        timestamp,
-                        self.config.beneficiary,
-                        difficulty,
-                    );
-                    template.state_root = state_root;
-                    template.transactions_root = transactions_root;
-                    template.receipts_root = receipts_root;
-                    template.gas_used = gas_used;
-
-                    // Mine the block
-                    self.worker.reset();
-                    match self.worker.mine(&template) {
-                        Ok(result) => {
-                            info!(
-                                target: "permia::node_miner",
-                                block = block_number,
-                                nonce = result.nonce,
-                                hash = %result.hash,
-                                hashrate = format!("{:.2} H/s", result.hashrate()),
-                                "Block mined!"
-                            );
-
-                            let mined_block = MinedBlock {
-                                number: block_number,
-                                parent_hash,
-                                hash: result.hash,
-                                nonce: result.nonce,
-                                mix_hash: result.mix_hash,
-                                difficulty,
-                                mining_result: result,
-                            };
-
-                            if let Err(e) = self.mined_tx.send(mined_block).await {
-                                error!(
-                                    target: "permia::node_miner",
-                                    error = %e,
-                                    "Failed to send mined block"
-                                );
-                            }
-                        }
-                        Err(MiningError::Cancelled) => {
-                            debug!(
-                                target: "permia::node_miner",
-                                block = block_number,
-                                "Mining cancelled"
-                            );
-                        }
-                        Err(e) => {
-                            warn!(
-                                target: "permia::node_miner",
-                                block = block_number,
-                                error = %e,
-                                "Mining failed"
-                            );
+        loop {
+            let msg = match self.held.as_ref().map(|held| held.release_at) {
+                // Keep handling messages while a block waits for its interval
+                Some(release_at) => tokio::select! {
+                    msg = self.rx.recv() => msg,
+                    _ = tokio::time::sleep_until(release_at) => {
+                        if let Some(held) = self.held.take() {
+                            self.deliver(held.block).instrument(held.span).await;
                         }
+                        continue
                     }
+                },
+                None if !self.deferred.is_empty() => self.deferred.pop_front(),
+                None => self.rx.recv().await,
+            };
+            let Some(msg) = msg else { break };
 
-                    self.running.store(false, Ordering::SeqCst);
+            match msg {
+                // Only the latest block is worth mining once resumed
+                msg @ MinerMessage::StartMining { .. } if self.paused.load(Ordering::SeqCst) => {
+                    let replaced = self.queued.replace(msg).is_some();
+                    debug!(target: "permia::node_miner", replaced, "Mining paused, queueing block");
+                }
+                msg @ MinerMessage::StartMining { .. } => {
+                    self.queued = None;
+                    self.drop_held("superseded by a new block");
+                    self.mine_block(msg).await
+                }
+                MinerMessage::SubmitSolution { template_id, nonce, mix_hash } => {
+                    let solution = WorkSolution { nonce, mix_hash, seal_hash: template_id };
+                    submit_solution(&self.work, &self.templates, &self.mined_tx, &solution);
                 }
                 MinerMessage::Stop => {
                     debug!(target: "permia::node_miner", "Stopping current mining");
                     self.worker.cancel();
+                    self.queued = None;
+                    self.drop_held("mining stopped");
                     self.running.store(false, Ordering::SeqCst);
                 }
+                MinerMessage::Pause => {
+                    info!(target: "permia::node_miner", "Mining paused");
+                }
+                MinerMessage::Resume => {
+                    info!(
+                        target: "permia::node_miner",
+                        queued = self.queued.is_some(),
+                        "Mining resumed"
+                    );
+                    if let Some(msg) =
+                        self.queued.take_if(|_| !self.paused.load(Ordering::SeqCst))
+                    {
+                        self.drop_held("superseded by a new block");
+                        self.mine_block(msg).await;
+                    }
+                }
                 MinerMessage::Shutdown => {
                     info!(target: "permia::node_miner", "Shutting down node miner");
                     self.worker.cancel();
@@ -297,6 +594,284 @@ impl NodeMiner {
             }
         }
     }
+
+    /// Drop the held block, if any, without delivering it
+    fn drop_held(&mut self, reason: &str) {
+        if let Some(held) = self.held.take() {
+            debug!(
+                target: "permia::node_miner",
+                parent: &held.span,
+                block = held.block.number,
+                reason,
+                "Dropping block held for the block interval"
+            );
+        }
+    }
+
+    /// Deliver a solved block on the mined block channel
+    async fn deliver(&mut self, mined_block: MinedBlock) {
+        let now = Instant::now();
+        let block_time_ms = self
+            .last_mined_at
+            .replace(now)
+            .map(|last| now.duration_since(last).as_millis() as u64);
+
+        let result = &mined_block.mining_result;
+        info!(
+            target: "permia::node_miner",
+            block = mined_block.number,
+            nonce = result.nonce,
+            hash = %result.hash,
+            hashrate = format!("{:.2} H/s", result.hashrate()),
+            block_time_ms,
+            "Block mined!"
+        );
+
+        if let Err(e) = mined_block.check_beneficiary(self.config.beneficiary) {
+            error!(
+                target: "permia::node_miner",
+                block = mined_block.number,
+                error = %e,
+                "Mined block does not pay the configured beneficiary, dropping"
+            );
+        } else if let Err(e) = self.mined_tx.send(mined_block).await {
+            error!(
+                target: "permia::node_miner",
+                error = %e,
+                "Failed to send mined block"
+            );
+        }
+    }
+
+    /// Mine the block of a [`MinerMessage::StartMining`] message
+    ///
+    /// Runs in a span carrying the block number, and the block hash once mined, so the
+    /// block can be followed through announcement and import.
+    async fn mine_block(&mut self, msg: MinerMessage) {
+        let MinerMessage::StartMining { parent, .. } = &msg else { return };
+        let span = span!(
+            target: "permia::node_miner",
+            Level::INFO,
+            "block",
+            block_number = parent.number + 1,
+            block_hash = field::Empty
+        );
+        self.mine_block_in_span(msg).instrument(span).await
+    }
+
+    async fn mine_block_in_span(&mut self, msg: MinerMessage) {
+        let MinerMessage::StartMining {
+            parent,
+            state_root,
+            transactions_root,
+            transactions,
+            receipts_root,
+            difficulty,
+            gas_used,
+        } = msg
+        else {
+            return;
+        };
+
+        let block_number = parent.number + 1;
+
+        // A block below finality could never become canonical
+        if let Some(finalized) = self.finalized_at_or_above(block_number) {
+            warn!(
+                target: "permia::node_miner",
+                block = block_number,
+                finalized,
+                "Block is at or below the finalized height, not mining"
+            );
+            return;
+        }
+
+        // Create block template
+        let mut template = self.template(&parent, difficulty);
+        template.state_root = state_root;
+        template.transactions_root = transactions_root;
+        template.receipts_root = receipts_root;
+        template.gas_used = gas_used;
+
+        // Never seal a header that commits to transactions other than the body's
+        if !template.verify_encoded_transactions_root(&transactions) {
+            error!(
+                target: "permia::node_miner",
+                block = block_number,
+                %transactions_root,
+                transactions = transactions.len(),
+                "Transactions root does not match the block transactions, not mining"
+            );
+            return;
+        }
+
+        self.running.store(true, Ordering::SeqCst);
+        info!(
+            target: "permia::node_miner",
+            block = block_number,
+            parent = %template.parent_hash,
+            difficulty = %difficulty,
+            "Starting to mine block"
+        );
+
+        // Mine the block, publishing it for external miners
+        self.worker.reset();
+        self.work.publish(template.clone());
+        let template_id = self.templates.register(template.clone());
+        let outcome = {
+            // Messages are checked between nonce batches: solutions are handled right away,
+            // anything else waits until mining stops, which a new block or a stop forces
+            let rx = RefCell::new(&mut self.rx);
+            let deferred = RefCell::new(&mut self.deferred);
+            let interrupted = Cell::new(false);
+            let (work, templates, mined_tx) = (&self.work, &self.templates, &self.mined_tx);
+            let finality = self.config.finality.as_ref();
+            let stop = || {
+                while let Ok(msg) = rx.borrow_mut().try_recv() {
+                    match msg {
+                        MinerMessage::SubmitSolution { template_id: id, nonce, mix_hash } => {
+                            let solution = WorkSolution { nonce, mix_hash, seal_hash: id };
+                            submit_solution(work, templates, mined_tx, &solution);
+                        }
+                        msg => {
+                            if matches!(
+                                msg,
+                                MinerMessage::StartMining { .. }
+                                    | MinerMessage::Stop
+                                    | MinerMessage::Shutdown
+                            ) {
+                                interrupted.set(true);
+                            }
+                            deferred.borrow_mut().push_back(msg);
+                        }
+                    }
+                }
+                interrupted.get() ||
+                    !work.is_current(&template_id) ||
+                    finalized_at_or_above(finality, block_number).is_some()
+            };
+            self.worker.mine_unless(&template, stop)
+        };
+        match outcome {
+            Ok(_) if self.work.claim(&template_id).is_none() => {
+                debug!(
+                    target: "permia::node_miner",
+                    block = block_number,
+                    "Block already solved by external miner"
+                );
+            }
+            Ok(result) => {
+                self.templates.remove(&template_id);
+
+                let mined_block = MinedBlock::new(template, result);
+                Span::current().record("block_hash", field::display(mined_block.hash));
+
+                // Hold the block back until the fixed interval has passed
+                let release_at = self
+                    .config
+                    .block_interval
+                    .zip(self.last_mined_at)
+                    .map(|(interval, last)| last + interval)
+                    .filter(|release_at| *release_at > Instant::now());
+                match release_at {
+                    Some(release_at) => {
+                        debug!(
+                            target: "permia::node_miner",
+                            block = block_number,
+                            "Holding block until the block interval has passed"
+                        );
+                        let span = Span::current();
+                        self.held = Some(HeldBlock { block: mined_block, release_at, span });
+                    }
+                    None => self.deliver(mined_block).await,
+                }
+            }
+            Err(MiningError::Cancelled) => {
+                self.work.clear();
+                if let Some(finalized) = self.finalized_at_or_above(block_number) {
+                    // Late external solutions would be below finality too
+                    self.templates.remove(&template_id);
+                    warn!(
+                        target: "permia::node_miner",
+                        block = block_number,
+                        finalized,
+                        "Finality passed the block being mined, aborted"
+                    );
+                } else {
+                    debug!(
+                        target: "permia::node_miner",
+                        block = block_number,
+                        "Mining cancelled"
+                    );
+                }
+            }
+            Err(e) => {
+                self.work.clear();
+                warn!(
+                    target: "permia::node_miner",
+                    block = block_number,
+                    error = %e,
+                    "Mining failed"
+                );
+            }
+        }
+
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Latest finalized height, if a block at `number` would be at or below it
+fn finalized_at_or_above(finality: Option<&SharedFinalityTracker>, number: u64) -> Option<u64> {
+    let finalized = finality?.read().latest_finalized_number()?;
+    (number <= finalized).then_some(finalized)
+}
+
+/// Verify an external solution and claim the template it solves
+///
+/// The solution claims the current work, or an earlier template still registered.
+fn accept_solution(
+    work: &WorkSlot,
+    templates: &TemplateRegistry,
+    solution: &WorkSolution,
+) -> Result<MinedBlock, MiningError> {
+    match work.submit(solution) {
+        Ok(block) => {
+            templates.remove(&solution.seal_hash);
+            Ok(block)
+        }
+        Err(MiningError::NoWork | MiningError::StaleWork { .. }) => templates.submit(solution),
+        Err(e) => Err(e),
+    }
+}
+
+/// Verify an external solution and deliver the block it solves
+fn submit_solution(
+    work: &WorkSlot,
+    templates: &TemplateRegistry,
+    mined_tx: &mpsc::Sender<MinedBlock>,
+    solution: &WorkSolution,
+) {
+    match accept_solution(work, templates, solution) {
+        Ok(block) => {
+            info!(
+                target: "permia::node_miner",
+                block = block.number,
+                hash = %block.hash,
+                "Block solved by external miner"
+            );
+            if let Err(e) = mined_tx.try_send(block) {
+                error!(target: "permia::node_miner", error = %e, "Failed to send mined block");
+            }
+        }
+        Err(e) => {
+            warn!(
+                target: "permia::node_miner",
+                template_id = %solution.seal_hash,
+                error = %e,
+                "Rejected external solution"
+            );
+        }
+    }
 }
 
 /// Spawn the node miner as a background task
@@ -315,6 +890,17 @@ pub fn spawn_node_miner(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloy_consensus::EMPTY_ROOT_HASH;
+    use alloy_eips::eip1559::INITIAL_BASE_FEE;
+
+    /// An empty genesis header
+    fn genesis() -> Header {
+        Header {
+            gas_limit: 60_000_000,
+            base_fee_per_gas: Some(INITIAL_BASE_FEE),
+            ..Default::default()
+        }
+    }
 
     #[tokio::test]
     async fn test_node_miner_creation() {
@@ -327,10 +913,10 @@ mod tests {
         // Start mining with easy difficulty
         handle
             .start_mining(
+                genesis(),
                 B256::ZERO,
-                0,
-                B256::ZERO,
-                B256::ZERO,
+                EMPTY_ROOT_HASH,
+                Vec::new(),
                 B256::ZERO,
                 U256::from(100u64), // Very easy
                 0,
@@ -345,9 +931,567 @@ mod tests {
             .expect("Should receive mined block");
 
         assert_eq!(mined.number, 1);
+        assert_eq!(mined.parent_hash, genesis().hash_slow());
         assert!(mined.nonce > 0 || mined.nonce == 0); // Any nonce is valid
 
+        // The empty genesis lowers the base fee by the maximum 12.5%
+        assert_eq!(mined.template.base_fee_per_gas, Some(875_000_000));
+
         // Shutdown
         handle.shutdown().await.unwrap();
     }
+
+    #[test]
+    fn test_configured_extra_data() {
+        assert!(matches!(
+            NodeMinerConfig::default().with_extra_data(Bytes::from(vec![0u8; 33])),
+            Err(MiningError::InvalidTemplate(_))
+        ));
+
+        let tag = Bytes::from_static(b"pool-7/v1.2");
+        let config = NodeMinerConfig::default().with_extra_data(tag.clone()).unwrap();
+        let (miner, _handle, _mined_rx) = NodeMiner::new(config);
+
+        // Templates carry the tag, and solving one seals it into the header
+        let template = miner.template(&genesis(), U256::from(100u64));
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+
+        let mut header = template.to_header();
+        header.nonce = result.nonce.to_be_bytes().into();
+        header.mix_hash = result.mix_hash;
+        assert_eq!(header.extra_data, tag);
+        permia_consensus::pow::verify_pow(&header).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submit_external_work() {
+        let (_miner, handle, mut mined_rx) = NodeMiner::new(NodeMinerConfig::default());
+        assert!(handle.work().is_none());
+
+        let template =
+            BlockTemplate::new(B256::repeat_byte(1), 5, 1_700_000_000, Address::ZERO, U256::from(100u64));
+        handle.work_slot().publish(template.clone());
+
+        let work = handle.work().unwrap();
+        assert_eq!(work.seal_hash, template.seal_hash());
+
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+        let solution =
+            WorkSolution { nonce: result.nonce, mix_hash: result.mix_hash, seal_hash: work.seal_hash };
+
+        // A wrong nonce is rejected without emitting a block or consuming the work
+        let invalid = WorkSolution { nonce: result.nonce.wrapping_add(1), ..solution };
+        assert!(matches!(handle.submit_work(invalid), Err(MiningError::Consensus(_))));
+        assert!(mined_rx.try_recv().is_err());
+        assert!(handle.work().is_some());
+
+        handle.submit_work(solution).unwrap();
+
+        let mined = mined_rx.recv().await.unwrap();
+        assert_eq!(mined.number, 5);
+        assert_eq!(mined.nonce, result.nonce);
+        assert!(handle.work().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_submit_solution_message() {
+        let (handle, mut mined_rx) = spawn_node_miner(NodeMinerConfig::default().with_threads(1));
+        let template =
+            BlockTemplate::new(B256::repeat_byte(1), 5, 1_700_000_000, Address::ZERO, U256::from(100u64));
+        let template_id = handle.templates().register(template.clone());
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+
+        // A wrong nonce is dropped, the right one is delivered like a mined block
+        handle.submit_solution(template_id, result.nonce.wrapping_add(1), result.mix_hash).await.unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(200), mined_rx.recv()).await.is_err());
+
+        handle.submit_solution(template_id, result.nonce, result.mix_hash).await.unwrap();
+        let mined = tokio::time::timeout(Duration::from_secs(5), mined_rx.recv()).await.unwrap().unwrap();
+        assert_eq!(mined.number, 5);
+        assert_eq!(mined.nonce, result.nonce);
+        assert!(handle.templates().is_empty());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    // The miner blocks a worker thread while mining, keep one free for the test
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_messages_handled_while_mining() {
+        let (handle, mut mined_rx) = spawn_node_miner(NodeMinerConfig::default().with_threads(1));
+
+        // Practically unsolvable, so block 1 is mined until something stops it
+        handle
+            .start_mining(genesis(), B256::ZERO, EMPTY_ROOT_HASH, Vec::new(), B256::ZERO, U256::MAX, 0)
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !handle.is_running() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Mining should start");
+
+        // A solution for an earlier template is delivered without waiting for the block
+        let earlier =
+            BlockTemplate::new(B256::repeat_byte(1), 1, 1_700_000_000, Address::ZERO, U256::from(100u64));
+        let earlier_id = handle.templates().register(earlier.clone());
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&earlier).unwrap();
+        handle.submit_solution(earlier_id, result.nonce, result.mix_hash).await.unwrap();
+        let mined = tokio::time::timeout(Duration::from_secs(5), mined_rx.recv()).await.unwrap().unwrap();
+        assert_eq!(mined.hash, earlier.to_sealed_header(result.nonce, result.mix_hash).hash_slow());
+        assert!(handle.is_running());
+
+        // Stopping aborts the block being mined
+        handle.stop().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handle.is_running() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Mining should be stopped");
+        assert!(handle.work().is_none());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submit_work_for_earlier_template() {
+        let (_miner, handle, mut mined_rx) = NodeMiner::new(NodeMinerConfig::default());
+        let earlier =
+            BlockTemplate::new(B256::repeat_byte(1), 5, 1_700_000_000, Address::ZERO, U256::from(100u64));
+        let current =
+            BlockTemplate::new(B256::repeat_byte(2), 6, 1_700_000_001, Address::ZERO, U256::from(100u64));
+        handle.templates().register(earlier.clone());
+        handle.templates().register(current.clone());
+        handle.work_slot().publish(current.clone());
+
+        // The tip moved on, but the earlier template is still registered
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&earlier).unwrap();
+        let solution =
+            WorkSolution { nonce: result.nonce, mix_hash: result.mix_hash, seal_hash: earlier.seal_hash() };
+        assert_eq!(handle.submit_work(solution).unwrap().number, 5);
+        assert_eq!(mined_rx.recv().await.unwrap().number, 5);
+
+        // The current work is untouched, and the earlier template can't be claimed twice
+        assert_eq!(handle.work().unwrap().seal_hash, current.seal_hash());
+        assert!(matches!(handle.submit_work(solution), Err(MiningError::UnknownTemplate(_))));
+    }
+
+    // The interval is measured on tokio's clock, which only advances while the miner waits
+    #[tokio::test(start_paused = true)]
+    async fn test_fixed_block_interval() {
+        let config = NodeMinerConfig::default()
+            .with_threads(1)
+            .with_block_interval(Duration::from_millis(1000));
+        let (handle, mut mined_rx) = spawn_node_miner(config);
+
+        let mut parent = genesis();
+        let mut mined_at = Vec::new();
+        for _ in 0..3 {
+            handle
+                .start_mining(
+                    parent,
+                    B256::ZERO,
+                    EMPTY_ROOT_HASH,
+                    Vec::new(),
+                    B256::ZERO,
+                    U256::from(1u64),
+                    0,
+                )
+                .await
+                .unwrap();
+            let mined = tokio::time::timeout(Duration::from_secs(10), mined_rx.recv())
+                .await
+                .expect("Mining should complete")
+                .expect("Should receive mined block");
+            mined_at.push(Instant::now());
+            parent = mined.into_header();
+        }
+
+        // Any nonce solves difficulty 1, yet blocks are produced a second apart
+        for pair in mined_at.windows(2) {
+            assert_eq!(pair[1].duration_since(pair[0]), Duration::from_millis(1000));
+        }
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pause_queues_mining() {
+        let (handle, mut mined_rx) = spawn_node_miner(NodeMinerConfig::default().with_threads(1));
+
+        handle.pause().await.unwrap();
+        assert!(handle.is_paused());
+
+        // Each new tip supersedes the previous one
+        for number in 0..5 {
+            handle
+                .start_mining(
+                    Header { number, ..genesis() },
+                    B256::ZERO,
+                    EMPTY_ROOT_HASH,
+                    Vec::new(),
+                    B256::ZERO,
+                    U256::from(100u64),
+                    0,
+                )
+                .await
+                .unwrap();
+        }
+
+        // Queued, not mined, while paused
+        assert!(tokio::time::timeout(Duration::from_millis(500), mined_rx.recv()).await.is_err());
+        assert!(!handle.is_running());
+
+        handle.resume().await.unwrap();
+        assert!(!handle.is_paused());
+
+        // Only the latest block is mined once resumed
+        let mined = tokio::time::timeout(Duration::from_secs(10), mined_rx.recv())
+            .await
+            .expect("Queued block should be mined once resumed")
+            .expect("Should receive mined block");
+        assert_eq!(mined.number, 5);
+        assert!(tokio::time::timeout(Duration::from_millis(500), mined_rx.recv()).await.is_err());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_mined_block_into_header() {
+        let mut template =
+            BlockTemplate::new(B256::repeat_byte(1), 5, 1_700_000_000, Address::ZERO, U256::from(100u64));
+        template.state_root = B256::repeat_byte(2);
+        template.gas_used = 21_000;
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+
+        let mined = MinedBlock::new(template.clone(), result.clone());
+        let header = mined.clone().into_header();
+
+        assert_eq!(header.hash_slow(), mined.hash);
+        assert_eq!(header.number, 5);
+        assert_eq!(header.state_root, template.state_root);
+        assert_eq!(header.gas_used, 21_000);
+        assert_eq!(header.mix_hash, result.mix_hash);
+        permia_consensus::pow::verify_pow(&header).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_transactions_root_not_mined() {
+        let (handle, mut mined_rx) = spawn_node_miner(NodeMinerConfig::default().with_threads(1));
+        let transactions = vec![Bytes::from_static(&[0xc0])];
+
+        // The root of an empty body with a transaction attached
+        handle
+            .start_mining(genesis(), B256::ZERO, EMPTY_ROOT_HASH, transactions.clone(), B256::ZERO, U256::from(1u64), 0)
+            .await
+            .unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(500), mined_rx.recv()).await.is_err());
+
+        // The matching root is mined
+        let transactions_root = alloy_consensus::proofs::ordered_trie_root_with_encoder(
+            &transactions,
+            |tx: &Bytes, buf| buf.extend_from_slice(tx),
+        );
+        handle
+            .start_mining(genesis(), B256::ZERO, transactions_root, transactions, B256::ZERO, U256::from(1u64), 0)
+            .await
+            .unwrap();
+        let mined = tokio::time::timeout(Duration::from_secs(10), mined_rx.recv())
+            .await
+            .expect("Mining should complete")
+            .expect("Should receive mined block");
+        assert_eq!(mined.into_header().transactions_root, transactions_root);
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mined_block_pays_beneficiary() {
+        let beneficiary = Address::repeat_byte(0xbe);
+        let (handle, mut mined_rx) =
+            spawn_node_miner(NodeMinerConfig::default().with_beneficiary(beneficiary).with_threads(1));
+        assert_eq!(handle.beneficiary(), beneficiary);
+
+        handle
+            .start_mining(genesis(), B256::ZERO, EMPTY_ROOT_HASH, Vec::new(), B256::ZERO, U256::from(1u64), 0)
+            .await
+            .unwrap();
+        let mined = tokio::time::timeout(Duration::from_secs(10), mined_rx.recv())
+            .await
+            .expect("Mining should complete")
+            .expect("Should receive mined block");
+        assert_eq!(mined.beneficiary, beneficiary);
+        assert!(mined.check_beneficiary(beneficiary).is_ok());
+        assert_eq!(mined.into_header().beneficiary, beneficiary);
+
+        handle.shutdown().await.unwrap();
+
+        // A block mined from someone else's template is caught
+        let template =
+            BlockTemplate::new(B256::ZERO, 1, 1_700_000_000, Address::repeat_byte(0xee), U256::from(1u64));
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+        let mined = MinedBlock::new(template, result);
+        assert!(matches!(
+            mined.check_beneficiary(beneficiary),
+            Err(MiningError::BeneficiaryMismatch { expected, actual })
+                if expected == beneficiary && actual == Address::repeat_byte(0xee)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mines_with_hash_config() {
+        use permia_consensus::pow::{verify_pow_with_config, DagVersion};
+
+        let v2 = PermiaHashConfig { dag_version: DagVersion::V2, ..Default::default() };
+        let config = NodeMinerConfig::default().with_threads(1).with_hash_config(v2.clone());
+        let (handle, mut mined_rx) = spawn_node_miner(config);
+
+        handle
+            .start_mining(genesis(), B256::ZERO, EMPTY_ROOT_HASH, Vec::new(), B256::ZERO, U256::from(100u64), 0)
+            .await
+            .unwrap();
+        let mined = tokio::time::timeout(Duration::from_secs(10), mined_rx.recv())
+            .await
+            .expect("Mining should complete")
+            .expect("Should receive mined block");
+        verify_pow_with_config(&mined.into_header(), &v2).unwrap();
+
+        handle.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_dag_cache_memory_check() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let config = NodeMinerConfig::default();
+        assert!(config.check_memory_with(0).is_ok());
+
+        // A full 4 GB DAG cache fits on a 16 GB machine but not a 4 GB one
+        let config = config.with_dag_cache_size(4 * GIB);
+        assert!(config.check_memory_with(16 * GIB).is_ok());
+        assert!(matches!(
+            config.check_memory_with(4 * GIB),
+            Err(MiningError::InsufficientMemory { required, available }) if required == 4 * GIB && available == 4 * GIB
+        ));
+
+        // This machine's memory is read when available
+        if let Some(available) = available_memory() {
+            assert!(available > 0);
+        }
+    }
+
+    // Available memory is only known on Linux, elsewhere the miner starts regardless
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_miner_refuses_to_start_without_memory() {
+        let config = NodeMinerConfig::default().with_threads(1).with_dag_cache_size(u64::MAX);
+        let (miner, handle, _mined_rx) = NodeMiner::new(config);
+
+        // The miner loop exits at once, dropping its end of the channel
+        miner.run().await;
+        assert!(handle
+            .start_mining(genesis(), B256::ZERO, EMPTY_ROOT_HASH, Vec::new(), B256::ZERO, U256::from(1u64), 0)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_mining_gives_up_at_iteration_cap() {
+        let config = NodeMinerConfig::default().with_threads(1).with_max_mining_iterations(200);
+        let (handle, mut mined_rx) = spawn_node_miner(config);
+
+        // Practically unsolvable, only the cap stops it before the mining time limit
+        handle
+            .start_mining(
+                genesis(),
+                B256::ZERO,
+                EMPTY_ROOT_HASH,
+                Vec::new(),
+                B256::ZERO,
+                U256::MAX,
+                0,
+            )
+            .await
+            .unwrap();
+
+        // Giving up clears the published work but keeps the template registered
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handle.templates().is_empty() || handle.work().is_some() || handle.is_running() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Mining should give up at the iteration cap");
+        assert!(mined_rx.try_recv().is_err());
+    }
+
+    // The miner blocks a worker thread while mining, keep one free for the test
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_mining_aborted_when_finality_passes() {
+        let tracker = SharedFinalityTracker::default();
+        let config = NodeMinerConfig::default().with_threads(1).with_finality(Arc::clone(&tracker));
+        let (handle, mut mined_rx) = spawn_node_miner(config);
+
+        // Practically unsolvable, so block 10 is mined until something stops it
+        handle
+            .start_mining(
+                Header { number: 9, ..genesis() },
+                B256::ZERO,
+                EMPTY_ROOT_HASH,
+                Vec::new(),
+                B256::ZERO,
+                U256::MAX,
+                0,
+            )
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !handle.is_running() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Mining should start");
+
+        // Block 10 finalizes, e.g. after adopting a peer's chain
+        let depth = tracker.read().implicit_finality_depth();
+        for number in 10..=10 + depth {
+            tracker.write().add_block_at(B256::with_last_byte(number as u8), number);
+        }
+        assert_eq!(tracker.read().latest_finalized_number(), Some(10));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while handle.is_running() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Mining should be aborted");
+        assert!(mined_rx.try_recv().is_err());
+        assert!(handle.work().is_none());
+        assert!(handle.templates().is_empty());
+
+        // A block at a finalized height is not started at all
+        handle
+            .start_mining(
+                Header { number: 8, ..genesis() },
+                B256::ZERO,
+                EMPTY_ROOT_HASH,
+                Vec::new(),
+                B256::ZERO,
+                U256::from(1u64),
+                0,
+            )
+            .await
+            .unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(500), mined_rx.recv()).await.is_err());
+        assert!(handle.work().is_none());
+
+        handle.shutdown().await.unwrap();
+    }
+
+    /// An event's message, with the `block_hash` of its closest span that has one
+    type CapturedEvent = (String, Option<String>);
+
+    /// Records every event with its block hash
+    #[derive(Clone, Default)]
+    struct BlockHashCapture {
+        events: Arc<std::sync::Mutex<Vec<CapturedEvent>>>,
+    }
+
+    /// `block_hash` of a span, stored in its extensions
+    struct SpanBlockHash(String);
+
+    /// Extracts one field as a string
+    struct FieldVisitor {
+        name: &'static str,
+        value: Option<String>,
+    }
+
+    impl FieldVisitor {
+        fn get(name: &'static str, record: impl FnOnce(&mut Self)) -> Option<String> {
+            let mut visitor = Self { name, value: None };
+            record(&mut visitor);
+            visitor.value
+        }
+    }
+
+    impl tracing::field::Visit for FieldVisitor {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == self.name {
+                self.value = Some(format!("{value:?}"));
+            }
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for BlockHashCapture
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if let Some(hash) = FieldVisitor::get("block_hash", |v| attrs.record(v)) {
+                ctx.span(id).unwrap().extensions_mut().insert(SpanBlockHash(hash));
+            }
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if let Some(hash) = FieldVisitor::get("block_hash", |v| values.record(v)) {
+                ctx.span(id).unwrap().extensions_mut().replace(SpanBlockHash(hash));
+            }
+        }
+
+        fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+            let message = FieldVisitor::get("message", |v| event.record(v)).unwrap_or_default();
+            let hash = ctx.event_scope(event).and_then(|mut scope| {
+                scope.find_map(|span| span.extensions().get::<SpanBlockHash>().map(|hash| hash.0.clone()))
+            });
+            self.events.lock().unwrap().push((message, hash));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_span_carries_hash() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = BlockHashCapture::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+
+        let (mut miner, _handle, mut mined_rx) = NodeMiner::new(NodeMinerConfig::default().with_threads(1));
+        miner
+            .mine_block(MinerMessage::StartMining {
+                parent: Box::new(genesis()),
+                state_root: B256::ZERO,
+                transactions_root: EMPTY_ROOT_HASH,
+                transactions: Vec::new(),
+                receipts_root: B256::ZERO,
+                difficulty: U256::from(1u64),
+                gas_used: 0,
+            })
+            .await;
+        let mined = mined_rx.try_recv().unwrap();
+
+        let events = capture.events.lock().unwrap();
+        let hash = mined.hash.to_string();
+
+        // Both the worker and the node miner report the block under its hash
+        let mined_events: Vec<_> = events.iter().filter(|(message, _)| message == "Block mined!").collect();
+        assert_eq!(mined_events.len(), 2, "{events:?}");
+        assert!(mined_events.iter().all(|(_, block_hash)| block_hash.as_ref() == Some(&hash)), "{events:?}");
+
+        // No event is attributed to another block
+        assert!(events.iter().filter_map(|(_, block_hash)| block_hash.as_ref()).all(|h| *h == hash));
+    }
 }
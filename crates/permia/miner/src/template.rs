@@ -2,21 +2,77 @@
 //!
 //! A block template contains all the information needed to mine a new block,
 //! except for the nonce and mix_hash which are found through PoW.
+//!
+//! Templates can be handed to remote workers either as JSON ([`BlockTemplate::to_json`])
+//! or in a compact binary form ([`BlockTemplate::to_bytes`]).
+//!
+//! When transactions change mid-session, [`BlockTemplate::update_transactions`] patches
+//! the transaction dependent fields in place and [`BlockTemplate::needs_remine`] tells
+//! the miner whether the seal hash it is working on is stale.
 
-use alloy_consensus::Header;
+use crate::MiningError;
+use alloy_consensus::{
+    proofs::{calculate_transaction_root, ordered_trie_root_with_encoder},
+    Header,
+};
+use alloy_eips::{
+    eip1559::{BaseFeeParams, INITIAL_BASE_FEE},
+    eip2718::Encodable2718,
+};
 use alloy_primitives::{Address, B256, Bytes, U256};
 use permia_consensus::pow::compute_seal_hash;
+pub use permia_consensus::MAX_EXTRA_DATA_SIZE;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Extra data stamped into templates unless configured otherwise
+pub const DEFAULT_EXTRA_DATA: &[u8] = b"permia";
+
+/// Check that extra data fits in a header
+pub fn validate_extra_data(extra_data: &[u8]) -> Result<(), MiningError> {
+    if extra_data.len() > MAX_EXTRA_DATA_SIZE {
+        return Err(MiningError::InvalidTemplate(format!(
+            "extra data is {} bytes, max {MAX_EXTRA_DATA_SIZE}",
+            extra_data.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Base fee of the block following `parent`, per EIP-1559
+///
+/// A parent without a base fee is followed by [`INITIAL_BASE_FEE`].
+pub fn next_base_fee(parent: &Header) -> u64 {
+    parent.next_block_base_fee(BaseFeeParams::ethereum()).unwrap_or(INITIAL_BASE_FEE)
+}
+
+/// Current time as a header timestamp (seconds since the UNIX epoch)
+///
+/// Header timestamps follow the Ethereum convention of whole seconds; sub-second
+/// block timing is measured with a local clock and never committed to the header.
+pub fn current_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Timestamp of a block following `parent` mined now
+///
+/// The current time, but at least a second after the parent, as headers must have
+/// increasing timestamps.
+pub fn next_timestamp(parent: &Header) -> u64 {
+    current_timestamp().max(parent.timestamp + 1)
+}
 
 /// Block template for mining
 ///
 /// Contains all block data except nonce/mix_hash which are found by mining.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BlockTemplate {
     /// Parent block hash
     pub parent_hash: B256,
     /// Block number
     pub number: u64,
-    /// Timestamp (milliseconds since epoch)
+    /// Timestamp (seconds since epoch)
     pub timestamp: u64,
     /// Miner address (coinbase)
     pub beneficiary: Address,
@@ -36,6 +92,9 @@ pub struct BlockTemplate {
     pub extra_data: Bytes,
     /// Base fee per gas (EIP-1559)
     pub base_fee_per_gas: Option<u64>,
+    /// Whether the seal hash changed since mining started, local to this node
+    #[serde(skip)]
+    remine: bool,
 }
 
 impl BlockTemplate {
@@ -58,9 +117,52 @@ impl BlockTemplate {
             difficulty,
             gas_limit: 60_000_000, // 60M gas limit per spec
             gas_used: 0,
-            extra_data: Bytes::from_static(b"permia"),
-            base_fee_per_gas: Some(1_000_000_000), // 1 gwei
+            extra_data: Bytes::from_static(DEFAULT_EXTRA_DATA),
+            base_fee_per_gas: Some(INITIAL_BASE_FEE),
+            remine: false,
+        }
+    }
+
+    /// Replace the transactions of the template
+    ///
+    /// Takes each transaction with the gas it used and recomputes only the
+    /// transactions root and gas used. If the seal hash changes, [`Self::needs_remine`]
+    /// is set; an identical transaction set leaves the template untouched.
+    pub fn update_transactions<T: Encodable2718>(&mut self, txs: &[(T, u64)]) {
+        let transactions_root = calculate_transaction_root(
+            &txs.iter().map(|(tx, _)| tx).collect::<Vec<_>>(),
+        );
+        let gas_used = txs.iter().map(|(_, gas)| gas).sum();
+
+        if transactions_root == self.transactions_root && gas_used == self.gas_used {
+            return;
         }
+
+        self.transactions_root = transactions_root;
+        self.gas_used = gas_used;
+        self.remine = true;
+    }
+
+    /// Whether `txs` are the transactions committed to by the template's transactions root
+    pub fn verify_transactions_root<T: Encodable2718>(&self, txs: &[T]) -> bool {
+        let encoded: Vec<_> = txs.iter().map(Encodable2718::encoded_2718).collect();
+        self.verify_encoded_transactions_root(&encoded)
+    }
+
+    /// Like [`Self::verify_transactions_root`], for EIP-2718 encoded transactions
+    pub fn verify_encoded_transactions_root<B: AsRef<[u8]>>(&self, txs: &[B]) -> bool {
+        ordered_trie_root_with_encoder(txs, |tx: &B, buf| buf.extend_from_slice(tx.as_ref()))
+            == self.transactions_root
+    }
+
+    /// Whether the seal hash changed since mining of this template started
+    pub fn needs_remine(&self) -> bool {
+        self.remine
+    }
+
+    /// Acknowledge a changed seal hash, called when mining restarts on the template
+    pub fn clear_remine(&mut self) {
+        self.remine = false;
     }
 
     /// Convert template to a header (without nonce/mix_hash)
@@ -90,11 +192,60 @@ impl BlockTemplate {
         }
     }
 
+    /// Convert template to the header sealed with the given PoW solution
+    pub fn to_sealed_header(&self, nonce: u64, mix_hash: B256) -> Header {
+        let mut header = self.to_header();
+        header.nonce = nonce.to_be_bytes().into();
+        header.mix_hash = mix_hash;
+        header
+    }
+
+    /// Reconstruct a template from a header
+    ///
+    /// Copies every pre-seal field; the nonce and mix hash are dropped.
+    pub fn from_header(header: &Header) -> Self {
+        Self {
+            parent_hash: header.parent_hash,
+            number: header.number,
+            timestamp: header.timestamp,
+            beneficiary: header.beneficiary,
+            state_root: header.state_root,
+            transactions_root: header.transactions_root,
+            receipts_root: header.receipts_root,
+            difficulty: header.difficulty,
+            gas_limit: header.gas_limit,
+            gas_used: header.gas_used,
+            extra_data: header.extra_data.clone(),
+            base_fee_per_gas: header.base_fee_per_gas,
+            remine: false,
+        }
+    }
+
     /// Compute the seal hash for this template
     pub fn seal_hash(&self) -> B256 {
         compute_seal_hash(&self.to_header())
     }
 
+    /// Serialize the template to JSON
+    pub fn to_json(&self) -> Result<String, MiningError> {
+        serde_json::to_string(self).map_err(|e| MiningError::Serialization(e.to_string()))
+    }
+
+    /// Deserialize a template from JSON
+    pub fn from_json(json: &str) -> Result<Self, MiningError> {
+        serde_json::from_str(json).map_err(|e| MiningError::Serialization(e.to_string()))
+    }
+
+    /// Serialize the template to its compact binary form (bincode)
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MiningError> {
+        bincode::serialize(self).map_err(|e| MiningError::Serialization(e.to_string()))
+    }
+
+    /// Deserialize a template from its compact binary form
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MiningError> {
+        bincode::deserialize(bytes).map_err(|e| MiningError::Serialization(e.to_string()))
+    }
+
     /// Get the target value from difficulty
     pub fn target(&self) -> U256 {
         permia_consensus::pow::difficulty_to_target(self.difficulty)
@@ -203,4 +354,145 @@ mod tests {
         assert_eq!(template.number, 1);
         assert_eq!(template.timestamp, 1000);
     }
+
+    #[test]
+    fn test_current_timestamp_is_seconds() {
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+        let secs = current_timestamp();
+
+        assert!(secs.abs_diff(millis / 1000) <= 1);
+        assert_eq!(template_with_timestamp(secs).to_header().timestamp, secs);
+    }
+
+    fn populated_template() -> BlockTemplate {
+        let mut template = BlockTemplate::new(
+            B256::repeat_byte(0x01),
+            42,
+            1_700_000_000,
+            Address::repeat_byte(0x02),
+            U256::from(123_456_789u64),
+        );
+        template.state_root = B256::repeat_byte(0x03);
+        template.transactions_root = B256::repeat_byte(0x04);
+        template.receipts_root = B256::repeat_byte(0x05);
+        template.gas_used = 21_000;
+        template.extra_data = Bytes::from_static(b"pool-7");
+        template
+    }
+
+    #[test]
+    fn test_template_json_roundtrip() {
+        let template = populated_template();
+        let json = template.to_json().unwrap();
+        assert!(json.contains("\"parentHash\""));
+
+        let decoded = BlockTemplate::from_json(&json).unwrap();
+        assert_eq!(decoded, template);
+        assert_eq!(decoded.seal_hash(), template.seal_hash());
+
+        assert!(matches!(
+            BlockTemplate::from_json("{}"),
+            Err(MiningError::Serialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_template_binary_roundtrip() {
+        let template = populated_template();
+        let bytes = template.to_bytes().unwrap();
+        assert!(bytes.len() < template.to_json().unwrap().len());
+
+        let decoded = BlockTemplate::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, template);
+        assert_eq!(decoded.seal_hash(), template.seal_hash());
+
+        assert!(BlockTemplate::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_template_header_roundtrip() {
+        let template = populated_template();
+        let mut header = template.to_header();
+
+        // The seal is not part of the template
+        header.nonce = 7u64.to_be_bytes().into();
+        header.mix_hash = B256::repeat_byte(0x06);
+
+        let decoded = BlockTemplate::from_header(&header);
+        assert_eq!(decoded.parent_hash, template.parent_hash);
+        assert_eq!(decoded.number, template.number);
+        assert_eq!(decoded.difficulty, template.difficulty);
+        assert_eq!(decoded.state_root, template.state_root);
+        assert_eq!(decoded.transactions_root, template.transactions_root);
+        assert_eq!(decoded.receipts_root, template.receipts_root);
+        assert_eq!(decoded.gas_limit, template.gas_limit);
+        assert_eq!(decoded.gas_used, template.gas_used);
+        assert_eq!(decoded.timestamp, template.timestamp);
+        assert_eq!(decoded.extra_data, template.extra_data);
+        assert_eq!(decoded, template);
+        assert_eq!(decoded.seal_hash(), template.seal_hash());
+    }
+
+    #[test]
+    fn test_update_transactions() {
+        use alloy_consensus::{Signed, TxEnvelope, TxLegacy};
+        use alloy_primitives::Signature;
+
+        let tx = |nonce| {
+            let tx = TxLegacy { nonce, gas_limit: 21_000, ..Default::default() };
+            TxEnvelope::Legacy(Signed::new_unchecked(tx, Signature::test_signature(), B256::repeat_byte(nonce as u8)))
+        };
+
+        let mut template = populated_template();
+        let seal_hash = template.seal_hash();
+        assert!(!template.needs_remine());
+
+        template.update_transactions(&[(tx(0), 21_000), (tx(1), 21_000)]);
+        assert!(template.needs_remine());
+        assert_eq!(template.gas_used, 42_000);
+        assert_ne!(template.seal_hash(), seal_hash);
+
+        // Same transactions again don't invalidate the work
+        template.clear_remine();
+        let seal_hash = template.seal_hash();
+        template.update_transactions(&[(tx(0), 21_000), (tx(1), 21_000)]);
+        assert!(!template.needs_remine());
+        assert_eq!(template.seal_hash(), seal_hash);
+
+        // Order matters for the transactions root
+        template.update_transactions(&[(tx(1), 21_000), (tx(0), 21_000)]);
+        assert!(template.needs_remine());
+        assert_ne!(template.seal_hash(), seal_hash);
+    }
+
+    #[test]
+    fn test_verify_transactions_root() {
+        use alloy_consensus::{Signed, TxEnvelope, TxLegacy};
+        use alloy_primitives::Signature;
+
+        let tx = |nonce| {
+            let tx = TxLegacy { nonce, gas_limit: 21_000, ..Default::default() };
+            TxEnvelope::Legacy(Signed::new_unchecked(tx, Signature::test_signature(), B256::repeat_byte(nonce as u8)))
+        };
+        let txs = [tx(0), tx(1)];
+
+        let mut template = populated_template();
+        template.update_transactions(&[(txs[0].clone(), 21_000), (txs[1].clone(), 21_000)]);
+        assert!(template.verify_transactions_root(&txs));
+        let encoded: Vec<_> = txs.iter().map(Encodable2718::encoded_2718).collect();
+        assert!(template.verify_encoded_transactions_root(&encoded));
+
+        // A root set by hand that doesn't match the body is caught
+        template.transactions_root = B256::repeat_byte(0xee);
+        assert!(!template.verify_transactions_root(&txs));
+
+        // As are missing or reordered transactions
+        template.update_transactions(&[(txs[0].clone(), 21_000), (txs[1].clone(), 21_000)]);
+        assert!(!template.verify_transactions_root(&txs[..1]));
+        assert!(!template.verify_transactions_root(&[txs[1].clone(), txs[0].clone()]));
+    }
+
+    fn template_with_timestamp(timestamp: u64) -> BlockTemplate {
+        BlockTemplate::new(B256::ZERO, 1, timestamp, Address::ZERO, U256::from(1u64))
+    }
 }
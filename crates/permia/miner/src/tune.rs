@@ -0,0 +1,130 @@
+//! Difficulty auto-tuning for standalone mining
+//!
+//! A solution to a template of difficulty `d` takes `d` hashes on average, so at a
+//! measured hashrate `h` the expected solve time is `d / h`. The tuner starts from the
+//! difficulty matching a target solve time and nudges it after every block found.
+
+use crate::{worker::hashrate, BlockTemplate, MiningConfig, MiningWorker};
+use alloy_primitives::{Address, B256, U256};
+use std::time::{Duration, Instant};
+
+/// Default duration of the hashrate benchmark
+pub const DEFAULT_CALIBRATION_TIME: Duration = Duration::from_secs(2);
+
+/// Maximum factor the difficulty moves by after a single block
+pub const MAX_RETUNE_FACTOR: f64 = 2.0;
+
+/// Fraction of the observed deviation applied per block
+///
+/// Solve times are exponentially distributed, so a single block says little; damping
+/// keeps the difficulty from chasing lucky and unlucky blocks.
+const RETUNE_DAMPING: f64 = 0.25;
+
+/// Difficulty tuner aiming for a fixed solve time
+#[derive(Debug, Clone)]
+pub struct DifficultyTuner {
+    /// Target solve time
+    target_time: Duration,
+    /// Current difficulty
+    difficulty: U256,
+}
+
+impl DifficultyTuner {
+    /// Create a tuner from a measured hashrate (H/s)
+    pub fn calibrate(hashrate: f64, target_time: Duration) -> Self {
+        let difficulty = difficulty_from_f64(hashrate * target_time.as_secs_f64());
+        Self { target_time, difficulty }
+    }
+
+    /// Current difficulty
+    pub fn difficulty(&self) -> U256 {
+        self.difficulty
+    }
+
+    /// Target solve time
+    pub fn target_time(&self) -> Duration {
+        self.target_time
+    }
+
+    /// Expected solve time for the current difficulty at the given hashrate (H/s)
+    pub fn predicted_solve_time(&self, hashrate: f64) -> Duration {
+        Duration::from_secs_f64(self.difficulty.saturating_to::<u128>() as f64 / hashrate.max(f64::MIN_POSITIVE))
+    }
+
+    /// Adjust the difficulty after a block took `solve_time` to find
+    ///
+    /// Returns the difficulty to use for the next block.
+    pub fn record_solve_time(&mut self, solve_time: Duration) -> U256 {
+        let ratio = self.target_time.as_secs_f64() / solve_time.as_secs_f64().max(f64::MIN_POSITIVE);
+        let ratio = ratio.clamp(1.0 / MAX_RETUNE_FACTOR, MAX_RETUNE_FACTOR);
+        let factor = 1.0 + (ratio - 1.0) * RETUNE_DAMPING;
+
+        self.difficulty = difficulty_from_f64(self.difficulty.saturating_to::<u128>() as f64 * factor);
+        self.difficulty
+    }
+}
+
+/// Measure the hashrate (H/s) of a worker mining with `config` by hashing for `duration`
+///
+/// Calibrate with the config that is then used for mining, so the measured hashrate is
+/// the one the tuned difficulty is mined at.
+pub fn measure_hashrate(config: &MiningConfig, duration: Duration) -> f64 {
+    let worker = MiningWorker::new(MiningConfig {
+        max_duration: Some(duration),
+        start_nonce: None,
+        max_iterations: None,
+        ..config.clone()
+    });
+    // Practically unsolvable, so the worker hashes until the duration runs out
+    let template = BlockTemplate::new(B256::ZERO, 0, 0, Address::ZERO, U256::MAX);
+
+    let start = Instant::now();
+    match worker.mine(&template) {
+        Ok(result) => result.hashrate(),
+        Err(_) => hashrate(worker.hash_count(), start.elapsed()),
+    }
+}
+
+fn difficulty_from_f64(difficulty: f64) -> U256 {
+    U256::from((difficulty as u128).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibration_matches_target_time() {
+        let hashrate = 48_500.0;
+        for target in [Duration::from_millis(400), Duration::from_secs(2), Duration::from_secs(60)] {
+            let tuner = DifficultyTuner::calibrate(hashrate, target);
+            let predicted = tuner.predicted_solve_time(hashrate).as_secs_f64();
+            assert!((predicted / target.as_secs_f64() - 1.0).abs() < 0.01, "{predicted} vs {target:?}");
+        }
+
+        // Never below the minimum difficulty
+        assert_eq!(DifficultyTuner::calibrate(0.0, Duration::from_secs(1)).difficulty(), U256::from(1));
+    }
+
+    #[test]
+    fn test_retune_towards_target() {
+        let mut tuner = DifficultyTuner::calibrate(10_000.0, Duration::from_secs(1));
+        assert_eq!(tuner.difficulty(), U256::from(10_000));
+
+        // Slow block lowers the difficulty, by a bounded step
+        assert_eq!(tuner.record_solve_time(Duration::from_secs(100)), U256::from(8_750));
+
+        // Fast block raises it
+        assert!(tuner.record_solve_time(Duration::from_millis(500)) > U256::from(8_750));
+
+        // On-target block leaves it alone
+        let difficulty = tuner.difficulty();
+        assert_eq!(tuner.record_solve_time(Duration::from_secs(1)), difficulty);
+    }
+
+    #[test]
+    fn test_measure_hashrate() {
+        let config = MiningConfig { batch_size: 1_000, ..MiningConfig::with_threads(2) };
+        assert!(measure_hashrate(&config, Duration::from_millis(50)) > 0.0);
+    }
+}
@@ -0,0 +1,392 @@
+//! External mining work
+//!
+//! The node miner publishes the template it is working on to a [`WorkSlot`] so external
+//! miners (getWork/submitWork) can work on the same block. Whoever solves the template
+//! first claims it from the slot; the other side discards its solution.
+//!
+//! Templates are also kept in a [`TemplateRegistry`] for a few blocks, so a solution for
+//! a template the node has since moved on from can still be matched to it.
+
+use crate::{BlockTemplate, MinedBlock, MiningError, MiningResult};
+use alloy_primitives::{B256, FixedBytes, U256};
+use permia_consensus::pow::{permia_hash_with_config, verify_pow_with_config, PermiaHashConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Number of blocks a template stays in the [`TemplateRegistry`] after a newer one
+pub const TEMPLATE_RETENTION_BLOCKS: u64 = 3;
+
+/// Work package handed to external miners
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkPackage {
+    /// Seal hash of the template (header hash without nonce/mix_hash)
+    pub seal_hash: B256,
+    /// Hash target, a solution's PermiaHash must be `<=` this value
+    pub target: U256,
+    /// Number of the block being mined
+    pub number: u64,
+}
+
+impl From<&BlockTemplate> for WorkPackage {
+    fn from(template: &BlockTemplate) -> Self {
+        Self { seal_hash: template.seal_hash(), target: template.target(), number: template.number }
+    }
+}
+
+/// Solution submitted by an external miner
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkSolution {
+    /// The winning nonce
+    pub nonce: u64,
+    /// Mix hash produced by PermiaHash for the nonce
+    pub mix_hash: B256,
+    /// Seal hash of the work package the solution is for
+    pub seal_hash: B256,
+}
+
+/// The template currently being mined, shared with external miners
+#[derive(Debug, Clone, Default)]
+pub struct WorkSlot {
+    current: Arc<Mutex<Option<BlockTemplate>>>,
+    /// PermiaHash parameters solutions are verified with
+    hash_config: PermiaHashConfig,
+}
+
+impl WorkSlot {
+    /// Create an empty slot
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verify solutions with the given PermiaHash parameters instead of the defaults
+    pub fn with_hash_config(mut self, config: PermiaHashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
+
+    /// Publish a new template, replacing any previous work
+    pub fn publish(&self, template: BlockTemplate) {
+        *self.current.lock().unwrap() = Some(template);
+    }
+
+    /// Withdraw the current work
+    pub fn clear(&self) {
+        self.current.lock().unwrap().take();
+    }
+
+    /// Get the template currently being mined
+    pub fn current(&self) -> Option<BlockTemplate> {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Whether the template with `seal_hash` is still the current, unclaimed work
+    pub fn is_current(&self, seal_hash: &B256) -> bool {
+        let current = self.current.lock().unwrap();
+        current.as_ref().is_some_and(|template| template.seal_hash() == *seal_hash)
+    }
+
+    /// Get the work package for the template currently being mined
+    pub fn work(&self) -> Option<WorkPackage> {
+        self.current.lock().unwrap().as_ref().map(WorkPackage::from)
+    }
+
+    /// Claim the current template if its seal hash matches
+    ///
+    /// Returns `None` if the work was already claimed or replaced.
+    pub fn claim(&self, seal_hash: &B256) -> Option<BlockTemplate> {
+        let mut current = self.current.lock().unwrap();
+        if current.as_ref().is_some_and(|template| template.seal_hash() == *seal_hash) {
+            return current.take();
+        }
+        None
+    }
+
+    /// Verify an external solution and claim the template it solves
+    ///
+    /// The solution is checked with [`verify_pow_with_config`] against the sealed header,
+    /// exactly as the consensus layer would. The returned block has no hashing statistics.
+    pub fn submit(&self, solution: &WorkSolution) -> Result<MinedBlock, MiningError> {
+        let mut current = self.current.lock().unwrap();
+        let template = current.as_ref().ok_or(MiningError::NoWork)?;
+
+        let seal_hash = template.seal_hash();
+        if seal_hash != solution.seal_hash {
+            return Err(MiningError::StaleWork { current: seal_hash, submitted: solution.seal_hash });
+        }
+
+        let hash = verify_solution(template, solution, &self.hash_config)?;
+        Ok(solved_block(current.take().expect("checked above"), solution, hash))
+    }
+}
+
+/// Recent templates by template id (the template's seal hash)
+///
+/// Templates are evicted once [`TEMPLATE_RETENTION_BLOCKS`] newer block numbers have
+/// been registered, or when claimed by a solution.
+#[derive(Debug, Clone)]
+pub struct TemplateRegistry {
+    inner: Arc<Mutex<RegistryInner>>,
+    /// PermiaHash parameters solutions are verified with
+    hash_config: PermiaHashConfig,
+}
+
+#[derive(Debug)]
+struct RegistryInner {
+    templates: HashMap<B256, BlockTemplate>,
+    /// Highest block number registered
+    tip: u64,
+    retention: u64,
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        Self::with_retention(TEMPLATE_RETENTION_BLOCKS)
+    }
+}
+
+impl TemplateRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty registry keeping templates for `retention` blocks behind the tip
+    pub fn with_retention(retention: u64) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RegistryInner {
+                templates: HashMap::new(),
+                tip: 0,
+                retention,
+            })),
+            hash_config: PermiaHashConfig::default(),
+        }
+    }
+
+    /// Verify solutions with the given PermiaHash parameters instead of the defaults
+    pub fn with_hash_config(mut self, config: PermiaHashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
+
+    /// Register a template, returning its template id
+    ///
+    /// Evicts templates more than the retention behind the highest registered block.
+    pub fn register(&self, template: BlockTemplate) -> B256 {
+        let mut inner = self.inner.lock().unwrap();
+        let id = template.seal_hash();
+
+        inner.tip = inner.tip.max(template.number);
+        let oldest = inner.tip.saturating_sub(inner.retention);
+        inner.templates.retain(|_, template| template.number >= oldest);
+        if template.number >= oldest {
+            inner.templates.insert(id, template);
+        }
+        id
+    }
+
+    /// Get a registered template by id
+    pub fn get(&self, id: &B256) -> Option<BlockTemplate> {
+        self.inner.lock().unwrap().templates.get(id).cloned()
+    }
+
+    /// Remove a template, e.g. once it was solved elsewhere
+    pub fn remove(&self, id: &B256) -> Option<BlockTemplate> {
+        self.inner.lock().unwrap().templates.remove(id)
+    }
+
+    /// Number of registered templates
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().templates.len()
+    }
+
+    /// Whether no template is registered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Verify a solution against the template it names and claim that template
+    ///
+    /// Fails with [`MiningError::UnknownTemplate`] if the template was never registered,
+    /// was evicted, or was already claimed.
+    pub fn submit(&self, solution: &WorkSolution) -> Result<MinedBlock, MiningError> {
+        let mut inner = self.inner.lock().unwrap();
+        let template = inner
+            .templates
+            .get(&solution.seal_hash)
+            .ok_or(MiningError::UnknownTemplate(solution.seal_hash))?;
+
+        let hash = verify_solution(template, solution, &self.hash_config)?;
+        let template = inner.templates.remove(&solution.seal_hash).expect("checked above");
+        Ok(solved_block(template, solution, hash))
+    }
+}
+
+/// Check a solution's PoW against the sealed template header, exactly as the consensus
+/// layer would, returning the solution's PermiaHash
+fn verify_solution(
+    template: &BlockTemplate,
+    solution: &WorkSolution,
+    config: &PermiaHashConfig,
+) -> Result<B256, MiningError> {
+    let mut header = template.to_header();
+    header.nonce = FixedBytes::from(solution.nonce.to_be_bytes());
+    header.mix_hash = solution.mix_hash;
+    verify_pow_with_config(&header, config)?;
+    Ok(permia_hash_with_config(&solution.seal_hash, solution.nonce, template.number, config)?.hash)
+}
+
+/// Block for a verified external solution, without hashing statistics
+fn solved_block(template: BlockTemplate, solution: &WorkSolution, hash: B256) -> MinedBlock {
+    MinedBlock::new(
+        template,
+        MiningResult {
+            nonce: solution.nonce,
+            mix_hash: solution.mix_hash,
+            hash,
+            hashes_computed: 0,
+            duration: Duration::ZERO,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MiningConfig, MiningWorker};
+    use alloy_primitives::Address;
+
+    fn easy_template() -> BlockTemplate {
+        BlockTemplate::new(B256::repeat_byte(1), 1, 1_700_000_000, Address::ZERO, U256::from(100u64))
+    }
+
+    fn solve(template: &BlockTemplate) -> WorkSolution {
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(template).unwrap();
+        WorkSolution { nonce: result.nonce, mix_hash: result.mix_hash, seal_hash: template.seal_hash() }
+    }
+
+    #[test]
+    fn test_work_package() {
+        let slot = WorkSlot::new();
+        assert!(slot.work().is_none());
+
+        let template = easy_template();
+        slot.publish(template.clone());
+
+        let work = slot.work().unwrap();
+        assert_eq!(work.seal_hash, template.seal_hash());
+        assert_eq!(work.target, template.target());
+        assert_eq!(work.number, 1);
+        assert_eq!(slot.work(), Some(work));
+    }
+
+    #[test]
+    fn test_submit_solution() {
+        let slot = WorkSlot::new();
+        let template = easy_template();
+        slot.publish(template.clone());
+
+        let solution = solve(&template);
+        let block = slot.submit(&solution).unwrap();
+        assert_eq!(block.number, template.number);
+        assert_eq!(block.nonce, solution.nonce);
+        assert_eq!(block.mix_hash, solution.mix_hash);
+
+        // The template is claimed, a second submission finds no work
+        assert!(slot.current().is_none());
+        assert!(matches!(slot.submit(&solution), Err(MiningError::NoWork)));
+    }
+
+    #[test]
+    fn test_submit_rejects_invalid() {
+        let slot = WorkSlot::new();
+        let template = easy_template();
+        slot.publish(template.clone());
+        let solution = solve(&template);
+
+        let stale = WorkSolution { seal_hash: B256::repeat_byte(9), ..solution };
+        assert!(matches!(slot.submit(&stale), Err(MiningError::StaleWork { .. })));
+
+        let bad_mix = WorkSolution { mix_hash: B256::repeat_byte(9), ..solution };
+        assert!(matches!(slot.submit(&bad_mix), Err(MiningError::Consensus(_))));
+
+        // Rejected solutions leave the work in place
+        assert!(slot.submit(&solution).is_ok());
+    }
+
+    #[test]
+    fn test_submit_with_hash_config() {
+        use permia_consensus::pow::DagVersion;
+
+        let v2 = PermiaHashConfig { dag_version: DagVersion::V2, ..Default::default() };
+        let template = easy_template();
+        let config = MiningConfig::single_thread().with_hash_config(v2.clone());
+        let result = MiningWorker::new(config).mine(&template).unwrap();
+        let seal_hash = template.seal_hash();
+        let solution = WorkSolution { nonce: result.nonce, mix_hash: result.mix_hash, seal_hash };
+
+        // Verified with the parameters it was mined with only
+        let slot = WorkSlot::new();
+        slot.publish(template.clone());
+        assert!(matches!(slot.submit(&solution), Err(MiningError::Consensus(_))));
+        let slot = WorkSlot::new().with_hash_config(v2.clone());
+        slot.publish(template.clone());
+        assert_eq!(slot.submit(&solution).unwrap().mining_result.hash, result.hash);
+
+        let registry = TemplateRegistry::new().with_hash_config(v2);
+        registry.register(template);
+        assert!(registry.submit(&solution).is_ok());
+    }
+
+    #[test]
+    fn test_claim() {
+        let slot = WorkSlot::new();
+        let template = easy_template();
+        slot.publish(template.clone());
+
+        assert!(slot.claim(&B256::ZERO).is_none());
+        assert_eq!(slot.claim(&template.seal_hash()), Some(template.clone()));
+        assert!(slot.claim(&template.seal_hash()).is_none());
+    }
+
+    #[test]
+    fn test_registry_matches_solutions() {
+        let registry = TemplateRegistry::new();
+        let first = easy_template();
+        let second =
+            BlockTemplate::new(B256::repeat_byte(2), 2, 1_700_000_001, Address::ZERO, U256::from(100u64));
+        let first_id = registry.register(first.clone());
+        let second_id = registry.register(second.clone());
+        assert_eq!(registry.len(), 2);
+
+        // Each solution is matched to its own template, whichever was registered last
+        let block = registry.submit(&solve(&first)).unwrap();
+        assert_eq!(block.template.seal_hash(), first_id);
+        assert_eq!(block.number, 1);
+        let block = registry.submit(&solve(&second)).unwrap();
+        assert_eq!(block.template.seal_hash(), second_id);
+        assert_eq!(block.number, 2);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_registry_evicts_old_templates() {
+        let registry = TemplateRegistry::with_retention(2);
+        let old = easy_template();
+        let solution = solve(&old);
+        let old_id = registry.register(old);
+
+        for number in 2..=3 {
+            registry.register(BlockTemplate::new(B256::ZERO, number, 0, Address::ZERO, U256::from(100u64)));
+        }
+        assert!(registry.get(&old_id).is_some());
+
+        // Three blocks on, the template is gone and its solution is rejected
+        registry.register(BlockTemplate::new(B256::ZERO, 4, 0, Address::ZERO, U256::from(100u64)));
+        assert!(registry.get(&old_id).is_none());
+        assert!(matches!(registry.submit(&solution), Err(MiningError::UnknownTemplate(id)) if id == old_id));
+        assert_eq!(registry.len(), 3);
+    }
+}
@@ -4,11 +4,61 @@
 
 use crate::{BlockTemplate, MiningError};
 use alloy_primitives::{B256, U256, FixedBytes};
-use permia_consensus::pow::{permia_hash_with_epoch, HashResult};
+use permia_consensus::pow::{
+    permia_hash_with_config, permia_hash_with_epoch, HashResult, PermiaHashConfig,
+};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tracing::{debug, info};
+use tokio::sync::mpsc;
+use tracing::{debug, field, info, span, Level};
+
+/// Default wall-clock time between cancellation checks when auto-tuning the batch size
+pub const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Hashrate in H/s of `hashes` computed over `elapsed`, zero if no time has passed
+pub(crate) fn hashrate(hashes: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        return 0.0;
+    }
+    hashes as f64 / elapsed.as_secs_f64()
+}
+
+/// Batch size auto-tuning
+///
+/// Sizes each batch from the per-hash latency measured over the previous one, so that
+/// cancellation is checked roughly every `interval` on slow and fast machines alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchTuning {
+    /// Target wall-clock time per batch
+    pub interval: Duration,
+    /// Smallest batch size
+    pub min_batch: u64,
+    /// Largest batch size
+    pub max_batch: u64,
+}
+
+impl Default for BatchTuning {
+    fn default() -> Self {
+        Self { interval: DEFAULT_BATCH_INTERVAL, min_batch: 1, max_batch: 1_000_000 }
+    }
+}
+
+impl BatchTuning {
+    /// Size of the next batch after `batch_size` nonces took `elapsed`
+    ///
+    /// Shrinks straight to the target, but at most doubles per batch so a single
+    /// unusually fast batch can't overshoot.
+    pub fn next_batch_size(&self, batch_size: u64, elapsed: Duration) -> u64 {
+        let min = self.min_batch.max(1);
+        let max = self.max_batch.max(min);
+        let batch = batch_size.max(1) as f64;
+
+        let target = batch * self.interval.as_secs_f64() / elapsed.as_secs_f64();
+        (target.min(batch * 2.0) as u64).clamp(min, max)
+    }
+}
 
 /// Mining configuration
 #[derive(Debug, Clone)]
@@ -16,9 +66,19 @@ pub struct MiningConfig {
     /// Number of mining threads
     pub threads: usize,
     /// Nonces to try per batch before checking for cancellation
+    ///
+    /// The initial batch size when [`Self::batch_tuning`] is set.
     pub batch_size: u64,
+    /// Auto-tune the batch size (None = fixed batch size)
+    pub batch_tuning: Option<BatchTuning>,
     /// Maximum time to mine before giving up (None = forever)
     pub max_duration: Option<Duration>,
+    /// Fixed nonce to start the search from (None = drawn from the worker's RNG)
+    pub start_nonce: Option<u64>,
+    /// Maximum number of hashes to try before giving up (None = unlimited)
+    pub max_iterations: Option<u64>,
+    /// PermiaHash parameters of the chain being mined
+    pub hash_config: PermiaHashConfig,
 }
 
 impl Default for MiningConfig {
@@ -26,7 +86,11 @@ impl Default for MiningConfig {
         Self {
             threads: num_cpus::get().max(1),
             batch_size: 10_000,
+            batch_tuning: Some(BatchTuning::default()),
             max_duration: None,
+            start_nonce: None,
+            max_iterations: None,
+            hash_config: PermiaHashConfig::default(),
         }
     }
 }
@@ -47,6 +111,31 @@ impl MiningConfig {
             ..Default::default()
         }
     }
+
+    /// Start the nonce search at a fixed nonce, for reproducible mining runs
+    pub fn with_start_nonce(mut self, nonce: u64) -> Self {
+        self.start_nonce = Some(nonce);
+        self
+    }
+
+    /// Auto-tune the batch size with the given bounds and interval
+    pub fn with_batch_tuning(mut self, tuning: BatchTuning) -> Self {
+        self.batch_tuning = Some(tuning);
+        self
+    }
+
+    /// Give up after trying `iterations` nonces, regardless of time
+    pub fn with_max_iterations(mut self, iterations: u64) -> Self {
+        self.max_iterations = Some(iterations);
+        self
+    }
+
+    /// Mine with the given PermiaHash parameters, e.g. the chain's
+    /// [`permia_hash_config`](permia_consensus::permia_hash_config)
+    pub fn with_hash_config(mut self, config: PermiaHashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
 }
 
 /// Result of successful mining
@@ -67,27 +156,89 @@ pub struct MiningResult {
 impl MiningResult {
     /// Get hashrate in H/s
     pub fn hashrate(&self) -> f64 {
-        self.hashes_computed as f64 / self.duration.as_secs_f64()
+        hashrate(self.hashes_computed, self.duration)
     }
 }
 
+/// Progress event emitted while mining a template
+#[derive(Debug, Clone)]
+pub enum MiningProgress {
+    /// Periodic update emitted after each nonce batch
+    Progress {
+        /// Hashes computed so far
+        hashes: u64,
+        /// Current hashrate in H/s
+        hashrate: f64,
+        /// Time spent mining so far
+        elapsed: Duration,
+    },
+    /// A valid solution was found
+    Solved {
+        /// The winning nonce
+        nonce: u64,
+        /// Total hashes computed
+        hashes: u64,
+        /// Time taken to find the solution
+        elapsed: Duration,
+    },
+    /// Mining was cancelled, reporting the work done before stopping
+    Cancelled {
+        /// Total hashes computed
+        hashes: u64,
+        /// Hashrate over the run in H/s
+        hashrate: f64,
+        /// Time spent before cancellation
+        elapsed: Duration,
+    },
+    /// Mining stopped without a solution
+    Failed {
+        /// Total hashes computed
+        hashes: u64,
+        /// Time spent before giving up
+        elapsed: Duration,
+        /// Why mining stopped
+        reason: String,
+    },
+}
+
+/// RNG shared by a worker and the blocking tasks it spawns
+type SharedRng = Arc<Mutex<Box<dyn RngCore + Send>>>;
+
 /// Mining worker that searches for valid nonces
 pub struct MiningWorker {
     config: MiningConfig,
     cancelled: Arc<AtomicBool>,
     total_hashes: Arc<AtomicU64>,
+    /// Source of random start nonces
+    rng: SharedRng,
 }
 
 impl MiningWorker {
     /// Create a new mining worker
+    ///
+    /// Start nonces are drawn from an RNG seeded from OS entropy, see [`Self::with_rng`].
     pub fn new(config: MiningConfig) -> Self {
         Self {
             config,
             cancelled: Arc::new(AtomicBool::new(false)),
             total_hashes: Arc::new(AtomicU64::new(0)),
+            rng: Arc::new(Mutex::new(Box::new(StdRng::from_entropy()))),
         }
     }
 
+    /// Draw start nonces from the given RNG, e.g. a seeded one for reproducible runs
+    ///
+    /// A [`MiningConfig::start_nonce`] takes precedence over the RNG.
+    pub fn with_rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Arc::new(Mutex::new(Box::new(rng)));
+        self
+    }
+
+    /// Nonce to start the next search from
+    fn start_nonce(&self) -> u64 {
+        self.config.start_nonce.unwrap_or_else(|| self.rng.lock().unwrap().next_u64())
+    }
+
     /// Cancel ongoing mining
     pub fn cancel(&self) {
         self.cancelled.store(true, Ordering::SeqCst);
@@ -106,11 +257,65 @@ impl MiningWorker {
 
     /// Mine a block template (blocking, single-threaded for simplicity)
     pub fn mine(&self, template: &BlockTemplate) -> Result<MiningResult, MiningError> {
+        self.mine_inner(template, None, &|| false)
+    }
+
+    /// Mine a block template, cancelling once `stop` returns true
+    ///
+    /// `stop` is checked before every batch of nonces.
+    pub fn mine_unless(
+        &self,
+        template: &BlockTemplate,
+        stop: impl Fn() -> bool,
+    ) -> Result<MiningResult, MiningError> {
+        self.mine_inner(template, None, &stop)
+    }
+
+    /// Mine a block template, emitting [`MiningProgress`] events on the given channel.
+    ///
+    /// Progress events are sent with `try_send`, so a full channel never stalls mining.
+    /// The final solved, cancelled or failed event is always delivered, waiting for room
+    /// in the channel unless it is closed; like the other mining methods this blocks, so
+    /// it must not be called from an async context.
+    pub fn mine_with_progress(
+        &self,
+        template: &BlockTemplate,
+        progress: &mpsc::Sender<MiningProgress>,
+    ) -> Result<MiningResult, MiningError> {
+        self.mine_inner(template, Some(progress), &|| false)
+    }
+
+    fn mine_inner(
+        &self,
+        template: &BlockTemplate,
+        progress: Option<&mpsc::Sender<MiningProgress>>,
+        stop: &dyn Fn() -> bool,
+    ) -> Result<MiningResult, MiningError> {
+        let emit = |event: MiningProgress| {
+            if let Some(tx) = progress {
+                if matches!(event, MiningProgress::Progress { .. }) {
+                    let _ = tx.try_send(event);
+                } else {
+                    let _ = tx.blocking_send(event);
+                }
+            }
+        };
+
         let start = Instant::now();
         let seal_hash = template.seal_hash();
         let target = template.target();
         let block_number = template.number;
 
+        // The block hash is only known once solved, and recorded then
+        let span = span!(
+            target: "permia::miner",
+            Level::INFO,
+            "mine",
+            block_number,
+            block_hash = field::Empty
+        );
+        let _span = span.enter();
+
         info!(
             target: "permia::miner",
             block = block_number,
@@ -118,18 +323,46 @@ impl MiningWorker {
             "Starting mining"
         );
 
-        let mut nonce: u64 = rand::random();
+        // Checked once, so hashing below can't fail
+        let hash_config = &self.config.hash_config;
+        if let Err(e) = hash_config.validate() {
+            let reason = e.to_string();
+            emit(MiningProgress::Failed { hashes: 0, elapsed: start.elapsed(), reason });
+            return Err(e.into());
+        }
+
+        let mut nonce: u64 = self.start_nonce();
         let start_nonce = nonce;
+        let mut iterations: u64 = 0;
+        let mut batch_size = self.config.batch_size;
 
         loop {
             // Check cancellation
-            if self.cancelled.load(Ordering::Relaxed) {
+            if self.cancelled.load(Ordering::Relaxed) || stop() {
+                let hashes = self.total_hashes.load(Ordering::Relaxed);
+                let elapsed = start.elapsed();
+                let hashrate = hashrate(hashes, elapsed);
+
+                info!(
+                    target: "permia::miner",
+                    block = block_number,
+                    hashes,
+                    elapsed_ms = elapsed.as_millis(),
+                    hashrate = format!("{:.2} H/s", hashrate),
+                    "Mining cancelled"
+                );
+                emit(MiningProgress::Cancelled { hashes, hashrate, elapsed });
                 return Err(MiningError::Cancelled);
             }
 
             // Check timeout
             if let Some(max_dur) = self.config.max_duration {
                 if start.elapsed() > max_dur {
+                    emit(MiningProgress::Failed {
+                        hashes: self.total_hashes.load(Ordering::Relaxed),
+                        elapsed: start.elapsed(),
+                        reason: "timed out".to_string(),
+                    });
                     return Err(MiningError::NoSolution {
                         start: start_nonce,
                         end: nonce,
@@ -138,15 +371,31 @@ impl MiningWorker {
             }
 
             // Try batch of nonces
-            for _ in 0..self.config.batch_size {
-                let result = permia_hash_with_epoch(&seal_hash, nonce, block_number);
+            let batch_start = Instant::now();
+            for _ in 0..batch_size {
+                if self.config.max_iterations.is_some_and(|max| iterations >= max) {
+                    emit(MiningProgress::Failed {
+                        hashes: self.total_hashes.load(Ordering::Relaxed),
+                        elapsed: start.elapsed(),
+                        reason: "iteration limit reached".to_string(),
+                    });
+                    return Err(MiningError::NoSolution {
+                        start: start_nonce,
+                        end: nonce,
+                    });
+                }
+
+                let result = permia_hash_with_config(&seal_hash, nonce, block_number, hash_config)?;
                 self.total_hashes.fetch_add(1, Ordering::Relaxed);
+                iterations += 1;
 
                 let hash_value = U256::from_be_bytes(result.hash.0);
 
                 if hash_value <= target {
                     let duration = start.elapsed();
                     let hashes = self.total_hashes.load(Ordering::Relaxed);
+                    let block_hash = template.to_sealed_header(nonce, result.mix_digest).hash_slow();
+                    span.record("block_hash", field::display(block_hash));
 
                     info!(
                         target: "permia::miner",
@@ -154,10 +403,12 @@ impl MiningWorker {
                         nonce = nonce,
                         hashes = hashes,
                         duration_ms = duration.as_millis(),
-                        hashrate = hashes as f64 / duration.as_secs_f64(),
+                        hashrate = hashrate(hashes, duration),
                         "Block mined!"
                     );
 
+                    emit(MiningProgress::Solved { nonce, hashes, elapsed: duration });
+
                     return Ok(MiningResult {
                         nonce,
                         mix_hash: result.mix_digest,
@@ -169,12 +420,17 @@ impl MiningWorker {
 
                 nonce = nonce.wrapping_add(1);
             }
+            if let Some(tuning) = &self.config.batch_tuning {
+                batch_size = tuning.next_batch_size(batch_size, batch_start.elapsed());
+            }
 
-            // Log progress periodically
+            // Report progress after every batch, log periodically
             let hashes = self.total_hashes.load(Ordering::Relaxed);
+            let elapsed = start.elapsed();
+            let hashrate = hashrate(hashes, elapsed);
+            emit(MiningProgress::Progress { hashes, hashrate, elapsed });
+
             if hashes % 100_000 == 0 {
-                let elapsed = start.elapsed();
-                let hashrate = hashes as f64 / elapsed.as_secs_f64();
                 debug!(
                     target: "permia::miner",
                     hashes = hashes,
@@ -193,6 +449,7 @@ impl MiningWorker {
                 config: worker.0,
                 cancelled: worker.1,
                 total_hashes: worker.2,
+                rng: worker.3,
             };
             miner.mine(&template)
         })
@@ -200,11 +457,12 @@ impl MiningWorker {
         .map_err(|_| MiningError::Cancelled)?
     }
 
-    fn clone_internals(&self) -> (MiningConfig, Arc<AtomicBool>, Arc<AtomicU64>) {
+    fn clone_internals(&self) -> (MiningConfig, Arc<AtomicBool>, Arc<AtomicU64>, SharedRng) {
         (
             self.config.clone(),
             Arc::clone(&self.cancelled),
             Arc::clone(&self.total_hashes),
+            Arc::clone(&self.rng),
         )
     }
 }
@@ -240,6 +498,40 @@ mod tests {
         assert_eq!(config.batch_size, 10_000);
     }
 
+    #[test]
+    fn test_batch_tuning() {
+        let tuning = BatchTuning { interval: Duration::from_millis(50), min_batch: 100, max_batch: 20_000 };
+        // Batch sizes a hasher with the given per-hash latency settles on
+        let settle = |per_hash: Duration| {
+            let mut batch = 10_000;
+            let mut sizes = Vec::new();
+            for _ in 0..20 {
+                batch = tuning.next_batch_size(batch, per_hash * batch as u32);
+                sizes.push(batch);
+            }
+            sizes
+        };
+
+        // 1µs/hash wants 50k per 50ms: grows, at most doubling, up to the max
+        let fast = settle(Duration::from_micros(1));
+        assert_eq!(fast[0], 20_000);
+        assert_eq!(*fast.last().unwrap(), 20_000);
+        let unbounded = BatchTuning { max_batch: u64::MAX, ..tuning };
+        assert_eq!(unbounded.next_batch_size(10_000, Duration::from_millis(10)), 20_000);
+        assert_eq!(unbounded.next_batch_size(20_000, Duration::from_millis(20)), 40_000);
+        assert_eq!(unbounded.next_batch_size(40_000, Duration::from_millis(40)), 50_000);
+
+        // 100µs/hash wants 500 per 50ms: shrinks straight to it
+        let slow = settle(Duration::from_micros(100));
+        assert!(slow.iter().all(|&batch| batch == 500), "{slow:?}");
+
+        // 1ms/hash wants 50 per 50ms: bounded by the min
+        assert_eq!(*settle(Duration::from_millis(1)).last().unwrap(), 100);
+
+        // An instant batch only doubles
+        assert_eq!(unbounded.next_batch_size(10_000, Duration::ZERO), 20_000);
+    }
+
     #[test]
     fn test_mine_easy_difficulty() {
         // Use very low difficulty so we find a solution quickly
@@ -254,7 +546,11 @@ mod tests {
         let config = MiningConfig {
             threads: 1,
             batch_size: 1000,
+            batch_tuning: None,
             max_duration: Some(Duration::from_secs(10)),
+            start_nonce: None,
+            max_iterations: None,
+            hash_config: PermiaHashConfig::default(),
         };
 
         let worker = MiningWorker::new(config);
@@ -269,4 +565,184 @@ mod tests {
             mining_result.hashrate()
         );
     }
+
+    #[test]
+    fn test_mine_with_hash_config() {
+        use permia_consensus::pow::{verify_pow, verify_pow_with_config, DagVersion};
+
+        let template = BlockTemplate::new(B256::ZERO, 1, 1000, Address::ZERO, U256::from(100u64));
+        let v2 = PermiaHashConfig { dag_version: DagVersion::V2, ..Default::default() };
+        let worker = MiningWorker::new(MiningConfig::single_thread().with_hash_config(v2.clone()));
+        let result = worker.mine(&template).unwrap();
+
+        let header = template.to_sealed_header(result.nonce, result.mix_hash);
+        assert!(verify_pow_with_config(&header, &v2).is_ok());
+        assert!(verify_pow(&header).is_err());
+
+        // Unusable parameters fail before hashing
+        let broken = PermiaHashConfig { rounds: 0, ..Default::default() };
+        let worker = MiningWorker::new(MiningConfig::single_thread().with_hash_config(broken));
+        assert!(matches!(worker.mine(&template), Err(MiningError::Consensus(_))));
+        assert_eq!(worker.hash_count(), 0);
+    }
+
+    #[test]
+    fn test_fixed_start_nonce_is_reproducible() {
+        let template = BlockTemplate::new(
+            B256::ZERO,
+            1,
+            1000,
+            Address::ZERO,
+            U256::from(1_000u64),
+        );
+        let config = MiningConfig::single_thread().with_start_nonce(42);
+
+        let first = MiningWorker::new(config.clone()).mine(&template).unwrap();
+        let second = MiningWorker::new(config).mine(&template).unwrap();
+
+        assert!(first.nonce >= 42);
+        assert_eq!(first.nonce, second.nonce);
+        assert_eq!(first.hash, second.hash);
+        assert_eq!(first.mix_hash, second.mix_hash);
+        assert_eq!(first.hashes_computed, second.hashes_computed);
+    }
+
+    #[test]
+    fn test_seeded_rng_is_reproducible() {
+        let template = BlockTemplate::new(B256::ZERO, 1, 1000, Address::ZERO, U256::MAX);
+        let config = MiningConfig::single_thread().with_max_iterations(1);
+        let start_nonce = |worker: MiningWorker| match worker.mine(&template) {
+            Err(MiningError::NoSolution { start, .. }) => start,
+            other => panic!("unexpected result: {other:?}"),
+        };
+
+        let first = start_nonce(MiningWorker::new(config.clone()).with_rng(StdRng::seed_from_u64(7)));
+        let second = start_nonce(MiningWorker::new(config.clone()).with_rng(StdRng::seed_from_u64(7)));
+        assert_eq!(first, second);
+        assert_eq!(first, StdRng::seed_from_u64(7).next_u64());
+
+        let other = start_nonce(MiningWorker::new(config.clone()).with_rng(StdRng::seed_from_u64(8)));
+        assert_ne!(first, other);
+
+        // A fixed start nonce overrides the RNG
+        let fixed = MiningWorker::new(config.with_start_nonce(42)).with_rng(StdRng::seed_from_u64(7));
+        assert_eq!(start_nonce(fixed), 42);
+    }
+
+    #[test]
+    fn test_max_iterations_gives_up() {
+        let template = BlockTemplate::new(
+            B256::ZERO,
+            1,
+            1000,
+            Address::ZERO,
+            U256::MAX, // Impossible difficulty
+        );
+        let config = MiningConfig {
+            threads: 1,
+            batch_size: 7,
+            batch_tuning: None,
+            max_duration: None,
+            start_nonce: Some(100),
+            max_iterations: Some(50),
+            hash_config: PermiaHashConfig::default(),
+        };
+
+        let worker = MiningWorker::new(config);
+        let result = worker.mine(&template);
+
+        assert!(matches!(result, Err(MiningError::NoSolution { start: 100, end: 150 })));
+        assert_eq!(worker.hash_count(), 50);
+    }
+
+    #[test]
+    fn test_mine_with_progress() {
+        let template = BlockTemplate::new(
+            B256::ZERO,
+            1,
+            1000,
+            Address::ZERO,
+            U256::from(1_000u64), // ~1000 hashes expected, one hash per batch
+        );
+
+        let config = MiningConfig {
+            threads: 1,
+            batch_size: 1,
+            batch_tuning: None,
+            max_duration: Some(Duration::from_secs(30)),
+            start_nonce: None,
+            max_iterations: None,
+            hash_config: PermiaHashConfig::default(),
+        };
+
+        // A slow consumer misses progress events, but never the final one
+        let (tx, mut rx) = mpsc::channel(1);
+        let handle = std::thread::spawn(move || {
+            MiningWorker::new(config).mine_with_progress(&template, &tx)
+        });
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.blocking_recv() {
+            events.push(event);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let result = handle.join().unwrap().unwrap();
+
+        let progress = events
+            .iter()
+            .filter(|e| matches!(e, MiningProgress::Progress { .. }))
+            .count();
+        assert!(progress >= 1 || result.hashes_computed == 1);
+        assert!(matches!(
+            events.last(),
+            Some(MiningProgress::Solved { nonce, .. }) if *nonce == result.nonce
+        ));
+    }
+
+    #[test]
+    fn test_hashrate_without_elapsed_time() {
+        assert_eq!(hashrate(1_000, Duration::from_millis(500)), 2_000.0);
+        assert_eq!(hashrate(1_000, Duration::ZERO), 0.0);
+        assert_eq!(hashrate(0, Duration::ZERO), 0.0);
+
+        // Cancelled before the first batch, the stats stay finite
+        let worker = MiningWorker::new(MiningConfig::single_thread());
+        worker.cancel();
+        let (tx, mut rx) = mpsc::channel(1);
+        let template = BlockTemplate::new(B256::ZERO, 1, 1000, Address::ZERO, U256::MAX);
+        assert!(matches!(worker.mine_with_progress(&template, &tx), Err(MiningError::Cancelled)));
+        let Ok(MiningProgress::Cancelled { hashes: 0, hashrate, .. }) = rx.try_recv() else {
+            panic!("expected a cancelled event");
+        };
+        assert!(hashrate.is_finite());
+    }
+
+    #[test]
+    fn test_cancel_reports_final_stats() {
+        let template = BlockTemplate::new(B256::ZERO, 1, 1000, Address::ZERO, U256::MAX);
+        let config = MiningConfig { batch_size: 10, batch_tuning: None, ..MiningConfig::single_thread() };
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let worker = Arc::new(MiningWorker::new(config));
+        let miner = Arc::clone(&worker);
+        let handle = std::thread::spawn(move || miner.mine_with_progress(&template, &tx));
+
+        while worker.hash_count() == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        worker.cancel();
+
+        // The channel is full of progress, the final event waits for room
+        let mut last = None;
+        while let Some(event) = rx.blocking_recv() {
+            last = Some(event);
+        }
+        assert!(matches!(handle.join().unwrap(), Err(MiningError::Cancelled)));
+        let Some(MiningProgress::Cancelled { hashes, hashrate, .. }) = last else {
+            panic!("expected a final cancelled event, got {last:?}");
+        };
+        assert!(hashes > 0);
+        assert_eq!(hashes, worker.hash_count());
+        assert!(hashrate > 0.0);
+    }
 }
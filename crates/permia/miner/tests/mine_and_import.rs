@@ -0,0 +1,53 @@
+//! End-to-end mining: template → PoW search → sealed header → consensus validation
+
+use alloy_consensus::Header;
+use alloy_primitives::{Address, FixedBytes, B256, U256};
+use permia_consensus::{permia_error, PermiaConsensusError, PermiaPoWConsensus};
+use permia_miner::{current_timestamp, BlockTemplate, MiningConfig, MiningResult, MiningWorker};
+use reth_chainspec::PERMIA_DEV;
+use reth_consensus::HeaderValidator;
+use reth_primitives_traits::SealedHeader;
+
+/// Seal a template with a mining result
+///
+/// The fields of the forks active from genesis on the dev chain are set empty, they are
+/// not covered by the seal hash.
+fn seal(template: &BlockTemplate, nonce: u64, result: &MiningResult) -> SealedHeader<Header> {
+    let mut header = template.to_header();
+    header.nonce = FixedBytes::from(nonce.to_be_bytes());
+    header.mix_hash = result.mix_hash;
+    header.withdrawals_root = Some(B256::ZERO);
+    header.parent_beacon_block_root = Some(B256::ZERO);
+    header.blob_gas_used = Some(0);
+    header.excess_blob_gas = Some(0);
+    header.requests_hash = Some(B256::ZERO);
+    SealedHeader::seal_slow(header)
+}
+
+#[test]
+fn test_mine_and_validate_block() {
+    let consensus = PermiaPoWConsensus::new(PERMIA_DEV.clone());
+
+    let mut template = BlockTemplate::new(
+        B256::repeat_byte(1),
+        1,
+        current_timestamp(),
+        Address::repeat_byte(2),
+        U256::from(100u64),
+    );
+    template.gas_used = 21_000;
+
+    let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+    let header = seal(&template, result.nonce, &result);
+
+    consensus.validate_header(&header).unwrap();
+
+    // Any other nonce breaks the mix digest
+    let corrupted = seal(&template, result.nonce.wrapping_add(1), &result);
+    let err = consensus.validate_header(&corrupted).unwrap_err();
+    assert!(matches!(permia_error(&err), Some(PermiaConsensusError::InvalidProofOfWork)));
+
+    // So does changing the sealed contents after mining
+    template.gas_used += 1;
+    assert!(consensus.validate_header(&seal(&template, result.nonce, &result)).is_err());
+}
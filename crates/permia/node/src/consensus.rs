@@ -2,9 +2,18 @@
 //!
 //! Provides integration between Permia consensus and Reth's node builder.
 
+use alloy_primitives::U256;
 use permia_consensus::{PermiaConsensus, PermiaPoWConsensus};
+use reth_chain_state::CanonStateSubscriptions;
 use reth_chainspec::ChainSpec;
-use std::sync::Arc;
+use reth_ethereum_primitives::EthPrimitives;
+use reth_node_api::{FullNodeTypes, NodeTypes};
+use reth_node_builder::{components::ConsensusBuilder, BuilderContext};
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio_stream::StreamExt;
 
 /// Builder for Permia consensus.
 ///
@@ -12,22 +21,71 @@ use std::sync::Arc;
 /// for block validation.
 #[derive(Debug, Default, Clone, Copy)]
 #[non_exhaustive]
-pub struct PermiaConsensusBuilder;
+pub struct PermiaConsensusBuilder {
+    /// Difficulty for every block, for regtest mode
+    fixed_difficulty: Option<U256>,
+}
 
 impl PermiaConsensusBuilder {
     /// Create a new Permia consensus builder
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+    
+    /// Disable difficulty adjustment, using `difficulty` for every block
+    pub fn with_fixed_difficulty(mut self, difficulty: U256) -> Self {
+        self.fixed_difficulty = Some(difficulty);
+        self
     }
     
     /// Build the standalone Permia consensus instance
     pub fn build_standalone(self) -> Arc<PermiaConsensus> {
-        Arc::new(PermiaConsensus::new())
+        let consensus = PermiaConsensus::new();
+        Arc::new(match self.fixed_difficulty {
+            Some(difficulty) => consensus.with_fixed_difficulty(difficulty),
+            None => consensus,
+        })
     }
     
     /// Build the Permia PoW consensus with chain spec
     pub fn build_with_chain_spec(self, chain_spec: Arc<ChainSpec>) -> Arc<PermiaPoWConsensus> {
-        Arc::new(PermiaPoWConsensus::new(chain_spec))
+        let consensus = PermiaPoWConsensus::new(chain_spec);
+        Arc::new(match self.fixed_difficulty {
+            Some(difficulty) => consensus.with_fixed_difficulty(difficulty),
+            None => consensus,
+        })
+    }
+}
+
+impl<Types, Node> ConsensusBuilder<Node> for PermiaConsensusBuilder
+where
+    Types: NodeTypes<ChainSpec = ChainSpec, Primitives = EthPrimitives>,
+    Node: FullNodeTypes<Types = Types>,
+{
+    type Consensus = Arc<PermiaPoWConsensus>;
+
+    async fn build_consensus(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::Consensus> {
+        Ok(self.build_with_chain_spec(ctx.chain_spec()))
+    }
+}
+
+/// Record the import latency of the canonical chain in `consensus` until the provider's
+/// canonical state notifications end
+///
+/// Each new canonical head is recorded at the time its notification arrives, see
+/// [`PermiaPoWConsensus::record_canonical_import`]. Block times are recorded by the
+/// consensus itself, from header timestamps.
+pub async fn record_import_latency<P>(provider: P, consensus: Arc<PermiaPoWConsensus>)
+where
+    P: CanonStateSubscriptions<Primitives = EthPrimitives>,
+{
+    let mut canonical = provider.canonical_state_stream();
+    while let Some(notification) = canonical.next().await {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        consensus.record_canonical_import(notification.tip().header().number, now_ms);
     }
 }
 
@@ -49,4 +107,13 @@ mod tests {
         let consensus = builder.build_with_chain_spec(PERMIA_DEV.clone());
         assert_eq!(consensus.chain_spec().chain.id(), 42071);
     }
+    
+    #[test]
+    fn test_build_regtest() {
+        let difficulty = alloy_primitives::U256::from(16u64);
+        let builder = PermiaConsensusBuilder::new().with_fixed_difficulty(difficulty);
+        assert_eq!(builder.build_standalone().fixed_difficulty(), Some(difficulty));
+        assert_eq!(builder.build_with_chain_spec(PERMIA_DEV.clone()).min_difficulty(), difficulty);
+        assert_eq!(PermiaConsensusBuilder::new().build_standalone().fixed_difficulty(), None);
+    }
 }
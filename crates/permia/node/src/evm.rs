@@ -0,0 +1,276 @@
+//! Block execution with the Permia block reward
+//!
+//! [`PermiaExecutorBuilder`] installs [`PermiaEvmConfig`], the Ethereum EVM config with a
+//! block executor that credits the block reward after the block's transactions: the
+//! [`PermiaPoWConsensus::base_block_reward_credits`] of the block, the miner's share to
+//! the beneficiary and the treasury's share to the chain's treasury.
+//!
+//! Blocks built locally and blocks imported from peers are executed the same way, so the
+//! state root of a valid block commits to exactly this reward.
+//!
+//! Blocks don't carry service proofs, so every block earns the base reward. The service
+//! multiplier, proof deduplication, caps and replay protection only apply to off-chain
+//! reward estimates, see [`PermiaPoWConsensus::block_reward_credits`].
+
+use alloy_consensus::Header;
+use alloy_evm::{
+    block::{
+        state_changes::balance_increment_state, BlockExecutionResult, BlockExecutorFactory,
+        BlockExecutorFor, ExecutableTx, StateChangePostBlockSource, StateChangeSource,
+    },
+    eth::{EthBlockExecutionCtx, EthBlockExecutor},
+    precompiles::PrecompilesMap,
+    revm::context::{result::ResultAndState, Block as _},
+    EthEvm, EthEvmFactory,
+};
+use alloy_primitives::{map::HashMap, Address};
+use alloy_rpc_types_engine::ExecutionData;
+use parking_lot::Mutex;
+use permia_consensus::PermiaPoWConsensus;
+use reth_chainspec::ChainSpec;
+use reth_ethereum_primitives::{Block, EthPrimitives, Receipt, TransactionSigned};
+use reth_evm::{
+    execute::{BlockExecutionError, BlockExecutor, BlockValidationError},
+    ConfigureEngineEvm, ConfigureEvm, Database, Evm, EvmEnv, EvmEnvFor, ExecutableTxIterator,
+    ExecutionCtxFor, InspectorFor, NextBlockEnvAttributes, OnStateHook,
+};
+use reth_evm_ethereum::{EthBlockAssembler, EthEvmConfig, RethReceiptBuilder};
+use reth_node_api::{FullNodeTypes, NodeTypes};
+use reth_node_builder::{components::ExecutorBuilder, BuilderContext};
+use reth_primitives_traits::{SealedBlock, SealedHeader};
+use reth_revm::{context::TxEnv, db::State, primitives::hardfork::SpecId, state::EvmState};
+use std::sync::Arc;
+
+/// Builds the [`PermiaEvmConfig`] of a node
+#[derive(Debug, Default, Clone, Copy)]
+#[non_exhaustive]
+pub struct PermiaExecutorBuilder;
+
+impl<Types, Node> ExecutorBuilder<Node> for PermiaExecutorBuilder
+where
+    Types: NodeTypes<ChainSpec = ChainSpec, Primitives = EthPrimitives>,
+    Node: FullNodeTypes<Types = Types>,
+{
+    type EVM = PermiaEvmConfig;
+
+    async fn build_evm(self, ctx: &BuilderContext<Node>) -> eyre::Result<Self::EVM> {
+        Ok(PermiaEvmConfig::new(ctx.chain_spec()))
+    }
+}
+
+/// Ethereum EVM config whose block executor credits the Permia block reward
+#[derive(Debug, Clone)]
+pub struct PermiaEvmConfig {
+    /// Ethereum EVM config everything but the reward is delegated to
+    inner: EthEvmConfig,
+    /// Consensus the block reward is computed with
+    consensus: Arc<PermiaPoWConsensus>,
+}
+
+impl PermiaEvmConfig {
+    /// Create the EVM config of a chain
+    pub fn new(chain_spec: Arc<ChainSpec>) -> Self {
+        Self {
+            inner: EthEvmConfig::new(chain_spec.clone()),
+            consensus: Arc::new(PermiaPoWConsensus::new(chain_spec)),
+        }
+    }
+
+    /// Get the consensus the block reward is computed with
+    pub fn consensus(&self) -> &PermiaPoWConsensus {
+        &self.consensus
+    }
+}
+
+impl BlockExecutorFactory for PermiaEvmConfig {
+    type EvmFactory = EthEvmFactory;
+    type ExecutionCtx<'a> = EthBlockExecutionCtx<'a>;
+    type Transaction = TransactionSigned;
+    type Receipt = Receipt;
+
+    fn evm_factory(&self) -> &Self::EvmFactory {
+        self.inner.evm_factory()
+    }
+
+    fn create_executor<'a, DB, I>(
+        &'a self,
+        evm: EthEvm<&'a mut State<DB>, I, PrecompilesMap>,
+        ctx: EthBlockExecutionCtx<'a>,
+    ) -> impl BlockExecutorFor<'a, Self, DB, I>
+    where
+        DB: Database + 'a,
+        I: InspectorFor<Self, &'a mut State<DB>> + 'a,
+    {
+        PermiaBlockExecutor {
+            inner: EthBlockExecutor::new(
+                evm,
+                ctx,
+                self.inner.chain_spec(),
+                self.inner.executor_factory.receipt_builder(),
+            ),
+            consensus: &self.consensus,
+            state_hook: None,
+        }
+    }
+}
+
+impl ConfigureEvm for PermiaEvmConfig {
+    type Primitives = <EthEvmConfig as ConfigureEvm>::Primitives;
+    type Error = <EthEvmConfig as ConfigureEvm>::Error;
+    type NextBlockEnvCtx = <EthEvmConfig as ConfigureEvm>::NextBlockEnvCtx;
+    type BlockExecutorFactory = Self;
+    type BlockAssembler = EthBlockAssembler<ChainSpec>;
+
+    fn block_executor_factory(&self) -> &Self::BlockExecutorFactory {
+        self
+    }
+
+    fn block_assembler(&self) -> &Self::BlockAssembler {
+        self.inner.block_assembler()
+    }
+
+    fn evm_env(&self, header: &Header) -> Result<EvmEnv<SpecId>, Self::Error> {
+        self.inner.evm_env(header)
+    }
+
+    fn next_evm_env(
+        &self,
+        parent: &Header,
+        attributes: &NextBlockEnvAttributes,
+    ) -> Result<EvmEnv<SpecId>, Self::Error> {
+        self.inner.next_evm_env(parent, attributes)
+    }
+
+    fn context_for_block<'a>(
+        &self,
+        block: &'a SealedBlock<Block>,
+    ) -> Result<EthBlockExecutionCtx<'a>, Self::Error> {
+        self.inner.context_for_block(block)
+    }
+
+    fn context_for_next_block(
+        &self,
+        parent: &SealedHeader,
+        attributes: Self::NextBlockEnvCtx,
+    ) -> Result<EthBlockExecutionCtx<'_>, Self::Error> {
+        self.inner.context_for_next_block(parent, attributes)
+    }
+}
+
+impl ConfigureEngineEvm<ExecutionData> for PermiaEvmConfig {
+    fn evm_env_for_payload(&self, payload: &ExecutionData) -> Result<EvmEnvFor<Self>, Self::Error> {
+        self.inner.evm_env_for_payload(payload)
+    }
+
+    fn context_for_payload<'a>(
+        &self,
+        payload: &'a ExecutionData,
+    ) -> Result<ExecutionCtxFor<'a, Self>, Self::Error> {
+        self.inner.context_for_payload(payload)
+    }
+
+    fn tx_iterator_for_payload(
+        &self,
+        payload: &ExecutionData,
+    ) -> Result<impl ExecutableTxIterator<Self>, Self::Error> {
+        self.inner.tx_iterator_for_payload(payload)
+    }
+}
+
+/// State hook shared with the inner executor
+type SharedStateHook = Arc<Mutex<Box<dyn OnStateHook>>>;
+
+/// Ethereum block executor that credits the block reward before finishing the block
+pub struct PermiaBlockExecutor<'a, Evm> {
+    /// Ethereum block executor
+    inner: EthBlockExecutor<'a, Evm, &'a Arc<ChainSpec>, &'a RethReceiptBuilder>,
+    /// Consensus the block reward is computed with
+    consensus: &'a PermiaPoWConsensus,
+    /// Hook the reward credit is reported to, like the inner executor's state changes
+    state_hook: Option<SharedStateHook>,
+}
+
+impl<'db, DB, E> PermiaBlockExecutor<'_, E>
+where
+    DB: Database + 'db,
+    E: Evm<DB = &'db mut State<DB>, Tx = TxEnv>,
+{
+    /// Credit the block reward to the beneficiary and the treasury
+    fn credit_block_reward(&mut self) -> Result<(), BlockExecutionError> {
+        let block = self.inner.evm().block();
+        let beneficiary = block.beneficiary();
+        let (miner, treasury) =
+            self.consensus.base_block_reward_credits(block.number().saturating_to());
+
+        let mut credits = HashMap::<Address, u128>::default();
+        *credits.entry(beneficiary).or_default() += miner;
+        if let Some(address) = self.consensus.treasury() {
+            *credits.entry(address).or_default() += treasury;
+        }
+
+        let db = self.inner.evm_mut().db_mut();
+        db.increment_balances(credits.clone())
+            .map_err(|_| BlockValidationError::IncrementBalanceFailed)?;
+        if let Some(hook) = &self.state_hook {
+            let state = balance_increment_state(&credits, db)?;
+            let source = StateChangeSource::PostBlock(StateChangePostBlockSource::BalanceIncrements);
+            hook.lock().on_state(source, &state);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'db, DB, E> BlockExecutor for PermiaBlockExecutor<'_, E>
+where
+    DB: Database + 'db,
+    E: Evm<DB = &'db mut State<DB>, Tx = TxEnv>,
+{
+    type Transaction = TransactionSigned;
+    type Receipt = Receipt;
+    type Evm = E;
+
+    fn apply_pre_execution_changes(&mut self) -> Result<(), BlockExecutionError> {
+        self.inner.apply_pre_execution_changes()
+    }
+
+    fn execute_transaction_without_commit(
+        &mut self,
+        tx: impl ExecutableTx<Self>,
+    ) -> Result<ResultAndState<<Self::Evm as Evm>::HaltReason>, BlockExecutionError> {
+        self.inner.execute_transaction_without_commit(tx)
+    }
+
+    fn commit_transaction(
+        &mut self,
+        output: ResultAndState<<Self::Evm as Evm>::HaltReason>,
+        tx: impl ExecutableTx<Self>,
+    ) -> Result<u64, BlockExecutionError> {
+        self.inner.commit_transaction(output, tx)
+    }
+
+    fn finish(mut self) -> Result<(Self::Evm, BlockExecutionResult<Receipt>), BlockExecutionError> {
+        self.credit_block_reward()?;
+        self.inner.finish()
+    }
+
+    fn set_state_hook(&mut self, hook: Option<Box<dyn OnStateHook>>) {
+        // The engine computes the state root from the reported changes, so the reward
+        // credit must be reported along with the changes of the inner executor
+        let hook = hook.map(|hook| Arc::new(Mutex::new(hook)));
+        self.inner.set_state_hook(hook.clone().map(|hook| {
+            Box::new(move |source: StateChangeSource, state: &EvmState| {
+                hook.lock().on_state(source, state)
+            }) as Box<dyn OnStateHook>
+        }));
+        self.state_hook = hook;
+    }
+
+    fn evm_mut(&mut self) -> &mut Self::Evm {
+        self.inner.evm_mut()
+    }
+
+    fn evm(&self) -> &Self::Evm {
+        self.inner.evm()
+    }
+}
@@ -0,0 +1,217 @@
+//! Finality-aware fork choice
+//!
+//! Reth picks the heaviest chain, but blocks finalized by the BFT layer (2/3+ votes) or
+//! by depth must never be reverted. [`FinalityGuard`] consults the shared
+//! [`FinalityTracker`](permia_finality::FinalityTracker) and rejects any fork choice
+//! update or reorg that would revert a finalized block. It guards the P2P importer's
+//! [`HeaviestChainRule`](permia_gossip::HeaviestChainRule) as a [`ReorgGuard`].
+
+use alloy_primitives::B256;
+use permia_finality::{SharedFinalityTracker, SharedValidatorSet};
+use permia_gossip::{PermiaGossipError, ReorgGuard};
+use thiserror::Error;
+use tracing::warn;
+
+/// Fork choice errors
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ForkChoiceError {
+    /// The update would revert a finalized block
+    #[error("Fork choice would revert finalized block {0}")]
+    RevertsFinalized(B256),
+
+    /// The fork point is older than the tracked chain, below the finalized block
+    #[error("Fork point {ancestor} is below finalized block {finalized}")]
+    BelowFinalized {
+        /// Common ancestor of the old and new head
+        ancestor: B256,
+        /// Latest finalized block
+        finalized: B256,
+    },
+}
+
+/// Guards fork choice against reverting finalized blocks
+#[derive(Debug, Clone)]
+pub struct FinalityGuard {
+    tracker: SharedFinalityTracker,
+    validators: SharedValidatorSet,
+}
+
+impl FinalityGuard {
+    /// Create a guard over the shared finality tracker and validator set
+    pub fn new(tracker: SharedFinalityTracker, validators: SharedValidatorSet) -> Self {
+        Self { tracker, validators }
+    }
+
+    /// Get the latest finalized block
+    pub fn latest_finalized(&self) -> Option<B256> {
+        self.tracker.read().latest_finalized(&self.validators.read())
+    }
+
+    /// Check that reverting the given canonical blocks keeps every finalized block
+    pub fn check_reorg(&self, reverted: &[B256]) -> Result<(), ForkChoiceError> {
+        let tracker = self.tracker.read();
+        let validators = self.validators.read();
+
+        match reverted.iter().find(|hash| tracker.is_final(hash, &validators)) {
+            Some(hash) => {
+                warn!(target: "permia::fork_choice", block = %hash, "Rejected reorg past finalized block");
+                Err(ForkChoiceError::RevertsFinalized(*hash))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Check a fork choice update that forks off the canonical chain at `common_ancestor`
+    ///
+    /// For a `forkchoiceUpdated` to `head`, `common_ancestor` is the last block shared by
+    /// `head` and the current canonical chain (`head` itself if it is canonical). Every
+    /// canonical block above it would be reverted.
+    pub fn check_fork_choice(&self, common_ancestor: B256) -> Result<(), ForkChoiceError> {
+        let reverted = self.tracker.read().descendants(&common_ancestor).map(<[B256]>::to_vec);
+
+        match reverted {
+            Some(reverted) => self.check_reorg(&reverted),
+            // Untracked fork points are older than the whole tracked chain
+            None => match self.latest_finalized() {
+                Some(finalized) => {
+                    warn!(
+                        target: "permia::fork_choice",
+                        ancestor = %common_ancestor,
+                        %finalized,
+                        "Rejected fork choice below finalized block"
+                    );
+                    Err(ForkChoiceError::BelowFinalized { ancestor: common_ancestor, finalized })
+                }
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+impl ReorgGuard for FinalityGuard {
+    fn check_reorg(&self, reverted: &[B256]) -> Result<(), PermiaGossipError> {
+        Self::check_reorg(self, reverted)
+            .map_err(|e| PermiaGossipError::ForkChoiceRejected(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::RwLock;
+    use permia_finality::{FinalityTracker, Validator, ValidatorSet, ValidatorSigner};
+    use std::sync::Arc;
+
+    /// Signer of test validator `i`
+    fn signer(i: u8) -> ValidatorSigner {
+        ValidatorSigner::from_hex(&B256::repeat_byte(i + 1).to_string()).unwrap()
+    }
+
+    fn guard() -> FinalityGuard {
+        let validators: Vec<_> = (0..100)
+            .map(|i| Validator::new(signer(i).address(), Validator::min_stake(), 10))
+            .collect();
+
+        FinalityGuard::new(
+            Arc::new(RwLock::new(FinalityTracker::new())),
+            Arc::new(RwLock::new(ValidatorSet::from_validators(validators, 1, 0))),
+        )
+    }
+
+    #[test]
+    fn test_reorg_past_depth_finalized_rejected() {
+        let guard = guard();
+        let blocks: Vec<_> = (0..6).map(|i| B256::repeat_byte(i)).collect();
+        for block in &blocks {
+            guard.tracker.write().add_block(*block);
+        }
+
+        // Blocks 4 and 5 are not final yet, reorging them is allowed
+        assert!(guard.check_reorg(&blocks[4..]).is_ok());
+        assert!(guard.check_fork_choice(blocks[3]).is_ok());
+
+        // Block 2 is 3 deep and final
+        assert_eq!(guard.check_reorg(&blocks[2..]), Err(ForkChoiceError::RevertsFinalized(blocks[2])));
+        assert_eq!(guard.check_fork_choice(blocks[1]), Err(ForkChoiceError::RevertsFinalized(blocks[2])));
+
+        // Forking off below the tracked chain is rejected too
+        assert!(matches!(
+            guard.check_fork_choice(B256::repeat_byte(0xaa)),
+            Err(ForkChoiceError::BelowFinalized { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reorg_past_bft_finalized_rejected() {
+        let guard = guard();
+        let parent = B256::repeat_byte(1);
+        let block = B256::repeat_byte(2);
+        guard.tracker.write().add_block(parent);
+        guard.tracker.write().add_block(block);
+
+        assert!(guard.check_fork_choice(parent).is_ok());
+
+        {
+            let validators = guard.validators.read();
+            let mut tracker = guard.tracker.write();
+            for i in 0..67u8 {
+                let vote = signer(i).sign_vote(block, 2).unwrap();
+                tracker.votes_mut().add_vote(vote, &validators).unwrap();
+            }
+        }
+
+        assert_eq!(guard.latest_finalized(), Some(block));
+        assert_eq!(guard.check_fork_choice(parent), Err(ForkChoiceError::RevertsFinalized(block)));
+
+        // Building on the finalized block is fine
+        assert!(guard.check_fork_choice(block).is_ok());
+    }
+
+    #[test]
+    fn test_heavier_fork_past_finalized_not_followed() {
+        use alloy_primitives::U256;
+        use permia_gossip::{ForkChoiceRule, HeaviestChainRule};
+        use reth_eth_wire::NewBlock;
+        use reth_primitives_traits::Header;
+        use reth_provider::test_utils::MockEthProvider;
+
+        fn child(parent: &Header, difficulty: u64, extra: u8) -> Header {
+            Header {
+                number: parent.number + 1,
+                parent_hash: parent.hash_slow(),
+                difficulty: U256::from(difficulty),
+                extra_data: vec![extra].into(),
+                ..Default::default()
+            }
+        }
+        fn new_block(header: &Header) -> NewBlock {
+            let header = header.clone();
+            let block = reth_ethereum_primitives::Block { header, body: Default::default() };
+            NewBlock { block, td: Default::default() }
+        }
+
+        // Canonical chain genesis <- a1 <- .. <- a4, a1 is final by depth
+        let guard = guard();
+        let provider = MockEthProvider::default();
+        let mut canonical = vec![Header::default()];
+        for _ in 0..4 {
+            canonical.push(child(canonical.last().unwrap(), 1, 0xa));
+        }
+        for header in &canonical {
+            provider.add_header(header.hash_slow(), header.clone());
+            guard.tracker.write().add_block(header.hash_slow());
+        }
+        let rule = HeaviestChainRule::new(provider).with_reorg_guard(guard);
+
+        // A heavier branch reverting a1 is refused
+        let deep = child(&canonical[0], 100, 0xb);
+        assert!(matches!(
+            rule.is_new_head(&new_block(&deep)),
+            Err(PermiaGossipError::ForkChoiceRejected(_))
+        ));
+
+        // A heavier branch reverting only a4 is followed
+        let shallow = child(&canonical[3], 100, 0xc);
+        assert!(rule.is_new_head(&new_block(&shallow)).unwrap());
+    }
+}
@@ -18,12 +18,32 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
 pub mod consensus;
+pub mod evm;
+pub mod fork_choice;
+pub mod mining;
 pub mod network;
 pub mod node;
+pub mod ordering;
+pub mod rpc;
 
-pub use consensus::PermiaConsensusBuilder;
+pub use consensus::{record_import_latency, PermiaConsensusBuilder};
+pub use evm::{PermiaBlockExecutor, PermiaEvmConfig, PermiaExecutorBuilder};
+pub use fork_choice::{FinalityGuard, ForkChoiceError};
+pub use mining::{
+    empty_block_state_root, mined_block, run_block_submitter, run_mining_driver,
+    spawn_mining_driver,
+};
 pub use network::{configure_permia_network, PermiaNetworkBuilder};
 pub use node::PermiaNode;
+pub use ordering::{
+    PermiaTransactionOrdering, PriorityPolicy, ServicePriorityConfig, ServiceProviderPriority,
+    ServiceProviders,
+};
+pub use rpc::{
+    PermiaGenesisApiServer, PermiaGenesisRpc, PermiaMiningApiServer, PermiaMiningRpc, PermiaStatus,
+    PermiaStatusApiServer, PermiaStatusRpc, PermiaValidatorApiServer, PermiaValidatorRpc,
+    ValidatorInfo,
+};
 pub use permia_consensus::{PermiaConsensus, PermiaConsensusError, PermiaPoWConsensus, BLOCK_TIME_MS};
 
 #[cfg(test)]
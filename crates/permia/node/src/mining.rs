@@ -0,0 +1,303 @@
+//! Block production by the node miner
+//!
+//! [`spawn_mining_driver`] keeps the node miner on the canonical tip: every new canonical
+//! head, whether mined locally or imported from a peer, starts an empty block on top of
+//! it. [`run_block_submitter`] completes the blocks the miner seals, including solutions
+//! submitted by external miners, and hands them to the
+//! [`PermiaP2PImporter`](permia_gossip::PermiaP2PImporter), which submits them to the
+//! engine like blocks received from peers.
+//!
+//! The state of an empty block differs from its parent's by the block reward, so its state
+//! root is computed by executing it, see [`empty_block_state_root`].
+//!
+//! Only empty blocks are mined: transactions in the pool are not included in mined blocks,
+//! and a sealed block with transactions can't be imported since only its header is kept.
+
+use alloy_consensus::{BlockHeader, Header, EMPTY_OMMER_ROOT_HASH, EMPTY_ROOT_HASH};
+use alloy_eips::{eip4895::Withdrawals, eip7685::EMPTY_REQUESTS_HASH};
+use alloy_primitives::{Address, B256};
+use permia_consensus::PermiaPoWConsensus;
+use permia_gossip::P2PBlockSender;
+use permia_miner::{next_timestamp, MinedBlock, NodeMinerHandle};
+use reth_chain_state::CanonStateSubscriptions;
+use reth_chainspec::{ChainSpec, ChainSpecProvider, EthChainSpec, EthereumHardforks};
+use reth_eth_wire::NewBlock;
+use reth_ethereum_primitives::{Block, BlockBody, EthPrimitives};
+use reth_evm::{execute::BlockBuilder, ConfigureEvm, NextBlockEnvAttributes};
+use reth_primitives_traits::SealedHeader;
+use reth_provider::{BlockReaderIdExt, HeaderProvider, StateProviderFactory};
+use reth_revm::{database::StateProviderDatabase, db::State};
+use std::{future::Future, sync::Arc};
+use tokio::sync::mpsc;
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, info, warn};
+
+/// State root of the empty block on top of `parent` that pays `beneficiary`
+///
+/// The block is built with `evm_config` like a payload without transactions, so the root
+/// includes the block reward credited during execution.
+pub fn empty_block_state_root<P, E>(
+    provider: &P,
+    evm_config: &E,
+    parent: &SealedHeader,
+    beneficiary: Address,
+) -> eyre::Result<B256>
+where
+    P: StateProviderFactory + ChainSpecProvider<ChainSpec = ChainSpec>,
+    E: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
+{
+    let chain_spec = provider.chain_spec();
+    let timestamp = next_timestamp(parent);
+    let state_provider = provider.state_by_block_hash(parent.hash())?;
+    let mut db = State::builder()
+        .with_database(StateProviderDatabase::new(state_provider.as_ref()))
+        .with_bundle_update()
+        .build();
+
+    let attributes = NextBlockEnvAttributes {
+        timestamp,
+        suggested_fee_recipient: beneficiary,
+        prev_randao: B256::ZERO,
+        gas_limit: parent.gas_limit,
+        parent_beacon_block_root: chain_spec
+            .is_cancun_active_at_timestamp(timestamp)
+            .then_some(B256::ZERO),
+        withdrawals: chain_spec
+            .is_shanghai_active_at_timestamp(timestamp)
+            .then(Withdrawals::default),
+        extra_data: Default::default(),
+    };
+    let mut builder = evm_config.builder_for_next_block(&mut db, parent, attributes)?;
+    builder.apply_pre_execution_changes()?;
+    let outcome = builder.finish(state_provider.as_ref())?;
+
+    Ok(outcome.block.header().state_root)
+}
+
+/// Mine an empty block on top of every header from `tips` until the stream ends
+///
+/// Each tip comes with the state root of the empty block on top of it. Blocks are mined
+/// at the difficulty `consensus`, the consensus the node validates blocks with, requires
+/// after the parent. Starting a block replaces the one being mined, so a new tip moves the
+/// miner onto it.
+pub async fn run_mining_driver<S>(
+    miner: NodeMinerHandle,
+    consensus: Arc<PermiaPoWConsensus>,
+    tips: S,
+) where
+    S: Stream<Item = (Header, B256)>,
+{
+    info!(target: "permia::mining", "Mining driver started");
+
+    let mut tips = std::pin::pin!(tips);
+    while let Some((parent, state_root)) = tips.next().await {
+        let difficulty = consensus.calculate_difficulty(&parent, next_timestamp(&parent));
+        debug!(
+            target: "permia::mining",
+            parent = parent.number,
+            %difficulty,
+            "Mining on new canonical tip"
+        );
+
+        let empty_root = EMPTY_ROOT_HASH;
+        let started = miner
+            .start_mining(parent, state_root, empty_root, Vec::new(), empty_root, difficulty, 0)
+            .await;
+        if started.is_err() {
+            warn!(target: "permia::mining", "Node miner stopped");
+            break;
+        }
+    }
+
+    info!(target: "permia::mining", "Mining driver stopped");
+}
+
+/// Spawn the mining driver, starting on the provider's latest block and following its
+/// canonical state notifications
+///
+/// The state roots of the empty blocks are computed with the node's `evm_config`.
+pub fn spawn_mining_driver<P, E>(
+    provider: P,
+    evm_config: E,
+    miner: NodeMinerHandle,
+    consensus: Arc<PermiaPoWConsensus>,
+) -> impl Future<Output = ()>
+where
+    P: BlockReaderIdExt<Header = Header>
+        + CanonStateSubscriptions<Primitives = EthPrimitives>
+        + StateProviderFactory
+        + ChainSpecProvider<ChainSpec = ChainSpec>
+        + 'static,
+    E: ConfigureEvm<Primitives = EthPrimitives, NextBlockEnvCtx = NextBlockEnvAttributes>,
+{
+    // Subscribe before reading the tip, so no block is missed in between
+    let canonical = provider
+        .canonical_state_stream()
+        .map(|notification| notification.tip().clone_sealed_header());
+    let tip = provider.latest_header().unwrap_or_else(|e| {
+        warn!(target: "permia::mining", error = %e, "Failed to read the canonical tip");
+        None
+    });
+    let beneficiary = miner.beneficiary();
+    let tips = tokio_stream::iter(tip).chain(canonical).filter_map(move |parent| {
+        match empty_block_state_root(&provider, &evm_config, &parent, beneficiary) {
+            Ok(state_root) => Some((parent.unseal(), state_root)),
+            Err(e) => {
+                warn!(
+                    target: "permia::mining",
+                    parent = parent.number,
+                    error = %e,
+                    "Failed to compute the state root of the next block"
+                );
+                None
+            }
+        }
+    });
+    async move {
+        run_mining_driver(miner, consensus, tips).await;
+    }
+}
+
+/// Complete an empty block sealed by the node miner for import
+///
+/// The miner only sets the header fields covered by the seal hash. The rest, the empty
+/// ommers hash and the fields of the forks active at the block's timestamp, are filled
+/// in as the Ethereum block assembler would for an empty block; none of them change the
+/// proof of work.
+pub fn mined_block(chain_spec: &ChainSpec, parent: &Header, mined: &MinedBlock) -> Block {
+    let mut header = mined.template.to_sealed_header(mined.nonce, mined.mix_hash);
+    let timestamp = header.timestamp;
+    header.ommers_hash = EMPTY_OMMER_ROOT_HASH;
+
+    let withdrawals =
+        chain_spec.is_shanghai_active_at_timestamp(timestamp).then(Withdrawals::default);
+    header.withdrawals_root = withdrawals.as_ref().map(|_| EMPTY_ROOT_HASH);
+
+    if chain_spec.is_cancun_active_at_timestamp(timestamp) {
+        header.blob_gas_used = Some(0);
+        // The first block of the fork evaluates its parent's blob gas as zero
+        header.excess_blob_gas = if chain_spec.is_cancun_active_at_timestamp(parent.timestamp) {
+            parent.maybe_next_block_excess_blob_gas(chain_spec.blob_params_at_timestamp(timestamp))
+        } else {
+            Some(0)
+        };
+        // There is no beacon chain to commit to
+        header.parent_beacon_block_root = Some(B256::ZERO);
+    }
+
+    header.requests_hash =
+        chain_spec.is_prague_active_at_timestamp(timestamp).then_some(EMPTY_REQUESTS_HASH);
+
+    Block { header, body: BlockBody { transactions: Vec::new(), ommers: Vec::new(), withdrawals } }
+}
+
+/// Import the blocks sealed by the node miner until either channel closes
+///
+/// Each block is completed with [`mined_block`] and sent to the P2P importer, which
+/// submits it to the engine and makes it the head if it is on the heaviest chain.
+pub async fn run_block_submitter<P>(
+    provider: P,
+    chain_spec: Arc<ChainSpec>,
+    mut mined_rx: mpsc::Receiver<MinedBlock>,
+    import_tx: P2PBlockSender,
+) where
+    P: HeaderProvider<Header = Header>,
+{
+    info!(target: "permia::mining", "Block submitter started");
+
+    while let Some(mined) = mined_rx.recv().await {
+        // Only the header of a mined block is kept, its transactions can't be recovered
+        if mined.template.transactions_root != EMPTY_ROOT_HASH {
+            warn!(
+                target: "permia::mining",
+                block = mined.number,
+                hash = %mined.hash,
+                transactions_root = %mined.template.transactions_root,
+                "Dropping mined block with transactions, only empty blocks can be imported"
+            );
+            continue;
+        }
+
+        let parent = match provider.header(mined.parent_hash) {
+            Ok(Some(parent)) => parent,
+            Ok(None) => {
+                warn!(
+                    target: "permia::mining",
+                    block = mined.number,
+                    parent = %mined.parent_hash,
+                    "Parent of mined block not found"
+                );
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    target: "permia::mining",
+                    block = mined.number,
+                    error = %e,
+                    "Failed to read parent of mined block"
+                );
+                continue;
+            }
+        };
+
+        let block = mined_block(&chain_spec, &parent, &mined);
+        info!(
+            target: "permia::mining",
+            block = mined.number,
+            hash = %block.header.hash_slow(),
+            nonce = mined.nonce,
+            "Submitting mined block"
+        );
+
+        // The importer weighs branches itself, the total difficulty is only announced
+        if import_tx.send(NewBlock { block, td: Default::default() }).await.is_err() {
+            warn!(target: "permia::mining", "P2P importer stopped");
+            break;
+        }
+    }
+
+    info!(target: "permia::mining", "Block submitter stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+    use permia_gossip::p2p_block_channel;
+    use permia_miner::{spawn_node_miner, NodeMinerConfig};
+    use reth_chainspec::PERMIA_DEV;
+    use reth_provider::test_utils::MockEthProvider;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_mined_blocks_submitted_for_import() {
+        let genesis = PERMIA_DEV.genesis_header().clone();
+        let (miner, mined_rx) = spawn_node_miner(NodeMinerConfig::default().with_threads(1));
+        let consensus = Arc::new(
+            PermiaPoWConsensus::new(PERMIA_DEV.clone()).with_fixed_difficulty(U256::from(100u64)),
+        );
+        let tips = tokio_stream::iter([(genesis.clone(), genesis.state_root)]);
+        run_mining_driver(miner, consensus, tips).await;
+
+        let provider = MockEthProvider::default();
+        provider.add_header(genesis.hash_slow(), genesis.clone());
+        let (import_tx, mut import_rx) = p2p_block_channel(1);
+        tokio::spawn(run_block_submitter(provider, PERMIA_DEV.clone(), mined_rx, import_tx));
+
+        let imported =
+            tokio::time::timeout(Duration::from_secs(5), import_rx.recv()).await.unwrap().unwrap();
+        let header = &imported.block.header;
+        assert_eq!(header.number, 1);
+        assert_eq!(header.parent_hash, genesis.hash_slow());
+        assert_eq!(header.difficulty, U256::from(100u64));
+        permia_consensus::pow::verify_pow(header).unwrap();
+
+        // Every fork is active at genesis on the dev chain
+        assert_eq!(header.ommers_hash, EMPTY_OMMER_ROOT_HASH);
+        assert_eq!(header.withdrawals_root, Some(EMPTY_ROOT_HASH));
+        assert_eq!(imported.block.body.withdrawals, Some(Withdrawals::default()));
+        assert_eq!(header.blob_gas_used, Some(0));
+        assert_eq!(header.excess_blob_gas, Some(0));
+        assert_eq!(header.requests_hash, Some(EMPTY_REQUESTS_HASH));
+    }
+}
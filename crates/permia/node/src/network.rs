@@ -3,8 +3,9 @@
 //! This module provides the network configuration for Permia nodes,
 //! integrating PermiaPoWBlockImport for P2P block validation.
 
-use permia_gossip::PermiaPoWBlockImport;
-use reth_chainspec::Hardforks;
+use permia_consensus::pow::PermiaHashConfig;
+use permia_gossip::{apply_peer_bans, peer_ban_channel, P2PBlockSender, PermiaPoWBlockImport};
+use reth_chainspec::{EthChainSpec, Hardforks};
 use reth_eth_wire::EthNetworkPrimitives;
 use reth_ethereum_primitives::EthPrimitives;
 use reth_network::{NetworkConfigBuilder, NetworkHandle, NetworkManager, PeersInfo};
@@ -24,12 +25,35 @@ use std::fmt::Debug;
 ///
 /// This network builder sets up the P2P network to use `PermiaPoWBlockImport`
 /// for validating incoming block announcements using PermiaHash proof-of-work.
-#[derive(Debug, Default, Clone, Copy)]
-pub struct PermiaNetworkBuilder;
+#[derive(Debug, Default, Clone)]
+pub struct PermiaNetworkBuilder {
+    /// Channel valid P2P blocks are forwarded on for import
+    importer: Option<P2PBlockSender>,
+    /// PermiaHash parameters of the chain
+    hash_config: PermiaHashConfig,
+}
+
+impl PermiaNetworkBuilder {
+    /// Forward valid P2P blocks on the given channel for import into the local chain
+    ///
+    /// See [`PermiaP2PImporter`](permia_gossip::PermiaP2PImporter).
+    pub fn with_importer(mut self, import_tx: P2PBlockSender) -> Self {
+        self.importer = Some(import_tx);
+        self
+    }
+
+    /// Validate P2P blocks under the chain's PermiaHash parameters
+    ///
+    /// See [`permia_hash_config`](permia_consensus::permia_hash_config).
+    pub fn with_hash_config(mut self, config: PermiaHashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
+}
 
 impl<Node, Pool> NetworkBuilder<Node, Pool> for PermiaNetworkBuilder
 where
-    Node: FullNodeTypes<Types: NodeTypes<ChainSpec: Hardforks, Primitives = EthPrimitives>>,
+    Node: FullNodeTypes<Types: NodeTypes<ChainSpec: Hardforks + EthChainSpec, Primitives = EthPrimitives>>,
     Node::Provider: BlockReaderIdExt + Clone + Debug + Send + Sync + 'static,
     Pool: TransactionPool<Transaction: PoolTransaction<Consensus = reth_node_api::TxTy<Node::Types>>>
         + Unpin
@@ -46,9 +70,18 @@ where
         // Get the network config builder
         let network_config_builder = ctx.network_config_builder()?;
         
-        // Set up PermiaPoWBlockImport for P2P block validation
+        // Set up PermiaPoWBlockImport for P2P block validation, banning peers that
+        // repeatedly send invalid blocks
         let provider = ctx.provider().clone();
-        let block_import = Box::new(PermiaPoWBlockImport::new(provider));
+        let chain_id = ctx.chain_spec().chain().id();
+        let (ban_tx, ban_rx) = peer_ban_channel();
+        let mut block_import = PermiaPoWBlockImport::new(provider, chain_id)
+            .with_ban_sender(ban_tx)
+            .with_hash_config(self.hash_config);
+        if let Some(import_tx) = self.importer {
+            block_import = block_import.with_importer(import_tx);
+        }
+        let block_import = Box::new(block_import);
         
         // Configure for PoW mode:
         // - Enable block propagation via NewBlock messages
@@ -63,6 +96,7 @@ where
         // Start the network
         let network = NetworkManager::builder(network_config).await?;
         let handle = ctx.start_network(network, pool);
+        ctx.task_executor().spawn(Box::pin(apply_peer_bans(handle.clone(), ban_rx)));
         
         info!(
             target: "permia::network",
@@ -74,15 +108,18 @@ where
     }
 }
 
-/// Configure the network for Permia PoW block gossip (helper function)
+/// Configure the network for Permia PoW block gossip on the given chain (helper function)
 pub fn configure_permia_network<Provider>(
     builder: NetworkConfigBuilder<EthNetworkPrimitives>,
     provider: Provider,
+    chain_id: u64,
+    hash_config: PermiaHashConfig,
 ) -> NetworkConfigBuilder<EthNetworkPrimitives>
 where
     Provider: BlockReaderIdExt + Clone + Debug + Send + Sync + 'static,
 {
-    let block_import = Box::new(PermiaPoWBlockImport::new(provider));
+    let block_import =
+        Box::new(PermiaPoWBlockImport::new(provider, chain_id).with_hash_config(hash_config));
     builder.block_import(block_import)
 }
 
@@ -0,0 +1,212 @@
+//! Service provider transaction ordering
+//!
+//! Miners that recently produced valid service proofs are service providers. When
+//! enabled through [`ServicePriorityConfig`], their transactions get a priority bump on
+//! top of the coinbase tip, so they are picked first when building block templates.
+
+use alloy_primitives::Address;
+use parking_lot::RwLock;
+use permia_services::ServiceProofBundle;
+use reth_transaction_pool::{PoolTransaction, Priority, TransactionOrdering};
+use std::{collections::HashMap, fmt::Debug, marker::PhantomData, sync::Arc};
+
+/// Default priority bump for service providers (1 gwei per gas)
+pub const DEFAULT_SERVICE_PRIORITY_DELTA: u128 = 1_000_000_000;
+
+/// Service provider prioritization configuration
+#[derive(Debug, Clone)]
+pub struct ServicePriorityConfig {
+    /// Whether service provider transactions are prioritized (default: false)
+    pub enabled: bool,
+    /// Priority added to the tip per gas of service provider transactions
+    pub priority_delta: u128,
+}
+
+impl Default for ServicePriorityConfig {
+    fn default() -> Self {
+        Self { enabled: false, priority_delta: DEFAULT_SERVICE_PRIORITY_DELTA }
+    }
+}
+
+impl ServicePriorityConfig {
+    /// Enable or disable service provider prioritization
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Set the priority added to service provider transactions
+    pub fn with_priority_delta(mut self, delta: u128) -> Self {
+        self.priority_delta = delta;
+        self
+    }
+}
+
+/// Miners known to provide services, keyed to the last epoch they proved one
+#[derive(Debug, Clone, Default)]
+pub struct ServiceProviders {
+    inner: Arc<RwLock<HashMap<Address, u64>>>,
+}
+
+impl ServiceProviders {
+    /// Create an empty set of service providers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the miner of a bundle if it carries at least one valid proof
+    ///
+    /// Blocks don't carry service proofs, so bundles are fed from off-chain sources, such
+    /// as proofs received from peers, rather than from imported blocks.
+    pub fn record_bundle(&self, bundle: &ServiceProofBundle, current_epoch: u64) {
+        if bundle.verify_all(current_epoch).valid.is_empty() {
+            return;
+        }
+
+        self.inner.write().insert(bundle.miner, current_epoch);
+    }
+
+    /// Forget providers that have not proved a service since `min_epoch`
+    pub fn prune(&self, min_epoch: u64) {
+        self.inner.write().retain(|_, epoch| *epoch >= min_epoch);
+    }
+
+    /// Check whether an address is a known service provider
+    pub fn contains(&self, address: &Address) -> bool {
+        self.inner.read().contains_key(address)
+    }
+
+    /// Number of known service providers
+    pub fn len(&self) -> usize {
+        self.inner.read().len()
+    }
+
+    /// Check whether no service providers are known
+    pub fn is_empty(&self) -> bool {
+        self.inner.read().is_empty()
+    }
+}
+
+/// Policy deciding the extra priority of a transaction sender
+pub trait PriorityPolicy: Debug + Send + Sync + 'static {
+    /// Priority added to the tip per gas of transactions sent by `sender`
+    fn priority_delta(&self, sender: &Address) -> u128;
+}
+
+/// Default policy, bumping known service providers by a fixed delta
+#[derive(Debug, Clone)]
+pub struct ServiceProviderPriority {
+    providers: ServiceProviders,
+    delta: u128,
+}
+
+impl ServiceProviderPriority {
+    /// Create the policy, a disabled config applies no bump
+    pub fn new(providers: ServiceProviders, config: &ServicePriorityConfig) -> Self {
+        let delta = if config.enabled { config.priority_delta } else { 0 };
+        Self { providers, delta }
+    }
+}
+
+impl PriorityPolicy for ServiceProviderPriority {
+    fn priority_delta(&self, sender: &Address) -> u128 {
+        if self.delta > 0 && self.providers.contains(sender) {
+            self.delta
+        } else {
+            0
+        }
+    }
+}
+
+/// Orders transactions by coinbase tip plus the bump of a [`PriorityPolicy`]
+#[derive(Debug)]
+pub struct PermiaTransactionOrdering<T, P = ServiceProviderPriority> {
+    policy: P,
+    _transaction: PhantomData<T>,
+}
+
+impl<T, P> PermiaTransactionOrdering<T, P> {
+    /// Create an ordering with the given policy
+    pub fn new(policy: P) -> Self {
+        Self { policy, _transaction: PhantomData }
+    }
+}
+
+impl<T, P: Clone> Clone for PermiaTransactionOrdering<T, P> {
+    fn clone(&self) -> Self {
+        Self::new(self.policy.clone())
+    }
+}
+
+impl<T, P> TransactionOrdering for PermiaTransactionOrdering<T, P>
+where
+    T: PoolTransaction + 'static,
+    P: PriorityPolicy,
+{
+    type PriorityValue = u128;
+    type Transaction = T;
+
+    fn priority(&self, transaction: &Self::Transaction, base_fee: u64) -> Priority<Self::PriorityValue> {
+        transaction
+            .effective_tip_per_gas(base_fee)
+            .map(|tip| tip.saturating_add(self.policy.priority_delta(transaction.sender_ref())))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::B256;
+    use permia_services::ServiceProof;
+    use reth_transaction_pool::test_utils::MockTransaction;
+
+    fn transaction(sender: Address) -> MockTransaction {
+        MockTransaction::eip1559().with_sender(sender).with_max_fee(100).with_priority_fee(10)
+    }
+
+    fn providers_with(miner: Address) -> ServiceProviders {
+        let providers = ServiceProviders::new();
+        let storage = ServiceProof::new_storage(miner, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        providers.record_bundle(&ServiceProofBundle::with_proofs(1, miner, vec![storage]), 100);
+        providers
+    }
+
+    #[test]
+    fn test_service_provider_selected_first() {
+        let provider = Address::repeat_byte(1);
+        let config = ServicePriorityConfig::default().with_enabled(true);
+        let ordering =
+            PermiaTransactionOrdering::new(ServiceProviderPriority::new(providers_with(provider), &config));
+
+        let mut transactions = vec![transaction(Address::repeat_byte(2)), transaction(provider)];
+        transactions.sort_by_key(|tx| std::cmp::Reverse(ordering.priority(tx, 0)));
+
+        assert_eq!(*transactions[0].get_sender(), provider);
+        assert_eq!(ordering.priority(&transactions[0], 0), Priority::Value(10 + DEFAULT_SERVICE_PRIORITY_DELTA));
+        assert_eq!(ordering.priority(&transactions[1], 0), Priority::Value(10));
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let provider = Address::repeat_byte(1);
+        let policy = ServiceProviderPriority::new(providers_with(provider), &ServicePriorityConfig::default());
+        assert_eq!(policy.priority_delta(&provider), 0);
+    }
+
+    #[test]
+    fn test_only_valid_proofs_make_providers() {
+        let miner = Address::repeat_byte(1);
+        let providers = providers_with(miner);
+        assert!(providers.contains(&miner));
+
+        // Expired proofs don't count
+        let other = Address::repeat_byte(2);
+        let storage = ServiceProof::new_storage(other, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        providers.record_bundle(&ServiceProofBundle::with_proofs(2, other, vec![storage]), 200);
+        assert!(!providers.contains(&other));
+
+        providers.prune(101);
+        assert!(providers.is_empty());
+    }
+}
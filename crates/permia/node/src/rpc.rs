@@ -0,0 +1,507 @@
+//! Permia Mining and Validator RPC
+//!
+//! Exposes the node miner's current work to external miners (FPGA/GPU prototypes),
+//! mirroring Ethash's `eth_getWork`/`eth_submitWork`:
+//!
+//! - `permia_getWork()`: seal hash, target and number of the block being mined
+//! - `permia_submitWork(nonce, mix_hash, seal_hash)`: submit a solution, returns whether
+//!   it was accepted
+//!
+//! And the BFT validator set to operators and explorers, starting from the validators
+//! listed in the genesis:
+//!
+//! - `permia_getValidators()`: active validators, highest weight first
+//! - `permia_validatorCount()`: number of active validators
+//! - `permia_totalStake()`: total stake of active validators in wei
+//! - `permia_isValidator(address)`: whether the address is an active validator
+//! - `permia_validatorInfo(address)`: stake, score and rank of an active validator, or null
+//!
+//! And the chain's genesis to operators bootstrapping new nodes:
+//!
+//! - `permia_getGenesis()`: the genesis the node was started with
+//!
+//! And a one-call summary of the node for dashboards:
+//!
+//! - `permia_status()`: head, difficulty, hashrate, block times, import latency, miner
+//!   state, finality and validators
+
+use alloy_genesis::Genesis;
+use alloy_primitives::{Address, B256, B64, U256};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, types::ErrorObjectOwned};
+use permia_consensus::{PermiaPoWConsensus, BLOCK_TIME_MS};
+use permia_finality::{SharedFinalityTracker, SharedValidatorSet, Validator};
+use permia_miner::{NodeMinerHandle, WorkPackage, WorkSolution};
+use reth_chainspec::ChainSpec;
+use reth_primitives_traits::AlloyBlockHeader;
+use reth_provider::{BlockNumReader, HeaderProvider, ProviderError};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Error code returned when the miner has no work to hand out
+pub const NO_WORK_ERROR_CODE: i32 = -32000;
+
+/// Error code returned when the node's database can't be read
+pub const PROVIDER_ERROR_CODE: i32 = -32001;
+
+/// Permia mining RPC API
+#[rpc(server, namespace = "permia")]
+pub trait PermiaMiningApi {
+    /// Returns the work package of the block currently being mined
+    #[method(name = "getWork")]
+    fn get_work(&self) -> RpcResult<WorkPackage>;
+
+    /// Submits a solution for the current work, returns whether it was accepted
+    #[method(name = "submitWork")]
+    fn submit_work(&self, nonce: B64, mix_hash: B256, seal_hash: B256) -> RpcResult<bool>;
+}
+
+/// Mining RPC backed by the node miner
+#[derive(Debug, Clone)]
+pub struct PermiaMiningRpc {
+    miner: NodeMinerHandle,
+}
+
+impl PermiaMiningRpc {
+    /// Create a new mining RPC for the given node miner
+    pub fn new(miner: NodeMinerHandle) -> Self {
+        Self { miner }
+    }
+}
+
+impl PermiaMiningApiServer for PermiaMiningRpc {
+    fn get_work(&self) -> RpcResult<WorkPackage> {
+        self.miner.work().ok_or_else(|| {
+            ErrorObjectOwned::owned(NO_WORK_ERROR_CODE, "no mining work available", None::<()>)
+        })
+    }
+
+    fn submit_work(&self, nonce: B64, mix_hash: B256, seal_hash: B256) -> RpcResult<bool> {
+        let solution = WorkSolution { nonce: u64::from_be_bytes(nonce.0), mix_hash, seal_hash };
+
+        match self.miner.submit_work(solution) {
+            Ok(block) => {
+                info!(
+                    target: "permia::rpc",
+                    block = block.number,
+                    nonce = block.nonce,
+                    hash = %block.hash,
+                    "Accepted externally mined block"
+                );
+                Ok(true)
+            }
+            Err(e) => {
+                debug!(target: "permia::rpc", %seal_hash, error = %e, "Rejected submitted work");
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Permia validator set RPC API
+#[rpc(server, namespace = "permia")]
+pub trait PermiaValidatorApi {
+    /// Returns the active validators, highest weight first
+    #[method(name = "getValidators")]
+    fn get_validators(&self) -> RpcResult<Vec<Validator>>;
+
+    /// Returns the number of active validators
+    #[method(name = "validatorCount")]
+    fn validator_count(&self) -> RpcResult<usize>;
+
+    /// Returns the total stake of active validators in wei
+    #[method(name = "totalStake")]
+    fn total_stake(&self) -> RpcResult<U256>;
+
+    /// Returns whether the address is an active validator
+    #[method(name = "isValidator")]
+    fn is_validator(&self, address: Address) -> RpcResult<bool>;
+
+    /// Returns an active validator's stake, score and rank, or null for other addresses
+    #[method(name = "validatorInfo")]
+    fn validator_info(&self, address: Address) -> RpcResult<Option<ValidatorInfo>>;
+}
+
+/// An active validator and its position in the set
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorInfo {
+    /// Validator address
+    pub address: Address,
+    /// Staked amount in wei
+    pub stake: U256,
+    /// Service score
+    pub service_score: u64,
+    /// Combined weight of stake and service score
+    pub weight: U256,
+    /// Rank in the active set, 0 being the highest weight
+    pub rank: usize,
+}
+
+/// Validator RPC backed by the shared validator set
+#[derive(Debug, Clone)]
+pub struct PermiaValidatorRpc {
+    validators: SharedValidatorSet,
+}
+
+impl PermiaValidatorRpc {
+    /// Create a new validator RPC reading from the given validator set
+    pub fn new(validators: SharedValidatorSet) -> Self {
+        Self { validators }
+    }
+}
+
+impl PermiaValidatorApiServer for PermiaValidatorRpc {
+    fn get_validators(&self) -> RpcResult<Vec<Validator>> {
+        Ok(self.validators.read().active_validators().into_iter().cloned().collect())
+    }
+
+    fn validator_count(&self) -> RpcResult<usize> {
+        Ok(self.validators.read().len())
+    }
+
+    fn total_stake(&self) -> RpcResult<U256> {
+        Ok(self.validators.read().total_stake())
+    }
+
+    fn is_validator(&self, address: Address) -> RpcResult<bool> {
+        Ok(self.validators.read().is_validator(&address))
+    }
+
+    fn validator_info(&self, address: Address) -> RpcResult<Option<ValidatorInfo>> {
+        let set = self.validators.read();
+        let info = set.rank_of(&address).zip(set.get(&address)).map(|(rank, validator)| ValidatorInfo {
+            address,
+            stake: validator.stake,
+            service_score: validator.service_score,
+            weight: validator.weight,
+            rank,
+        });
+        Ok(info)
+    }
+}
+
+/// Permia genesis RPC API
+#[rpc(server, namespace = "permia")]
+pub trait PermiaGenesisApi {
+    /// Returns the genesis of the chain
+    #[method(name = "getGenesis")]
+    fn get_genesis(&self) -> RpcResult<Genesis>;
+}
+
+/// Genesis RPC backed by the node's chain spec
+#[derive(Debug, Clone)]
+pub struct PermiaGenesisRpc {
+    chain_spec: Arc<ChainSpec>,
+}
+
+impl PermiaGenesisRpc {
+    /// Create a new genesis RPC for the given chain spec
+    pub fn new(chain_spec: Arc<ChainSpec>) -> Self {
+        Self { chain_spec }
+    }
+}
+
+impl PermiaGenesisApiServer for PermiaGenesisRpc {
+    fn get_genesis(&self) -> RpcResult<Genesis> {
+        Ok(self.chain_spec.genesis.clone())
+    }
+}
+
+/// Summary of the node's view of the chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermiaStatus {
+    /// Number of the canonical head
+    pub head_number: u64,
+    /// Hash of the canonical head
+    pub head_hash: B256,
+    /// Difficulty of the canonical head
+    pub difficulty: U256,
+    /// Network hashrate (H/s) estimated from the head difficulty and target block time
+    pub hashrate: U256,
+    /// Median time between recent blocks and their parent in milliseconds, from header
+    /// timestamps (whole seconds), if any were validated
+    pub median_block_time_ms: Option<u64>,
+    /// 95th percentile time between recent blocks and their parent in milliseconds
+    pub p95_block_time_ms: Option<u64>,
+    /// Median time between canonical head updates on this node in milliseconds
+    ///
+    /// Import latency, not block production: it reflects import, sync and restart timing.
+    pub median_import_latency_ms: Option<u64>,
+    /// 95th percentile time between canonical head updates on this node in milliseconds
+    pub p95_import_latency_ms: Option<u64>,
+    /// Whether the node miner is running
+    pub mining: bool,
+    /// Whether the node miner is paused
+    pub mining_paused: bool,
+    /// Number of the latest finalized block, if any
+    pub finalized_number: Option<u64>,
+    /// Hash of the latest finalized block, if any
+    pub finalized_hash: Option<B256>,
+    /// Number of active validators
+    pub validator_count: usize,
+}
+
+/// Permia node status RPC API
+#[rpc(server, namespace = "permia")]
+pub trait PermiaStatusApi {
+    /// Returns a summary of the node's chain, miner and finality state
+    #[method(name = "status")]
+    fn status(&self) -> RpcResult<PermiaStatus>;
+}
+
+/// Status RPC aggregating the provider, node miner and finality tracker
+#[derive(Debug, Clone)]
+pub struct PermiaStatusRpc<P> {
+    provider: P,
+    miner: Option<NodeMinerHandle>,
+    tracker: SharedFinalityTracker,
+    validators: SharedValidatorSet,
+    target_block_time_ms: u64,
+    consensus: Option<Arc<PermiaPoWConsensus>>,
+}
+
+impl<P> PermiaStatusRpc<P> {
+    /// Create a new status RPC for a node without a miner
+    pub fn new(provider: P, tracker: SharedFinalityTracker, validators: SharedValidatorSet) -> Self {
+        Self {
+            provider,
+            miner: None,
+            tracker,
+            validators,
+            target_block_time_ms: BLOCK_TIME_MS,
+            consensus: None,
+        }
+    }
+
+    /// Report the block times and import latency recorded by `consensus`
+    pub fn with_consensus(mut self, consensus: Arc<PermiaPoWConsensus>) -> Self {
+        self.consensus = Some(consensus);
+        self
+    }
+
+    /// Report the state of the given node miner
+    pub fn with_miner(mut self, miner: NodeMinerHandle) -> Self {
+        self.miner = Some(miner);
+        self
+    }
+
+    /// Set the target block time the hashrate is estimated against
+    ///
+    /// Defaults to [`BLOCK_TIME_MS`].
+    pub fn with_target_block_time(mut self, target_block_time_ms: u64) -> Self {
+        self.target_block_time_ms = target_block_time_ms.max(1);
+        self
+    }
+}
+
+impl<P> PermiaStatusApiServer for PermiaStatusRpc<P>
+where
+    P: BlockNumReader + HeaderProvider + Send + Sync + 'static,
+{
+    fn status(&self) -> RpcResult<PermiaStatus> {
+        let head_number = self.provider.best_block_number().map_err(provider_error)?;
+        let head = self
+            .provider
+            .sealed_header(head_number)
+            .map_err(provider_error)?
+            .ok_or_else(|| provider_error(ProviderError::HeaderNotFound(head_number.into())))?;
+
+        let difficulty = head.difficulty();
+        // A block of difficulty `d` takes `d` hashes on average
+        let hashrate = difficulty.saturating_mul(U256::from(1_000)) / U256::from(self.target_block_time_ms);
+
+        let block_times = self.consensus.as_ref().map(|consensus| consensus.block_time_stats());
+        let import_latency =
+            self.consensus.as_ref().map(|consensus| consensus.import_latency_stats());
+        let tracker = self.tracker.read();
+        let validators = self.validators.read();
+
+        Ok(PermiaStatus {
+            head_number,
+            head_hash: head.hash(),
+            difficulty,
+            hashrate,
+            median_block_time_ms: block_times.as_ref().and_then(|stats| stats.median()),
+            p95_block_time_ms: block_times.as_ref().and_then(|stats| stats.p95()),
+            median_import_latency_ms: import_latency.as_ref().and_then(|stats| stats.median()),
+            p95_import_latency_ms: import_latency.as_ref().and_then(|stats| stats.p95()),
+            mining: self.miner.as_ref().is_some_and(|miner| miner.is_running()),
+            mining_paused: self.miner.as_ref().is_some_and(|miner| miner.is_paused()),
+            finalized_number: tracker.latest_finalized_number(),
+            finalized_hash: tracker.latest_finalized(&validators),
+            validator_count: validators.len(),
+        })
+    }
+}
+
+fn provider_error(error: ProviderError) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(PROVIDER_ERROR_CODE, error.to_string(), None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+    use jsonrpsee::core::EmptyServerParams;
+    use parking_lot::RwLock;
+    use permia_finality::{FinalityTracker, ValidatorSet};
+    use permia_miner::{BlockTemplate, MiningConfig, MiningWorker, NodeMiner, NodeMinerConfig};
+    use reth_primitives_traits::Header;
+    use reth_provider::test_utils::MockEthProvider;
+
+    #[tokio::test]
+    async fn test_get_and_submit_work() {
+        let (_miner, handle, mut mined_rx) = NodeMiner::new(NodeMinerConfig::default());
+        let rpc = PermiaMiningRpc::new(handle.clone());
+
+        // Nothing to mine yet
+        assert_eq!(rpc.get_work().unwrap_err().code(), NO_WORK_ERROR_CODE);
+
+        let template =
+            BlockTemplate::new(B256::repeat_byte(1), 7, 1_700_000_000, Address::ZERO, U256::from(100u64));
+        handle.work_slot().publish(template.clone());
+
+        // getWork is consistent with the current template
+        let work = rpc.get_work().unwrap();
+        assert_eq!(work.seal_hash, template.seal_hash());
+        assert_eq!(work.target, template.target());
+        assert_eq!(work.number, 7);
+        assert_eq!(rpc.get_work().unwrap(), work);
+
+        let result = MiningWorker::new(MiningConfig::single_thread()).mine(&template).unwrap();
+        let nonce = B64::from(result.nonce.to_be_bytes());
+
+        // A wrong mix hash is rejected and leaves the work in place
+        assert!(!rpc.submit_work(nonce, B256::repeat_byte(9), work.seal_hash).unwrap());
+
+        // The mined nonce is accepted and forwarded to the node miner's output
+        assert!(rpc.submit_work(nonce, result.mix_hash, work.seal_hash).unwrap());
+        let mined = mined_rx.recv().await.unwrap();
+        assert_eq!(mined.number, 7);
+        assert_eq!(mined.nonce, result.nonce);
+
+        // The work is consumed
+        assert!(!rpc.submit_work(nonce, result.mix_hash, work.seal_hash).unwrap());
+        assert!(rpc.get_work().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validator_rpc() {
+        let validators = [(1u8, 100u64), (2, 300), (3, 200)].map(|(byte, extra)| {
+            Validator::new(Address::repeat_byte(byte), Validator::min_stake() + U256::from(extra), 0)
+        });
+        let set = ValidatorSet::from_validators(validators.to_vec(), 1, 0);
+        let module = PermiaValidatorRpc::new(Arc::new(RwLock::new(set))).into_rpc();
+
+        let listed: Vec<Validator> =
+            module.call("permia_getValidators", EmptyServerParams::new()).await.unwrap();
+        let addresses: Vec<_> = listed.iter().map(|v| v.address).collect();
+        assert_eq!(addresses, [2, 3, 1].map(Address::repeat_byte));
+        assert!(listed.windows(2).all(|pair| pair[0].weight >= pair[1].weight));
+        assert_eq!(listed[0], validators[1]);
+
+        let count: usize = module.call("permia_validatorCount", EmptyServerParams::new()).await.unwrap();
+        assert_eq!(count, 3);
+
+        let total: U256 = module.call("permia_totalStake", EmptyServerParams::new()).await.unwrap();
+        assert_eq!(total, Validator::min_stake() * U256::from(3u64) + U256::from(600u64));
+    }
+
+    #[tokio::test]
+    async fn test_validator_lookup_rpc() {
+        let validators = [(1u8, 100u64), (2, 300), (3, 200)].map(|(byte, extra)| {
+            Validator::new(Address::repeat_byte(byte), Validator::min_stake() + U256::from(extra), 7)
+        });
+        let set = ValidatorSet::from_validators(validators.to_vec(), 1, 0);
+        let module = PermiaValidatorRpc::new(Arc::new(RwLock::new(set))).into_rpc();
+
+        let member = Address::repeat_byte(3);
+        let is_validator: bool = module.call("permia_isValidator", [member]).await.unwrap();
+        assert!(is_validator);
+        let info: Option<ValidatorInfo> = module.call("permia_validatorInfo", [member]).await.unwrap();
+        assert_eq!(
+            info,
+            Some(ValidatorInfo {
+                address: member,
+                stake: validators[2].stake,
+                service_score: 7,
+                weight: validators[2].weight,
+                rank: 1,
+            })
+        );
+
+        let stranger = Address::repeat_byte(9);
+        let is_validator: bool = module.call("permia_isValidator", [stranger]).await.unwrap();
+        assert!(!is_validator);
+        let info: Option<ValidatorInfo> = module.call("permia_validatorInfo", [stranger]).await.unwrap();
+        assert_eq!(info, None);
+    }
+
+    #[tokio::test]
+    async fn test_genesis_validators_rpc() {
+        // The node serves the validators listed in the dev genesis
+        let set = ValidatorSet::from_genesis(&reth_chainspec::PERMIA_DEV.genesis).unwrap();
+        let module = PermiaValidatorRpc::new(Arc::new(RwLock::new(set))).into_rpc();
+
+        let listed: Vec<Validator> =
+            module.call("permia_getValidators", EmptyServerParams::new()).await.unwrap();
+        let addresses: Vec<_> = listed.iter().map(|v| v.address).collect();
+        assert_eq!(addresses, [address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266")]);
+        assert_eq!(listed[0].stake, Validator::min_stake());
+    }
+
+    #[tokio::test]
+    async fn test_genesis_rpc() {
+        let module = PermiaGenesisRpc::new(reth_chainspec::PERMIA_DEV.clone()).into_rpc();
+
+        let genesis: Genesis = module.call("permia_getGenesis", EmptyServerParams::new()).await.unwrap();
+        assert_eq!(genesis.config.chain_id, 42071);
+        assert_eq!(genesis.difficulty, U256::from(0x100000));
+        assert_eq!(genesis, reth_chainspec::PERMIA_DEV.genesis);
+    }
+
+    #[tokio::test]
+    async fn test_status_rpc() {
+        let provider = MockEthProvider::default();
+        let tracker: SharedFinalityTracker = Arc::new(RwLock::new(FinalityTracker::new().with_implicit_finality_depth(3)));
+        let mut parent_hash = B256::ZERO;
+        for number in 0..=10u64 {
+            let header = Header {
+                parent_hash,
+                number,
+                difficulty: U256::from(1_000 + number),
+                ..Default::default()
+            };
+            parent_hash = header.hash_slow();
+            provider.add_header(parent_hash, header);
+            tracker.write().add_block_at(parent_hash, number);
+        }
+        let validators = Arc::new(RwLock::new(ValidatorSet::new(0, 0)));
+        let consensus = Arc::new(PermiaPoWConsensus::new(reth_chainspec::PERMIA_DEV.clone()));
+        for (number, at_ms) in [(9, 5_000), (10, 5_600)] {
+            consensus.record_canonical_import(number, at_ms);
+        }
+        let module = PermiaStatusRpc::new(provider, tracker, validators)
+            .with_target_block_time(2_000)
+            .with_consensus(consensus)
+            .into_rpc();
+
+        let status: PermiaStatus = module.call("permia_status", EmptyServerParams::new()).await.unwrap();
+        assert_eq!(status.head_number, 10);
+        assert_eq!(status.head_hash, parent_hash);
+        assert_eq!(status.difficulty, U256::from(1_010));
+        assert_eq!(status.hashrate, U256::from(505));
+        assert_eq!(status.median_import_latency_ms, Some(600));
+        assert_eq!(status.p95_import_latency_ms, Some(600));
+        // No header was validated against its parent
+        assert_eq!(status.median_block_time_ms, None);
+        assert!(!status.mining);
+
+        // Depth finality trails the head
+        assert_eq!(status.finalized_number, Some(7));
+        assert!(status.finalized_hash.is_some());
+        assert_eq!(status.validator_count, 0);
+    }
+}
@@ -23,8 +23,9 @@
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
 use permia_consensus::PermiaConsensus;
+use permia_miner::NodeMinerConfig;
 use reth_basic_payload_builder::{BuildArguments, BuildOutcome, MissingPayloadBehaviour, PayloadBuilder, PayloadConfig};
-use reth_chainspec::{ChainSpecProvider, EthereumHardforks};
+use reth_chainspec::{permia_block_time_ms, ChainSpec, ChainSpecProvider, EthereumHardforks};
 use reth_ethereum_payload_builder::{EthereumBuilderConfig, EthereumPayloadBuilder};
 use reth_ethereum_primitives::{EthPrimitives, TransactionSigned};
 use reth_evm::{ConfigureEvm, NextBlockEnvAttributes};
@@ -45,22 +46,32 @@ pub struct PermiaBuilderConfig {
     pub target_block_time_ms: u64,
     /// Whether to enable PoW mining (default: true)
     pub pow_enabled: bool,
-    /// Maximum mining iterations before giving up
-    pub max_mining_iterations: u64,
+    /// Maximum mining iterations before giving up on a block (default: no limit)
+    ///
+    /// A block given up on stays unmined until the next tip, so a cap below the chain's
+    /// difficulty stalls mining.
+    pub max_mining_iterations: Option<u64>,
 }
 
 impl Default for PermiaBuilderConfig {
     fn default() -> Self {
         Self {
             eth_config: EthereumBuilderConfig::default(),
-            target_block_time_ms: 400,
+            target_block_time_ms: permia_consensus::BLOCK_TIME_MS,
             pow_enabled: true,
-            max_mining_iterations: 1_000_000,
+            max_mining_iterations: None,
         }
     }
 }
 
 impl PermiaBuilderConfig {
+    /// Create config with the target block time and fixed gas limit of a chain
+    pub fn from_chain_spec(chain_spec: &ChainSpec) -> Self {
+        let mut config = Self::default().with_block_time(permia_block_time_ms(chain_spec));
+        config.eth_config = config.eth_config.with_gas_limit(chain_spec.genesis().gas_limit);
+        config
+    }
+
     /// Create new config with target block time
     pub fn with_block_time(mut self, ms: u64) -> Self {
         self.target_block_time_ms = ms;
@@ -72,6 +83,21 @@ impl PermiaBuilderConfig {
         self.pow_enabled = enabled;
         self
     }
+
+    /// Set the maximum mining iterations before giving up
+    pub fn with_max_mining_iterations(mut self, iterations: u64) -> Self {
+        self.max_mining_iterations = Some(iterations);
+        self
+    }
+
+    /// Node miner configuration sealing blocks at this config's block time and iteration cap
+    pub fn node_miner_config(&self) -> NodeMinerConfig {
+        let config = NodeMinerConfig::default().with_target_block_time(self.target_block_time_ms);
+        match self.max_mining_iterations {
+            Some(iterations) => config.with_max_mining_iterations(iterations),
+            None => config,
+        }
+    }
 }
 
 /// Permia payload builder with PermiaHash PoW
@@ -106,6 +132,13 @@ impl<Pool, Client, EvmConfig> PermiaPayloadBuilder<Pool, Client, EvmConfig> {
         }
     }
 
+    /// Use `consensus` for the difficulty of built blocks, e.g. one with the chain's
+    /// parameters or a fixed regtest difficulty
+    pub fn with_consensus(mut self, consensus: Arc<PermiaConsensus>) -> Self {
+        self.consensus = consensus;
+        self
+    }
+
     /// Get reference to the PermiaHash consensus
     pub fn consensus(&self) -> &Arc<PermiaConsensus> {
         &self.consensus
@@ -143,7 +176,7 @@ where
         //
         // TODO: Integrate PermiaHash mining into block sealing:
         // 1. Extract block header from outcome
-        // 2. Mine nonce using PermiaConsensus
+        // 2. Mine nonce as the node miner does with `self.config.node_miner_config()`
         // 3. Re-seal block with mined nonce and mix_hash
         //
         // This requires modifying the block header after construction,
@@ -197,4 +230,22 @@ mod tests {
         assert_eq!(config.target_block_time_ms, 1000);
         assert!(!config.pow_enabled);
     }
+
+    #[test]
+    fn test_config_from_chain_spec() {
+        let config = PermiaBuilderConfig::from_chain_spec(&reth_chainspec::PERMIA_DEV);
+        assert_eq!(config.target_block_time_ms, reth_chainspec::PERMIA_BLOCK_TIME_MS);
+        assert_eq!(config.eth_config.desired_gas_limit, reth_chainspec::PERMIA_DEV.genesis().gas_limit);
+    }
+
+    #[test]
+    fn test_node_miner_config_iteration_cap() {
+        let config = PermiaBuilderConfig::default();
+        assert_eq!(config.node_miner_config().max_mining_iterations, None);
+
+        let config = config.with_max_mining_iterations(500).with_block_time(1000);
+        let miner_config = config.node_miner_config();
+        assert_eq!(miner_config.max_mining_iterations, Some(500));
+        assert_eq!(miner_config.target_block_time_ms, 1000);
+    }
 }
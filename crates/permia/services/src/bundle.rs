@@ -0,0 +1,357 @@
+//! Service proof bundles attached to mined blocks
+//!
+//! A [`ServiceProofBundle`] collects the service proofs a miner includes in a block.
+//! It is the input to the reward multiplier, and its [`ServiceProofBundle::hash`] is a
+//! 32-byte commitment that fits in the header's `extra_data`.
+//!
+//! A block carries at most [`MAX_SERVICE_PROOFS_PER_BLOCK`] proofs, so miners can't
+//! inflate their reward or bloat the chain by stuffing blocks with proofs.
+
+use alloy_primitives::{keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{ServiceError, ServiceProof, ServiceProofData};
+
+/// Default maximum number of service proofs in a block
+///
+/// Chains may override it in their genesis, see `reth_chainspec::permia_max_service_proofs`.
+pub const MAX_SERVICE_PROOFS_PER_BLOCK: usize = 256;
+
+/// Service proofs included by a miner in a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceProofBundle {
+    /// Block the proofs are included in
+    pub block_number: u64,
+    /// Miner of the block
+    pub miner: Address,
+    /// Included service proofs
+    pub proofs: Vec<ServiceProof>,
+}
+
+/// Result of verifying every proof in a bundle
+#[derive(Debug, Default)]
+pub struct BundleVerification {
+    /// Indices of proofs that passed verification
+    pub valid: Vec<usize>,
+    /// Indices of proofs that failed verification, with the reason
+    pub rejected: Vec<(usize, ServiceError)>,
+    /// Aggregate service score of the valid proofs
+    pub service_score: u64,
+}
+
+impl BundleVerification {
+    /// Number of valid proofs
+    pub fn valid_count(&self) -> usize {
+        self.valid.len()
+    }
+
+    /// Whether every proof in the bundle is valid
+    pub fn all_valid(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+impl ServiceProofBundle {
+    /// Create an empty bundle
+    pub fn new(block_number: u64, miner: Address) -> Self {
+        Self { block_number, miner, proofs: Vec::new() }
+    }
+
+    /// Create a bundle with the given proofs
+    pub fn with_proofs(block_number: u64, miner: Address, proofs: Vec<ServiceProof>) -> Self {
+        Self { block_number, miner, proofs }
+    }
+
+    /// Add a proof to the bundle
+    pub fn push(&mut self, proof: ServiceProof) {
+        self.proofs.push(proof);
+    }
+
+    /// Add a proof unless the bundle already holds `max` proofs
+    ///
+    /// Block builders use this to stay within the chain's proof limit.
+    pub fn try_push(&mut self, proof: ServiceProof, max: usize) -> Result<(), ServiceError> {
+        if self.proofs.len() >= max {
+            return Err(ServiceError::TooManyProofs { count: self.proofs.len() + 1, max });
+        }
+        self.proofs.push(proof);
+        Ok(())
+    }
+
+    /// Check that the bundle holds at most `max` proofs
+    pub fn check_size(&self, max: usize) -> Result<(), ServiceError> {
+        if self.proofs.len() > max {
+            return Err(ServiceError::TooManyProofs { count: self.proofs.len(), max });
+        }
+        Ok(())
+    }
+
+    /// Number of proofs in the bundle
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Whether the bundle has no proofs
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Remove repeated proofs, keeping the first occurrence
+    ///
+    /// Returns the number of proofs removed.
+    pub fn dedup(&mut self) -> usize {
+        let before = self.proofs.len();
+        let mut seen = HashSet::new();
+        self.proofs.retain(|proof| seen.insert(proof.hash()));
+        before - self.proofs.len()
+    }
+
+    /// Verify every proof against the current epoch
+    ///
+    /// Proofs generated by an address other than the bundle's miner are rejected, as
+    /// are repeats of an earlier proof in the bundle (see [`ServiceProof::hash`]).
+    pub fn verify_all(&self, current_epoch: u64) -> BundleVerification {
+        let mut result = BundleVerification::default();
+        let mut seen = HashSet::new();
+
+        for (index, proof) in self.proofs.iter().enumerate() {
+            let key = proof.hash();
+            let verified = if proof.miner != self.miner {
+                Err(ServiceError::InvalidProof(format!(
+                    "proof miner {} does not match bundle miner {}",
+                    proof.miner, self.miner
+                )))
+            } else if !seen.insert(key) {
+                Err(ServiceError::DuplicateProof(key))
+            } else {
+                proof.verify(current_epoch)
+            };
+
+            match verified {
+                Ok(()) => {
+                    result.valid.push(index);
+                    result.service_score = result.service_score.saturating_add(proof.service_score());
+                }
+                Err(err) => result.rejected.push((index, err)),
+            }
+        }
+
+        result
+    }
+
+    /// Aggregate service score of all distinct proofs, without verification
+    ///
+    /// Use [`Self::verify_all`] to get the score of only the valid proofs.
+    pub fn total_service_score(&self) -> u64 {
+        let mut seen = HashSet::new();
+        self.proofs
+            .iter()
+            .filter(|proof| seen.insert(proof.hash()))
+            .fold(0u64, |acc, proof| acc.saturating_add(proof.service_score()))
+    }
+
+    /// Canonical hash of the bundle
+    ///
+    /// `keccak256` over the block number, miner and every proof in order, with all
+    /// integers big-endian and variable-length fields prefixed by a `u32` length.
+    pub fn hash(&self) -> B256 {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.block_number.to_be_bytes());
+        buf.extend_from_slice(self.miner.as_slice());
+        put_len(&mut buf, self.proofs.len());
+
+        for proof in &self.proofs {
+            encode_proof(&mut buf, proof);
+        }
+
+        keccak256(&buf)
+    }
+}
+
+/// Append a `u32` big-endian length prefix
+fn put_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_be_bytes());
+}
+
+/// Append the canonical encoding of a proof
+fn encode_proof(buf: &mut Vec<u8>, proof: &ServiceProof) {
+    buf.push(proof.proof_type as u8);
+    buf.extend_from_slice(proof.miner.as_slice());
+    buf.extend_from_slice(&proof.epoch.to_be_bytes());
+
+    match &proof.data {
+        ServiceProofData::Storage { cid, merkle_proof, challenge_response } => {
+            buf.extend_from_slice(cid.as_slice());
+            put_len(buf, merkle_proof.len());
+            for node in merkle_proof {
+                buf.extend_from_slice(node.as_slice());
+            }
+            buf.extend_from_slice(challenge_response.as_slice());
+        }
+        ServiceProofData::Cdn { cid, bandwidth_bytes, receipt_bytes, client_receipts } => {
+            buf.extend_from_slice(cid.as_slice());
+            buf.extend_from_slice(&bandwidth_bytes.to_be_bytes());
+            buf.extend_from_slice(&receipt_bytes.to_be_bytes());
+            put_len(buf, client_receipts.len());
+            for receipt in client_receipts {
+                buf.extend_from_slice(receipt.as_slice());
+            }
+        }
+        ServiceProofData::Compute { wasm_cid, input_hash, output_hash, cycles } => {
+            buf.extend_from_slice(wasm_cid.as_slice());
+            buf.extend_from_slice(input_hash.as_slice());
+            buf.extend_from_slice(output_hash.as_slice());
+            buf.extend_from_slice(&cycles.to_be_bytes());
+        }
+    }
+
+    put_len(buf, proof.signature.len());
+    buf.extend_from_slice(&proof.signature);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINER: Address = Address::repeat_byte(0x11);
+
+    fn mixed_bundle() -> ServiceProofBundle {
+        let proofs = vec![
+            // Valid storage proof, 1 point
+            ServiceProof::new_storage(
+                MINER,
+                100,
+                B256::repeat_byte(1),
+                vec![B256::repeat_byte(2)],
+                B256::repeat_byte(3),
+            ),
+            // Valid compute proof, 5B cycles = 5 points
+            ServiceProof::new_compute(
+                MINER,
+                100,
+                B256::repeat_byte(4),
+                B256::repeat_byte(5),
+                B256::repeat_byte(6),
+                5_000_000_000,
+            ),
+            // Expired CDN proof, 20GB = 2 points
+            ServiceProof::new_cdn(
+                MINER,
+                50,
+                B256::repeat_byte(7),
+                20 * 1024 * 1024 * 1024,
+                20 * 1024 * 1024 * 1024,
+                vec![B256::repeat_byte(8)],
+            ),
+        ];
+
+        ServiceProofBundle::with_proofs(1000, MINER, proofs)
+    }
+
+    #[test]
+    fn test_verify_mixed_bundle() {
+        let bundle = mixed_bundle();
+        let verification = bundle.verify_all(100);
+
+        assert_eq!(verification.valid_count(), 2);
+        assert_eq!(verification.valid, vec![0, 1]);
+        assert_eq!(verification.rejected.len(), 1);
+        assert!(matches!(verification.rejected[0], (2, ServiceError::ProofExpired(50, 100))));
+        assert!(!verification.all_valid());
+
+        // Only valid proofs count towards the verified score
+        assert_eq!(verification.service_score, 6);
+        assert_eq!(bundle.total_service_score(), 8);
+    }
+
+    #[test]
+    fn test_foreign_proof_rejected() {
+        let mut bundle = ServiceProofBundle::new(1000, MINER);
+        bundle.push(ServiceProof::new_storage(
+            Address::repeat_byte(0x22),
+            100,
+            B256::repeat_byte(1),
+            vec![B256::repeat_byte(2)],
+            B256::repeat_byte(3),
+        ));
+
+        let verification = bundle.verify_all(100);
+        assert_eq!(verification.valid_count(), 0);
+        assert_eq!(verification.service_score, 0);
+    }
+
+    #[test]
+    fn test_unbacked_bandwidth_rejected() {
+        let gb = 1024 * 1024 * 1024;
+        let receipts = vec![B256::repeat_byte(8)];
+        let mut bundle = ServiceProofBundle::new(1000, MINER);
+        bundle.push(ServiceProof::new_cdn(MINER, 100, B256::ZERO, 105 * gb, 100 * gb, receipts));
+        bundle.push(ServiceProof::new_cdn(MINER, 100, B256::ZERO, 106 * gb, 100 * gb, vec![]));
+
+        let verification = bundle.verify_all(100);
+        assert_eq!(verification.valid, vec![0]);
+        assert!(matches!(verification.rejected[0], (1, ServiceError::InvalidProof(_))));
+    }
+
+    #[test]
+    fn test_duplicate_proof_counted_once() {
+        let mut bundle = mixed_bundle();
+        let duplicate = bundle.proofs[1].clone();
+        bundle.push(duplicate);
+
+        let verification = bundle.verify_all(100);
+        assert_eq!(verification.valid, vec![0, 1]);
+        assert!(matches!(verification.rejected[1], (3, ServiceError::DuplicateProof(_))));
+        assert_eq!(verification.service_score, 6);
+        assert_eq!(bundle.total_service_score(), 8);
+
+        assert_eq!(bundle.dedup(), 1);
+        assert_eq!(bundle.len(), 3);
+        assert_eq!(bundle.dedup(), 0);
+    }
+
+    #[test]
+    fn test_proof_limit() {
+        let storage = |i: u8| {
+            ServiceProof::new_storage(MINER, 100, B256::repeat_byte(i), vec![], B256::ZERO)
+        };
+
+        let mut bundle = ServiceProofBundle::new(1000, MINER);
+        for i in 0..4 {
+            bundle.try_push(storage(i), 4).unwrap();
+        }
+        assert!(bundle.check_size(4).is_ok());
+
+        // One more is refused, and leaves the bundle at the limit
+        assert!(matches!(
+            bundle.try_push(storage(4), 4),
+            Err(ServiceError::TooManyProofs { count: 5, max: 4 })
+        ));
+        assert_eq!(bundle.len(), 4);
+
+        bundle.push(storage(4));
+        assert!(matches!(bundle.check_size(4), Err(ServiceError::TooManyProofs { count: 5, max: 4 })));
+        assert!(bundle.check_size(MAX_SERVICE_PROOFS_PER_BLOCK).is_ok());
+    }
+
+    #[test]
+    fn test_bundle_hash() {
+        let bundle = mixed_bundle();
+        assert_eq!(bundle.hash(), mixed_bundle().hash());
+
+        let mut other_block = mixed_bundle();
+        other_block.block_number += 1;
+        assert_ne!(bundle.hash(), other_block.hash());
+
+        let mut reordered = mixed_bundle();
+        reordered.proofs.swap(0, 1);
+        assert_ne!(bundle.hash(), reordered.hash());
+
+        let mut fewer = mixed_bundle();
+        fewer.proofs.pop();
+        assert_ne!(bundle.hash(), fewer.hash());
+
+        assert_ne!(ServiceProofBundle::new(1000, MINER).hash(), B256::ZERO);
+    }
+}
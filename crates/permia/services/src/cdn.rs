@@ -1,7 +1,23 @@
 //! CDN service proofs (Content Delivery)
+//!
+//! # Sampled verification
+//!
+//! A busy CDN proof carries thousands of client receipts, too many to check every
+//! signature. [`CdnProof::verify_sampled`] checks `k` receipts picked by a block hash
+//! instead. If a fraction `f` of the receipts is forged, all `k` samples miss the
+//! forgeries with probability about `(1 - f)^k`: with 32 samples, a proof with 10% forged
+//! receipts passes 3.4% of the time, one with 1% forged passes 72% of the time. Sampling
+//! bounds the share of forged receipts a proof can get away with, it does not rule
+//! forgeries out; [`CdnProof::verify_receipts`] checks every receipt.
+//!
+//! The sample is only unpredictable if the prover can't choose the block hash, so it
+//! should come from a block sealed after the proof was committed to.
 
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{keccak256, Address, Signature, B256};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::ServiceError;
 
 /// CDN service parameters (from PROTOCOL_SPEC_v4.md)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +48,44 @@ impl CdnParams {
     }
 }
 
+/// Bytes per CDN score point (10GB)
+const BYTES_PER_POINT: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Limits applied when crediting CDN bandwidth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CdnLimits {
+    /// Maximum bandwidth credited to a single proof per epoch, in bytes
+    pub max_bandwidth_per_epoch: u64,
+    /// How far declared bandwidth may exceed the receipted bytes, in percent
+    pub receipt_tolerance_percent: u64,
+}
+
+impl Default for CdnLimits {
+    fn default() -> Self {
+        Self {
+            // ~10 Gbit/s sustained for a one hour epoch
+            max_bandwidth_per_epoch: 4 * 1024 * 1024 * 1024 * 1024,
+            receipt_tolerance_percent: 5,
+        }
+    }
+}
+
+impl CdnLimits {
+    /// Bandwidth credited for a declared amount
+    pub fn creditable_bandwidth(&self, bandwidth_bytes: u64) -> u64 {
+        bandwidth_bytes.min(self.max_bandwidth_per_epoch)
+    }
+
+    /// Whether declared bandwidth is backed by `receipt_bytes`, within the tolerance
+    pub fn is_backed(&self, bandwidth_bytes: u64, receipt_bytes: u64) -> bool {
+        // Multiplied before dividing so small totals keep their tolerance, in u128 so it
+        // can't overflow
+        let receipt_bytes = u128::from(receipt_bytes);
+        let tolerance = receipt_bytes * u128::from(self.receipt_tolerance_percent) / 100;
+        u128::from(bandwidth_bytes) <= receipt_bytes + tolerance
+    }
+}
+
 /// CDN delivery proof
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CdnProof {
@@ -54,42 +108,194 @@ pub struct CdnProof {
 pub struct ClientReceipt {
     /// Client address or ID hash
     pub client_id: B256,
+    /// Miner that delivered the content
+    pub miner: Address,
+    /// Epoch the content was delivered in
+    pub epoch: u64,
     /// Content delivered
     pub cid: B256,
     /// Bytes delivered
     pub bytes: u64,
     /// Timestamp
     pub timestamp: u64,
-    /// Client signature (simplified)
+    /// Client signature over [`Self::signing_message`] (r, s, v concatenated)
     pub signature: Vec<u8>,
 }
 
+impl ClientReceipt {
+    /// Get the message that should be signed
+    ///
+    /// Covers the miner and epoch, so a receipt can't be claimed by another miner or
+    /// replayed in a later epoch.
+    pub fn signing_message(&self) -> B256 {
+        let mut data = Vec::with_capacity(19 + 32 + 20 + 8 + 32 + 16);
+        data.extend_from_slice(b"PERMIA_CDN_RECEIPT:");
+        data.extend_from_slice(self.client_id.as_slice());
+        data.extend_from_slice(self.miner.as_slice());
+        data.extend_from_slice(&self.epoch.to_be_bytes());
+        data.extend_from_slice(self.cid.as_slice());
+        data.extend_from_slice(&self.bytes.to_be_bytes());
+        data.extend_from_slice(&self.timestamp.to_be_bytes());
+
+        keccak256(&data)
+    }
+
+    /// Verify the receipt is signed by the client
+    ///
+    /// The client id is the client's address, left-padded to 32 bytes.
+    pub fn verify(&self) -> Result<(), ServiceError> {
+        let signature = Signature::from_raw(&self.signature)
+            .map_err(|e| ServiceError::VerificationFailed(format!("invalid signature: {e}")))?;
+        let signer = signature
+            .recover_address_from_prehash(&self.signing_message())
+            .map_err(|e| ServiceError::VerificationFailed(format!("invalid signature: {e}")))?;
+
+        if signer.into_word() != self.client_id {
+            return Err(ServiceError::VerificationFailed(format!(
+                "receipt signed by {signer}, expected client {}",
+                self.client_id
+            )));
+        }
+
+        Ok(())
+    }
+}
+
 impl CdnProof {
-    /// Verify the CDN proof
+    /// Verify the CDN proof with the default limits
     pub fn verify(&self) -> bool {
+        self.verify_with(&CdnLimits::default())
+    }
+
+    /// Verify the CDN proof
+    ///
+    /// Proofs repeating a receipt are rejected, see [`Self::duplicate_receipt`].
+    pub fn verify_with(&self, limits: &CdnLimits) -> bool {
         // Basic validation
         if self.bandwidth_bytes == 0 || self.client_receipts.is_empty() {
             return false;
         }
+        if self.duplicate_receipt().is_some() {
+            return false;
+        }
 
-        // Verify total bandwidth matches receipts
-        let receipt_total: u64 = self.client_receipts.iter().map(|r| r.bytes).sum();
-        
-        // Allow some tolerance (receipts might be sampled)
-        receipt_total > 0
+        let receipt_total = self.receipt_bytes();
+        if receipt_total == 0 {
+            return false;
+        }
+
+        // Declared bandwidth must be backed by receipts, within tolerance
+        limits.is_backed(self.bandwidth_bytes, receipt_total)
     }
 
-    /// Calculate service score contribution
+    /// Verify the proof and the signature of every client receipt
+    pub fn verify_receipts(&self) -> Result<(), ServiceError> {
+        self.verify_receipts_at(0..self.client_receipts.len())
+    }
+
+    /// Verify the proof and the signatures of `k` client receipts sampled by `block_hash`
+    ///
+    /// See the [module docs](self) for how far a passing sample can be trusted.
+    pub fn verify_sampled(&self, block_hash: B256, k: usize) -> Result<(), ServiceError> {
+        self.verify_receipts_at(self.sampled_receipts(block_hash, k))
+    }
+
+    /// Indices of the `k` client receipts sampled by `block_hash`
+    ///
+    /// Deterministic in the block hash and the proof, without repeats; every receipt is
+    /// sampled if there are at most `k`.
+    pub fn sampled_receipts(&self, block_hash: B256, k: usize) -> Vec<usize> {
+        let n = self.client_receipts.len();
+        if k >= n {
+            return (0..n).collect();
+        }
+
+        let mut seed = Vec::with_capacity(32 + 20 + 32 + 8);
+        seed.extend_from_slice(block_hash.as_slice());
+        seed.extend_from_slice(self.miner.as_slice());
+        seed.extend_from_slice(self.cid.as_slice());
+        seed.extend_from_slice(&self.epoch.to_be_bytes());
+        let seed = keccak256(&seed);
+
+        // Partial Fisher-Yates shuffle, drawing each swap from keccak256(seed, round)
+        let mut indices: Vec<usize> = (0..n).collect();
+        for round in 0..k {
+            let mut data = [0u8; 40];
+            data[..32].copy_from_slice(seed.as_slice());
+            data[32..].copy_from_slice(&(round as u64).to_be_bytes());
+            let draw = u64::from_be_bytes(keccak256(data)[..8].try_into().unwrap());
+            let pick = round + (draw % (n - round) as u64) as usize;
+            indices.swap(round, pick);
+        }
+        indices.truncate(k);
+        indices
+    }
+
+    /// Index of the first receipt repeating an earlier one, if any
+    ///
+    /// Receipts are the same if they have the same
+    /// [`signing_message`](ClientReceipt::signing_message), whatever their signatures, so
+    /// a receipt's bytes can't be counted twice.
+    pub fn duplicate_receipt(&self) -> Option<usize> {
+        let mut seen = HashSet::new();
+        self.client_receipts.iter().position(|receipt| !seen.insert(receipt.signing_message()))
+    }
+
+    fn verify_receipts_at(&self, indices: impl IntoIterator<Item = usize>) -> Result<(), ServiceError> {
+        if let Some(index) = self.duplicate_receipt() {
+            return Err(ServiceError::InvalidProof(format!(
+                "receipt {index} repeats an earlier receipt"
+            )));
+        }
+        if !self.verify() {
+            return Err(ServiceError::InvalidProof(
+                "bandwidth not backed by client receipts".to_string(),
+            ));
+        }
+
+        for index in indices {
+            let receipt = &self.client_receipts[index];
+            if receipt.cid != self.cid {
+                return Err(ServiceError::InvalidProof(format!(
+                    "receipt {index} is for content {}, proof serves {}",
+                    receipt.cid, self.cid
+                )));
+            }
+            if receipt.miner != self.miner || receipt.epoch != self.epoch {
+                return Err(ServiceError::InvalidProof(format!(
+                    "receipt {index} is for miner {} in epoch {}, proof is by {} in epoch {}",
+                    receipt.miner, receipt.epoch, self.miner, self.epoch
+                )));
+            }
+            receipt.verify().map_err(|e| {
+                ServiceError::VerificationFailed(format!("receipt {index}: {e}"))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Total bytes acknowledged by client receipts
+    pub fn receipt_bytes(&self) -> u64 {
+        self.client_receipts.iter().fold(0u64, |total, r| total.saturating_add(r.bytes))
+    }
+
+    /// Calculate service score contribution with the default limits
     pub fn service_score(&self) -> u64 {
-        // Score based on bandwidth served (1 point per 10GB)
-        let gb = self.bandwidth_bytes / (1024 * 1024 * 1024);
-        (gb / 10).max(1)
+        self.service_score_with(&CdnLimits::default())
+    }
+
+    /// Calculate service score contribution
+    pub fn service_score_with(&self, limits: &CdnLimits) -> u64 {
+        // Score based on creditable bandwidth served (1 point per 10GB)
+        (limits.creditable_bandwidth(self.bandwidth_bytes) / BYTES_PER_POINT).max(1)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::signing_key;
 
     #[test]
     fn test_cdn_params() {
@@ -102,26 +308,194 @@ mod tests {
         assert_eq!(params.cost_cents(), 10);
     }
 
-    #[test]
-    fn test_cdn_proof() {
-        let receipt = ClientReceipt {
-            client_id: B256::repeat_byte(1),
-            cid: B256::repeat_byte(2),
-            bytes: 1024 * 1024,
-            timestamp: 1000,
-            signature: vec![0u8; 65],
-        };
+    fn cdn_proof(bandwidth_bytes: u64, receipt_bytes: &[u64]) -> CdnProof {
+        let client_receipts = receipt_bytes
+            .iter()
+            .zip(1000..)
+            .map(|(&bytes, timestamp)| ClientReceipt {
+                client_id: B256::repeat_byte(1),
+                miner: Address::ZERO,
+                epoch: 100,
+                cid: B256::repeat_byte(2),
+                bytes,
+                timestamp,
+                signature: vec![0u8; 65],
+            })
+            .collect();
 
-        let proof = CdnProof {
+        CdnProof {
             miner: Address::ZERO,
             cid: B256::repeat_byte(2),
-            bandwidth_bytes: 10 * 1024 * 1024 * 1024,
+            bandwidth_bytes,
             requests: 1000,
-            client_receipts: vec![receipt],
+            client_receipts,
             epoch: 100,
-        };
+        }
+    }
+
+    #[test]
+    fn test_cdn_proof() {
+        let proof = cdn_proof(10 * 1024 * 1024 * 1024, &[10 * 1024 * 1024 * 1024]);
 
         assert!(proof.verify());
         assert_eq!(proof.service_score(), 1);
+
+        // Receipts may undercount slightly
+        let proof = cdn_proof(BYTES_PER_POINT * 3, &[BYTES_PER_POINT, BYTES_PER_POINT * 19 / 10]);
+        assert!(proof.verify());
+        assert_eq!(proof.service_score(), 3);
+    }
+
+    #[test]
+    fn test_over_declared_bandwidth_clamped() {
+        let limits = CdnLimits::default();
+        let petabyte = 1024u64.pow(5);
+        let proof = cdn_proof(petabyte, &[petabyte]);
+
+        assert!(proof.verify());
+        assert_eq!(proof.service_score(), limits.max_bandwidth_per_epoch / BYTES_PER_POINT);
+
+        // A tighter cap lowers the score
+        let tight = CdnLimits { max_bandwidth_per_epoch: BYTES_PER_POINT * 2, ..limits };
+        assert_eq!(proof.service_score_with(&tight), 2);
+    }
+
+    #[test]
+    fn test_bandwidth_unbacked_by_receipts_rejected() {
+        // Claims 1TB with 1MB of receipts
+        let proof = cdn_proof(1024u64.pow(4), &[1024 * 1024]);
+        assert!(!proof.verify());
+
+        // Just past the tolerance
+        let proof = cdn_proof(BYTES_PER_POINT * 106 / 100, &[BYTES_PER_POINT]);
+        assert!(!proof.verify());
+
+        // Small totals keep their tolerance: 5% of 99 bytes is 4 bytes
+        assert!(cdn_proof(103, &[99]).verify());
+        assert!(!cdn_proof(104, &[99]).verify());
+
+        // Overflowing receipts saturate instead of wrapping
+        let proof = cdn_proof(u64::MAX, &[u64::MAX, u64::MAX]);
+        assert!(proof.verify());
+    }
+
+    /// Proof of `receipts` receipts, each signed by a distinct client
+    fn signed_cdn_proof(receipts: u8) -> CdnProof {
+        let mut proof = cdn_proof(1024 * u64::from(receipts), &[]);
+        proof.client_receipts = (1..=receipts)
+            .map(|seed| {
+                let key = signing_key(seed);
+                let mut receipt = ClientReceipt {
+                    client_id: Address::from_private_key(&key).into_word(),
+                    miner: proof.miner,
+                    epoch: proof.epoch,
+                    cid: proof.cid,
+                    bytes: 1024,
+                    timestamp: 1000 + u64::from(seed),
+                    signature: Vec::new(),
+                };
+                let signature: Signature = key
+                    .sign_prehash_recoverable(receipt.signing_message().as_slice())
+                    .unwrap()
+                    .into();
+                receipt.signature = signature.as_bytes().to_vec();
+                receipt
+            })
+            .collect();
+        proof
+    }
+
+    #[test]
+    fn test_sampled_receipts() {
+        let proof = signed_cdn_proof(100);
+        let block_hash = B256::repeat_byte(7);
+
+        let sample = proof.sampled_receipts(block_hash, 10);
+        assert_eq!(sample.len(), 10);
+        assert!(sample.iter().all(|&index| index < 100));
+        let mut distinct = sample.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 10);
+
+        // Deterministic in the block hash
+        assert_eq!(proof.sampled_receipts(block_hash, 10), sample);
+        assert_ne!(proof.sampled_receipts(B256::repeat_byte(8), 10), sample);
+
+        // Small proofs are checked in full
+        assert_eq!(proof.sampled_receipts(block_hash, 500), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_verify_sampled() {
+        let proof = signed_cdn_proof(100);
+        let block_hash = B256::repeat_byte(7);
+
+        // An honest proof passes however it is sampled
+        assert!(proof.verify_receipts().is_ok());
+        for seed in 0..10u8 {
+            assert!(proof.verify_sampled(B256::repeat_byte(seed), 10).is_ok());
+        }
+
+        // A forged receipt is caught when sampled
+        let sample = proof.sampled_receipts(block_hash, 10);
+        let mut forged = proof.clone();
+        forged.client_receipts[sample[3]].bytes *= 2;
+        assert!(matches!(
+            forged.verify_sampled(block_hash, 10),
+            Err(ServiceError::VerificationFailed(_))
+        ));
+
+        // ...but not when it falls outside the sample, the tradeoff for not checking
+        // every signature
+        let unsampled = (0..100).find(|index| !sample.contains(index)).unwrap();
+        let mut forged = proof.clone();
+        forged.client_receipts[unsampled].signature = vec![0u8; 65];
+        assert!(forged.verify_sampled(block_hash, 10).is_ok());
+        assert!(forged.verify_receipts().is_err());
+
+        // Receipts must still back the declared bandwidth
+        let mut inflated = proof;
+        inflated.bandwidth_bytes *= 2;
+        assert!(matches!(inflated.verify_sampled(block_hash, 10), Err(ServiceError::InvalidProof(_))));
+    }
+
+    #[test]
+    fn test_duplicate_receipts_rejected() {
+        let proof = signed_cdn_proof(10);
+        assert_eq!(proof.duplicate_receipt(), None);
+
+        // Repeating a receipt to back more bandwidth
+        let mut repeated = proof.clone();
+        repeated.client_receipts.push(proof.client_receipts[3].clone());
+        repeated.bandwidth_bytes += 1024;
+        assert_eq!(repeated.duplicate_receipt(), Some(10));
+        assert!(!repeated.verify());
+        assert!(matches!(repeated.verify_receipts(), Err(ServiceError::InvalidProof(_))));
+
+        // Sampling doesn't skip the check, whichever receipts are sampled
+        for seed in 0..10u8 {
+            assert!(repeated.verify_sampled(B256::repeat_byte(seed), 2).is_err());
+        }
+    }
+
+    #[test]
+    fn test_receipts_bound_to_miner_and_epoch() {
+        let proof = signed_cdn_proof(4);
+
+        // Another miner claiming the receipts
+        let stolen = CdnProof { miner: Address::repeat_byte(9), ..proof.clone() };
+        assert!(matches!(stolen.verify_receipts(), Err(ServiceError::InvalidProof(_))));
+
+        // Or rewriting them to its address, which breaks the client signatures
+        let mut rewritten = stolen;
+        for receipt in &mut rewritten.client_receipts {
+            receipt.miner = rewritten.miner;
+        }
+        assert!(matches!(rewritten.verify_receipts(), Err(ServiceError::VerificationFailed(_))));
+
+        // Replaying them in a later epoch
+        let replayed = CdnProof { epoch: proof.epoch + 1, ..proof };
+        assert!(matches!(replayed.verify_receipts(), Err(ServiceError::InvalidProof(_))));
     }
 }
@@ -1,7 +1,23 @@
 //! Compute service proofs (WASM Execution)
+//!
+//! A [`WasmExecutor`] runs compute jobs and [`prove_compute`] turns a run into a
+//! [`ComputeProof`]. [`WasmiExecutor`] is the default executor. Cycles follow the
+//! deterministic cost model of [`crate::metering`].
+//!
+//! # Calling Convention
+//!
+//! Arguments are the function's parameters in order, each encoded little-endian at its
+//! natural width (`i32` as 4 bytes, `i64` as 8). The output is the function's results,
+//! encoded the same way.
 
-use alloy_primitives::{Address, B256, Bytes};
+use alloy_primitives::{keccak256, Address, B256, Bytes};
 use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+use crate::{
+    metering::{instrument, CYCLES_GLOBAL},
+    ServiceError,
+};
 
 /// Compute service parameters (from PROTOCOL_SPEC_v4.md)
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,11 +71,46 @@ pub struct ComputeProof {
 }
 
 impl ComputeProof {
-    /// Verify the compute proof
+    /// Verify the compute proof is well formed
+    ///
+    /// The proof must report cycles and its trace hash must commit to its module, input,
+    /// output and cycles. Whether the execution actually produced them is checked by
+    /// [`Self::verify_execution`].
     pub fn verify(&self) -> bool {
-        // Basic validation
-        // In production, would verify the execution trace
-        self.cycles > 0 && self.trace_hash != B256::ZERO
+        self.cycles > 0 &&
+            self.trace_hash ==
+                compute_trace_hash(self.wasm_cid, self.input_hash, self.output_hash, self.cycles)
+    }
+
+    /// Verify the proof by re-executing its job
+    ///
+    /// `params` and `wasm` must be the job the proof claims to have run. The job is run
+    /// again with `executor`, and its output and cycles must match the proof's.
+    pub fn verify_execution<E: WasmExecutor + ?Sized>(
+        &self,
+        executor: &E,
+        params: &ComputeParams,
+        wasm: &[u8],
+    ) -> Result<(), ServiceError> {
+        let fail = |reason: &str| Err(ServiceError::VerificationFailed(reason.to_string()));
+        if !self.verify() {
+            return fail("malformed compute proof");
+        }
+        if self.wasm_cid != params.wasm_cid {
+            return fail("proof is for another module");
+        }
+        if self.input_hash != compute_input_hash(&params.function, &params.args) {
+            return fail("proof is for another input");
+        }
+
+        let (expected, _) = prove_compute(executor, params, wasm, self.miner, self.epoch)?;
+        if self.output_hash != expected.output_hash {
+            return fail("output does not match the execution");
+        }
+        if self.cycles != expected.cycles {
+            return fail("cycles do not match the execution");
+        }
+        Ok(())
     }
 
     /// Calculate service score contribution
@@ -71,7 +122,7 @@ impl ComputeProof {
 }
 
 /// Result of a compute execution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ComputeResult {
     /// Whether execution succeeded
     pub success: bool,
@@ -85,9 +136,204 @@ pub struct ComputeResult {
     pub execution_time_ms: u64,
 }
 
+impl ComputeResult {
+    /// Successful execution
+    pub fn success(output: Vec<u8>, cycles: u64, execution_time_ms: u64) -> Self {
+        Self { success: true, output, error: None, cycles, execution_time_ms }
+    }
+
+    /// Failed execution
+    pub fn failure(error: impl Into<String>, cycles: u64, execution_time_ms: u64) -> Self {
+        Self { success: false, output: Vec::new(), error: Some(error.into()), cycles, execution_time_ms }
+    }
+}
+
+/// Runs WASM for compute jobs
+pub trait WasmExecutor {
+    /// Call `function` of the module `wasm` with `args`, consuming at most `max_cycles`
+    ///
+    /// Reported cycles must follow [`crate::metering`], so that every honest executor
+    /// reports the same cycles for the same job.
+    fn execute(&self, wasm: &[u8], function: &str, args: &[u8], max_cycles: u64) -> ComputeResult;
+}
+
+/// Content identifier of a WASM module, the `keccak256` of its binary
+pub fn compute_wasm_cid(wasm: &[u8]) -> B256 {
+    keccak256(wasm)
+}
+
+/// Hash of a compute job's input: the entry function and its arguments
+pub fn compute_input_hash(function: &str, args: &[u8]) -> B256 {
+    let mut buf = Vec::with_capacity(4 + function.len() + args.len());
+    buf.extend_from_slice(&(function.len() as u32).to_be_bytes());
+    buf.extend_from_slice(function.as_bytes());
+    buf.extend_from_slice(args);
+    keccak256(buf)
+}
+
+/// Hash committing to a whole execution: module, input, output and cycles
+pub fn compute_trace_hash(wasm_cid: B256, input_hash: B256, output_hash: B256, cycles: u64) -> B256 {
+    let mut buf = [0u8; 32 * 3 + 8];
+    buf[..32].copy_from_slice(wasm_cid.as_slice());
+    buf[32..64].copy_from_slice(input_hash.as_slice());
+    buf[64..96].copy_from_slice(output_hash.as_slice());
+    buf[96..].copy_from_slice(&cycles.to_be_bytes());
+    keccak256(buf)
+}
+
+/// Run a compute job and prove its execution
+///
+/// `wasm` must be the module identified by `params.wasm_cid`, see [`compute_wasm_cid`].
+/// Returns the proof together with the execution result, whose output the proof commits
+/// to.
+pub fn prove_compute<E: WasmExecutor + ?Sized>(
+    executor: &E,
+    params: &ComputeParams,
+    wasm: &[u8],
+    miner: Address,
+    epoch: u64,
+) -> Result<(ComputeProof, ComputeResult), ServiceError> {
+    let wasm_cid = compute_wasm_cid(wasm);
+    if wasm_cid != params.wasm_cid {
+        return Err(ServiceError::InvalidWasm(format!(
+            "module hashes to {wasm_cid}, job expects {}",
+            params.wasm_cid
+        )));
+    }
+
+    let result = executor.execute(wasm, &params.function, &params.args, params.max_cycles);
+    if !result.success {
+        return Err(ServiceError::ExecutionFailed(result.error.unwrap_or_default()));
+    }
+    if result.cycles > params.max_cycles {
+        return Err(ServiceError::ExecutionFailed(format!(
+            "consumed {} cycles, limit is {}",
+            result.cycles, params.max_cycles
+        )));
+    }
+
+    let input_hash = compute_input_hash(&params.function, &params.args);
+    let output_hash = keccak256(&result.output);
+    let proof = ComputeProof {
+        miner,
+        wasm_cid: params.wasm_cid,
+        input_hash,
+        output_hash,
+        cycles: result.cycles,
+        trace_hash: compute_trace_hash(params.wasm_cid, input_hash, output_hash, result.cycles),
+        epoch,
+    };
+    Ok((proof, result))
+}
+
+/// Largest linear memory a compute job may use, in bytes
+pub const MAX_JOB_MEMORY: usize = 16 * 1024 * 1024;
+
+/// Largest table a compute job may use, in elements
+pub const MAX_JOB_TABLE_ELEMENTS: usize = 10_000;
+
+/// [`WasmExecutor`] backed by the `wasmi` interpreter
+///
+/// Runs the module as rewritten by [`instrument`], so cycles are metered by the module
+/// itself. Modules may not import anything, and may use at most one memory of
+/// [`MAX_JOB_MEMORY`] and one table of [`MAX_JOB_TABLE_ELEMENTS`]; growing past either
+/// fails like on any other limit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WasmiExecutor;
+
+impl WasmiExecutor {
+    /// Returns the output and cycles consumed, or the error and cycles consumed
+    fn run(
+        wasm: &[u8],
+        function: &str,
+        args: &[u8],
+        max_cycles: u64,
+    ) -> Result<(Vec<u8>, u64), (String, u64)> {
+        use wasmi::{
+            Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Val, ValType,
+        };
+
+        let wasm = instrument(wasm, max_cycles).map_err(|e| (e.to_string(), 0))?;
+        let engine = Engine::default();
+        let module = Module::new(&engine, &wasm[..]).map_err(|e| (e.to_string(), 0))?;
+
+        // Cycles used so far, from the cycles left in the metering global
+        let max = i64::try_from(max_cycles).unwrap_or(i64::MAX);
+        let consumed = |store: &Store<StoreLimits>, instance: &Instance| {
+            match instance.get_global(store, CYCLES_GLOBAL).map(|global| global.get(store)) {
+                Some(Val::I64(left)) => max.saturating_sub(left.max(0)) as u64,
+                _ => max_cycles,
+            }
+        };
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_JOB_MEMORY)
+            .table_elements(MAX_JOB_TABLE_ELEMENTS)
+            .instances(1)
+            .memories(1)
+            .tables(1)
+            .build();
+        let mut store = Store::new(&engine, limits);
+        store.limiter(|limits| limits);
+        let instance = Linker::<StoreLimits>::new(&engine)
+            .instantiate(&mut store, &module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| (e.to_string(), 0))?;
+        let func = instance.get_func(&store, function).ok_or_else(|| {
+            (format!("no exported function `{function}`"), consumed(&store, &instance))
+        })?;
+        let ty = func.ty(&store);
+
+        let mut params = Vec::with_capacity(ty.params().len());
+        let mut rest = args;
+        for param in ty.params() {
+            let value = match param {
+                ValType::I32 => rest.split_first_chunk().map(|(v, r)| (Val::I32(i32::from_le_bytes(*v)), r)),
+                ValType::I64 => rest.split_first_chunk().map(|(v, r)| (Val::I64(i64::from_le_bytes(*v)), r)),
+                other => return Err((format!("unsupported parameter type {other:?}"), 0)),
+            };
+            let (value, remaining) = value.ok_or_else(|| ("arguments too short".to_string(), 0))?;
+            params.push(value);
+            rest = remaining;
+        }
+        if !rest.is_empty() {
+            return Err(("arguments too long".to_string(), 0));
+        }
+
+        let mut results: Vec<Val> = ty.results().iter().map(|ty| Val::default(*ty)).collect();
+        func.call(&mut store, &params, &mut results)
+            .map_err(|e| (e.to_string(), consumed(&store, &instance)))?;
+
+        let mut output = Vec::new();
+        for result in &results {
+            match result {
+                Val::I32(v) => output.extend_from_slice(&v.to_le_bytes()),
+                Val::I64(v) => output.extend_from_slice(&v.to_le_bytes()),
+                other => {
+                    return Err((format!("unsupported result {other:?}"), consumed(&store, &instance)))
+                }
+            }
+        }
+        Ok((output, consumed(&store, &instance)))
+    }
+}
+
+impl WasmExecutor for WasmiExecutor {
+    fn execute(&self, wasm: &[u8], function: &str, args: &[u8], max_cycles: u64) -> ComputeResult {
+        let start = Instant::now();
+        let outcome = Self::run(wasm, function, args, max_cycles);
+        let elapsed = start.elapsed().as_millis() as u64;
+        match outcome {
+            Ok((output, cycles)) => ComputeResult::success(output, cycles, elapsed),
+            Err((error, cycles)) => ComputeResult::failure(error, cycles, elapsed),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::metering::GROW_CYCLES;
 
     #[test]
     fn test_compute_params() {
@@ -103,17 +349,194 @@ mod tests {
 
     #[test]
     fn test_compute_proof() {
+        let (wasm_cid, input_hash, output_hash) =
+            (B256::repeat_byte(1), B256::repeat_byte(2), B256::repeat_byte(3));
         let proof = ComputeProof {
             miner: Address::ZERO,
-            wasm_cid: B256::repeat_byte(1),
-            input_hash: B256::repeat_byte(2),
-            output_hash: B256::repeat_byte(3),
+            wasm_cid,
+            input_hash,
+            output_hash,
             cycles: 1_000_000_000,
-            trace_hash: B256::repeat_byte(4),
+            trace_hash: compute_trace_hash(wasm_cid, input_hash, output_hash, 1_000_000_000),
             epoch: 100,
         };
 
         assert!(proof.verify());
         assert_eq!(proof.service_score(), 1);
+
+        // The trace hash must commit to the proof's fields
+        assert!(!ComputeProof { cycles: 2_000_000_000, ..proof.clone() }.verify());
+        assert!(!ComputeProof { trace_hash: B256::repeat_byte(4), ..proof }.verify());
+    }
+
+    /// Executor echoing its arguments, one cycle per byte
+    struct EchoExecutor;
+
+    impl WasmExecutor for EchoExecutor {
+        fn execute(&self, _wasm: &[u8], _function: &str, args: &[u8], _max: u64) -> ComputeResult {
+            ComputeResult::success(args.to_vec(), args.len() as u64, 0)
+        }
+    }
+
+    #[test]
+    fn test_prove_checks_wasm_cid() {
+        let wasm = b"echo module";
+        let cid = compute_wasm_cid(wasm);
+        let params = ComputeParams::new(cid, "echo".to_string(), vec![7; 4], 100);
+        assert!(prove_compute(&EchoExecutor, &params, wasm, Address::ZERO, 100).is_ok());
+
+        // A module other than the job's is not run
+        assert!(matches!(
+            prove_compute(&EchoExecutor, &params, b"other module", Address::ZERO, 100),
+            Err(ServiceError::InvalidWasm(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_execution() {
+        let wasm = b"echo module";
+        let cid = compute_wasm_cid(wasm);
+        let params = ComputeParams::new(cid, "echo".to_string(), vec![7; 4], 100);
+        let (proof, _) = prove_compute(&EchoExecutor, &params, wasm, Address::ZERO, 100).unwrap();
+        proof.verify_execution(&EchoExecutor, &params, wasm).unwrap();
+
+        // A consistent proof of a different output is caught by re-executing
+        let output_hash = keccak256([8; 4]);
+        let forged = ComputeProof {
+            output_hash,
+            trace_hash: compute_trace_hash(cid, proof.input_hash, output_hash, 4),
+            ..proof.clone()
+        };
+        assert!(forged.verify());
+        assert!(matches!(
+            forged.verify_execution(&EchoExecutor, &params, wasm),
+            Err(ServiceError::VerificationFailed(_))
+        ));
+
+        // As are inflated cycles
+        let cycles = 4_000_000_000;
+        let forged = ComputeProof {
+            cycles,
+            trace_hash: compute_trace_hash(cid, proof.input_hash, proof.output_hash, cycles),
+            ..proof.clone()
+        };
+        assert!(forged.verify_execution(&EchoExecutor, &params, wasm).is_err());
+
+        // The proof must be for the job it is checked against
+        let other = ComputeParams { args: vec![8; 4], ..params.clone() };
+        assert!(proof.verify_execution(&EchoExecutor, &other, wasm).is_err());
+        assert!(proof.verify_execution(&EchoExecutor, &params, b"other module").is_err());
+    }
+
+    /// `(module (func (export "add") (param i32 i32) (result i32) local.get 0 local.get 1 i32.add))`
+    const ADD_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic, version
+        0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f, // type: (i32, i32) -> i32
+        0x03, 0x02, 0x01, 0x00, // function 0 has type 0
+        0x07, 0x07, 0x01, 0x03, b'a', b'd', b'd', 0x00, 0x00, // export "add"
+        0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b, // body
+    ];
+
+    /// `(func (export "count") (param i32) (result i32) (loop (br_if 0 (local.tee 0 (i32.sub (local.get 0) (i32.const 1))))) (local.get 0))`
+    const COUNT_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic, version
+        0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f, // type: i32 -> i32
+        0x03, 0x02, 0x01, 0x00, // function 0 has type 0
+        0x07, 0x09, 0x01, 0x05, b'c', b'o', b'u', b'n', b't', 0x00, 0x00, // export "count"
+        0x0a, 0x12, 0x01, 0x10, 0x00, // body
+        0x03, 0x40, 0x20, 0x00, 0x41, 0x01, 0x6b, 0x22, 0x00, 0x0d, 0x00, 0x0b, 0x20, 0x00, 0x0b,
+    ];
+
+    /// `(module (memory 1) (func (export "grow") (param i32) (result i32) local.get 0 memory.grow 0))`
+    const GROW_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic, version
+        0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f, // type: i32 -> i32
+        0x03, 0x02, 0x01, 0x00, // function 0 has type 0
+        0x05, 0x03, 0x01, 0x00, 0x01, // memory of one page
+        0x07, 0x08, 0x01, 0x04, b'g', b'r', b'o', b'w', 0x00, 0x00, // export "grow"
+        0x0a, 0x08, 0x01, 0x06, 0x00, 0x20, 0x00, 0x40, 0x00, 0x0b, // body
+    ];
+
+    fn add_args(a: i32, b: i32) -> Vec<u8> {
+        [a.to_le_bytes(), b.to_le_bytes()].concat()
+    }
+
+    #[test]
+    fn test_prove_wasm_add() {
+        let cid = compute_wasm_cid(ADD_WASM);
+        let params = ComputeParams::new(cid, "add".to_string(), add_args(2, 3), 1_000);
+        let (proof, result) =
+            prove_compute(&WasmiExecutor, &params, ADD_WASM, Address::repeat_byte(7), 100).unwrap();
+
+        assert_eq!(result.output, 5i32.to_le_bytes());
+        assert!(proof.verify());
+        proof.verify_execution(&WasmiExecutor, &params, ADD_WASM).unwrap();
+        assert_eq!(proof.output_hash, keccak256(&result.output));
+        assert_eq!(proof.input_hash, compute_input_hash("add", &params.args));
+        assert_eq!(proof.cycles, result.cycles);
+        assert!(proof.cycles > 0 && proof.cycles <= params.max_cycles);
+        assert_eq!(
+            proof.trace_hash,
+            compute_trace_hash(params.wasm_cid, proof.input_hash, proof.output_hash, proof.cycles)
+        );
+    }
+
+    #[test]
+    fn test_failed_execution_not_proven() {
+        let prove = |function: &str, args: Vec<u8>, max_cycles| {
+            let cid = compute_wasm_cid(ADD_WASM);
+            let params = ComputeParams::new(cid, function.to_string(), args, max_cycles);
+            prove_compute(&WasmiExecutor, &params, ADD_WASM, Address::ZERO, 100)
+        };
+
+        assert!(prove("add", add_args(2, 3), 1_000).is_ok());
+        assert!(matches!(prove("sub", add_args(2, 3), 1_000), Err(ServiceError::ExecutionFailed(_))));
+        assert!(matches!(prove("add", vec![2, 0, 0, 0], 1_000), Err(ServiceError::ExecutionFailed(_))));
+        assert!(matches!(prove("add", add_args(2, 3), 1), Err(ServiceError::ExecutionFailed(_))));
+    }
+
+    #[test]
+    fn test_cycles_deterministic() {
+        let run = |wasm, function, args: &[u8]| WasmiExecutor.execute(wasm, function, args, 1_000);
+
+        // Two runs of the same job report the same cycles
+        let first = run(ADD_WASM, "add", &add_args(2, 3));
+        let second = run(ADD_WASM, "add", &add_args(2, 3));
+        assert!(first.success);
+        assert_eq!(first.cycles, second.cycles);
+
+        // Cycles follow the cost table: 4 per add, 5 per loop iteration plus 4
+        assert_eq!(first.cycles, 4);
+        for n in [1, 3, 10] {
+            let result = run(COUNT_WASM, "count", &(n as i32).to_le_bytes());
+            assert_eq!(result.output, 0i32.to_le_bytes());
+            assert_eq!(result.cycles, 5 * n + 4);
+        }
+
+        // Counting down from 0 only stops when the cycles run out
+        let result = run(COUNT_WASM, "count", &0i32.to_le_bytes());
+        assert!(!result.success);
+        assert_eq!(result.cycles, 1_000);
+    }
+
+    #[test]
+    fn test_memory_growth_metered_and_limited() {
+        let grow = |pages: i32, max_cycles| {
+            WasmiExecutor.execute(GROW_WASM, "grow", &pages.to_le_bytes(), max_cycles)
+        };
+
+        // Every page asked for is charged, returning the previous size in pages
+        let result = grow(2, 10_000);
+        assert_eq!(result.output, 1i32.to_le_bytes());
+        assert_eq!(result.cycles, 3 + 2 * GROW_CYCLES);
+
+        // A growth the cycles can't pay for traps before it happens
+        assert!(!grow(20, 10_000).success);
+
+        // Growing past the memory limit fails, but is still charged
+        let pages = (MAX_JOB_MEMORY / 65_536) as i32;
+        let result = grow(pages, u64::MAX);
+        assert_eq!(result.output, (-1i32).to_le_bytes());
+        assert_eq!(result.cycles, 3 + pages as u64 * GROW_CYCLES);
     }
 }
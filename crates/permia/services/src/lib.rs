@@ -19,6 +19,9 @@
 //!
 //! Maximum Multiplier: 2.0x
 //! ```
+//!
+//! Blocks don't carry service proofs yet, so rewards with the service multiplier are
+//! off-chain estimates; on-chain every block earns the base block reward.
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
@@ -26,13 +29,38 @@ pub mod proof;
 pub mod storage;
 pub mod cdn;
 pub mod compute;
+pub mod metering;
 pub mod multiplier;
+pub mod bundle;
+pub mod uptime;
+pub mod reward;
+pub mod store;
+
+#[cfg(test)]
+mod test_utils;
 
-pub use proof::{ServiceProof, ServiceProofType, ServiceProofData};
-pub use storage::{StorageProof, StorageParams};
-pub use cdn::{CdnProof, CdnParams};
-pub use compute::{ComputeProof, ComputeParams};
-pub use multiplier::{ServiceMultiplier, calculate_multiplier};
+pub use proof::{
+    epoch_service_score, ServiceProof, ServiceProofType, ServiceProofData, MAX_PROOF_WIRE_BYTES,
+    PROOF_EXPIRY_EPOCHS, PROOF_WIRE_VERSION,
+};
+pub use storage::{PricingSchedule, PricingTier, StorageProof, StorageParams};
+pub use cdn::{CdnLimits, CdnProof, CdnParams};
+pub use compute::{
+    compute_input_hash, compute_trace_hash, compute_wasm_cid, prove_compute, ComputeParams,
+    ComputeProof, ComputeResult, WasmExecutor, WasmiExecutor, MAX_JOB_MEMORY,
+    MAX_JOB_TABLE_ELEMENTS,
+};
+pub use multiplier::{
+    BonusRange, MultiplierSchedule, ServiceMultiplier, calculate_multiplier,
+    calculate_multiplier_with_schedule,
+};
+pub use bundle::{BundleVerification, ServiceProofBundle, MAX_SERVICE_PROOFS_PER_BLOCK};
+pub use uptime::{SamplerSignature, UptimeAttestation, MIN_UPTIME_SAMPLERS};
+pub use reward::{
+    base_block_reward, block_reward, epoch_at, expected_block_reward, reward_split,
+    BASE_BLOCK_REWARD, DEFAULT_TREASURY_SHARE_BPS, TREASURY_SHARE_DENOMINATOR,
+};
+pub use store::{ProofStore, MAX_STORED_PROOFS};
 
 use alloy_primitives::{Address, B256};
 use thiserror::Error;
@@ -55,6 +83,35 @@ pub enum ServiceError {
     /// Proof expired
     #[error("Proof expired at epoch {0}, current epoch is {1}")]
     ProofExpired(u64, u64),
+    
+    /// Proof already counted
+    #[error("Duplicate proof: {0}")]
+    DuplicateProof(B256),
+    
+    /// Malformed proof on the wire
+    #[error("Invalid proof encoding: {0}")]
+    InvalidEncoding(String),
+    
+    /// Compute job failed to execute
+    #[error("Compute execution failed: {0}")]
+    ExecutionFailed(String),
+    
+    /// WASM module that can't be metered
+    #[error("Invalid WASM module: {0}")]
+    InvalidWasm(String),
+
+    /// Bundle over the per-block proof limit
+    #[error("Too many service proofs: {count} exceeds the limit of {max}")]
+    TooManyProofs {
+        /// Number of proofs in the bundle
+        count: usize,
+        /// Maximum number of proofs allowed in a block
+        max: usize,
+    },
+
+    /// Proof store at capacity
+    #[error("Proof store full: {0} unexpired proofs stored")]
+    StoreFull(usize),
 }
 
 /// Service type identifiers (from PROTOCOL_SPEC_v4.md)
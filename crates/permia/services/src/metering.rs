@@ -0,0 +1,540 @@
+//! Deterministic cycle metering for compute jobs
+//!
+//! Compute cycles are defined by a fixed per-opcode cost table rather than by the
+//! interpreter running the job, so two honest executors of the same job always report
+//! the same cycles.
+//!
+//! | Instructions                                  | Cycles |
+//! |-----------------------------------------------|--------|
+//! | `call`, `return_call`                         | 10     |
+//! | `call_indirect`, `return_call_indirect`       | 20     |
+//! | Memory loads and stores                       | 3      |
+//! | Integer multiplication                        | 3      |
+//! | Integer division and remainder                | 8      |
+//! | Floating point arithmetic                     | 4      |
+//! | Bulk memory and table operations              | 100    |
+//! | Everything else                               | 1      |
+//!
+//! `memory.grow` and `table.grow` additionally cost [`GROW_CYCLES`] per page or element
+//! requested, charged right before they run, whether or not the growth succeeds.
+//!
+//! # Instrumentation
+//!
+//! [`instrument`] rewrites a module to meter itself. It adds a mutable `i64` global,
+//! exported as [`CYCLES_GLOBAL`], holding the cycles left. Function bodies are split
+//! into straight-line segments ending at a control instruction, and every segment
+//! starts by charging its total cost to the global and trapping once it goes negative.
+//! A segment is charged in full when entered, so a job trapping mid-segment is
+//! charged for the whole segment. A second, unexported `i32` global holds the size of
+//! a growth while it is charged.
+//!
+//! Modules are validated before they are rewritten, so malformed modules are rejected
+//! rather than instrumented.
+
+use crate::ServiceError;
+
+/// Name of the exported global holding the cycles left
+pub const CYCLES_GLOBAL: &str = "__permia_cycles";
+
+/// Cycles charged per memory page or table element a `memory.grow` or `table.grow` asks for
+pub const GROW_CYCLES: u64 = 1_000;
+
+/// Section ids
+const IMPORT_SECTION: u8 = 2;
+const GLOBAL_SECTION: u8 = 6;
+const EXPORT_SECTION: u8 = 7;
+const CODE_SECTION: u8 = 10;
+
+/// Cycles charged for an instruction
+///
+/// `sub_opcode` is only meaningful for `0xfc`-prefixed instructions.
+pub const fn opcode_cycles(opcode: u8, sub_opcode: u32) -> u64 {
+    match opcode {
+        0x10 | 0x12 => 10,
+        0x11 | 0x13 => 20,
+        0x28..=0x3e => 3,
+        0x6c | 0x7e => 3,
+        0x6d..=0x70 | 0x7f..=0x82 => 8,
+        0x8b..=0xa6 => 4,
+        0xfc => match sub_opcode {
+            0..=7 | 15 => 1,
+            _ => 100,
+        },
+        _ => 1,
+    }
+}
+
+/// Rewrite a module to meter its own execution, starting with `max_cycles` cycles
+///
+/// Invalid modules, and modules with imports or instructions outside the supported set
+/// (MVP, sign extension, saturating conversions, bulk memory, reference types and tail
+/// calls) are rejected.
+pub fn instrument(wasm: &[u8], max_cycles: u64) -> Result<Vec<u8>, ServiceError> {
+    // The rewriter trusts the module's structure, so only valid modules are rewritten
+    wasmi::Module::validate(&wasmi::Engine::default(), wasm).map_err(|e| invalid(e.to_string()))?;
+
+    let mut reader = Reader::new(wasm);
+    let header = reader.take(8)?;
+    if header != b"\0asm\x01\0\0\0" {
+        return Err(invalid("not a wasm module"));
+    }
+
+    // Read every section first: the new global's index depends on the global section
+    let mut sections = Vec::new();
+    while !reader.is_empty() {
+        let id = reader.byte()?;
+        let size = reader.uleb()? as usize;
+        sections.push((id, reader.take(size)?));
+    }
+    if sections.iter().any(|(id, _)| *id == IMPORT_SECTION) {
+        return Err(invalid("imports are not supported"));
+    }
+
+    let global_count = match sections.iter().find(|(id, _)| *id == GLOBAL_SECTION) {
+        Some((_, contents)) => Reader::new(contents).uleb()? as u32,
+        None => 0,
+    };
+    let initial = i64::try_from(max_cycles).unwrap_or(i64::MAX);
+
+    let mut global = Vec::new();
+    global.extend_from_slice(&[0x7e, 0x01, 0x42]); // mutable i64 = i64.const
+    write_sleb(&mut global, initial);
+    global.push(0x0b);
+    global.extend_from_slice(&[0x7f, 0x01, 0x41, 0x00, 0x0b]); // mutable i32 = 0
+
+    let mut export = Vec::new();
+    write_uleb(&mut export, CYCLES_GLOBAL.len() as u64);
+    export.extend_from_slice(CYCLES_GLOBAL.as_bytes());
+    export.push(0x03);
+    write_uleb(&mut export, global_count as u64);
+
+    let mut out = header.to_vec();
+    let mut added_global = false;
+    let mut added_export = false;
+    for (id, contents) in sections {
+        // Sections appear in a fixed order, insert the new ones where they belong
+        if !added_global && id != 0 && section_rank(id) > section_rank(GLOBAL_SECTION) {
+            write_section(&mut out, GLOBAL_SECTION, &append_entries(&[0], 2, &global)?);
+            added_global = true;
+        }
+        if !added_export && id != 0 && section_rank(id) > section_rank(EXPORT_SECTION) {
+            write_section(&mut out, EXPORT_SECTION, &append_entries(&[0], 1, &export)?);
+            added_export = true;
+        }
+
+        match id {
+            GLOBAL_SECTION => {
+                write_section(&mut out, id, &append_entries(contents, 2, &global)?);
+                added_global = true;
+            }
+            EXPORT_SECTION => {
+                write_section(&mut out, id, &append_entries(contents, 1, &export)?);
+                added_export = true;
+            }
+            CODE_SECTION => write_section(&mut out, id, &instrument_code(contents, global_count)?),
+            _ => write_section(&mut out, id, contents),
+        }
+    }
+    if !added_global {
+        write_section(&mut out, GLOBAL_SECTION, &append_entries(&[0], 2, &global)?);
+    }
+    if !added_export {
+        write_section(&mut out, EXPORT_SECTION, &append_entries(&[0], 1, &export)?);
+    }
+
+    Ok(out)
+}
+
+/// Position of a non-custom section in the required section order
+fn section_rank(id: u8) -> u8 {
+    match id {
+        13 => 6,            // tag, between memory and global
+        6..=9 => id + 1,    // global, export, start, element
+        12 => 11,           // data count, before code
+        10 | 11 => id + 2,  // code, data
+        _ => id,
+    }
+}
+
+/// Append `count` encoded entries to a vector section
+fn append_entries(contents: &[u8], count: u64, entries: &[u8]) -> Result<Vec<u8>, ServiceError> {
+    let mut reader = Reader::new(contents);
+    let existing = reader.uleb()?;
+    let mut out = Vec::with_capacity(contents.len() + entries.len() + 1);
+    write_uleb(&mut out, existing + count);
+    out.extend_from_slice(reader.rest());
+    out.extend_from_slice(entries);
+    Ok(out)
+}
+
+fn instrument_code(contents: &[u8], global: u32) -> Result<Vec<u8>, ServiceError> {
+    let mut reader = Reader::new(contents);
+    let count = reader.uleb()?;
+    let mut out = Vec::with_capacity(contents.len() * 2);
+    write_uleb(&mut out, count);
+
+    for _ in 0..count {
+        let size = reader.uleb()? as usize;
+        let body = instrument_body(reader.take(size)?, global)?;
+        write_uleb(&mut out, body.len() as u64);
+        out.extend_from_slice(&body);
+    }
+    if !reader.is_empty() {
+        return Err(invalid("trailing bytes in code section"));
+    }
+    Ok(out)
+}
+
+fn instrument_body(body: &[u8], global: u32) -> Result<Vec<u8>, ServiceError> {
+    let mut reader = Reader::new(body);
+    for _ in 0..reader.uleb()? {
+        reader.skip_leb()?;
+        reader.byte()?;
+    }
+    let code_start = reader.pos;
+
+    let mut out = Vec::with_capacity(body.len() * 2);
+    out.extend_from_slice(&body[..code_start]);
+    let mut copied = code_start;
+    let (segments, grows) = segments(&mut reader)?;
+    // In code order, with a segment's charge before that of a growth starting it
+    let mut charges: Vec<_> = segments
+        .into_iter()
+        .map(|(start, cycles)| (start, Some(cycles)))
+        .chain(grows.into_iter().map(|start| (start, None)))
+        .collect();
+    charges.sort_by_key(|(start, cycles)| (*start, cycles.is_none()));
+    for (start, cycles) in charges {
+        out.extend_from_slice(&body[copied..start]);
+        match cycles {
+            Some(cycles) => write_charge(&mut out, global, cycles),
+            None => write_grow_charge(&mut out, global),
+        }
+        copied = start;
+    }
+    out.extend_from_slice(&body[copied..]);
+    Ok(out)
+}
+
+/// Split a function's code into segments
+///
+/// Returns each segment's start and cost, and the position of every `memory.grow` and
+/// `table.grow`.
+fn segments(reader: &mut Reader<'_>) -> Result<(Vec<(usize, u64)>, Vec<usize>), ServiceError> {
+    let mut segments = Vec::new();
+    let mut grows = Vec::new();
+    let mut current: Option<(usize, u64)> = None;
+    let mut depth = 1usize;
+
+    while depth > 0 {
+        let start = reader.pos;
+        let opcode = reader.byte()?;
+        let mut sub_opcode = 0;
+        let mut ends_segment = false;
+
+        match opcode {
+            0x00 | 0x01 | 0x0f | 0x1a | 0x1b | 0x45..=0xc4 | 0xd1 => {}
+            0x02..=0x04 => {
+                read_block_type(reader)?;
+                depth += 1;
+                ends_segment = true;
+            }
+            0x05 => ends_segment = true,
+            0x0b => {
+                depth -= 1;
+                ends_segment = true;
+            }
+            0x0c | 0x0d | 0x10 | 0x12 | 0x20..=0x26 | 0x3f | 0x40 | 0x41 | 0x42 | 0xd2 => {
+                reader.skip_leb()?;
+            }
+            0x0e => {
+                for _ in 0..=reader.uleb()? {
+                    reader.skip_leb()?;
+                }
+            }
+            0x11 | 0x13 => {
+                reader.skip_leb()?;
+                reader.skip_leb()?;
+            }
+            0x1c => {
+                let count = reader.uleb()? as usize;
+                reader.take(count)?;
+            }
+            0x28..=0x3e => {
+                // memarg, with a memory index when bit 6 of the alignment is set
+                if reader.uleb()? & 0x40 != 0 {
+                    reader.skip_leb()?;
+                }
+                reader.skip_leb()?;
+            }
+            0x43 => {
+                reader.take(4)?;
+            }
+            0x44 => {
+                reader.take(8)?;
+            }
+            0xd0 => {
+                reader.byte()?;
+            }
+            0xfc => {
+                sub_opcode = reader.uleb()? as u32;
+                let immediates = match sub_opcode {
+                    0..=7 => 0,
+                    9 | 11 | 13 | 15..=17 => 1,
+                    8 | 10 | 12 | 14 => 2,
+                    _ => return Err(invalid(format!("unsupported instruction 0xfc {sub_opcode}"))),
+                };
+                for _ in 0..immediates {
+                    reader.skip_leb()?;
+                }
+            }
+            _ => return Err(invalid(format!("unsupported instruction 0x{opcode:02x}"))),
+        }
+
+        if opcode == 0x40 || (opcode == 0xfc && sub_opcode == 15) {
+            grows.push(start);
+        }
+        let segment = current.get_or_insert((start, 0));
+        segment.1 += opcode_cycles(opcode, sub_opcode);
+        // Branches also end a segment, the code after them is reached another way
+        if ends_segment || matches!(opcode, 0x0c..=0x0f | 0x12 | 0x13) {
+            segments.extend(current.take());
+        }
+    }
+
+    if !reader.is_empty() {
+        return Err(invalid("trailing bytes after function end"));
+    }
+    Ok((segments, grows))
+}
+
+fn read_block_type(reader: &mut Reader<'_>) -> Result<(), ServiceError> {
+    match reader.peek()? {
+        // Empty or a single value type
+        0x40 | 0x6f | 0x70 | 0x7b..=0x7f => {
+            reader.byte()?;
+            Ok(())
+        }
+        // Type index
+        _ => reader.skip_leb(),
+    }
+}
+
+/// `cycles -= cost; if cycles < 0 { unreachable }`
+fn write_charge(out: &mut Vec<u8>, global: u32, cycles: u64) {
+    out.push(0x23); // global.get
+    write_uleb(out, global as u64);
+    out.push(0x42); // i64.const
+    write_sleb(out, cycles as i64);
+    out.push(0x7d); // i64.sub
+    write_check(out, global);
+}
+
+/// `cycles -= delta * GROW_CYCLES; if cycles < 0 { unreachable }`, keeping the growth's
+/// `delta` operand on the stack
+fn write_grow_charge(out: &mut Vec<u8>, global: u32) {
+    let scratch = global as u64 + 1;
+    out.push(0x24); // global.set
+    write_uleb(out, scratch);
+    out.push(0x23); // global.get
+    write_uleb(out, global as u64);
+    out.push(0x23); // global.get
+    write_uleb(out, scratch);
+    out.push(0xad); // i64.extend_i32_u
+    out.push(0x42); // i64.const
+    write_sleb(out, GROW_CYCLES as i64);
+    out.push(0x7e); // i64.mul
+    out.push(0x7d); // i64.sub
+    write_check(out, global);
+    out.push(0x23); // global.get
+    write_uleb(out, scratch);
+}
+
+/// Store the cycles left from the stack and trap if they are negative
+fn write_check(out: &mut Vec<u8>, global: u32) {
+    out.push(0x24); // global.set
+    write_uleb(out, global as u64);
+    out.push(0x23); // global.get
+    write_uleb(out, global as u64);
+    out.extend_from_slice(&[
+        0x42, 0x00, // i64.const 0
+        0x53, // i64.lt_s
+        0x04, 0x40, // if
+        0x00, // unreachable
+        0x0b, // end
+    ]);
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    out.push(id);
+    write_uleb(out, contents.len() as u64);
+    out.extend_from_slice(contents);
+}
+
+fn write_uleb(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_sleb(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> ServiceError {
+    ServiceError::InvalidWasm(reason.into())
+}
+
+/// Cursor over a wasm binary
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    fn peek(&self) -> Result<u8, ServiceError> {
+        self.bytes.get(self.pos).copied().ok_or_else(|| invalid("unexpected end of module"))
+    }
+
+    fn byte(&mut self) -> Result<u8, ServiceError> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ServiceError> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or_else(|| invalid("unexpected end of module"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn uleb(&mut self) -> Result<u64, ServiceError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("integer too long"))
+    }
+
+    fn skip_leb(&mut self) -> Result<(), ServiceError> {
+        self.uleb().map(drop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(module (func (export "add") (param i32 i32) (result i32) local.get 0 local.get 1 i32.add))`
+    const ADD_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic, version
+        0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f, // type: (i32, i32) -> i32
+        0x03, 0x02, 0x01, 0x00, // function 0 has type 0
+        0x07, 0x07, 0x01, 0x03, b'a', b'd', b'd', 0x00, 0x00, // export "add"
+        0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b, // body
+    ];
+
+    #[test]
+    fn test_instrument_add() {
+        let mut expected = ADD_WASM[..21].to_vec();
+        // Global 0: mutable i64 = 1000, global 1: mutable i32 = 0
+        expected.extend_from_slice(&[0x06, 0x0c, 0x02, 0x7e, 0x01, 0x42, 0xe8, 0x07, 0x0b]);
+        expected.extend_from_slice(&[0x7f, 0x01, 0x41, 0x00, 0x0b]);
+        // Exports "add" and the cycles global
+        expected.extend_from_slice(&[0x07, 0x19, 0x02, 0x03, b'a', b'd', b'd', 0x00, 0x00, 0x0f]);
+        expected.extend_from_slice(CYCLES_GLOBAL.as_bytes());
+        expected.extend_from_slice(&[0x03, 0x00]);
+        // The whole body is one segment costing 4 cycles
+        expected.extend_from_slice(&[0x0a, 0x19, 0x01, 0x17, 0x00]);
+        expected.extend_from_slice(&[
+            0x23, 0x00, 0x42, 0x04, 0x7d, 0x24, 0x00, 0x23, 0x00, 0x42, 0x00, 0x53, 0x04, 0x40, 0x00,
+            0x0b,
+        ]);
+        expected.extend_from_slice(&[0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b]);
+
+        assert_eq!(instrument(ADD_WASM, 1_000).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_segments_split_at_control_flow() {
+        // loop; local.get 0; i32.const 1; i32.sub; local.tee 0; br_if 0; end; local.get 0; end
+        let code = [0x03, 0x40, 0x20, 0x00, 0x41, 0x01, 0x6b, 0x22, 0x00, 0x0d, 0x00, 0x0b, 0x20, 0x00, 0x0b];
+        let (split, grows) = segments(&mut Reader::new(&code)).unwrap();
+        assert_eq!(split, [(0, 1), (2, 5), (11, 1), (12, 2)]);
+        assert!(grows.is_empty());
+
+        // Division and calls cost more
+        let code = [0x20, 0x00, 0x20, 0x01, 0x6d, 0x10, 0x00, 0x0b];
+        assert_eq!(segments(&mut Reader::new(&code)).unwrap().0, [(0, 1 + 1 + 8 + 10 + 1)]);
+    }
+
+    #[test]
+    fn test_grow_charged_per_page() {
+        // local.get 0; memory.grow 0; end
+        let code = [0x20, 0x00, 0x40, 0x00, 0x0b];
+        assert_eq!(segments(&mut Reader::new(&code)).unwrap(), (vec![(0, 3)], vec![2]));
+
+        // The growth's size is charged right before it, from the scratch global
+        let mut out = Vec::new();
+        write_grow_charge(&mut out, 0);
+        assert_eq!(
+            out,
+            [
+                0x24, 0x01, 0x23, 0x00, 0x23, 0x01, 0xad, 0x42, 0xe8, 0x07, 0x7e, 0x7d, 0x24, 0x00,
+                0x23, 0x00, 0x42, 0x00, 0x53, 0x04, 0x40, 0x00, 0x0b, 0x23, 0x01,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instrument_rejects_unsupported() {
+        assert!(matches!(instrument(b"not wasm", 1_000), Err(ServiceError::InvalidWasm(_))));
+
+        // SIMD prefix
+        let mut simd = ADD_WASM.to_vec();
+        simd[39] = 0xfd;
+        assert!(matches!(instrument(&simd, 1_000), Err(ServiceError::InvalidWasm(_))));
+
+        // Invalid module the rewriter could parse: i64.add on i32 operands
+        let mut mistyped = ADD_WASM.to_vec();
+        mistyped[39] = 0x7c;
+        assert!(matches!(instrument(&mistyped, 1_000), Err(ServiceError::InvalidWasm(_))));
+
+        // Import section
+        let mut imports = ADD_WASM[..8].to_vec();
+        imports.extend_from_slice(&[0x02, 0x01, 0x00]);
+        assert!(matches!(instrument(&imports, 1_000), Err(ServiceError::InvalidWasm(_))));
+    }
+}
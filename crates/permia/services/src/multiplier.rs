@@ -1,10 +1,68 @@
 //! Service multiplier calculation for mining rewards
 
-use crate::{ServiceProof, ServiceProofType, ServiceType};
+use crate::{ServiceProof, ServiceProofType, ServiceType, UptimeAttestation};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 /// Maximum service multiplier (2.0x)
 pub const MAX_MULTIPLIER: f64 = 2.0;
 
+/// Denominator of multipliers in basis points (10_000 = 1.0x)
+pub const MULTIPLIER_DENOMINATOR: u128 = 10_000;
+
+/// Bonus range of a single multiplier component
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BonusRange {
+    /// Bonus for the lowest qualifying factor
+    pub min: f64,
+    /// Bonus for the highest factor
+    pub max: f64,
+}
+
+impl BonusRange {
+    /// Create a new bonus range
+    pub const fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+
+    /// Bonus for a factor, interpolated linearly from `min` at 0.0 to `max` at 1.0
+    pub fn bonus(&self, factor: f64) -> f64 {
+        self.min + unit(factor) * (self.max - self.min)
+    }
+}
+
+/// Bonus ranges of the multiplier components and the overall cap
+///
+/// The default is the protocol's current economics.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MultiplierSchedule {
+    /// Storage proof bonus
+    pub storage: BonusRange,
+    /// Compute proof bonus
+    pub compute: BonusRange,
+    /// CDN proof bonus
+    pub cdn: BonusRange,
+    /// Uptime bonus, `min` for 95%+ and `max` for 99%+
+    pub uptime: BonusRange,
+    /// Geographic bonus
+    pub geographic: BonusRange,
+    /// Cap on the total multiplier
+    pub max_multiplier: f64,
+}
+
+impl Default for MultiplierSchedule {
+    fn default() -> Self {
+        Self {
+            storage: BonusRange::new(0.1, 0.3),
+            compute: BonusRange::new(0.1, 0.3),
+            cdn: BonusRange::new(0.05, 0.15),
+            uptime: BonusRange::new(0.05, 0.1),
+            geographic: BonusRange::new(0.2, 0.5),
+            max_multiplier: MAX_MULTIPLIER,
+        }
+    }
+}
+
 /// Service multiplier components
 #[derive(Debug, Clone, Default)]
 pub struct ServiceMultiplier {
@@ -18,6 +76,8 @@ pub struct ServiceMultiplier {
     pub uptime: f64,
     /// Geographic bonus (0.2 to 0.5)
     pub geographic: f64,
+    /// Bonus ranges and cap the components are computed with
+    pub schedule: MultiplierSchedule,
 }
 
 impl ServiceMultiplier {
@@ -26,30 +86,44 @@ impl ServiceMultiplier {
         Self::default()
     }
 
-    /// Calculate total multiplier (capped at MAX_MULTIPLIER)
+    /// Create a new multiplier with no bonuses, using the given schedule
+    pub fn from_schedule(schedule: MultiplierSchedule) -> Self {
+        Self { schedule, ..Self::default() }
+    }
+
+    /// Calculate total multiplier (between 1.0 and the schedule's cap)
     pub fn total(&self) -> f64 {
         let sum = 1.0 + self.storage + self.compute + self.cdn + self.uptime + self.geographic;
-        sum.min(MAX_MULTIPLIER)
+        if sum.is_nan() {
+            return 1.0;
+        }
+        let cap = self.schedule.max_multiplier;
+        sum.clamp(1.0, if cap.is_nan() { 1.0 } else { cap.max(1.0) })
+    }
+
+    /// Total multiplier in basis points, rounded to the nearest one
+    pub fn total_bps(&self) -> u128 {
+        (self.total() * MULTIPLIER_DENOMINATOR as f64).round() as u128
     }
 
     /// Add storage bonus based on proof quality
     pub fn with_storage(mut self, proof_quality: f64) -> Self {
-        // quality: 0.0 to 1.0 -> bonus: 0.1 to 0.3
-        self.storage = 0.1 + (proof_quality.clamp(0.0, 1.0) * 0.2);
+        // quality: 0.0 to 1.0 -> bonus: 0.1 to 0.3 by default
+        self.storage = self.schedule.storage.bonus(proof_quality);
         self
     }
 
     /// Add compute bonus based on proof quality
     pub fn with_compute(mut self, proof_quality: f64) -> Self {
-        // quality: 0.0 to 1.0 -> bonus: 0.1 to 0.3
-        self.compute = 0.1 + (proof_quality.clamp(0.0, 1.0) * 0.2);
+        // quality: 0.0 to 1.0 -> bonus: 0.1 to 0.3 by default
+        self.compute = self.schedule.compute.bonus(proof_quality);
         self
     }
 
     /// Add CDN bonus based on bandwidth served
     pub fn with_cdn(mut self, bandwidth_factor: f64) -> Self {
-        // factor: 0.0 to 1.0 -> bonus: 0.05 to 0.15
-        self.cdn = 0.05 + (bandwidth_factor.clamp(0.0, 1.0) * 0.1);
+        // factor: 0.0 to 1.0 -> bonus: 0.05 to 0.15 by default
+        self.cdn = self.schedule.cdn.bonus(bandwidth_factor);
         self
     }
 
@@ -57,35 +131,54 @@ impl ServiceMultiplier {
     pub fn with_uptime(mut self, uptime_percent: f64) -> Self {
         // 99%+ uptime gets full bonus
         if uptime_percent >= 99.0 {
-            self.uptime = 0.1;
+            self.uptime = self.schedule.uptime.max;
         } else if uptime_percent >= 95.0 {
-            self.uptime = 0.05;
+            self.uptime = self.schedule.uptime.min;
         }
         self
     }
 
     /// Add geographic bonus based on region rarity
     pub fn with_geographic(mut self, rarity_factor: f64) -> Self {
-        // factor: 0.0 to 1.0 -> bonus: 0.2 to 0.5
-        self.geographic = 0.2 + (rarity_factor.clamp(0.0, 1.0) * 0.3);
+        // factor: 0.0 to 1.0 -> bonus: 0.2 to 0.5 by default
+        self.geographic = self.schedule.geographic.bonus(rarity_factor);
         self
     }
 }
 
+/// Clamp a factor to `0.0..=1.0`, treating NaN as 0
+fn unit(factor: f64) -> f64 {
+    if factor.is_nan() { 0.0 } else { factor.clamp(0.0, 1.0) }
+}
+
 /// Calculate multiplier from a set of service proofs
+///
+/// Repeated proofs (same [`ServiceProof::hash`]) are only counted once. The uptime
+/// bonus is only applied for an attestation that passes [`UptimeAttestation::verify`].
 pub fn calculate_multiplier(
     proofs: &[ServiceProof],
-    uptime_percent: f64,
+    uptime: Option<&UptimeAttestation>,
     geographic_rarity: f64,
 ) -> ServiceMultiplier {
-    let mut multiplier = ServiceMultiplier::new();
+    calculate_multiplier_with_schedule(proofs, uptime, geographic_rarity, MultiplierSchedule::default())
+}
+
+/// Calculate multiplier from a set of service proofs under a custom [`MultiplierSchedule`]
+pub fn calculate_multiplier_with_schedule(
+    proofs: &[ServiceProof],
+    uptime: Option<&UptimeAttestation>,
+    geographic_rarity: f64,
+    schedule: MultiplierSchedule,
+) -> ServiceMultiplier {
+    let mut multiplier = ServiceMultiplier::from_schedule(schedule);
 
     // Check for each proof type
     let mut has_storage = false;
     let mut has_compute = false;
     let mut has_cdn = false;
+    let mut seen = HashSet::new();
 
-    for proof in proofs {
+    for proof in proofs.iter().filter(|proof| seen.insert(proof.hash())) {
         match proof.proof_type {
             ServiceProofType::StoragePoST => {
                 has_storage = true;
@@ -111,7 +204,9 @@ pub fn calculate_multiplier(
     }
 
     // Apply uptime and geographic bonuses
-    multiplier = multiplier.with_uptime(uptime_percent);
+    if let Some(attestation) = uptime.filter(|attestation| attestation.verify().is_ok()) {
+        multiplier = multiplier.with_uptime(attestation.uptime_percent());
+    }
     
     if geographic_rarity > 0.0 {
         multiplier = multiplier.with_geographic(geographic_rarity);
@@ -121,9 +216,15 @@ pub fn calculate_multiplier(
 }
 
 /// Calculate final reward with multiplier
+///
+/// Scales `base_reward` by [`ServiceMultiplier::total_bps`] in integer arithmetic, rounding
+/// down and saturating at `u128::MAX`, so every node computes the same reward.
 pub fn apply_multiplier(base_reward: u128, multiplier: &ServiceMultiplier) -> u128 {
-    let factor = multiplier.total();
-    ((base_reward as f64) * factor) as u128
+    let bps = multiplier.total_bps();
+    // Split the multiplication so large rewards don't overflow before saturating
+    (base_reward / MULTIPLIER_DENOMINATOR)
+        .saturating_mul(bps)
+        .saturating_add(base_reward % MULTIPLIER_DENOMINATOR * bps / MULTIPLIER_DENOMINATOR)
 }
 
 #[cfg(test)]
@@ -158,6 +259,55 @@ mod tests {
         assert!((m.total() - 1.3).abs() < 0.01);
     }
 
+    #[test]
+    fn test_duplicate_proof_counted_once() {
+        use alloy_primitives::{Address, B256};
+
+        let proof = ServiceProof::new_storage(
+            Address::ZERO,
+            100,
+            B256::repeat_byte(1),
+            vec![B256::repeat_byte(2)],
+            B256::repeat_byte(3),
+        );
+
+        let single = calculate_multiplier(std::slice::from_ref(&proof), None, 0.0);
+        let doubled = calculate_multiplier(&[proof.clone(), proof], None, 0.0);
+
+        assert_eq!(doubled.storage, single.storage);
+        assert_eq!(doubled.total(), single.total());
+        assert!((doubled.total() - 1.2).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_attested_uptime() {
+        use crate::test_utils::{sign, signed_attestation, signing_key};
+        use alloy_primitives::Address;
+
+        let miner = Address::repeat_byte(1);
+
+        // 99.5% responded earns the full bonus
+        let full = signed_attestation(miner, 1000, 995);
+        assert_eq!(calculate_multiplier(&[], Some(&full), 0.0).uptime, 0.1);
+
+        // 96% responded earns the partial tier
+        let partial = signed_attestation(miner, 1000, 960);
+        assert_eq!(calculate_multiplier(&[], Some(&partial), 0.0).uptime, 0.05);
+
+        // A forged attestation earns nothing
+        let mut forged = signed_attestation(miner, 1000, 995);
+        forged.responded_pings = 1000;
+        assert_eq!(calculate_multiplier(&[], Some(&forged), 0.0).uptime, 0.0);
+
+        // Neither does one the miner signed itself
+        let key = signing_key(1);
+        let mut own = UptimeAttestation::new(Address::from_private_key(&key), 100, 1000, 1000);
+        sign(&mut own, &key);
+        assert_eq!(calculate_multiplier(&[], Some(&own), 0.0).uptime, 0.0);
+
+        assert_eq!(calculate_multiplier(&[], None, 0.0).uptime, 0.0);
+    }
+
     #[test]
     fn test_apply_multiplier() {
         let base = 1000u128;
@@ -165,5 +315,106 @@ mod tests {
         
         let result = apply_multiplier(base, &m);
         assert_eq!(result, 1200);
+        assert_eq!(m.total_bps(), 12_000);
+
+        // Exact for rewards far beyond f64 precision
+        let base = u128::MAX / 4 + 1;
+        assert_eq!(apply_multiplier(base, &m), base / 10 * 12 + base % 10 * 12 / 10);
+        assert_eq!(apply_multiplier(base, &ServiceMultiplier::new()), base);
+    }
+
+    #[test]
+    fn test_all_maxed_regression() {
+        let m = ServiceMultiplier::new()
+            .with_storage(f64::MAX)
+            .with_compute(f64::INFINITY)
+            .with_cdn(f64::MAX)
+            .with_uptime(f64::INFINITY)
+            .with_geographic(f64::INFINITY);
+
+        assert_eq!(m.total(), MAX_MULTIPLIER);
+        assert_eq!(apply_multiplier(1000, &m), 2000);
+        assert_eq!(apply_multiplier(u128::MAX, &m), u128::MAX);
+    }
+
+    #[test]
+    fn test_custom_schedule() {
+        let default = MultiplierSchedule::default();
+        let doubled = MultiplierSchedule {
+            storage: BonusRange::new(default.storage.min * 2.0, default.storage.max * 2.0),
+            ..default
+        };
+
+        for quality in [0.0, 0.25, 0.5, 1.0] {
+            let base = ServiceMultiplier::new().with_storage(quality).storage;
+            let custom = ServiceMultiplier::from_schedule(doubled).with_storage(quality).storage;
+            assert!((custom - base * 2.0).abs() < 1e-9, "quality {quality}: {custom} vs {base}");
+        }
+        // Other components keep the default bands
+        assert_eq!(
+            ServiceMultiplier::from_schedule(doubled).with_cdn(1.0).cdn,
+            ServiceMultiplier::new().with_cdn(1.0).cdn
+        );
+
+        // The configured cap replaces MAX_MULTIPLIER
+        let capped = MultiplierSchedule { max_multiplier: 1.5, ..default };
+        let m = ServiceMultiplier::from_schedule(capped).with_storage(1.0).with_geographic(1.0);
+        assert_eq!(m.total(), 1.5);
+        assert_eq!(apply_multiplier(1000, &m), 1500);
+
+        // And applies to multipliers calculated from proofs
+        let proof = ServiceProof::new_storage(
+            alloy_primitives::Address::ZERO,
+            100,
+            alloy_primitives::B256::repeat_byte(1),
+            vec![alloy_primitives::B256::repeat_byte(2)],
+            alloy_primitives::B256::repeat_byte(3),
+        );
+        let m = calculate_multiplier_with_schedule(&[proof], None, 1.0, capped);
+        assert_eq!(m.total(), 1.5);
+    }
+
+    #[test]
+    fn test_nan_inputs_earn_minimum_bonus() {
+        let m = ServiceMultiplier::new()
+            .with_storage(f64::NAN)
+            .with_cdn(f64::NAN)
+            .with_uptime(f64::NAN)
+            .with_geographic(f64::NAN);
+
+        assert!((m.total() - 1.35).abs() < 1e-9);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn proptest_multiplier_bounds(
+            storage in proptest::option::of(proptest::num::f64::ANY),
+            compute in proptest::option::of(proptest::num::f64::ANY),
+            cdn in proptest::option::of(proptest::num::f64::ANY),
+            uptime in proptest::option::of(proptest::num::f64::ANY),
+            geographic in proptest::option::of(proptest::num::f64::ANY),
+            base_reward in proptest::prelude::any::<u128>(),
+        ) {
+            let mut m = ServiceMultiplier::new();
+            if let Some(quality) = storage {
+                m = m.with_storage(quality);
+            }
+            if let Some(quality) = compute {
+                m = m.with_compute(quality);
+            }
+            if let Some(factor) = cdn {
+                m = m.with_cdn(factor);
+            }
+            if let Some(percent) = uptime {
+                m = m.with_uptime(percent);
+            }
+            if let Some(factor) = geographic {
+                m = m.with_geographic(factor);
+            }
+
+            let total = m.total();
+            proptest::prop_assert!((1.0..=MAX_MULTIPLIER).contains(&total), "total {total}");
+            proptest::prop_assert!(apply_multiplier(base_reward, &m) >= base_reward);
+        }
     }
 }
@@ -1,9 +1,32 @@
 //! Service proof types
 
-use alloy_primitives::{Address, B256, Bytes};
+use alloy_primitives::{keccak256, Address, B256, Bytes};
+use bincode::Options;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
-use crate::{ServiceError, ServiceType};
+use crate::{CdnLimits, ServiceError, ServiceType};
+
+/// Epochs after which a service proof expires (24 epochs = 24 hours)
+pub const PROOF_EXPIRY_EPOCHS: u64 = 24;
+
+/// Version tag of the [`ServiceProof::to_bytes`] encoding
+pub const PROOF_WIRE_VERSION: u8 = 2;
+
+/// Maximum size of an encoded proof, without the version tag
+pub const MAX_PROOF_WIRE_BYTES: u64 = 1024 * 1024;
+
+/// bincode options of the [`ServiceProof::to_bytes`] encoding
+///
+/// Fixed-size integers as in bincode's default encoding. Decoding allocates at most
+/// [`MAX_PROOF_WIRE_BYTES`], so a forged length prefix can't exhaust memory, and rejects
+/// bytes after the proof, so every proof has a single encoding.
+fn wire_options() -> impl Options {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(MAX_PROOF_WIRE_BYTES)
+        .reject_trailing_bytes()
+}
 
 /// Service proof type identifier (from PROTOCOL_SPEC_v4.md)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,7 +41,7 @@ pub enum ServiceProofType {
 }
 
 /// Service proof data (type-specific)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ServiceProofData {
     /// Storage proof data
     Storage {
@@ -35,6 +58,8 @@ pub enum ServiceProofData {
         cid: B256,
         /// Bandwidth served (bytes)
         bandwidth_bytes: u64,
+        /// Bytes acknowledged by the client receipts
+        receipt_bytes: u64,
         /// Client receipts (hashes)
         client_receipts: Vec<B256>,
     },
@@ -51,8 +76,19 @@ pub enum ServiceProofData {
     },
 }
 
+impl ServiceProofData {
+    /// Proof type this data belongs to
+    pub fn proof_type(&self) -> ServiceProofType {
+        match self {
+            ServiceProofData::Storage { .. } => ServiceProofType::StoragePoST,
+            ServiceProofData::Cdn { .. } => ServiceProofType::CdnDelivery,
+            ServiceProofData::Compute { .. } => ServiceProofType::ComputeExecution,
+        }
+    }
+}
+
 /// A service proof from a miner
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ServiceProof {
     /// Type of service proof
     pub proof_type: ServiceProofType,
@@ -94,6 +130,7 @@ impl ServiceProof {
         epoch: u64,
         cid: B256,
         bandwidth_bytes: u64,
+        receipt_bytes: u64,
         client_receipts: Vec<B256>,
     ) -> Self {
         Self {
@@ -103,6 +140,7 @@ impl ServiceProof {
             data: ServiceProofData::Cdn {
                 cid,
                 bandwidth_bytes,
+                receipt_bytes,
                 client_receipts,
             },
             signature: Vec::new(),
@@ -141,22 +179,163 @@ impl ServiceProof {
         }
     }
 
+    /// Content the proof is about (`cid` for storage/CDN, `wasm_cid` for compute)
+    pub fn subject(&self) -> B256 {
+        match &self.data {
+            ServiceProofData::Storage { cid, .. } | ServiceProofData::Cdn { cid, .. } => *cid,
+            ServiceProofData::Compute { wasm_cid, .. } => *wasm_cid,
+        }
+    }
+
+    /// Canonical hash of the proof, its identity wherever proofs are deduplicated
+    ///
+    /// Hash of the proof type, miner and type-specific data. The signature is excluded:
+    /// it authenticates the proof rather than being part of it, so re-signing a proof
+    /// keeps its identity. So is the epoch, so a proof replayed in a later epoch, or
+    /// re-stamped with a later epoch, is recognized as the same proof.
+    pub fn hash(&self) -> B256 {
+        let mut buf = Vec::with_capacity(1 + 20 + 32 * 3);
+        buf.push(self.proof_type as u8);
+        buf.extend_from_slice(self.miner.as_slice());
+        match &self.data {
+            ServiceProofData::Storage { cid, merkle_proof, challenge_response } => {
+                buf.extend_from_slice(cid.as_slice());
+                buf.extend_from_slice(challenge_response.as_slice());
+                merkle_proof.iter().for_each(|node| buf.extend_from_slice(node.as_slice()));
+            }
+            ServiceProofData::Cdn { cid, bandwidth_bytes, receipt_bytes, client_receipts } => {
+                buf.extend_from_slice(cid.as_slice());
+                buf.extend_from_slice(&bandwidth_bytes.to_be_bytes());
+                buf.extend_from_slice(&receipt_bytes.to_be_bytes());
+                client_receipts.iter().for_each(|receipt| buf.extend_from_slice(receipt.as_slice()));
+            }
+            ServiceProofData::Compute { wasm_cid, input_hash, output_hash, cycles } => {
+                buf.extend_from_slice(wasm_cid.as_slice());
+                buf.extend_from_slice(input_hash.as_slice());
+                buf.extend_from_slice(output_hash.as_slice());
+                buf.extend_from_slice(&cycles.to_be_bytes());
+            }
+        }
+        keccak256(buf)
+    }
+
+    /// Encode the proof for RPC and gossip
+    ///
+    /// Layout: `version (1) | bincode(proof)`. bincode writes integers little-endian,
+    /// prefixes byte strings and vectors with a `u64` length and tags enum variants with
+    /// a `u32` index, so hashes take 40 bytes instead of the 68 of their JSON hex form.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ServiceError> {
+        let mut out = vec![PROOF_WIRE_VERSION];
+        wire_options()
+            .serialize_into(&mut out, self)
+            .map_err(|e| ServiceError::InvalidEncoding(e.to_string()))?;
+        Ok(out)
+    }
+
+    /// Decode a proof produced by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ServiceError> {
+        match bytes.split_first() {
+            Some((&PROOF_WIRE_VERSION, payload)) => wire_options()
+                .deserialize(payload)
+                .map_err(|e| ServiceError::InvalidEncoding(e.to_string())),
+            Some((version, _)) => {
+                Err(ServiceError::InvalidEncoding(format!("unsupported version {version}")))
+            }
+            None => Err(ServiceError::InvalidEncoding("empty proof".to_string())),
+        }
+    }
+
+    /// Calculate service score contribution
+    ///
+    /// Mirrors the per-service scoring of [`crate::CdnProof`] and [`crate::ComputeProof`].
+    /// Storage proofs don't carry the stored size, so each scores a single point.
+    pub fn service_score(&self) -> u64 {
+        match &self.data {
+            ServiceProofData::Storage { .. } => 1,
+            ServiceProofData::Cdn { bandwidth_bytes, .. } => {
+                // 1 point per 10GB served, capped per epoch
+                let bandwidth = CdnLimits::default().creditable_bandwidth(*bandwidth_bytes);
+                (bandwidth / (10 * 1024 * 1024 * 1024)).max(1)
+            }
+            ServiceProofData::Compute { cycles, .. } => {
+                // 1 point per 1B cycles executed
+                (cycles / 1_000_000_000).max(1)
+            }
+        }
+    }
+
+    /// Whether the proof is too old to be counted at `current_epoch`
+    pub fn is_expired(&self, current_epoch: u64) -> bool {
+        self.epoch.saturating_add(PROOF_EXPIRY_EPOCHS) < current_epoch
+    }
+
     /// Verify the proof (basic validation)
+    ///
+    /// Checks the proof is not expired, its data matches its type, and a CDN proof's
+    /// bandwidth is backed by distinct receipts. The data only summarizes the full proof,
+    /// so the service itself is not verified:
+    ///
+    /// - Storage: the Merkle proof and challenge response are not checked, the data carries
+    ///   no Merkle root or challenge index to check them against
+    /// - CDN: the client receipts are hashes, their signatures are only checked on the full
+    ///   proof with [`CdnProof::verify_receipts`](crate::CdnProof::verify_receipts)
+    /// - Compute: the output and cycles are only checked on the full proof, by re-execution
+    ///   with [`ComputeProof::verify_execution`](crate::ComputeProof::verify_execution)
+    ///
+    /// The miner's signature is not checked either.
     pub fn verify(&self, current_epoch: u64) -> Result<(), ServiceError> {
-        // Check epoch is not too old (max 24 epochs = 24 hours)
-        if self.epoch + 24 < current_epoch {
+        // Check epoch is not too old
+        if self.is_expired(current_epoch) {
             return Err(ServiceError::ProofExpired(self.epoch, current_epoch));
         }
 
-        // TODO: Implement full verification for each proof type
-        // - Storage: verify merkle proof against chain state
-        // - CDN: verify client receipt signatures
-        // - Compute: verify execution trace
+        // The declared type must match the data it carries
+        if self.data.proof_type() != self.proof_type {
+            return Err(ServiceError::InvalidProof(format!(
+                "{:?} proof carries {:?} data",
+                self.proof_type,
+                self.data.proof_type()
+            )));
+        }
+
+        if let ServiceProofData::Cdn { bandwidth_bytes, receipt_bytes, client_receipts, .. } =
+            &self.data
+        {
+            // A receipt may only back bandwidth once
+            let mut seen = HashSet::new();
+            if let Some(receipt) = client_receipts.iter().find(|receipt| !seen.insert(**receipt)) {
+                let reason = format!("duplicate client receipt {receipt}");
+                return Err(ServiceError::InvalidProof(reason));
+            }
+
+            // Declared bandwidth must be backed by receipts, as for a full CDN proof
+            if !CdnLimits::default().is_backed(*bandwidth_bytes, *receipt_bytes) {
+                return Err(ServiceError::InvalidProof(format!(
+                    "{bandwidth_bytes} bytes served but only {receipt_bytes} receipted"
+                )));
+            }
+        }
 
+        // Nothing else can be checked from the summary, see the method docs
         Ok(())
     }
 }
 
+/// Service score a miner earned with its proofs of `epoch`
+///
+/// Sums [`ServiceProof::service_score`] over the proofs of `epoch` that
+/// [verify](ServiceProof::verify), counting repeated proofs (same
+/// [`ServiceProof::hash`]) once. Proofs of other epochs and invalid proofs score nothing.
+pub fn epoch_service_score(proofs: &[ServiceProof], epoch: u64) -> u64 {
+    let mut seen = HashSet::new();
+    proofs
+        .iter()
+        .filter(|proof| proof.epoch == epoch && proof.verify(epoch).is_ok())
+        .filter(|proof| seen.insert(proof.hash()))
+        .map(ServiceProof::service_score)
+        .fold(0u64, u64::saturating_add)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,10 +362,25 @@ mod tests {
             100,
             B256::repeat_byte(1),
             1_000_000,
+            1_000_000,
             vec![B256::repeat_byte(2)],
         );
 
         assert_eq!(proof.service_type(), ServiceType::Cdn);
+        assert_eq!(proof.service_score(), 1);
+        assert!(proof.verify(100).is_ok());
+
+        // Repeated receipts are rejected
+        let receipts = vec![B256::repeat_byte(2), B256::repeat_byte(3), B256::repeat_byte(2)];
+        let repeated =
+            ServiceProof::new_cdn(Address::ZERO, 100, B256::repeat_byte(1), 1_000, 1_000, receipts);
+        assert!(matches!(repeated.verify(100), Err(ServiceError::InvalidProof(_))));
+
+        // So is bandwidth past the receipted bytes and their tolerance
+        let receipts = vec![B256::repeat_byte(2)];
+        let unbacked =
+            ServiceProof::new_cdn(Address::ZERO, 100, B256::repeat_byte(1), 1_051, 1_000, receipts);
+        assert!(matches!(unbacked.verify(100), Err(ServiceError::InvalidProof(_))));
     }
 
     #[test]
@@ -201,5 +395,178 @@ mod tests {
         );
 
         assert_eq!(proof.service_type(), ServiceType::Compute);
+        assert_eq!(proof.service_score(), 1);
+    }
+
+    #[test]
+    fn test_type_mismatch_rejected() {
+        let storage = ServiceProof::new_storage(Address::ZERO, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        let cdn = ServiceProof::new_cdn(Address::ZERO, 100, B256::repeat_byte(1), 1, 1, vec![]);
+        let compute =
+            ServiceProof::new_compute(Address::ZERO, 100, B256::ZERO, B256::ZERO, B256::ZERO, 1);
+
+        // Constructor-built proofs are consistent
+        for proof in [&storage, &cdn, &compute] {
+            assert_eq!(proof.data.proof_type(), proof.proof_type);
+            assert!(proof.verify(100).is_ok());
+        }
+
+        // Storage type carrying CDN data
+        let mismatched = ServiceProof { data: cdn.data.clone(), ..storage.clone() };
+        assert!(matches!(mismatched.verify(100), Err(ServiceError::InvalidProof(_))));
+
+        // Compute type carrying storage data
+        let mismatched = ServiceProof { data: storage.data.clone(), ..compute };
+        assert!(matches!(mismatched.verify(100), Err(ServiceError::InvalidProof(_))));
+    }
+
+    #[test]
+    fn test_proof_hash() {
+        let proof = ServiceProof::new_storage(
+            Address::repeat_byte(1),
+            100,
+            B256::repeat_byte(1),
+            vec![B256::repeat_byte(2)],
+            B256::repeat_byte(3),
+        );
+        assert_eq!(proof.hash(), proof.clone().hash());
+
+        // Re-signing and re-stamping the epoch keep the identity
+        let resigned = ServiceProof { signature: vec![0xab; 65], ..proof.clone() };
+        assert_eq!(proof.hash(), resigned.hash());
+        let restamped = ServiceProof { epoch: 101, ..proof.clone() };
+        assert_eq!(proof.hash(), restamped.hash());
+
+        // Any other field changes it, a fresh challenge response is new work
+        let fresh = ServiceProof::new_storage(
+            Address::repeat_byte(1),
+            100,
+            B256::repeat_byte(1),
+            vec![B256::repeat_byte(2)],
+            B256::repeat_byte(9),
+        );
+        let others = [
+            fresh,
+            ServiceProof { miner: Address::repeat_byte(2), ..proof.clone() },
+            ServiceProof { proof_type: ServiceProofType::CdnDelivery, ..proof.clone() },
+            ServiceProof::new_cdn(Address::repeat_byte(1), 100, B256::repeat_byte(1), 1, 1, vec![]),
+        ];
+        for other in &others {
+            assert_ne!(proof.hash(), other.hash());
+        }
+    }
+
+    #[test]
+    fn test_epoch_service_score() {
+        let storage_miner = Address::repeat_byte(1);
+        let cdn_miner = Address::repeat_byte(2);
+
+        // 100GB stored; storage proofs don't carry the size, so it scores a flat point
+        let storage = ServiceProof::new_storage(
+            storage_miner,
+            100,
+            B256::repeat_byte(1),
+            vec![B256::repeat_byte(2)],
+            B256::repeat_byte(3),
+        );
+        let compute = ServiceProof::new_compute(
+            storage_miner,
+            100,
+            B256::repeat_byte(4),
+            B256::ZERO,
+            B256::ZERO,
+            5_000_000_000,
+        );
+        let cid = B256::repeat_byte(5);
+        let small_cdn = ServiceProof::new_cdn(cdn_miner, 100, cid, 1_000_000, 1_000_000, vec![]);
+
+        let full = epoch_service_score(&[storage.clone(), compute.clone()], 100);
+        let cdn_only = epoch_service_score(&[small_cdn], 100);
+        assert_eq!(full, 1 + 5);
+        assert_eq!(cdn_only, 1);
+        assert!(full > cdn_only);
+
+        // Repeated proofs are only counted once
+        assert_eq!(epoch_service_score(&[storage.clone(), storage.clone()], 100), 1);
+        assert_eq!(epoch_service_score(&[], 100), 0);
+
+        // Proofs of other epochs don't count
+        assert_eq!(epoch_service_score(&[storage.clone(), compute], 101), 0);
+
+        // Neither do proofs that fail verification
+        let mismatched = ServiceProof { proof_type: ServiceProofType::CdnDelivery, ..storage };
+        assert_eq!(epoch_service_score(&[mismatched], 100), 0);
+    }
+
+    #[test]
+    fn test_wire_roundtrip() {
+        let receipts: Vec<_> = (0..16).map(B256::repeat_byte).collect();
+        let mut storage = ServiceProof::new_storage(
+            Address::repeat_byte(0xa1),
+            100,
+            B256::repeat_byte(1),
+            receipts.clone(),
+            B256::repeat_byte(3),
+        );
+        storage.signature = vec![7; 65];
+        let cdn_miner = Address::repeat_byte(0xb0);
+        let cdn = ServiceProof::new_cdn(cdn_miner, 101, B256::ZERO, 1 << 40, 1 << 40, receipts);
+        let compute = ServiceProof::new_compute(
+            Address::repeat_byte(0xc0),
+            102,
+            B256::repeat_byte(1),
+            B256::repeat_byte(2),
+            B256::repeat_byte(3),
+            5_000_000_000,
+        );
+
+        for proof in [storage, cdn, compute] {
+            let bytes = proof.to_bytes().unwrap();
+            assert_eq!(bytes[0], PROOF_WIRE_VERSION);
+            assert_eq!(ServiceProof::from_bytes(&bytes).unwrap(), proof);
+
+            // Well under the JSON encoding
+            let json = serde_json::to_vec(&proof).unwrap();
+            assert!(bytes.len() * 3 < json.len() * 2, "{} bytes vs {} bytes of JSON", bytes.len(), json.len());
+        }
+    }
+
+    #[test]
+    fn test_wire_rejects_malformed() {
+        let proof = ServiceProof::new_cdn(Address::ZERO, 100, B256::ZERO, 1, 1, vec![B256::ZERO]);
+        let bytes = proof.to_bytes().unwrap();
+
+        assert!(matches!(ServiceProof::from_bytes(&[]), Err(ServiceError::InvalidEncoding(_))));
+        assert!(matches!(
+            ServiceProof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ServiceError::InvalidEncoding(_))
+        ));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            ServiceProof::from_bytes(&trailing),
+            Err(ServiceError::InvalidEncoding(_))
+        ));
+
+        let mut future = bytes;
+        future[0] = PROOF_WIRE_VERSION + 1;
+        assert!(matches!(ServiceProof::from_bytes(&future), Err(ServiceError::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn test_wire_size_limit() {
+        // More receipt hashes than fit in the limit
+        let receipts = (MAX_PROOF_WIRE_BYTES / 32 + 1) as usize;
+        let proof =
+            ServiceProof::new_cdn(Address::ZERO, 100, B256::ZERO, 1, 1, vec![B256::ZERO; receipts]);
+        assert!(matches!(proof.to_bytes(), Err(ServiceError::InvalidEncoding(_))));
+
+        // A length prefix claiming a huge receipt list fails without allocating it
+        let proof = ServiceProof::new_cdn(Address::ZERO, 100, B256::ZERO, 1, 1, vec![B256::ZERO]);
+        let mut bytes = proof.to_bytes().unwrap();
+        let len = bytes.windows(8).rposition(|w| w == 1u64.to_le_bytes()).unwrap();
+        bytes[len..len + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(ServiceProof::from_bytes(&bytes), Err(ServiceError::InvalidEncoding(_))));
     }
 }
@@ -0,0 +1,231 @@
+//! Block reward calculation
+//!
+//! The coinbase of a block is owed the base reward scaled by the service multiplier
+//! earned by the valid proofs in the block's [`ServiceProofBundle`] that were not credited
+//! before, as tracked by a [`ProofStore`]:
+//!
+//! ```text
+//! reward = base_block_reward(number) × multiplier(valid uncredited proofs, uptime)
+//! ```
+//!
+//! The geographic bonus has no on-chain source yet and is not part of the block reward;
+//! [`expected_block_reward`] can include it for estimates.
+//!
+//! Blocks don't commit to service proofs yet, so the multiplier is an off-chain estimate:
+//! on-chain every block pays [`base_block_reward`].
+//!
+//! The reward is split between the coinbase and the treasury, see [`reward_split`].
+
+use crate::{
+    multiplier::{apply_multiplier, calculate_multiplier},
+    ProofStore, ServiceProof, ServiceProofBundle, UptimeAttestation,
+};
+
+/// Base block reward in wei (10 MIA = 10 * 10^18)
+pub const BASE_BLOCK_REWARD: u128 = 10_000_000_000_000_000_000;
+
+/// Default share of the block reward paid to the treasury, in basis points (10%)
+pub const DEFAULT_TREASURY_SHARE_BPS: u32 = 1_000;
+
+/// Denominator of treasury shares (basis points, 10_000 = 100%)
+pub const TREASURY_SHARE_DENOMINATOR: u32 = 10_000;
+
+/// Length of a service proof epoch in seconds
+pub const EPOCH_DURATION_SECS: u64 = 3600;
+
+/// Service epoch containing a header timestamp (seconds)
+pub fn epoch_at(timestamp: u64) -> u64 {
+    timestamp / EPOCH_DURATION_SECS
+}
+
+/// Base reward of the block at `block_number`, before the service multiplier
+///
+/// There is no halving schedule: every height earns [`BASE_BLOCK_REWARD`].
+pub const fn base_block_reward(_block_number: u64) -> u128 {
+    BASE_BLOCK_REWARD
+}
+
+/// Reward owed to the coinbase of a block carrying `bundle`
+///
+/// Only proofs that pass [`ServiceProofBundle::verify_all`] at `current_epoch` and that
+/// `store` [credits](ProofStore::credit) count towards the multiplier, so a proof earns
+/// a bonus in one block only. The uptime bonus needs an attestation of the bundle's miner.
+pub fn block_reward(
+    bundle: &ServiceProofBundle,
+    store: &mut ProofStore,
+    current_epoch: u64,
+    uptime: Option<&UptimeAttestation>,
+) -> u128 {
+    let credited = store.credit(&valid_proofs(bundle, current_epoch), current_epoch);
+    let multiplier = calculate_multiplier(&credited, miner_uptime(bundle, uptime), 0.0);
+    apply_multiplier(base_block_reward(bundle.block_number), &multiplier)
+}
+
+/// Reward a pending block at `block_number` would pay its coinbase, for display to miners
+///
+/// The base reward at that height scaled by the multiplier of the bundle's valid proofs
+/// that `store` has not credited yet, the uptime attestation and a geographic rarity
+/// bonus. With no geographic bonus this is the [`block_reward`] of the bundle.
+pub fn expected_block_reward(
+    block_number: u64,
+    bundle: &ServiceProofBundle,
+    store: &ProofStore,
+    current_epoch: u64,
+    uptime: Option<&UptimeAttestation>,
+    geographic_rarity: f64,
+) -> u128 {
+    let mut valid = valid_proofs(bundle, current_epoch);
+    valid.retain(|proof| !store.is_credited(proof));
+
+    let multiplier = calculate_multiplier(&valid, miner_uptime(bundle, uptime), geographic_rarity);
+    apply_multiplier(base_block_reward(block_number), &multiplier)
+}
+
+/// `uptime` if it attests the bundle's miner
+fn miner_uptime<'a>(
+    bundle: &ServiceProofBundle,
+    uptime: Option<&'a UptimeAttestation>,
+) -> Option<&'a UptimeAttestation> {
+    uptime.filter(|attestation| attestation.miner == bundle.miner)
+}
+
+/// Proofs of `bundle` that pass [`ServiceProofBundle::verify_all`] at `current_epoch`
+fn valid_proofs(bundle: &ServiceProofBundle, current_epoch: u64) -> Vec<ServiceProof> {
+    let verification = bundle.verify_all(current_epoch);
+    verification.valid.iter().map(|&index| bundle.proofs[index].clone()).collect()
+}
+
+/// Split a block reward into the miner's and the treasury's amounts
+///
+/// The treasury gets `treasury_share_bps` basis points of `total`, rounded down, and the
+/// miner the rest, so the two always sum to `total`. Shares above 100% are capped.
+pub fn reward_split(total: u128, treasury_share_bps: u32) -> (u128, u128) {
+    let bps = treasury_share_bps.min(TREASURY_SHARE_DENOMINATOR) as u128;
+    let denominator = TREASURY_SHARE_DENOMINATOR as u128;
+    // Split the multiplication so large totals can't overflow
+    let treasury = total / denominator * bps + total % denominator * bps / denominator;
+    (total - treasury, treasury)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_utils::signed_attestation,
+        ServiceProof,
+    };
+    use alloy_primitives::{Address, B256};
+
+    #[test]
+    fn test_base_reward_without_proofs() {
+        let bundle = ServiceProofBundle::new(1, Address::ZERO);
+        assert_eq!(block_reward(&bundle, &mut ProofStore::new(), 100, None), BASE_BLOCK_REWARD);
+    }
+
+    #[test]
+    fn test_reward_counts_valid_proofs_only() {
+        let miner = Address::repeat_byte(1);
+        let storage = ServiceProof::new_storage(miner, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+
+        let bundle = ServiceProofBundle::with_proofs(1, miner, vec![storage.clone()]);
+        let reward = |bundle, epoch| block_reward(bundle, &mut ProofStore::new(), epoch, None);
+        assert_eq!(reward(&bundle, 100), BASE_BLOCK_REWARD / 10 * 12);
+
+        // Expired proofs earn nothing
+        assert_eq!(reward(&bundle, 200), BASE_BLOCK_REWARD);
+
+        // Neither do proofs by another miner
+        let foreign = ServiceProofBundle::with_proofs(1, Address::repeat_byte(2), vec![storage]);
+        assert_eq!(reward(&foreign, 100), BASE_BLOCK_REWARD);
+    }
+
+    #[test]
+    fn test_reward_credits_proof_once() {
+        let miner = Address::repeat_byte(1);
+        let storage = ServiceProof::new_storage(miner, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        let bundle = ServiceProofBundle::with_proofs(1, miner, vec![storage.clone()]);
+        let mut store = ProofStore::new();
+
+        let expected = expected_block_reward(1, &bundle, &store, 100, None, 0.0);
+        assert_eq!(block_reward(&bundle, &mut store, 100, None), expected);
+        assert_eq!(expected, BASE_BLOCK_REWARD / 10 * 12);
+
+        // Replaying the proof in a later block, or in the next epoch, earns the base reward
+        let replay = ServiceProofBundle::with_proofs(2, miner, vec![storage.clone()]);
+        assert_eq!(expected_block_reward(2, &replay, &store, 101, None, 0.0), BASE_BLOCK_REWARD);
+        assert_eq!(block_reward(&replay, &mut store, 101, None), BASE_BLOCK_REWARD);
+
+        // Re-stamping it with the new epoch doesn't change its hash
+        let restamped = ServiceProof { epoch: 101, ..storage };
+        let restamped = ServiceProofBundle::with_proofs(3, miner, vec![restamped]);
+        assert_eq!(block_reward(&restamped, &mut store, 101, None), BASE_BLOCK_REWARD);
+    }
+
+    #[test]
+    fn test_expected_block_reward() {
+        let miner = Address::repeat_byte(1);
+        let uptime = signed_attestation(miner, 1000, 990);
+        let storage = ServiceProof::new_storage(miner, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        let bundle = ServiceProofBundle::with_proofs(7, miner, vec![storage]);
+
+        // Without proofs, the base reward at that height
+        let store = ProofStore::new();
+        let empty = ServiceProofBundle::new(7, miner);
+        assert_eq!(expected_block_reward(7, &empty, &store, 100, None, 0.0), base_block_reward(7));
+
+        // base × (1 + storage 0.2 + uptime 0.1 + geographic 0.5)
+        let multiplier = calculate_multiplier(&bundle.proofs, Some(&uptime), 1.0);
+        assert!((multiplier.total() - 1.8).abs() < 1e-9);
+        assert_eq!(
+            expected_block_reward(7, &bundle, &store, 100, Some(&uptime), 1.0),
+            apply_multiplier(base_block_reward(7), &multiplier)
+        );
+
+        // Without the geographic bonus, the block reward of the bundle
+        assert_eq!(
+            expected_block_reward(7, &bundle, &store, 100, Some(&uptime), 0.0),
+            block_reward(&bundle, &mut ProofStore::new(), 100, Some(&uptime))
+        );
+
+        // Another miner's attestation earns no uptime bonus
+        let other = signed_attestation(Address::repeat_byte(2), 1000, 990);
+        assert_eq!(
+            block_reward(&empty, &mut ProofStore::new(), 100, Some(&other)),
+            base_block_reward(7)
+        );
+    }
+
+    #[test]
+    fn test_reward_split() {
+        // No treasury share: everything goes to the miner
+        assert_eq!(reward_split(BASE_BLOCK_REWARD, 0), (BASE_BLOCK_REWARD, 0));
+
+        // Default 10%: 9 MIA to the miner, 1 MIA to the treasury
+        assert_eq!(
+            reward_split(BASE_BLOCK_REWARD, DEFAULT_TREASURY_SHARE_BPS),
+            (9_000_000_000_000_000_000, 1_000_000_000_000_000_000)
+        );
+
+        // Capped at 100%
+        assert_eq!(reward_split(1_000, 20_000), (0, 1_000));
+    }
+
+    #[test]
+    fn test_reward_split_is_exact() {
+        for total in [0, 1, 9_999, 12_345, BASE_BLOCK_REWARD / 10 * 12 + 7, u128::MAX] {
+            for bps in [0, 1, 3_333, DEFAULT_TREASURY_SHARE_BPS, 9_999, 10_000] {
+                let (miner, treasury) = reward_split(total, bps);
+                assert_eq!(miner + treasury, total, "{total} at {bps} bps");
+            }
+        }
+        // Rounding favours the miner
+        assert_eq!(reward_split(12_345, 3_333), (8_231, 4_114));
+    }
+
+    #[test]
+    fn test_epoch_at() {
+        assert_eq!(epoch_at(0), 0);
+        assert_eq!(epoch_at(3599), 0);
+        assert_eq!(epoch_at(3600), 1);
+    }
+}
@@ -3,6 +3,69 @@
 use alloy_primitives::{Address, B256};
 use serde::{Deserialize, Serialize};
 
+/// Flat storage rate in USD cents per GB-month
+pub const FLAT_RATE_CENTS_PER_GB_MONTH: f64 = 0.1;
+
+/// A pricing tier, applying to storage up to `up_to_gb`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingTier {
+    /// Upper bound of the tier in GB, `None` for everything above the previous tier
+    pub up_to_gb: Option<u64>,
+    /// Rate in USD cents per GB-month
+    pub cents_per_gb_month: f64,
+}
+
+/// Volume pricing for storage
+///
+/// Each tier prices only the GB that fall within it, so crossing a boundary never
+/// makes the GB below it more expensive. Tiers are ordered by `up_to_gb`, and the last
+/// tier also prices anything beyond its bound.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingSchedule {
+    /// Tiers, ascending by `up_to_gb`
+    pub tiers: Vec<PricingTier>,
+}
+
+impl Default for PricingSchedule {
+    fn default() -> Self {
+        Self::flat(FLAT_RATE_CENTS_PER_GB_MONTH)
+    }
+}
+
+impl PricingSchedule {
+    /// Single rate for any volume
+    pub fn flat(cents_per_gb_month: f64) -> Self {
+        Self { tiers: vec![PricingTier { up_to_gb: None, cents_per_gb_month }] }
+    }
+
+    /// Schedule from tiers, sorted by upper bound with the unbounded tier last
+    pub fn tiered(mut tiers: Vec<PricingTier>) -> Self {
+        tiers.sort_by_key(|tier| tier.up_to_gb.unwrap_or(u64::MAX));
+        Self { tiers }
+    }
+
+    /// Cost in USD cents of storing `gb` for one month, before replication
+    pub fn cost_per_month(&self, gb: f64) -> f64 {
+        let mut cost = 0.0;
+        let mut lower = 0.0;
+
+        for (i, tier) in self.tiers.iter().enumerate() {
+            let upper = match tier.up_to_gb {
+                Some(limit) if i + 1 < self.tiers.len() => limit as f64,
+                _ => f64::INFINITY,
+            };
+            if gb <= lower {
+                break;
+            }
+
+            cost += (gb.min(upper) - lower) * tier.cents_per_gb_month;
+            lower = upper;
+        }
+
+        cost
+    }
+}
+
 /// Storage service parameters (from PROTOCOL_SPEC_v4.md)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageParams {
@@ -28,11 +91,19 @@ impl StorageParams {
     }
 
     /// Calculate storage cost in USD cents per month (simplified)
+    ///
+    /// Uses the flat default [`PricingSchedule`].
     pub fn monthly_cost_cents(&self) -> u64 {
-        // $0.001 per GB per month base rate
+        self.monthly_cost_cents_with(&PricingSchedule::default())
+    }
+
+    /// Calculate storage cost in USD cents under a pricing schedule
+    ///
+    /// Tiers apply to the content size; each replica is charged the same tiered cost.
+    pub fn monthly_cost_cents_with(&self, schedule: &PricingSchedule) -> u64 {
         let gb = (self.size_bytes as f64) / (1024.0 * 1024.0 * 1024.0);
         let months = (self.duration_seconds as f64) / (30.0 * 24.0 * 3600.0);
-        let base_cost = gb * months * 0.1; // 0.1 cents per GB-month
+        let base_cost = schedule.cost_per_month(gb) * months;
         let replicated_cost = base_cost * (self.replication as f64);
         replicated_cost.ceil() as u64
     }
@@ -92,6 +163,34 @@ mod tests {
         assert!(params.monthly_cost_cents() > 0);
     }
 
+    #[test]
+    fn test_tiered_pricing() {
+        const GB: u64 = 1024 * 1024 * 1024;
+        const MONTH: u64 = 30 * 24 * 3600;
+
+        // First 100 GB at 0.5 cents, the rest at 0.25 cents
+        let schedule = PricingSchedule::tiered(vec![
+            PricingTier { up_to_gb: None, cents_per_gb_month: 0.25 },
+            PricingTier { up_to_gb: Some(100), cents_per_gb_month: 0.5 },
+        ]);
+        let cost = |gb: u64, replication| {
+            StorageParams::new(B256::ZERO, gb * GB, MONTH, replication).monthly_cost_cents_with(&schedule)
+        };
+
+        assert_eq!(cost(100, 3), 150);
+        assert_eq!(cost(104, 3), 153);
+        assert_eq!(cost(200, 3), 225);
+
+        // Replication multiplies the tiered cost, not a flat one
+        assert_eq!(cost(200, 4), 300);
+        assert_eq!(cost(200, 6), 2 * cost(200, 3));
+
+        // The default stays flat
+        let params = StorageParams::new(B256::ZERO, 1000 * GB, MONTH, 3);
+        assert_eq!(params.monthly_cost_cents(), 300);
+        assert_eq!(params.monthly_cost_cents(), params.monthly_cost_cents_with(&PricingSchedule::flat(0.1)));
+    }
+
     #[test]
     fn test_storage_proof() {
         let proof = StorageProof {
@@ -0,0 +1,362 @@
+//! In-memory store for received service proofs
+//!
+//! Proofs are keyed by [`ServiceProof::hash`] and indexed by miner, content
+//! identifier and epoch so the reward calculator can look them up without scanning.
+//! A proof is only stored, and so only credited, once during its valid lifetime. Only
+//! proofs that verify are stored, and at most [`MAX_STORED_PROOFS`] of them.
+
+use alloy_primitives::{Address, B256};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{ServiceError, ServiceProof, ServiceProofBundle};
+
+/// Default maximum number of proofs a [`ProofStore`] holds
+pub const MAX_STORED_PROOFS: usize = 65_536;
+
+/// Content-addressed store of service proofs
+#[derive(Debug)]
+pub struct ProofStore {
+    /// Proofs by hash
+    proofs: HashMap<B256, ServiceProof>,
+    /// Miner -> proof keys (insertion order)
+    by_miner: HashMap<Address, Vec<B256>>,
+    /// Content identifier -> proof keys (insertion order)
+    by_cid: HashMap<B256, Vec<B256>>,
+    /// Epoch -> proof keys (insertion order)
+    by_epoch: BTreeMap<u64, Vec<B256>>,
+    /// Maximum number of stored proofs
+    max_proofs: usize,
+}
+
+impl Default for ProofStore {
+    fn default() -> Self {
+        Self {
+            proofs: HashMap::new(),
+            by_miner: HashMap::new(),
+            by_cid: HashMap::new(),
+            by_epoch: BTreeMap::new(),
+            max_proofs: MAX_STORED_PROOFS,
+        }
+    }
+}
+
+impl ProofStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of stored proofs
+    pub fn with_max_proofs(mut self, max_proofs: usize) -> Self {
+        self.max_proofs = max_proofs;
+        self
+    }
+
+    /// Insert a proof submitted at `current_epoch`
+    ///
+    /// The proof must [verify](ServiceProof::verify) at `current_epoch`. Fails with
+    /// [`ServiceError::DuplicateProof`] if a proof with the same [`ServiceProof::hash`] is
+    /// already stored, and with [`ServiceError::StoreFull`] if the store is still full
+    /// after pruning the proofs expired at `current_epoch`.
+    pub fn insert(
+        &mut self,
+        proof: ServiceProof,
+        current_epoch: u64,
+    ) -> Result<B256, ServiceError> {
+        proof.verify(current_epoch)?;
+
+        let key = proof.hash();
+        if self.proofs.contains_key(&key) {
+            return Err(ServiceError::DuplicateProof(key));
+        }
+        if self.proofs.len() >= self.max_proofs && self.prune(current_epoch) == 0 {
+            return Err(ServiceError::StoreFull(self.proofs.len()));
+        }
+
+        self.by_miner.entry(proof.miner).or_default().push(key);
+        self.by_cid.entry(proof.subject()).or_default().push(key);
+        self.by_epoch.entry(proof.epoch).or_default().push(key);
+        self.proofs.insert(key, proof);
+        Ok(key)
+    }
+
+    /// Credit proofs submitted at `current_epoch`
+    ///
+    /// Stores and returns the proofs that verify and have not been credited before, which
+    /// are the proofs that may count towards a multiplier. Replays of a proof still in
+    /// the store are skipped, see [`Self::is_credited`].
+    pub fn credit(&mut self, proofs: &[ServiceProof], current_epoch: u64) -> Vec<ServiceProof> {
+        proofs
+            .iter()
+            .filter(|proof| self.insert((*proof).clone(), current_epoch).is_ok())
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a proof with the same [`ServiceProof::hash`] was credited
+    ///
+    /// The hash excludes the epoch, so re-stamped replays are recognized too.
+    pub fn is_credited(&self, proof: &ServiceProof) -> bool {
+        self.proofs.contains_key(&proof.hash())
+    }
+
+    /// Build the bundle of `miner`'s stored proofs for the block at `block_number`
+    ///
+    /// Proofs that verify at `current_epoch` are added in insertion order until the bundle
+    /// holds the chain's limit of `max` proofs.
+    pub fn bundle(
+        &self,
+        block_number: u64,
+        miner: Address,
+        current_epoch: u64,
+        max: usize,
+    ) -> ServiceProofBundle {
+        let mut bundle = ServiceProofBundle::new(block_number, miner);
+        for proof in self.by_miner(&miner) {
+            if proof.verify(current_epoch).is_err() {
+                continue;
+            }
+            if bundle.try_push(proof.clone(), max).is_err() {
+                break;
+            }
+        }
+        bundle
+    }
+
+    /// Get a proof by its hash
+    pub fn get(&self, key: &B256) -> Option<&ServiceProof> {
+        self.proofs.get(key)
+    }
+
+    /// Proofs submitted by a miner
+    pub fn by_miner(&self, miner: &Address) -> Vec<&ServiceProof> {
+        self.lookup(self.by_miner.get(miner))
+    }
+
+    /// Proofs about a content identifier (`wasm_cid` for compute proofs)
+    pub fn by_cid(&self, cid: &B256) -> Vec<&ServiceProof> {
+        self.lookup(self.by_cid.get(cid))
+    }
+
+    /// Proofs for an epoch
+    pub fn by_epoch(&self, epoch: u64) -> Vec<&ServiceProof> {
+        self.lookup(self.by_epoch.get(&epoch))
+    }
+
+    /// Number of stored proofs
+    pub fn len(&self) -> usize {
+        self.proofs.len()
+    }
+
+    /// Whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.proofs.is_empty()
+    }
+
+    /// Remove proofs that have expired at `current_epoch`
+    ///
+    /// Uses the same expiry as [`ServiceProof::verify`]. Returns the number of proofs removed.
+    pub fn prune(&mut self, current_epoch: u64) -> usize {
+        // Proofs of an epoch expire together, so checking the first one of each will do
+        let expired: Vec<u64> = self
+            .by_epoch
+            .iter()
+            .take_while(|(_, keys)| {
+                keys.first()
+                    .and_then(|key| self.proofs.get(key))
+                    .is_some_and(|proof| proof.is_expired(current_epoch))
+            })
+            .map(|(epoch, _)| *epoch)
+            .collect();
+
+        let mut removed = 0;
+        for epoch in expired {
+            for key in self.by_epoch.remove(&epoch).unwrap_or_default() {
+                let Some(proof) = self.proofs.remove(&key) else { continue };
+                remove_key(&mut self.by_miner, &proof.miner, &key);
+                remove_key(&mut self.by_cid, &proof.subject(), &key);
+                removed += 1;
+            }
+        }
+        removed
+    }
+
+    fn lookup(&self, keys: Option<&Vec<B256>>) -> Vec<&ServiceProof> {
+        keys.into_iter().flatten().filter_map(|key| self.proofs.get(key)).collect()
+    }
+}
+
+/// Remove `key` from an index entry, dropping the entry once empty
+fn remove_key<K: std::hash::Hash + Eq>(index: &mut HashMap<K, Vec<B256>>, entry: &K, key: &B256) {
+    if let Some(keys) = index.get_mut(entry) {
+        keys.retain(|k| k != key);
+        if keys.is_empty() {
+            index.remove(entry);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServiceType;
+
+    fn mixed_store() -> ProofStore {
+        let alice = Address::repeat_byte(0xa1);
+        let bob = Address::repeat_byte(0xb0);
+        let cid = B256::repeat_byte(1);
+
+        let wasm = B256::repeat_byte(2);
+
+        let mut store = ProofStore::new();
+        for proof in [
+            ServiceProof::new_storage(alice, 100, cid, vec![], B256::ZERO),
+            ServiceProof::new_cdn(alice, 101, cid, 1_000, 1_000, vec![]),
+            ServiceProof::new_cdn(bob, 100, cid, 2_000, 2_000, vec![]),
+            ServiceProof::new_compute(bob, 130, wasm, B256::ZERO, B256::ZERO, 1),
+        ] {
+            store.insert(proof, 100).unwrap();
+        }
+        store
+    }
+
+    #[test]
+    fn test_indices() {
+        let store = mixed_store();
+        assert_eq!(store.len(), 4);
+
+        let alice = store.by_miner(&Address::repeat_byte(0xa1));
+        assert_eq!(alice.len(), 2);
+        assert_eq!(alice[0].service_type(), ServiceType::Storage);
+        assert_eq!(alice[1].service_type(), ServiceType::Cdn);
+        assert!(store.by_miner(&Address::ZERO).is_empty());
+
+        assert_eq!(store.by_cid(&B256::repeat_byte(1)).len(), 3);
+        let wasm = store.by_cid(&B256::repeat_byte(2));
+        assert_eq!(wasm.len(), 1);
+        assert_eq!(wasm[0].service_type(), ServiceType::Compute);
+
+        let epoch = store.by_epoch(100);
+        assert_eq!(epoch.len(), 2);
+        assert!(epoch.iter().all(|proof| proof.epoch == 100));
+        assert!(store.by_epoch(99).is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_rejected() {
+        let mut store = mixed_store();
+        let alice = Address::repeat_byte(0xa1);
+        let proof = ServiceProof::new_storage(alice, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        assert!(matches!(store.insert(proof.clone(), 100), Err(ServiceError::DuplicateProof(_))));
+
+        // Also under a later epoch
+        let restamped = ServiceProof { epoch: 105, ..proof };
+        assert!(matches!(store.insert(restamped, 105), Err(ServiceError::DuplicateProof(_))));
+        assert_eq!(store.len(), 4);
+    }
+
+    #[test]
+    fn test_unverified_rejected() {
+        let mut store = ProofStore::new();
+        let alice = Address::repeat_byte(0xa1);
+        let proof = ServiceProof::new_storage(alice, 100, B256::repeat_byte(1), vec![], B256::ZERO);
+        assert!(matches!(store.insert(proof, 200), Err(ServiceError::ProofExpired(100, 200))));
+
+        let receipt = B256::repeat_byte(7);
+        let receipts = vec![receipt, receipt];
+        let cdn = ServiceProof::new_cdn(alice, 100, B256::ZERO, 1_000, 1_000, receipts);
+        assert!(matches!(store.insert(cdn, 100), Err(ServiceError::InvalidProof(_))));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_store_bounded() {
+        let alice = Address::repeat_byte(0xa1);
+        let proof = |epoch, cid| {
+            ServiceProof::new_storage(alice, epoch, B256::repeat_byte(cid), vec![], B256::ZERO)
+        };
+        let mut store = ProofStore::new().with_max_proofs(2);
+        store.insert(proof(100, 1), 100).unwrap();
+        store.insert(proof(101, 2), 101).unwrap();
+        assert!(matches!(store.insert(proof(101, 3), 101), Err(ServiceError::StoreFull(2))));
+
+        // Expired proofs make room
+        store.insert(proof(125, 3), 125).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.by_epoch(100).is_empty());
+    }
+
+    #[test]
+    fn test_bundle_capped() {
+        let alice = Address::repeat_byte(0xa1);
+        let mut store = ProofStore::new();
+        for i in 1..=3 {
+            let cid = B256::repeat_byte(i);
+            let proof = ServiceProof::new_storage(alice, 100, cid, vec![], B256::ZERO);
+            store.insert(proof, 100).unwrap();
+        }
+
+        let bundle = store.bundle(5, alice, 100, 2);
+        assert_eq!((bundle.block_number, bundle.miner), (5, alice));
+        assert_eq!(bundle.len(), 2);
+        assert!(bundle.check_size(2).is_ok());
+        assert_eq!(store.bundle(5, alice, 100, 10).len(), 3);
+
+        // Only proofs that still verify are bundled
+        assert!(store.bundle(5, alice, 200, 10).is_empty());
+        assert!(store.bundle(5, Address::repeat_byte(0xb0), 100, 10).is_empty());
+    }
+
+    #[test]
+    fn test_prune_expired() {
+        let mut store = mixed_store();
+
+        // Epoch 124: proofs at epoch 100 are still verifiable
+        assert_eq!(store.prune(124), 0);
+
+        // Epoch 125: epoch 100 expires, epoch 101 does not
+        assert_eq!(store.prune(125), 2);
+        assert_eq!(store.len(), 2);
+        assert!(store.by_epoch(100).is_empty());
+        assert_eq!(store.by_miner(&Address::repeat_byte(0xa1)).len(), 1);
+        assert_eq!(store.by_miner(&Address::repeat_byte(0xb0)).len(), 1);
+        assert_eq!(store.by_cid(&B256::repeat_byte(1)).len(), 1);
+
+        // Everything left in the store still verifies
+        for epoch in [101, 130] {
+            for proof in store.by_epoch(epoch) {
+                assert!(proof.verify(125).is_ok());
+            }
+        }
+
+        assert_eq!(store.prune(1_000), 2);
+        assert!(store.is_empty());
+        assert!(store.by_miner(&Address::repeat_byte(0xb0)).is_empty());
+    }
+
+    #[test]
+    fn test_replayed_proof_credited_once() {
+        use crate::calculate_multiplier;
+
+        let miner = Address::repeat_byte(0xa1);
+        let proof = ServiceProof::new_storage(miner, 100, B256::repeat_byte(1), vec![], B256::repeat_byte(3));
+        let mut store = ProofStore::new();
+
+        // Credited in the epoch it was produced for
+        let credited = store.credit(std::slice::from_ref(&proof), 100);
+        assert!(calculate_multiplier(&credited, None, 0.0).storage > 0.0);
+
+        // The same, still unexpired, proof in the next epoch earns nothing
+        let credited = store.credit(std::slice::from_ref(&proof), 101);
+        assert!(credited.is_empty());
+        assert_eq!(calculate_multiplier(&credited, None, 0.0).storage, 0.0);
+
+        // Nor does re-stamping it with the new epoch
+        let restamped = ServiceProof { epoch: 101, ..proof.clone() };
+        assert!(store.credit(&[restamped], 101).is_empty());
+        assert_eq!(store.len(), 1);
+
+        // Expired proofs are never credited
+        assert!(store.credit(&[proof], 200).is_empty());
+    }
+}
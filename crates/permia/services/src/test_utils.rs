@@ -0,0 +1,33 @@
+//! Helpers shared by the tests of the service modules
+
+use crate::{SamplerSignature, UptimeAttestation, MIN_UPTIME_SAMPLERS};
+use alloy_primitives::{Address, Signature};
+use k256::ecdsa::SigningKey;
+
+/// Deterministic test key
+pub(crate) fn signing_key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32].into()).unwrap()
+}
+
+/// Add the signature of `key` to an attestation
+pub(crate) fn sign(attestation: &mut UptimeAttestation, key: &SigningKey) {
+    let signature: Signature =
+        key.sign_prehash_recoverable(attestation.signing_message().as_slice()).unwrap().into();
+    attestation.signatures.push(SamplerSignature {
+        sampler: Address::from_private_key(key),
+        signature: signature.as_bytes().to_vec(),
+    });
+}
+
+/// Build an attestation of `miner`, signed by [`MIN_UPTIME_SAMPLERS`] samplers
+pub(crate) fn signed_attestation(
+    miner: Address,
+    sampled_pings: u64,
+    responded_pings: u64,
+) -> UptimeAttestation {
+    let mut attestation = UptimeAttestation::new(miner, 100, sampled_pings, responded_pings);
+    for seed in 0..MIN_UPTIME_SAMPLERS as u8 {
+        sign(&mut attestation, &signing_key(0xa0 + seed));
+    }
+    attestation
+}
@@ -0,0 +1,221 @@
+//! Uptime attestations
+//!
+//! An [`UptimeAttestation`] records how many liveness pings a miner answered during an
+//! epoch. The signed attestation is the only source of the uptime multiplier bonus.
+//!
+//! The pings are sent by samplers, and the attestation is only valid with the signatures
+//! of at least [`MIN_UPTIME_SAMPLERS`] distinct samplers. The miner can't attest its own
+//! uptime. Verifiers that know the set of samplers should also check the signers are in
+//! it, see [`UptimeAttestation::verify_samplers`].
+
+use alloy_primitives::{keccak256, Address, Signature, B256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::ServiceError;
+
+/// Number of distinct samplers that must sign an uptime attestation
+pub const MIN_UPTIME_SAMPLERS: usize = 3;
+
+/// Signed record of a miner's responsiveness over an epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UptimeAttestation {
+    /// Attested miner
+    pub miner: Address,
+    /// Epoch the pings were sampled in
+    pub epoch: u64,
+    /// Number of pings sent to the miner
+    pub sampled_pings: u64,
+    /// Number of pings the miner answered
+    pub responded_pings: u64,
+    /// Sampler signatures over [`Self::signing_message`]
+    pub signatures: Vec<SamplerSignature>,
+}
+
+/// Signature of a sampler over an [`UptimeAttestation`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SamplerSignature {
+    /// Sampler that sent the pings
+    pub sampler: Address,
+    /// Sampler signature (r, s, v concatenated)
+    pub signature: Vec<u8>,
+}
+
+impl UptimeAttestation {
+    /// Create a new unsigned attestation
+    pub fn new(miner: Address, epoch: u64, sampled_pings: u64, responded_pings: u64) -> Self {
+        Self { miner, epoch, sampled_pings, responded_pings, signatures: Vec::new() }
+    }
+
+    /// Get the message that should be signed
+    pub fn signing_message(&self) -> B256 {
+        let mut data = Vec::with_capacity(14 + 20 + 24);
+        data.extend_from_slice(b"PERMIA_UPTIME:");
+        data.extend_from_slice(self.miner.as_slice());
+        data.extend_from_slice(&self.epoch.to_be_bytes());
+        data.extend_from_slice(&self.sampled_pings.to_be_bytes());
+        data.extend_from_slice(&self.responded_pings.to_be_bytes());
+
+        keccak256(&data)
+    }
+
+    /// Uptime as a percentage of sampled pings answered
+    pub fn uptime_percent(&self) -> f64 {
+        if self.sampled_pings == 0 {
+            return 0.0;
+        }
+
+        let responded = self.responded_pings.min(self.sampled_pings);
+        responded as f64 * 100.0 / self.sampled_pings as f64
+    }
+
+    /// Samplers that signed the attestation, in signature order
+    pub fn samplers(&self) -> impl Iterator<Item = Address> + '_ {
+        self.signatures.iter().map(|signature| signature.sampler)
+    }
+
+    /// Verify the attestation is well-formed and signed by enough samplers
+    ///
+    /// Requires valid signatures of at least [`MIN_UPTIME_SAMPLERS`] distinct samplers,
+    /// none of them the miner.
+    pub fn verify(&self) -> Result<(), ServiceError> {
+        if self.sampled_pings == 0 {
+            return Err(ServiceError::InvalidProof("no pings sampled".to_string()));
+        }
+
+        if self.responded_pings > self.sampled_pings {
+            return Err(ServiceError::InvalidProof(format!(
+                "responded to {} of {} pings",
+                self.responded_pings, self.sampled_pings
+            )));
+        }
+
+        let message = self.signing_message();
+        let mut samplers = HashSet::new();
+        for SamplerSignature { sampler, signature } in &self.signatures {
+            if *sampler == self.miner {
+                return Err(ServiceError::VerificationFailed(format!(
+                    "miner {sampler} attested its own uptime"
+                )));
+            }
+            if !samplers.insert(*sampler) {
+                return Err(ServiceError::VerificationFailed(format!(
+                    "sampler {sampler} signed more than once"
+                )));
+            }
+
+            let signer = Signature::from_raw(signature)
+                .and_then(|signature| signature.recover_address_from_prehash(&message))
+                .map_err(|e| ServiceError::VerificationFailed(format!("invalid signature: {e}")))?;
+            if signer != *sampler {
+                return Err(ServiceError::VerificationFailed(format!(
+                    "signature of sampler {sampler} signed by {signer}"
+                )));
+            }
+        }
+
+        if samplers.len() < MIN_UPTIME_SAMPLERS {
+            return Err(ServiceError::VerificationFailed(format!(
+                "signed by {} samplers, {MIN_UPTIME_SAMPLERS} required",
+                samplers.len()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Verify the attestation and that every signing sampler is one of `samplers`
+    pub fn verify_samplers(&self, samplers: &[Address]) -> Result<(), ServiceError> {
+        self.verify()?;
+        if let Some(unknown) = self.samplers().find(|sampler| !samplers.contains(sampler)) {
+            return Err(ServiceError::VerificationFailed(format!("unknown sampler {unknown}")));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{sign, signed_attestation, signing_key};
+
+    #[test]
+    fn test_uptime_percent() {
+        let miner = Address::repeat_byte(1);
+
+        assert_eq!(signed_attestation(miner, 1000, 995).uptime_percent(), 99.5);
+        assert_eq!(signed_attestation(miner, 1000, 960).uptime_percent(), 96.0);
+        assert_eq!(UptimeAttestation::new(Address::ZERO, 100, 0, 0).uptime_percent(), 0.0);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let miner_key = signing_key(1);
+        let miner = Address::from_private_key(&miner_key);
+        let attestation = signed_attestation(miner, 1000, 995);
+        assert!(attestation.verify().is_ok());
+        assert_eq!(attestation.samplers().count(), MIN_UPTIME_SAMPLERS);
+
+        // Only samplers of a known set
+        let samplers: Vec<_> = attestation.samplers().collect();
+        assert!(attestation.verify_samplers(&samplers).is_ok());
+        assert!(matches!(
+            attestation.verify_samplers(&samplers[1..]),
+            Err(ServiceError::VerificationFailed(_))
+        ));
+
+        // Tampered after signing
+        let mut tampered = attestation.clone();
+        tampered.responded_pings = 1000;
+        assert!(matches!(tampered.verify(), Err(ServiceError::VerificationFailed(_))));
+
+        // Missing signatures
+        let unsigned = UptimeAttestation::new(miner, 100, 1000, 995);
+        assert!(matches!(unsigned.verify(), Err(ServiceError::VerificationFailed(_))));
+        let mut malformed = attestation.clone();
+        malformed.signatures[0].signature = vec![0u8; 65];
+        assert!(matches!(malformed.verify(), Err(ServiceError::VerificationFailed(_))));
+
+        // A signature claimed for another sampler
+        let mut misattributed = attestation;
+        misattributed.signatures[0].sampler = Address::repeat_byte(9);
+        assert!(matches!(misattributed.verify(), Err(ServiceError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn test_miner_cannot_attest_itself() {
+        let miner_key = signing_key(1);
+        let miner = Address::from_private_key(&miner_key);
+
+        // Signed by the miner alone, as before samplers were required
+        let mut own = UptimeAttestation::new(miner, 100, 1000, 1000);
+        sign(&mut own, &miner_key);
+        assert!(matches!(own.verify(), Err(ServiceError::VerificationFailed(_))));
+
+        // The miner can't stand in for a missing sampler either
+        let mut padded = UptimeAttestation::new(miner, 100, 1000, 1000);
+        for seed in 1..MIN_UPTIME_SAMPLERS as u8 {
+            sign(&mut padded, &signing_key(0xa0 + seed));
+        }
+        sign(&mut padded, &miner_key);
+        assert!(matches!(padded.verify(), Err(ServiceError::VerificationFailed(_))));
+
+        // Nor can one sampler sign repeatedly
+        let mut repeated = UptimeAttestation::new(miner, 100, 1000, 1000);
+        for _ in 0..MIN_UPTIME_SAMPLERS {
+            sign(&mut repeated, &signing_key(0xa0));
+        }
+        assert!(matches!(repeated.verify(), Err(ServiceError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn test_verify_bounds() {
+        let miner = Address::repeat_byte(1);
+
+        assert!(matches!(
+            signed_attestation(miner, 1000, 1001).verify(),
+            Err(ServiceError::InvalidProof(_))
+        ));
+        assert!(matches!(signed_attestation(miner, 0, 0).verify(), Err(ServiceError::InvalidProof(_))));
+    }
+}